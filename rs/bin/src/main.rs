@@ -1,27 +1,286 @@
+use std::collections::HashMap;
 use std::env;
 use std::fs::File;
 use std::io::prelude::*;
+use std::path::Path;
 
+use swf_parser::avm1::{build_cfg, parse_actions_with_legacy_encoding, write_disassembly};
 use swf_parser::complete::parse_swf;
+use swf_parser::font::{cff_font_to_otf, font_to_ttf, read_opentype_info};
+use swf_parser::image::{decode_jpeg_with_alpha, decode_lossless, get_standalone_jpeg, ImageDecodeError};
+use swf_parser::legacy::{default_legacy_encoding, LegacyEncoding};
 
 use swf_types as swf;
 
+const USAGE: &str = "Usage:
+  swf-parser <file.swf>                  Print the AST of the movie as JSON
+  swf-parser disasm [--cfg] <file.swf>   Disassemble the AVM1 actions of the movie
+  swf-parser extract-images <file.swf> [<output-dir>]
+                                         Write the bitmaps of the movie to the output directory
+  swf-parser extract-fonts [--cff] <file.swf> [<output-dir>]
+                                         Convert the DefineFont2/DefineFont3 fonts of the movie to TrueType,
+                                         or write its DefineFont4 (CFF) fonts with `--cff`";
+
 fn main() {
   let args: Vec<String> = env::args().collect();
-  if args.len() < 2 {
-    println!("Missing input path");
-    return;
+  match args.get(1).map(String::as_str) {
+    None => {
+      println!("Missing input path");
+      println!("{}", USAGE);
+    }
+    Some("disasm") => disassemble_movie(&args[2..]),
+    Some("extract-images") => extract_images(&args[2..]),
+    Some("extract-fonts") => extract_fonts(&args[2..]),
+    Some(file_path) => print_movie(file_path),
   }
+}
 
-  let file_path = &args[1];
-  //  println!("Reading file: {}", filename);
-
+fn read_movie(file_path: &str) -> swf::Movie {
   let mut file = File::open(file_path).expect("File not found");
   let mut data: Vec<u8> = Vec::new();
   file.read_to_end(&mut data).expect("Unable to read file");
+  parse_swf(&data[..]).expect("Failed to parse movie")
+}
 
-  //  println!("Input:\n{:?}", &data);
-
-  let movie: swf::Movie = parse_swf(&data[..]).expect("Failed to parse movie");
+fn print_movie(file_path: &str) {
+  let movie: swf::Movie = read_movie(file_path);
   println!("{}", serde_json_v8::to_string_pretty(&movie).unwrap());
 }
+
+fn disassemble_movie(args: &[String]) {
+  let with_cfg = args.iter().any(|arg| arg == "--cfg");
+  let file_path = match args.iter().find(|arg| !arg.starts_with("--")) {
+    Some(file_path) => file_path,
+    None => {
+      println!("Missing input path");
+      println!("{}", USAGE);
+      return;
+    }
+  };
+  let movie: swf::Movie = read_movie(file_path);
+  let encoding = default_legacy_encoding(&movie.tags);
+  disassemble_tags(&movie.tags, "", movie.header.swf_version, encoding, with_cfg);
+}
+
+/// Prints the listing of each action string of the tags, `path` is the path of the parent sprite.
+///
+/// The strings of SWF 5 (and earlier) movies are decoded with `encoding`, as the tags of the movie.
+fn disassemble_tags(tags: &[swf::Tag], path: &str, swf_version: u8, encoding: LegacyEncoding, with_cfg: bool) {
+  for (index, tag) in tags.iter().enumerate() {
+    let tag_path = format!("{}/{}", path, index);
+    let action_strings: Vec<(String, &[u8])> = match tag {
+      swf::Tag::DefineSprite(sprite) => {
+        disassemble_tags(&sprite.tags, &tag_path, swf_version, encoding, with_cfg);
+        Vec::new()
+      }
+      swf::Tag::DoAction(tag) => vec![(String::from("DoAction"), &tag.actions[..])],
+      swf::Tag::DoInitAction(tag) => {
+        vec![(format!("DoInitAction (sprite {})", tag.sprite_id), &tag.actions[..])]
+      }
+      swf::Tag::DefineButton(tag) => tag
+        .actions
+        .iter()
+        .enumerate()
+        .map(|(cond_index, cond_action)| {
+          let name = format!("DefineButton {} (condition {})", tag.id, cond_index);
+          (name, &cond_action.actions[..])
+        })
+        .collect(),
+      swf::Tag::PlaceObject(tag) => tag
+        .clip_actions
+        .iter()
+        .flatten()
+        .enumerate()
+        .map(|(clip_index, clip_action)| {
+          let name = format!("PlaceObject at depth {} (clip action {})", tag.depth, clip_index);
+          (name, &clip_action.actions[..])
+        })
+        .collect(),
+      _ => Vec::new(),
+    };
+    for (name, actions) in action_strings {
+      println!("// {}: {}", tag_path, name);
+      let records = parse_actions_with_legacy_encoding(actions, swf_version, encoding);
+      let mut listing = String::new();
+      write_disassembly(&mut listing, &records).unwrap();
+      print!("{}", listing);
+      if with_cfg {
+        for block in build_cfg(&records).blocks {
+          let successors: Vec<String> = block.successors.iter().map(|offset| format!("@{:#x}", offset)).collect();
+          println!(
+            "// block @{:#x} ({} actions) -> [{}]",
+            block.offset,
+            block.actions.len(),
+            successors.join(", ")
+          );
+        }
+      }
+      println!();
+    }
+  }
+}
+
+fn extract_images(args: &[String]) {
+  let file_path = match args.first() {
+    Some(file_path) => file_path,
+    None => {
+      println!("Missing input path");
+      println!("{}", USAGE);
+      return;
+    }
+  };
+  let output_dir = Path::new(args.get(1).map_or(".", String::as_str));
+  std::fs::create_dir_all(output_dir).expect("Unable to create output directory");
+  let movie: swf::Movie = read_movie(file_path);
+  let names = symbol_names(&movie);
+
+  for tag in &movie.tags {
+    let bitmap = match tag {
+      swf::Tag::DefineBitmap(bitmap) => bitmap,
+      _ => continue,
+    };
+    let file_stem = file_stem(&names, bitmap.id);
+    match extract_image(&movie, bitmap) {
+      Ok(files) => {
+        for (suffix, data) in files {
+          let file_path = output_dir.join(format!("{}{}", file_stem, suffix));
+          std::fs::write(&file_path, data).expect("Unable to write image");
+          println!("{}", file_path.display());
+        }
+      }
+      Err(e) => eprintln!("Failed to extract bitmap {}: {}", bitmap.id, e),
+    }
+  }
+}
+
+/// Returns the export or class names of the characters of a movie, the first name wins.
+fn symbol_names(movie: &swf::Movie) -> HashMap<u16, &str> {
+  let mut names: HashMap<u16, &str> = HashMap::new();
+  for tag in &movie.tags {
+    let symbols = match tag {
+      swf::Tag::ExportAssets(tag) => &tag.assets,
+      swf::Tag::SymbolClass(tag) => &tag.symbols,
+      _ => continue,
+    };
+    for symbol in symbols {
+      names.entry(symbol.id).or_insert(&symbol.name);
+    }
+  }
+  names
+}
+
+/// Returns the file name of a character without extension: its id, followed by its export or class name.
+fn file_stem(names: &HashMap<u16, &str>, id: u16) -> String {
+  match names.get(&id) {
+    Some(name) => format!("{}_{}", id, sanitize_file_name(name)),
+    None => id.to_string(),
+  }
+}
+
+/// Returns the files of a bitmap, as `(file name suffix, data)` pairs.
+///
+/// JPEG images with an alpha channel produce two files: the JPEG image and the alpha channel as a grayscale PNG.
+/// Opaque `DefineBitsJpeg3` images only produce the JPEG image.
+fn extract_image(
+  movie: &swf::Movie,
+  bitmap: &swf::tags::DefineBitmap,
+) -> Result<Vec<(&'static str, Vec<u8>)>, ImageDecodeError> {
+  let files = match bitmap.media_type {
+    swf::ImageType::Jpeg | swf::ImageType::SwfPartialJpeg => vec![(".jpg", get_standalone_jpeg(movie, bitmap.id)?)],
+    swf::ImageType::Png => vec![(".png", bitmap.data.clone())],
+    swf::ImageType::Gif => vec![(".gif", bitmap.data.clone())],
+    swf::ImageType::SwfJpeg3 | swf::ImageType::SwfJpeg4 => {
+      let image = decode_jpeg_with_alpha(bitmap)?;
+      match &image.alpha {
+        Some(alpha) => {
+          let alpha = encode_png(image.width, image.height, png::ColorType::Grayscale, alpha)?;
+          vec![(".jpg", image.jpeg), (".alpha.png", alpha)]
+        }
+        None => vec![(".jpg", image.jpeg)],
+      }
+    }
+    swf::ImageType::SwfLossless1 | swf::ImageType::SwfLossless2 => {
+      let image = decode_lossless(bitmap)?;
+      vec![(".png", encode_png(image.width, image.height, png::ColorType::Rgba, &image.data)?)]
+    }
+  };
+  Ok(files)
+}
+
+fn extract_fonts(args: &[String]) {
+  let cff = args.iter().any(|arg| arg == "--cff");
+  let paths: Vec<&String> = args.iter().filter(|arg| !arg.starts_with("--")).collect();
+  let file_path = match paths.first() {
+    Some(file_path) => file_path,
+    None => {
+      println!("Missing input path");
+      println!("{}", USAGE);
+      return;
+    }
+  };
+  let output_dir = Path::new(paths.get(1).map_or(".", |path| path.as_str()));
+  std::fs::create_dir_all(output_dir).expect("Unable to create output directory");
+  let movie: swf::Movie = read_movie(file_path);
+  let names = symbol_names(&movie);
+
+  for tag in &movie.tags {
+    let (id, font_name, result, extension) = match tag {
+      swf::Tag::DefineFont(font) if !cff => (font.id, &font.font_name, font_to_ttf(font), "ttf"),
+      swf::Tag::DefineCffFont(font) if cff => (font.id, &font.font_name, cff_font_to_otf(font), "otf"),
+      _ => continue,
+    };
+    let data = match result {
+      Ok(data) => data,
+      Err(e) => {
+        eprintln!("Failed to extract font {}: {}", id, e);
+        continue;
+      }
+    };
+    // Fonts are rarely exported, fall back to their name
+    let file_stem = if names.contains_key(&id) {
+      file_stem(&names, id)
+    } else {
+      format!("{}_{}", id, sanitize_file_name(font_name))
+    };
+    let file_path = output_dir.join(format!("{}.{}", file_stem, extension));
+    std::fs::write(&file_path, &data).expect("Unable to write font");
+    if cff {
+      let info = read_opentype_info(&data).expect("Font validated on extraction");
+      println!(
+        "{} ({}, {} glyphs, {} code points)",
+        file_path.display(),
+        info.name.as_deref().unwrap_or(font_name),
+        info.glyph_count,
+        info.code_points.len()
+      );
+    } else {
+      println!("{}", file_path.display());
+    }
+  }
+}
+
+/// Encodes 8-bit pixels as a PNG image, empty images are rejected by the PNG format.
+fn encode_png(
+  width: usize,
+  height: usize,
+  color_type: png::ColorType,
+  data: &[u8],
+) -> Result<Vec<u8>, ImageDecodeError> {
+  let to_error = |e: png::EncodingError| ImageDecodeError::InvalidData(format!("unable to encode PNG: {}", e));
+  let mut png_data: Vec<u8> = Vec::new();
+  let mut encoder = png::Encoder::new(&mut png_data, width as u32, height as u32);
+  encoder.set_color(color_type);
+  encoder.set_depth(png::BitDepth::Eight);
+  let mut writer = encoder.write_header().map_err(to_error)?;
+  writer.write_image_data(data).map_err(to_error)?;
+  writer.finish().map_err(to_error)?;
+  Ok(png_data)
+}
+
+/// Replaces the characters of an export or class name that are not safe in file names.
+fn sanitize_file_name(name: &str) -> String {
+  name
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' { c } else { '_' })
+    .collect()
+}
@@ -0,0 +1,284 @@
+use crate::abc::file::{AbcFile, TraitKind};
+use crate::abc::parser::{parse_abc, AbcParseError};
+use swf_types as ast;
+
+/// AS3 class defined by a `DoAbc` tag, with its names resolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AbcClass {
+  /// Qualified name of the class, such as `com.example.Player`.
+  pub name: String,
+  /// Qualified name of the super class, `None` for `Object` and interfaces.
+  pub super_name: Option<String>,
+  /// Qualified names of the implemented interfaces.
+  pub interfaces: Vec<String>,
+  pub is_interface: bool,
+  /// Instance methods followed by the static methods.
+  pub methods: Vec<ClassMethod>,
+  /// Index of the `DoAbc` tag in the tags of the movie.
+  pub tag_index: usize,
+  /// Index of the class in the ABC file of the tag.
+  pub class_index: usize,
+}
+
+/// Method declared by a class.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClassMethod {
+  pub name: String,
+  pub kind: MethodKind,
+  pub is_static: bool,
+  /// Qualified names of the parameter types, `*` for untyped parameters.
+  pub parameter_types: Vec<String>,
+  /// Qualified name of the return type, `*` if untyped.
+  pub return_type: String,
+  /// Index of the method in the ABC file of the class.
+  pub method: u32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MethodKind {
+  Method,
+  Getter,
+  Setter,
+}
+
+/// `SymbolClass` entry joined with the class definition.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolClassLink {
+  /// Character id, `0` for the main timeline (document class).
+  pub id: u16,
+  /// Class name from the `SymbolClass` tag.
+  pub name: String,
+  /// Definition of the class, `None` if no `DoAbc` tag defines it.
+  pub class: Option<AbcClass>,
+}
+
+/// Returns the classes defined by the `DoAbc` tags of a movie, in definition order.
+///
+/// Classes whose name can't be resolved are omitted. `DoAbc` tags with an invalid ABC file are skipped: they are
+/// returned with their tag index and the parse error.
+pub fn abc_classes(tags: &[ast::Tag]) -> (Vec<AbcClass>, Vec<(usize, AbcParseError)>) {
+  let mut classes: Vec<AbcClass> = Vec::new();
+  let mut errors: Vec<(usize, AbcParseError)> = Vec::new();
+  for (tag_index, tag) in tags.iter().enumerate() {
+    if let ast::Tag::DoAbc(tag) = tag {
+      match parse_abc(&tag.data) {
+        Ok(abc) => classes.extend(resolve_classes(&abc, tag_index)),
+        Err(e) => errors.push((tag_index, e)),
+      }
+    }
+  }
+  (classes, errors)
+}
+
+/// Joins the `SymbolClass` entries of a movie with the classes defined by its `DoAbc` tags.
+///
+/// Classes are resolved by qualified name across all the `DoAbc` tags of the movie, see [[abc_classes]]. If a
+/// name is defined multiple times, the first definition is used. The invalid `DoAbc` tags are returned with the
+/// links, the classes of the other tags are still linked.
+pub fn link_symbol_classes(tags: &[ast::Tag]) -> (Vec<SymbolClassLink>, Vec<(usize, AbcParseError)>) {
+  let (classes, errors) = abc_classes(tags);
+  let links = tags
+    .iter()
+    .filter_map(|tag| match tag {
+      ast::Tag::SymbolClass(tag) => Some(&tag.symbols),
+      _ => None,
+    })
+    .flatten()
+    .map(|symbol| SymbolClassLink {
+      id: symbol.id,
+      name: symbol.name.clone(),
+      class: classes.iter().find(|class| class.name == symbol.name).cloned(),
+    })
+    .collect();
+  (links, errors)
+}
+
+fn resolve_classes(abc: &AbcFile, tag_index: usize) -> Vec<AbcClass> {
+  let pool = &abc.constant_pool;
+  let mut classes: Vec<AbcClass> = Vec::new();
+  for (class_index, instance) in abc.instances.iter().enumerate() {
+    let name = match pool.get_qualified_name(instance.name) {
+      Some(name) => name,
+      None => continue,
+    };
+    let super_name = match instance.super_name {
+      0 => None,
+      super_name => pool.get_qualified_name(super_name),
+    };
+    let interfaces = instance
+      .interfaces
+      .iter()
+      .filter_map(|interface| pool.get_qualified_name(*interface))
+      .collect();
+    let static_traits = abc.classes.get(class_index).map_or(&[][..], |class| &class.traits[..]);
+    let instance_methods = instance.traits.iter().map(|t| (t, false));
+    let static_methods = static_traits.iter().map(|t| (t, true));
+    let methods = instance_methods
+      .chain(static_methods)
+      .filter_map(|(t, is_static)| {
+        let (kind, method) = match t.kind {
+          TraitKind::Method { method, .. } => (MethodKind::Method, method),
+          TraitKind::Getter { method, .. } => (MethodKind::Getter, method),
+          TraitKind::Setter { method, .. } => (MethodKind::Setter, method),
+          _ => return None,
+        };
+        let info = abc.methods.get(usize::try_from(method).ok()?)?;
+        Some(ClassMethod {
+          name: pool.get_qualified_name(t.name)?,
+          kind,
+          is_static,
+          parameter_types: info.parameter_types.iter().map(|t| resolve_type(abc, *t)).collect(),
+          return_type: resolve_type(abc, info.return_type),
+          method,
+        })
+      })
+      .collect();
+    classes.push(AbcClass {
+      name,
+      super_name,
+      interfaces,
+      is_interface: instance.is_interface,
+      methods,
+      tag_index,
+      class_index,
+    });
+  }
+  classes
+}
+
+/// Resolves a type multiname, `0` is the "any" type `*`.
+fn resolve_type(abc: &AbcFile, type_name: u32) -> String {
+  match type_name {
+    0 => String::from("*"),
+    type_name => abc
+      .constant_pool
+      .get_qualified_name(type_name)
+      .unwrap_or_else(|| String::from("*")),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// `package foo { class Bar { function baz(x: int): void; static function create(): Bar; } }`
+  const BAR_ABC: &[u8] = &[
+    0x10, 0x00, 0x2e, 0x00, 0x00, 0x00, 0x00, //
+    // Strings: ["foo", "Bar", "baz", "int", "void", "", "create"]
+    0x08, 0x03, b'f', b'o', b'o', 0x03, b'B', b'a', b'r', 0x03, b'b', b'a', b'z', 0x03, b'i', b'n', b't', 0x04, b'v',
+    b'o', b'i', b'd', 0x00, 0x06, b'c', b'r', b'e', b'a', b't', b'e', //
+    0x03, 0x16, 0x01, 0x16, 0x06, // namespaces: [package "foo", package ""]
+    0x00, // namespace sets
+    0x06, 0x07, 0x01, 0x02, 0x07, 0x02, 0x03, 0x07, 0x02, 0x04, 0x07, 0x02, 0x05, 0x07, 0x02, 0x07, //
+    // Methods: class initializer, instance initializer, `baz`, `create`
+    0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x04, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, //
+    0x00, // metadata
+    0x01, // 1 class
+    0x01, 0x00, 0x00, 0x00, 0x01, 0x01, 0x02, 0x01, 0x01, 0x02, // instance
+    0x00, 0x01, 0x05, 0x01, 0x01, 0x03, // class
+    0x00, 0x00, // scripts, method bodies
+  ];
+
+  /// `class Baz extends foo.Bar {}`, referencing `foo.Bar` with a namespace set.
+  const BAZ_ABC: &[u8] = &[
+    0x10, 0x00, 0x2e, 0x00, 0x00, 0x00, 0x00, //
+    0x05, 0x03, b'f', b'o', b'o', 0x03, b'B', b'a', b'r', 0x03, b'B', b'a', b'z', 0x00, // strings
+    0x03, 0x16, 0x01, 0x16, 0x04, // namespaces: [package "foo", package ""]
+    0x02, 0x01, 0x01, // namespace sets: [[package "foo"]]
+    0x03, 0x09, 0x02, 0x01, 0x07, 0x02, 0x03, // multinames: [{foo}::Bar, Baz]
+    0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // methods
+    0x00, 0x01, // metadata, 1 class
+    0x02, 0x01, 0x01, 0x00, 0x00, 0x00, // instance
+    0x01, 0x00, // class
+    0x00, 0x00, // scripts, method bodies
+  ];
+
+  fn do_abc(data: &[u8]) -> ast::Tag {
+    ast::Tag::DoAbc(ast::tags::DoAbc {
+      header: None,
+      data: data.to_vec(),
+    })
+  }
+
+  #[test]
+  fn test_link_symbol_classes() {
+    let tags = vec![
+      do_abc(BAR_ABC),
+      do_abc(BAZ_ABC),
+      ast::Tag::SymbolClass(ast::tags::SymbolClass {
+        symbols: vec![
+          ast::NamedId {
+            id: 42,
+            name: String::from("Baz"),
+          },
+          ast::NamedId {
+            id: 43,
+            name: String::from("foo.Bar"),
+          },
+          ast::NamedId {
+            id: 44,
+            name: String::from("Missing"),
+          },
+        ],
+      }),
+    ];
+    let (links, errors) = link_symbol_classes(&tags);
+    assert_eq!(errors, vec![]);
+    assert_eq!(links.len(), 3);
+    assert_eq!(
+      links[0].class,
+      Some(AbcClass {
+        name: String::from("Baz"),
+        super_name: Some(String::from("foo.Bar")),
+        interfaces: vec![],
+        is_interface: false,
+        methods: vec![],
+        tag_index: 1,
+        class_index: 0,
+      })
+    );
+    let bar = links[1].class.as_ref().unwrap();
+    assert_eq!((bar.name.as_str(), bar.super_name.as_ref(), bar.tag_index), ("foo.Bar", None, 0));
+    assert_eq!(
+      bar.methods,
+      vec![
+        ClassMethod {
+          name: String::from("baz"),
+          kind: MethodKind::Method,
+          is_static: false,
+          parameter_types: vec![String::from("int")],
+          return_type: String::from("void"),
+          method: 2,
+        },
+        ClassMethod {
+          name: String::from("create"),
+          kind: MethodKind::Method,
+          is_static: true,
+          parameter_types: vec![],
+          return_type: String::from("foo.Bar"),
+          method: 3,
+        },
+      ]
+    );
+    assert_eq!((links[2].id, links[2].class.as_ref()), (44, None));
+  }
+
+  #[test]
+  fn test_link_symbol_classes_invalid_abc() {
+    let tags = vec![
+      do_abc(&BAR_ABC[..10]),
+      do_abc(BAR_ABC),
+      ast::Tag::SymbolClass(ast::tags::SymbolClass {
+        symbols: vec![ast::NamedId {
+          id: 42,
+          name: String::from("foo.Bar"),
+        }],
+      }),
+    ];
+    let (links, errors) = link_symbol_classes(&tags);
+    assert_eq!(errors.iter().map(|(tag_index, _)| *tag_index).collect::<Vec<_>>(), vec![0]);
+    assert_eq!(errors[0].1.kind, nom::error::ErrorKind::Eof);
+    assert_eq!(links.len(), 1);
+    assert_eq!(links[0].class.as_ref().map(|class| class.tag_index), Some(1));
+  }
+}
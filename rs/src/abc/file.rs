@@ -0,0 +1,342 @@
+/// Parsed ABC file, the AVM2 bytecode of a `DoAbc` tag.
+///
+/// The items reference each other with indices in the constant pool or in the lists of the file, as in the ABC
+/// format. See [[ConstantPool]] for the constant pool indices.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AbcFile {
+  pub minor_version: u16,
+  pub major_version: u16,
+  pub constant_pool: ConstantPool,
+  pub methods: Vec<MethodInfo>,
+  pub metadata: Vec<Metadata>,
+  /// Instance side of each class, in the same order as `classes`.
+  pub instances: Vec<InstanceInfo>,
+  /// Static side of each class, in the same order as `instances`.
+  pub classes: Vec<ClassInfo>,
+  pub scripts: Vec<ScriptInfo>,
+  pub method_bodies: Vec<MethodBody>,
+}
+
+/// Constant pool of an ABC file.
+///
+/// The index `0` of each pool is reserved (for example, it is the "any" name for multinames) and is not stored:
+/// the index `i` refers to the item `i - 1` of the corresponding list. Use the getters to resolve indices.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct ConstantPool {
+  pub integers: Vec<i32>,
+  pub unsigned_integers: Vec<u32>,
+  pub doubles: Vec<f64>,
+  pub strings: Vec<String>,
+  pub namespaces: Vec<Namespace>,
+  /// Namespace indices of each namespace set.
+  pub namespace_sets: Vec<Vec<u32>>,
+  pub multinames: Vec<Multiname>,
+}
+
+impl ConstantPool {
+  pub fn get_integer(&self, index: u32) -> Option<i32> {
+    get_item(&self.integers, index).copied()
+  }
+
+  pub fn get_unsigned_integer(&self, index: u32) -> Option<u32> {
+    get_item(&self.unsigned_integers, index).copied()
+  }
+
+  pub fn get_double(&self, index: u32) -> Option<f64> {
+    get_item(&self.doubles, index).copied()
+  }
+
+  pub fn get_string(&self, index: u32) -> Option<&str> {
+    get_item(&self.strings, index).map(String::as_str)
+  }
+
+  pub fn get_namespace(&self, index: u32) -> Option<&Namespace> {
+    get_item(&self.namespaces, index)
+  }
+
+  pub fn get_namespace_set(&self, index: u32) -> Option<&[u32]> {
+    get_item(&self.namespace_sets, index).map(Vec::as_slice)
+  }
+
+  pub fn get_multiname(&self, index: u32) -> Option<&Multiname> {
+    get_item(&self.multinames, index)
+  }
+
+  /// Resolves a multiname to a name such as `flash.display.MovieClip`, the format used by `SymbolClass`.
+  ///
+  /// Names are qualified by their package: names in other namespaces are returned unqualified. A multiname with a
+  /// namespace set is qualified only if the set has a single namespace. Returns `None` for invalid indices and for
+  /// names resolved at runtime.
+  pub fn get_qualified_name(&self, index: u32) -> Option<String> {
+    self.resolve_name(index, 0)
+  }
+
+  /// Resolves a multiname, `depth` is the nesting level of type parameters (bounded to reject cyclic type names).
+  fn resolve_name(&self, index: u32, depth: usize) -> Option<String> {
+    const MAX_TYPE_NAME_DEPTH: usize = 16;
+    match self.get_multiname(index)? {
+      Multiname::QName { namespace, name, .. } => self.qualify(*namespace, *name),
+      Multiname::Multiname {
+        name, namespace_set, ..
+      } => match self.get_namespace_set(*namespace_set)? {
+        [namespace] => self.qualify(*namespace, *name),
+        _ => self.get_name_string(*name),
+      },
+      Multiname::RtqName { name, .. } => self.get_name_string(*name),
+      Multiname::RtqNameL { .. } | Multiname::MultinameL { .. } => None,
+      Multiname::TypeName { .. } if depth >= MAX_TYPE_NAME_DEPTH => None,
+      Multiname::TypeName { name, parameters } => {
+        let name = self.resolve_name(*name, depth + 1)?;
+        let parameters: Option<Vec<String>> = parameters
+          .iter()
+          .map(|parameter| match parameter {
+            0 => Some(String::from("*")),
+            parameter => self.resolve_name(*parameter, depth + 1),
+          })
+          .collect();
+        Some(format!("{}.<{}>", name, parameters?.join(", ")))
+      }
+    }
+  }
+
+  /// Returns the string of a name, `0` is the "any" name `*`.
+  fn get_name_string(&self, name: u32) -> Option<String> {
+    match name {
+      0 => Some(String::from("*")),
+      name => self.get_string(name).map(String::from),
+    }
+  }
+
+  fn qualify(&self, namespace: u32, name: u32) -> Option<String> {
+    let name = self.get_name_string(name)?;
+    let package = match self.get_namespace(namespace) {
+      Some(Namespace {
+        kind: NamespaceKind::Package | NamespaceKind::PackageInternal,
+        name: package,
+      }) => self.get_string(*package).unwrap_or(""),
+      _ => "",
+    };
+    if package.is_empty() {
+      Some(name)
+    } else {
+      Some(format!("{}.{}", package, name))
+    }
+  }
+}
+
+/// Returns the item of a constant pool, `index` `0` is reserved.
+fn get_item<T>(items: &[T], index: u32) -> Option<&T> {
+  let index = usize::try_from(index).ok()?.checked_sub(1)?;
+  items.get(index)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Namespace {
+  pub kind: NamespaceKind,
+  /// String index of the namespace name.
+  pub name: u32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NamespaceKind {
+  Namespace,
+  Package,
+  PackageInternal,
+  Protected,
+  Explicit,
+  StaticProtected,
+  Private,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Multiname {
+  /// Name qualified by a namespace.
+  QName {
+    /// Namespace index, `0` for any namespace.
+    namespace: u32,
+    /// String index, `0` for any name.
+    name: u32,
+    is_attribute: bool,
+  },
+  /// Name qualified by a namespace popped from the stack.
+  RtqName { name: u32, is_attribute: bool },
+  /// Name and namespace popped from the stack.
+  RtqNameL { is_attribute: bool },
+  /// Name qualified by any namespace of a set.
+  Multiname {
+    name: u32,
+    namespace_set: u32,
+    is_attribute: bool,
+  },
+  /// Name popped from the stack, qualified by any namespace of a set.
+  MultinameL { namespace_set: u32, is_attribute: bool },
+  /// Parameterized type, such as `Vector.<int>`.
+  TypeName {
+    /// Multiname index of the generic type.
+    name: u32,
+    /// Multiname indices of the type parameters.
+    parameters: Vec<u32>,
+  },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MethodInfo {
+  /// Multiname indices of the parameter types, `0` for any type.
+  pub parameter_types: Vec<u32>,
+  /// Multiname index of the return type, `0` for any type.
+  pub return_type: u32,
+  /// String index of the method name.
+  pub name: u32,
+  pub need_arguments: bool,
+  pub need_activation: bool,
+  pub need_rest: bool,
+  pub set_dxns: bool,
+  /// Default values of the last parameters.
+  pub optional_parameters: Vec<Value>,
+  /// String indices of the parameter names, if present.
+  pub parameter_names: Option<Vec<u32>>,
+}
+
+/// Constant value, used for optional parameters and slot values.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Value {
+  /// Integer index.
+  Int(u32),
+  /// Unsigned integer index.
+  Uint(u32),
+  /// Double index.
+  Double(u32),
+  /// String index.
+  String(u32),
+  True,
+  False,
+  Null,
+  Undefined,
+  /// Namespace index.
+  Namespace(NamespaceKind, u32),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Metadata {
+  /// String index of the metadata name.
+  pub name: u32,
+  pub items: Vec<MetadataItem>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MetadataItem {
+  /// String index of the key, `0` for keyless items.
+  pub key: u32,
+  /// String index of the value.
+  pub value: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstanceInfo {
+  /// Multiname index of the class name.
+  pub name: u32,
+  /// Multiname index of the super class, `0` for no super class.
+  pub super_name: u32,
+  pub is_sealed: bool,
+  pub is_final: bool,
+  pub is_interface: bool,
+  /// Namespace index of the protected namespace, if any.
+  pub protected_namespace: Option<u32>,
+  /// Multiname indices of the implemented interfaces.
+  pub interfaces: Vec<u32>,
+  /// Method index of the instance initializer (constructor).
+  pub initializer: u32,
+  pub traits: Vec<Trait>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClassInfo {
+  /// Method index of the static initializer.
+  pub initializer: u32,
+  pub traits: Vec<Trait>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptInfo {
+  /// Method index of the script initializer.
+  pub initializer: u32,
+  pub traits: Vec<Trait>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trait {
+  /// Multiname index of the trait name.
+  pub name: u32,
+  pub kind: TraitKind,
+  pub is_final: bool,
+  pub is_override: bool,
+  /// Metadata indices.
+  pub metadata: Vec<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraitKind {
+  Slot {
+    slot_id: u32,
+    /// Multiname index of the type, `0` for any type.
+    type_name: u32,
+    value: Option<Value>,
+  },
+  Const {
+    slot_id: u32,
+    /// Multiname index of the type, `0` for any type.
+    type_name: u32,
+    value: Option<Value>,
+  },
+  Method {
+    disp_id: u32,
+    method: u32,
+  },
+  Getter {
+    disp_id: u32,
+    method: u32,
+  },
+  Setter {
+    disp_id: u32,
+    method: u32,
+  },
+  Class {
+    slot_id: u32,
+    /// Class index.
+    class: u32,
+  },
+  Function {
+    slot_id: u32,
+    /// Method index.
+    function: u32,
+  },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MethodBody {
+  /// Method index.
+  pub method: u32,
+  pub max_stack: u32,
+  pub local_count: u32,
+  pub init_scope_depth: u32,
+  pub max_scope_depth: u32,
+  /// AVM2 bytecode.
+  pub code: Vec<u8>,
+  pub exceptions: Vec<Exception>,
+  pub traits: Vec<Trait>,
+}
+
+/// Exception handler of a method body.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Exception {
+  /// Start offset of the protected code.
+  pub from: u32,
+  /// End offset of the protected code.
+  pub to: u32,
+  /// Offset of the handler.
+  pub target: u32,
+  /// Multiname index of the caught type, `0` for any type.
+  pub type_name: u32,
+  /// Multiname index of the variable receiving the exception, `0` if there is none.
+  pub variable_name: u32,
+}
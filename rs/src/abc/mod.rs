@@ -0,0 +1,10 @@
+pub(crate) mod class;
+pub(crate) mod file;
+pub(crate) mod parser;
+
+pub use class::{abc_classes, link_symbol_classes, AbcClass, ClassMethod, MethodKind, SymbolClassLink};
+pub use file::{
+  AbcFile, ClassInfo, ConstantPool, Exception, InstanceInfo, Metadata, MetadataItem, MethodBody, MethodInfo,
+  Multiname, Namespace, NamespaceKind, ScriptInfo, Trait, TraitKind, Value,
+};
+pub use parser::{parse_abc, AbcParseError};
@@ -0,0 +1,632 @@
+use crate::abc::file::{
+  AbcFile, ClassInfo, ConstantPool, Exception, InstanceInfo, Metadata, MetadataItem, MethodBody, MethodInfo,
+  Multiname, Namespace, NamespaceKind, ScriptInfo, Trait, TraitKind, Value,
+};
+use crate::streaming::basic_data_types::parse_leb128_u32;
+use crate::streaming::error::{LocatedError, NomResult};
+use nom::number::complete::{le_f64 as parse_le_f64, le_u16 as parse_le_u16, le_u8 as parse_u8};
+use std::fmt;
+
+/// Error when parsing an ABC file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AbcParseError {
+  /// Offset of the failure in the ABC file.
+  pub offset: usize,
+  /// Kind of the `nom` parser that failed: `Eof` for truncated files, `Switch` for unknown kinds and
+  /// `Verify` for invalid strings.
+  pub kind: nom::error::ErrorKind,
+}
+
+impl std::error::Error for AbcParseError {}
+
+impl fmt::Display for AbcParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "invalid ABC file at offset {}: {:?}", self.offset, self.kind)
+  }
+}
+
+/// Parses an ABC file, such as the `data` of a `DoAbc` tag.
+///
+/// This function never panics.
+pub fn parse_abc(input: &[u8]) -> Result<AbcFile, AbcParseError> {
+  match parse_abc_file(input) {
+    Ok((_, abc)) => Ok(abc),
+    Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Err(AbcParseError {
+      offset: input.len() - e.input.len(),
+      kind: e.code,
+    }),
+    Err(nom::Err::Incomplete(_)) => Err(AbcParseError {
+      offset: input.len(),
+      kind: nom::error::ErrorKind::Eof,
+    }),
+  }
+}
+
+pub(crate) fn parse_abc_file(input: &[u8]) -> NomResult<&[u8], AbcFile> {
+  let (input, minor_version) = parse_le_u16(input)?;
+  let (input, major_version) = parse_le_u16(input)?;
+  let (input, constant_pool) = parse_constant_pool(input)?;
+  let (input, methods) = parse_list(input, parse_method_info)?;
+  let (input, metadata) = parse_list(input, parse_metadata)?;
+  let (input, class_count) = parse_u30(input)?;
+  let (input, instances) = parse_count(input, parse_instance_info, class_count)?;
+  let (input, classes) = parse_count(input, parse_class_info, class_count)?;
+  let (input, scripts) = parse_list(input, parse_script_info)?;
+  let (input, method_bodies) = parse_list(input, parse_method_body)?;
+  Ok((
+    input,
+    AbcFile {
+      minor_version,
+      major_version,
+      constant_pool,
+      methods,
+      metadata,
+      instances,
+      classes,
+      scripts,
+      method_bodies,
+    },
+  ))
+}
+
+/// Parses a variable-length `u30`, as a `usize` for counts.
+fn parse_u30(input: &[u8]) -> NomResult<&[u8], usize> {
+  nom::combinator::map(parse_leb128_u32, |x| x as usize)(input)
+}
+
+/// Parses a variable-length `s32`: the last bit read is the sign bit.
+fn parse_leb128_i32(input: &[u8]) -> NomResult<&[u8], i32> {
+  let (remaining, value) = parse_leb128_u32(input)?;
+  let bit_count = 7 * (input.len() - remaining.len());
+  let value = if bit_count < 32 {
+    let shift = 32 - bit_count;
+    ((value as i32) << shift) >> shift
+  } else {
+    value as i32
+  };
+  Ok((remaining, value))
+}
+
+/// Parses `item_count` items.
+///
+/// Unlike `nom::multi::count`, the capacity is bounded by the input length: each item uses at least one byte,
+/// so an untrusted count can't allocate more than the input.
+fn parse_count<'a, O, F>(mut input: &'a [u8], mut parser: F, item_count: usize) -> NomResult<&'a [u8], Vec<O>>
+where
+  F: FnMut(&'a [u8]) -> NomResult<&'a [u8], O>,
+{
+  let mut items: Vec<O> = Vec::with_capacity(item_count.min(input.len()));
+  for _ in 0..item_count {
+    let (next_input, item) = parser(input)?;
+    items.push(item);
+    input = next_input;
+  }
+  Ok((input, items))
+}
+
+/// Parses a `u30` item count followed by the items.
+fn parse_list<'a, O, F>(input: &'a [u8], parser: F) -> NomResult<&'a [u8], Vec<O>>
+where
+  F: FnMut(&'a [u8]) -> NomResult<&'a [u8], O>,
+{
+  let (input, item_count) = parse_u30(input)?;
+  parse_count(input, parser, item_count)
+}
+
+/// Parses a constant pool list, the count includes the reserved index `0`.
+fn parse_pool<'a, O, F>(input: &'a [u8], parser: F) -> NomResult<&'a [u8], Vec<O>>
+where
+  F: FnMut(&'a [u8]) -> NomResult<&'a [u8], O>,
+{
+  let (input, item_count) = parse_u30(input)?;
+  parse_count(input, parser, item_count.saturating_sub(1))
+}
+
+fn parse_constant_pool(input: &[u8]) -> NomResult<&[u8], ConstantPool> {
+  let (input, integers) = parse_pool(input, parse_leb128_i32)?;
+  let (input, unsigned_integers) = parse_pool(input, parse_leb128_u32)?;
+  let (input, doubles) = parse_pool(input, parse_le_f64)?;
+  let (input, strings) = parse_pool(input, parse_string)?;
+  let (input, namespaces) = parse_pool(input, parse_namespace)?;
+  let (input, namespace_sets) = parse_pool(input, |i| parse_list(i, parse_leb128_u32))?;
+  let (input, multinames) = parse_pool(input, parse_multiname)?;
+  Ok((
+    input,
+    ConstantPool {
+      integers,
+      unsigned_integers,
+      doubles,
+      strings,
+      namespaces,
+      namespace_sets,
+      multinames,
+    },
+  ))
+}
+
+fn parse_string(input: &[u8]) -> NomResult<&[u8], String> {
+  let (input, len) = parse_u30(input)?;
+  let (remaining, raw) = nom::bytes::complete::take(len)(input)?;
+  match std::str::from_utf8(raw) {
+    Ok(checked) => Ok((remaining, checked.to_string())),
+    Err(_) => Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Verify))),
+  }
+}
+
+fn namespace_kind_from_code(code: u8) -> Result<NamespaceKind, ()> {
+  match code {
+    0x05 => Ok(NamespaceKind::Private),
+    0x08 => Ok(NamespaceKind::Namespace),
+    0x16 => Ok(NamespaceKind::Package),
+    0x17 => Ok(NamespaceKind::PackageInternal),
+    0x18 => Ok(NamespaceKind::Protected),
+    0x19 => Ok(NamespaceKind::Explicit),
+    0x1a => Ok(NamespaceKind::StaticProtected),
+    _ => Err(()),
+  }
+}
+
+fn parse_namespace(input: &[u8]) -> NomResult<&[u8], Namespace> {
+  let (remaining, code) = parse_u8(input)?;
+  let kind = namespace_kind_from_code(code)
+    .map_err(|_| nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch)))?;
+  let (remaining, name) = parse_leb128_u32(remaining)?;
+  Ok((remaining, Namespace { kind, name }))
+}
+
+fn parse_multiname(input: &[u8]) -> NomResult<&[u8], Multiname> {
+  let (remaining, code) = parse_u8(input)?;
+  match code {
+    0x07 | 0x0d => {
+      let (remaining, namespace) = parse_leb128_u32(remaining)?;
+      let (remaining, name) = parse_leb128_u32(remaining)?;
+      let is_attribute = code == 0x0d;
+      Ok((remaining, Multiname::QName { namespace, name, is_attribute }))
+    }
+    0x0f | 0x10 => {
+      let (remaining, name) = parse_leb128_u32(remaining)?;
+      let is_attribute = code == 0x10;
+      Ok((remaining, Multiname::RtqName { name, is_attribute }))
+    }
+    0x11 | 0x12 => Ok((remaining, Multiname::RtqNameL { is_attribute: code == 0x12 })),
+    0x09 | 0x0e => {
+      let (remaining, name) = parse_leb128_u32(remaining)?;
+      let (remaining, namespace_set) = parse_leb128_u32(remaining)?;
+      let is_attribute = code == 0x0e;
+      Ok((
+        remaining,
+        Multiname::Multiname {
+          name,
+          namespace_set,
+          is_attribute,
+        },
+      ))
+    }
+    0x1b | 0x1c => {
+      let (remaining, namespace_set) = parse_leb128_u32(remaining)?;
+      let is_attribute = code == 0x1c;
+      Ok((remaining, Multiname::MultinameL { namespace_set, is_attribute }))
+    }
+    0x1d => {
+      let (remaining, name) = parse_leb128_u32(remaining)?;
+      let (remaining, parameters) = parse_list(remaining, parse_leb128_u32)?;
+      Ok((remaining, Multiname::TypeName { name, parameters }))
+    }
+    _ => Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch))),
+  }
+}
+
+fn parse_method_info(input: &[u8]) -> NomResult<&[u8], MethodInfo> {
+  let (input, parameter_count) = parse_u30(input)?;
+  let (input, return_type) = parse_leb128_u32(input)?;
+  let (input, parameter_types) = parse_count(input, parse_leb128_u32, parameter_count)?;
+  let (input, name) = parse_leb128_u32(input)?;
+  let (input, flags) = parse_u8(input)?;
+  let need_arguments = (flags & (1 << 0)) != 0;
+  let need_activation = (flags & (1 << 1)) != 0;
+  let need_rest = (flags & (1 << 2)) != 0;
+  let has_optional = (flags & (1 << 3)) != 0;
+  // Skip bits [4, 5]
+  let set_dxns = (flags & (1 << 6)) != 0;
+  let has_parameter_names = (flags & (1 << 7)) != 0;
+  let (input, optional_parameters) = if has_optional {
+    parse_list(input, parse_value)?
+  } else {
+    (input, Vec::new())
+  };
+  let (input, parameter_names) = if has_parameter_names {
+    let (input, parameter_names) = parse_count(input, parse_leb128_u32, parameter_count)?;
+    (input, Some(parameter_names))
+  } else {
+    (input, None)
+  };
+  Ok((
+    input,
+    MethodInfo {
+      parameter_types,
+      return_type,
+      name,
+      need_arguments,
+      need_activation,
+      need_rest,
+      set_dxns,
+      optional_parameters,
+      parameter_names,
+    },
+  ))
+}
+
+/// Parses a value index followed by its kind.
+fn parse_value(input: &[u8]) -> NomResult<&[u8], Value> {
+  let (input, index) = parse_leb128_u32(input)?;
+  parse_value_kind(input, index)
+}
+
+/// Parses the kind of a value with the provided `index`.
+fn parse_value_kind(input: &[u8], index: u32) -> NomResult<&[u8], Value> {
+  let (remaining, code) = parse_u8(input)?;
+  let value = match code {
+    0x00 => Value::Undefined,
+    0x01 => Value::String(index),
+    0x03 => Value::Int(index),
+    0x04 => Value::Uint(index),
+    0x06 => Value::Double(index),
+    0x0a => Value::False,
+    0x0b => Value::True,
+    0x0c => Value::Null,
+    code => match namespace_kind_from_code(code) {
+      Ok(kind) => Value::Namespace(kind, index),
+      Err(()) => return Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch))),
+    },
+  };
+  Ok((remaining, value))
+}
+
+fn parse_metadata(input: &[u8]) -> NomResult<&[u8], Metadata> {
+  let (input, name) = parse_leb128_u32(input)?;
+  let (input, item_count) = parse_u30(input)?;
+  // The items are stored as the list of keys followed by the list of values
+  let (input, keys) = parse_count(input, parse_leb128_u32, item_count)?;
+  let (input, values) = parse_count(input, parse_leb128_u32, item_count)?;
+  let items = keys
+    .into_iter()
+    .zip(values)
+    .map(|(key, value)| MetadataItem { key, value })
+    .collect();
+  Ok((input, Metadata { name, items }))
+}
+
+fn parse_instance_info(input: &[u8]) -> NomResult<&[u8], InstanceInfo> {
+  let (input, name) = parse_leb128_u32(input)?;
+  let (input, super_name) = parse_leb128_u32(input)?;
+  let (input, flags) = parse_u8(input)?;
+  let is_sealed = (flags & (1 << 0)) != 0;
+  let is_final = (flags & (1 << 1)) != 0;
+  let is_interface = (flags & (1 << 2)) != 0;
+  let has_protected_namespace = (flags & (1 << 3)) != 0;
+  // Skip bits [4, 7]
+  let (input, protected_namespace) = if has_protected_namespace {
+    let (input, protected_namespace) = parse_leb128_u32(input)?;
+    (input, Some(protected_namespace))
+  } else {
+    (input, None)
+  };
+  let (input, interfaces) = parse_list(input, parse_leb128_u32)?;
+  let (input, initializer) = parse_leb128_u32(input)?;
+  let (input, traits) = parse_list(input, parse_trait)?;
+  Ok((
+    input,
+    InstanceInfo {
+      name,
+      super_name,
+      is_sealed,
+      is_final,
+      is_interface,
+      protected_namespace,
+      interfaces,
+      initializer,
+      traits,
+    },
+  ))
+}
+
+fn parse_class_info(input: &[u8]) -> NomResult<&[u8], ClassInfo> {
+  let (input, initializer) = parse_leb128_u32(input)?;
+  let (input, traits) = parse_list(input, parse_trait)?;
+  Ok((input, ClassInfo { initializer, traits }))
+}
+
+fn parse_script_info(input: &[u8]) -> NomResult<&[u8], ScriptInfo> {
+  let (input, initializer) = parse_leb128_u32(input)?;
+  let (input, traits) = parse_list(input, parse_trait)?;
+  Ok((input, ScriptInfo { initializer, traits }))
+}
+
+fn parse_trait(input: &[u8]) -> NomResult<&[u8], Trait> {
+  let (input, name) = parse_leb128_u32(input)?;
+  let kind_input = input;
+  let (input, flags) = parse_u8(input)?;
+  let is_final = (flags & (1 << 4)) != 0;
+  let is_override = (flags & (1 << 5)) != 0;
+  let has_metadata = (flags & (1 << 6)) != 0;
+  let (input, kind) = match flags & 0x0f {
+    kind_code @ (0 | 6) => {
+      let (input, slot_id) = parse_leb128_u32(input)?;
+      let (input, type_name) = parse_leb128_u32(input)?;
+      let (input, value_index) = parse_leb128_u32(input)?;
+      let (input, value) = if value_index == 0 {
+        (input, None)
+      } else {
+        let (input, value) = parse_value_kind(input, value_index)?;
+        (input, Some(value))
+      };
+      let kind = if kind_code == 0 {
+        TraitKind::Slot {
+          slot_id,
+          type_name,
+          value,
+        }
+      } else {
+        TraitKind::Const {
+          slot_id,
+          type_name,
+          value,
+        }
+      };
+      (input, kind)
+    }
+    kind_code @ 1..=3 => {
+      let (input, disp_id) = parse_leb128_u32(input)?;
+      let (input, method) = parse_leb128_u32(input)?;
+      let kind = match kind_code {
+        1 => TraitKind::Method { disp_id, method },
+        2 => TraitKind::Getter { disp_id, method },
+        _ => TraitKind::Setter { disp_id, method },
+      };
+      (input, kind)
+    }
+    4 => {
+      let (input, slot_id) = parse_leb128_u32(input)?;
+      let (input, class) = parse_leb128_u32(input)?;
+      (input, TraitKind::Class { slot_id, class })
+    }
+    5 => {
+      let (input, slot_id) = parse_leb128_u32(input)?;
+      let (input, function) = parse_leb128_u32(input)?;
+      (input, TraitKind::Function { slot_id, function })
+    }
+    _ => return Err(nom::Err::Error(LocatedError::new(kind_input, nom::error::ErrorKind::Switch))),
+  };
+  let (input, metadata) = if has_metadata {
+    parse_list(input, parse_leb128_u32)?
+  } else {
+    (input, Vec::new())
+  };
+  Ok((
+    input,
+    Trait {
+      name,
+      kind,
+      is_final,
+      is_override,
+      metadata,
+    },
+  ))
+}
+
+fn parse_method_body(input: &[u8]) -> NomResult<&[u8], MethodBody> {
+  let (input, method) = parse_leb128_u32(input)?;
+  let (input, max_stack) = parse_leb128_u32(input)?;
+  let (input, local_count) = parse_leb128_u32(input)?;
+  let (input, init_scope_depth) = parse_leb128_u32(input)?;
+  let (input, max_scope_depth) = parse_leb128_u32(input)?;
+  let (input, code_len) = parse_u30(input)?;
+  let (input, code) = nom::bytes::complete::take(code_len)(input)?;
+  let (input, exceptions) = parse_list(input, parse_exception)?;
+  let (input, traits) = parse_list(input, parse_trait)?;
+  Ok((
+    input,
+    MethodBody {
+      method,
+      max_stack,
+      local_count,
+      init_scope_depth,
+      max_scope_depth,
+      code: code.to_vec(),
+      exceptions,
+      traits,
+    },
+  ))
+}
+
+fn parse_exception(input: &[u8]) -> NomResult<&[u8], Exception> {
+  let (input, from) = parse_leb128_u32(input)?;
+  let (input, to) = parse_leb128_u32(input)?;
+  let (input, target) = parse_leb128_u32(input)?;
+  let (input, type_name) = parse_leb128_u32(input)?;
+  let (input, variable_name) = parse_leb128_u32(input)?;
+  Ok((
+    input,
+    Exception {
+      from,
+      to,
+      target,
+      type_name,
+      variable_name,
+    },
+  ))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_leb128_i32() {
+    assert_eq!(parse_leb128_i32(&[0x01]), Ok((&[][..], 1)));
+    assert_eq!(parse_leb128_i32(&[0x7f]), Ok((&[][..], -1)));
+    assert_eq!(parse_leb128_i32(&[0x80, 0x7f]), Ok((&[][..], -128)));
+    assert_eq!(parse_leb128_i32(&[0xff, 0xff, 0xff, 0xff, 0x0f]), Ok((&[][..], -1)));
+    assert_eq!(parse_leb128_i32(&[0xff, 0xff, 0xff, 0xff, 0x07]), Ok((&[][..], i32::MAX)));
+  }
+
+  /// Minimal ABC file declaring `package foo { public class Bar extends Object { public function baz() {} } }`
+  const BAR_ABC: &[u8] = &[
+    0x10, 0x00, 0x2e, 0x00, // minor version 16, major version 46
+    // Constant pool
+    0x02, 0x2a, // integers: [42]
+    0x00, // unsigned integers
+    0x00, // doubles
+    0x05, 0x03, b'f', b'o', b'o', 0x03, b'B', b'a', b'r', 0x03, b'b', b'a', b'z', 0x06, b'O', b'b', b'j', b'e', b'c',
+    b't', // strings: ["foo", "Bar", "baz", "Object"]
+    0x03, 0x16, 0x01, 0x16, 0x00, // namespaces: [package "foo", package ""]
+    0x00, // namespace sets
+    0x04, 0x07, 0x01, 0x02, 0x07, 0x01, 0x03, 0x07, 0x02, 0x04, // multinames: [foo.Bar, foo.baz, Object]
+    // Methods: class initializer, instance initializer, `baz`
+    0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, //
+    0x01, // metadata: [Bar(key="baz")]
+    0x02, 0x01, 0x03, 0x03, //
+    0x01, // 1 class
+    // Instance: foo.Bar extends Object, sealed, method `baz`
+    0x01, 0x03, 0x01, 0x00, 0x01, 0x01, 0x02, 0x41, 0x01, 0x02, 0x01, 0x00, //
+    // Class
+    0x00, 0x00, //
+    // Script: class trait `foo.Bar`
+    0x01, 0x00, 0x01, 0x01, 0x04, 0x01, 0x00, //
+    // Method body of `baz`, with a catch-all exception handler
+    0x01, 0x02, 0x01, 0x01, 0x00, 0x01, 0x02, 0x47, 0x47, 0x01, 0x00, 0x01, 0x01, 0x00, 0x00, 0x00,
+  ];
+
+  #[test]
+  fn test_parse_abc() {
+    let abc = parse_abc(BAR_ABC).unwrap();
+    assert_eq!((abc.minor_version, abc.major_version), (16, 46));
+    let pool = &abc.constant_pool;
+    assert_eq!(pool.integers, vec![42]);
+    assert_eq!(pool.strings, vec!["foo", "Bar", "baz", "Object"]);
+    assert_eq!(pool.get_string(0), None);
+    assert_eq!(pool.get_string(2), Some("Bar"));
+    assert_eq!(
+      pool.get_namespace(1),
+      Some(&Namespace {
+        kind: NamespaceKind::Package,
+        name: 1,
+      })
+    );
+    assert_eq!(
+      pool.get_multiname(3),
+      Some(&Multiname::QName {
+        namespace: 2,
+        name: 4,
+        is_attribute: false,
+      })
+    );
+    assert_eq!(abc.methods.len(), 3);
+    assert_eq!(abc.methods[2].name, 3);
+    assert_eq!(
+      abc.metadata,
+      vec![Metadata {
+        name: 2,
+        items: vec![MetadataItem { key: 3, value: 3 }],
+      }]
+    );
+    assert_eq!(
+      abc.instances,
+      vec![InstanceInfo {
+        name: 1,
+        super_name: 3,
+        is_sealed: true,
+        is_final: false,
+        is_interface: false,
+        protected_namespace: None,
+        interfaces: vec![],
+        initializer: 1,
+        traits: vec![Trait {
+          name: 2,
+          kind: TraitKind::Method { disp_id: 1, method: 2 },
+          is_final: false,
+          is_override: false,
+          metadata: vec![0],
+        }],
+      }]
+    );
+    assert_eq!(
+      abc.classes,
+      vec![ClassInfo {
+        initializer: 0,
+        traits: vec![],
+      }]
+    );
+    assert_eq!(
+      abc.scripts[0].traits[0].kind,
+      TraitKind::Class { slot_id: 1, class: 0 }
+    );
+    assert_eq!(
+      abc.method_bodies,
+      vec![MethodBody {
+        method: 2,
+        max_stack: 1,
+        local_count: 1,
+        init_scope_depth: 0,
+        max_scope_depth: 1,
+        code: vec![0x47, 0x47],
+        exceptions: vec![Exception {
+          from: 0,
+          to: 1,
+          target: 1,
+          type_name: 0,
+          variable_name: 0,
+        }],
+        traits: vec![],
+      }]
+    );
+  }
+
+  #[test]
+  fn test_parse_abc_truncated() {
+    assert_eq!(
+      parse_abc(&BAR_ABC[..20]),
+      Err(AbcParseError {
+        offset: 18,
+        kind: nom::error::ErrorKind::Eof,
+      })
+    );
+  }
+
+  #[test]
+  fn test_parse_abc_huge_counts() {
+    // Huge `u30` counts (`0x3fffffff`) with a tiny body fail without allocating for each item
+    let huge_count: &[u8] = &[0xff, 0xff, 0xff, 0xff, 0x03];
+    let empty_pool: &[u8] = &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    let inputs: Vec<Vec<u8>> = vec![
+      // Integer pool
+      [&[0x10, 0x00, 0x2e, 0x00][..], huge_count, &[0x01]].concat(),
+      // Method list
+      [&[0x10, 0x00, 0x2e, 0x00][..], empty_pool, huge_count, &[0x00]].concat(),
+      // Method parameter types
+      [&[0x10, 0x00, 0x2e, 0x00][..], empty_pool, &[0x01], huge_count, &[0x00]].concat(),
+      // Class list
+      [&[0x10, 0x00, 0x2e, 0x00][..], empty_pool, &[0x00, 0x00], huge_count, &[0x00]].concat(),
+    ];
+    for input in inputs {
+      assert_eq!(
+        parse_abc(&input).map_err(|e| e.kind),
+        Err(nom::error::ErrorKind::Eof),
+        "{:?}",
+        input
+      );
+    }
+  }
+
+  #[test]
+  fn test_parse_abc_invalid_multiname() {
+    let input: &[u8] = &[0x10, 0x00, 0x2e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0xff];
+    assert_eq!(
+      parse_abc(input),
+      Err(AbcParseError {
+        offset: 11,
+        kind: nom::error::ErrorKind::Switch,
+      })
+    );
+  }
+}
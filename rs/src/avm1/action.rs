@@ -0,0 +1,260 @@
+use std::ops::Range;
+
+/// Action with its location in the action string.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActionRecord {
+  /// Offset of the action (its code) in the action string.
+  pub offset: usize,
+  /// Length of the action, including its code and length header.
+  pub len: usize,
+  pub action: Action,
+}
+
+impl ActionRecord {
+  /// Byte range of the action in the action string.
+  pub fn range(&self) -> Range<usize> {
+    self.offset..(self.offset + self.len)
+  }
+}
+
+/// AVM1 action.
+///
+/// Offsets (jump targets and the ranges of code blocks) are resolved relative to the start of the action string.
+/// Actions are named after their documentation in `docs/avm1/actions`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+  /// Action code `0x00`, ending the action string.
+  End,
+
+  NextFrame,
+  PreviousFrame,
+  Play,
+  Stop,
+  ToggleQuality,
+  StopSounds,
+  Add,
+  Subtract,
+  Multiply,
+  Divide,
+  Equals,
+  Less,
+  And,
+  Or,
+  Not,
+  StringEquals,
+  StringLength,
+  StringExtract,
+  Pop,
+  ToInteger,
+  GetVariable,
+  SetVariable,
+  SetTarget2,
+  StringAdd,
+  GetProperty,
+  SetProperty,
+  CloneSprite,
+  RemoveSprite,
+  Trace,
+  StartDrag,
+  EndDrag,
+  StringLess,
+  Throw,
+  CastOp,
+  ImplementsOp,
+  FsCommand2,
+  RandomNumber,
+  MbStringLength,
+  CharToAscii,
+  AsciiToChar,
+  GetTime,
+  MbStringExtract,
+  MbCharToAscii,
+  MbAsciiToChar,
+  Delete,
+  Delete2,
+  DefineLocal,
+  CallFunction,
+  Return,
+  Modulo,
+  NewObject,
+  DefineLocal2,
+  InitArray,
+  InitObject,
+  TypeOf,
+  TargetPath,
+  Enumerate,
+  Add2,
+  Less2,
+  Equals2,
+  ToNumber,
+  ToString,
+  PushDuplicate,
+  StackSwap,
+  GetMember,
+  SetMember,
+  Increment,
+  Decrement,
+  CallMethod,
+  NewMethod,
+  InstanceOf,
+  Enumerate2,
+  BitAnd,
+  BitOr,
+  BitXor,
+  BitLShift,
+  BitRShift,
+  BitURShift,
+  StrictEquals,
+  Greater,
+  StringGreater,
+  Extends,
+
+  GotoFrame {
+    frame: u16,
+  },
+  GetUrl {
+    url: String,
+    target: String,
+  },
+  StoreRegister {
+    register: u8,
+  },
+  ConstantPool {
+    pool: Vec<String>,
+  },
+  StrictMode {
+    is_strict: bool,
+  },
+  WaitForFrame {
+    frame: u16,
+    /// Number of actions to skip if the frame is not loaded.
+    skip_count: u8,
+  },
+  SetTarget {
+    target_name: String,
+  },
+  GotoLabel {
+    label: String,
+  },
+  WaitForFrame2 {
+    /// Number of actions to skip if the frame is not loaded.
+    skip_count: u8,
+  },
+  DefineFunction2(DefineFunction2),
+  Try(Try),
+  With {
+    /// Range of the `with` block.
+    body: Range<usize>,
+  },
+  Push {
+    values: Vec<PushValue>,
+  },
+  Jump {
+    /// Offset of the next action if the jump is taken.
+    target: usize,
+  },
+  GetUrl2 {
+    method: SendVarsMethod,
+    /// `true` if the target is a path to a sprite, `false` if it is a browser window.
+    load_target: bool,
+    load_variables: bool,
+  },
+  DefineFunction(DefineFunction),
+  If {
+    /// Offset of the next action if the condition is true.
+    target: usize,
+  },
+  Call,
+  GotoFrame2 {
+    play: bool,
+    /// Number added to the frame popped from the stack.
+    scene_bias: u16,
+  },
+
+  /// Action with an unknown code, or that could not be parsed.
+  Raw(RawAction),
+}
+
+/// Action kept as raw bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawAction {
+  pub code: u8,
+  /// Action data, following the action length (if any).
+  pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PushValue {
+  String(String),
+  Float32(f32),
+  Null,
+  Undefined,
+  Register(u8),
+  Boolean(bool),
+  Float64(f64),
+  Sint32(i32),
+  /// Index in the constant pool, encoded with 8 or 16 bits.
+  Constant(u16),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SendVarsMethod {
+  None,
+  Get,
+  Post,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DefineFunction {
+  /// Function name, empty for anonymous functions.
+  pub name: String,
+  pub parameters: Vec<String>,
+  /// Range of the function body.
+  pub body: Range<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DefineFunction2 {
+  /// Function name, empty for anonymous functions.
+  pub name: String,
+  /// Number of registers to allocate.
+  pub register_count: u8,
+  pub preload_parent: bool,
+  pub preload_root: bool,
+  pub suppress_super: bool,
+  pub preload_super: bool,
+  pub suppress_arguments: bool,
+  pub preload_arguments: bool,
+  pub suppress_this: bool,
+  pub preload_this: bool,
+  pub preload_global: bool,
+  pub parameters: Vec<Parameter>,
+  /// Range of the function body.
+  pub body: Range<usize>,
+}
+
+/// Parameter of a `DefineFunction2` action.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Parameter {
+  /// Register receiving the parameter, or `None` if it is stored in a variable.
+  pub register: Option<u8>,
+  pub name: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Try {
+  /// Range of the `try` block.
+  pub try_body: Range<usize>,
+  /// Range of the `catch` block, if any.
+  pub catch_body: Option<Range<usize>>,
+  /// Range of the `finally` block, if any.
+  pub finally_body: Option<Range<usize>>,
+  /// Where the caught value is stored.
+  pub catch_target: CatchTarget,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CatchTarget {
+  Variable(String),
+  Register(u8),
+}
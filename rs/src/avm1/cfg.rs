@@ -0,0 +1,164 @@
+use crate::avm1::action::{Action, ActionRecord};
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+/// Control-flow graph of an action string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ControlFlowGraph {
+  /// Basic blocks, ordered by offset.
+  pub blocks: Vec<BasicBlock>,
+}
+
+/// Sequence of actions executed in order, only the last action may branch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BasicBlock {
+  /// Offset of the first action of the block.
+  pub offset: usize,
+  /// Indices of the actions of the block, in the records passed to [[build_cfg]].
+  pub actions: Range<usize>,
+  /// Offsets of the blocks that may be executed after this one.
+  ///
+  /// Branch targets that are not the offset of an action are omitted.
+  pub successors: Vec<usize>,
+}
+
+impl ControlFlowGraph {
+  /// Returns the block starting at `offset`.
+  pub fn get_block(&self, offset: usize) -> Option<&BasicBlock> {
+    self
+      .blocks
+      .binary_search_by_key(&offset, |block| block.offset)
+      .ok()
+      .map(|index| &self.blocks[index])
+  }
+}
+
+/// Builds the control-flow graph of a parsed action string, see [[crate::avm1::parse_actions]].
+///
+/// Blocks are split at the targets of `Jump` and `If`, and after the actions ending the control flow:
+/// `Jump`, `If`, `Return`, `Throw` and `End`. The bodies of `DefineFunction` and `DefineFunction2` start new blocks
+/// without predecessors: the action defining a function is followed by the end of its body.
+pub fn build_cfg(records: &[ActionRecord]) -> ControlFlowGraph {
+  let offsets: BTreeSet<usize> = records.iter().map(|record| record.offset).collect();
+
+  let mut leaders: BTreeSet<usize> = BTreeSet::new();
+  if let Some(first) = records.first() {
+    leaders.insert(first.offset);
+  }
+  for record in records {
+    let next = record.offset + record.len;
+    match &record.action {
+      Action::Jump { target } | Action::If { target } => {
+        leaders.insert(*target);
+        leaders.insert(next);
+      }
+      Action::DefineFunction(action) => {
+        leaders.insert(action.body.start);
+        leaders.insert(action.body.end);
+        leaders.insert(next);
+      }
+      Action::DefineFunction2(action) => {
+        leaders.insert(action.body.start);
+        leaders.insert(action.body.end);
+        leaders.insert(next);
+      }
+      Action::Return | Action::Throw | Action::End => {
+        leaders.insert(next);
+      }
+      _ => {}
+    }
+  }
+  leaders.retain(|offset| offsets.contains(offset));
+
+  let mut blocks: Vec<BasicBlock> = Vec::new();
+  let mut start: usize = 0;
+  for (index, record) in records.iter().enumerate() {
+    let next = record.offset + record.len;
+    let is_last = index + 1 == records.len() || leaders.contains(&next);
+    if !is_last {
+      continue;
+    }
+    let successors: Vec<usize> = match &record.action {
+      Action::Jump { target } => vec![*target],
+      Action::If { target } => vec![next, *target],
+      Action::DefineFunction(action) => vec![action.body.end],
+      Action::DefineFunction2(action) => vec![action.body.end],
+      Action::Return | Action::Throw | Action::End => Vec::new(),
+      _ => vec![next],
+    };
+    let mut successors: Vec<usize> = successors.into_iter().filter(|offset| leaders.contains(offset)).collect();
+    successors.dedup();
+    blocks.push(BasicBlock {
+      offset: records[start].offset,
+      actions: start..(index + 1),
+      successors,
+    });
+    start = index + 1;
+  }
+  ControlFlowGraph { blocks }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::avm1::parse_actions;
+
+  #[test]
+  fn test_build_cfg_branches() {
+    let input: &[u8] = &[
+      0x96, 0x02, 0x00, 0x05, 0x01, // 0: Push true
+      0x9d, 0x02, 0x00, 0x06, 0x00, // 5: If +6 (16)
+      0x06, // 10: Play
+      0x99, 0x02, 0x00, 0x01, 0x00, // 11: Jump +1 (17)
+      0x07, // 16: Stop
+      0x00, // 17: End
+    ];
+    let records = parse_actions(input, 10);
+    let cfg = build_cfg(&records);
+    assert_eq!(
+      cfg.blocks,
+      vec![
+        BasicBlock {
+          offset: 0,
+          actions: 0..2,
+          successors: vec![10, 16],
+        },
+        BasicBlock {
+          offset: 10,
+          actions: 2..4,
+          successors: vec![17],
+        },
+        BasicBlock {
+          offset: 16,
+          actions: 4..5,
+          successors: vec![17],
+        },
+        BasicBlock {
+          offset: 17,
+          actions: 5..6,
+          successors: vec![],
+        },
+      ]
+    );
+    assert_eq!(cfg.get_block(16).map(|block| block.actions.clone()), Some(4..5));
+    assert_eq!(cfg.get_block(11), None);
+  }
+
+  #[test]
+  fn test_build_cfg_function() {
+    let input: &[u8] = &[
+      0x9b, 0x06, 0x00, b'f', 0x00, 0x00, 0x00, 0x02, 0x00, // 0: DefineFunction f, body [9, 11)
+      0x17, 0x3e, // 9: Pop, Return
+      0x06, // 11: Play
+      0x00, // 12: End
+    ];
+    let records = parse_actions(input, 10);
+    let cfg = build_cfg(&records);
+    let blocks: Vec<(usize, Vec<usize>)> = cfg
+      .blocks
+      .iter()
+      .map(|block| (block.offset, block.successors.clone()))
+      .collect();
+    assert_eq!(blocks, vec![(0, vec![11]), (9, vec![]), (11, vec![])]);
+  }
+}
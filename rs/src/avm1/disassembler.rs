@@ -0,0 +1,353 @@
+use crate::avm1::action::{Action, ActionRecord, CatchTarget, PushValue, SendVarsMethod};
+use crate::avm1::parser::parse_actions;
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+/// Disassembles an action string into a textual listing.
+///
+/// Each action is written on its own line with its arguments, using the action names in lower camel case.
+/// Branch targets are replaced by labels, the constant pool indices pushed by `Push` are replaced by the
+/// values of the last `ConstantPool` action, and the bodies of functions, `With` and `Try` blocks are indented.
+/// The `End` action is omitted.
+pub fn disassemble(input: &[u8], swf_version: u8) -> String {
+  let records = parse_actions(input, swf_version);
+  let mut listing = String::new();
+  write_disassembly(&mut listing, &records).expect("Writing to a `String` never fails");
+  listing
+}
+
+/// Writes the listing of a parsed action string, see [[disassemble]].
+pub fn write_disassembly<W: Write>(writer: &mut W, records: &[ActionRecord]) -> fmt::Result {
+  let labels = get_labels(records);
+  let mut constants: &[String] = &[];
+  // Open blocks, innermost last: each block has a list of `(offset, line)` to write when reaching `offset`
+  let mut blocks: Vec<Vec<(usize, String)>> = Vec::new();
+  for record in records {
+    close_blocks(writer, &mut blocks, record.offset)?;
+    if let Some(label) = labels.get(&record.offset) {
+      writeln!(writer, "{}{}:", indent(blocks.len()), label)?;
+    }
+    if record.action == Action::End {
+      continue;
+    }
+    write!(writer, "{}", indent(blocks.len() + 1))?;
+    write_action(writer, &record.action, &labels, constants)?;
+    writeln!(writer)?;
+    match &record.action {
+      Action::ConstantPool { pool } => constants = pool,
+      Action::DefineFunction(action) => {
+        blocks.push(vec![(action.body.end, format!("end // of function {}", quote(&action.name)))]);
+      }
+      Action::DefineFunction2(action) => {
+        blocks.push(vec![(action.body.end, format!("end // of function {}", quote(&action.name)))]);
+      }
+      Action::With { body } => blocks.push(vec![(body.end, String::from("end // of with"))]),
+      Action::Try(action) => {
+        let mut lines: Vec<(usize, String)> = Vec::new();
+        if let Some(catch_body) = &action.catch_body {
+          let catch_target = match &action.catch_target {
+            CatchTarget::Variable(name) => quote(name),
+            CatchTarget::Register(register) => format!("r:{}", register),
+          };
+          lines.push((catch_body.start, format!("catch {}", catch_target)));
+        }
+        if let Some(finally_body) = &action.finally_body {
+          lines.push((finally_body.start, String::from("finally")));
+        }
+        let end = [Some(&action.try_body), action.catch_body.as_ref(), action.finally_body.as_ref()]
+          .iter()
+          .flatten()
+          .map(|body| body.end)
+          .max()
+          .unwrap_or(action.try_body.end);
+        lines.push((end, String::from("end // of try")));
+        blocks.push(lines);
+      }
+      _ => {}
+    }
+  }
+  let end = records.last().map_or(0, |record| record.offset + record.len);
+  close_blocks(writer, &mut blocks, end)?;
+  if let Some(label) = labels.get(&end) {
+    writeln!(writer, "{}:", label)?;
+  }
+  // Blocks extending past the last action
+  close_blocks(writer, &mut blocks, usize::MAX)
+}
+
+/// Writes the lines of the open blocks up to `offset`, closing the completed blocks.
+fn close_blocks<W: Write>(writer: &mut W, blocks: &mut Vec<Vec<(usize, String)>>, offset: usize) -> fmt::Result {
+  while let Some(lines) = blocks.last_mut() {
+    match lines.first() {
+      Some((line_offset, _)) if *line_offset <= offset => {
+        let (_, line) = lines.remove(0);
+        let is_closed = lines.is_empty();
+        if is_closed {
+          blocks.pop();
+          writeln!(writer, "{}{}", indent(blocks.len() + 1), line)?;
+        } else {
+          writeln!(writer, "{}{}", indent(blocks.len()), line)?;
+        }
+      }
+      _ => break,
+    }
+  }
+  Ok(())
+}
+
+/// Returns the label of each branch target, in the order of the targets.
+///
+/// Only the offsets of actions and the end of the action string get labels.
+fn get_labels(records: &[ActionRecord]) -> BTreeMap<usize, String> {
+  let end = records.last().map_or(0, |record| record.offset + record.len);
+  let mut targets: Vec<usize> = records
+    .iter()
+    .filter_map(|record| match &record.action {
+      Action::Jump { target } | Action::If { target } => Some(*target),
+      _ => None,
+    })
+    .filter(|target| *target == end || records.binary_search_by_key(target, |record| record.offset).is_ok())
+    .collect();
+  targets.sort_unstable();
+  targets.dedup();
+  targets
+    .into_iter()
+    .enumerate()
+    .map(|(index, target)| (target, format!("label{}", index + 1)))
+    .collect()
+}
+
+fn indent(depth: usize) -> String {
+  "  ".repeat(depth)
+}
+
+fn write_target<W: Write>(writer: &mut W, target: usize, labels: &BTreeMap<usize, String>) -> fmt::Result {
+  match labels.get(&target) {
+    Some(label) => writer.write_str(label),
+    None => write!(writer, "@{:#x}", target),
+  }
+}
+
+fn write_action<W: Write>(
+  writer: &mut W,
+  action: &Action,
+  labels: &BTreeMap<usize, String>,
+  constants: &[String],
+) -> fmt::Result {
+  match action {
+    Action::GotoFrame { frame } => write!(writer, "gotoFrame {}", frame),
+    Action::GetUrl { url, target } => write!(writer, "getUrl {}, {}", quote(url), quote(target)),
+    Action::StoreRegister { register } => write!(writer, "storeRegister r:{}", register),
+    Action::ConstantPool { pool } => {
+      writer.write_str("constantPool")?;
+      write_list(writer, pool.iter().map(|constant| quote(constant)))
+    }
+    Action::StrictMode { is_strict } => write!(writer, "strictMode {}", is_strict),
+    Action::WaitForFrame { frame, skip_count } => write!(writer, "waitForFrame {}, skip={}", frame, skip_count),
+    Action::SetTarget { target_name } => write!(writer, "setTarget {}", quote(target_name)),
+    Action::GotoLabel { label } => write!(writer, "gotoLabel {}", quote(label)),
+    Action::WaitForFrame2 { skip_count } => write!(writer, "waitForFrame2 skip={}", skip_count),
+    Action::DefineFunction2(action) => {
+      write!(writer, "defineFunction2 {} (", quote(&action.name))?;
+      for (index, parameter) in action.parameters.iter().enumerate() {
+        if index > 0 {
+          writer.write_str(", ")?;
+        }
+        if let Some(register) = parameter.register {
+          write!(writer, "r:{}=", register)?;
+        }
+        writer.write_str(&quote(&parameter.name))?;
+      }
+      write!(writer, ") registers={}", action.register_count)?;
+      let flags = [
+        (action.preload_parent, "preloadParent"),
+        (action.preload_root, "preloadRoot"),
+        (action.suppress_super, "suppressSuper"),
+        (action.preload_super, "preloadSuper"),
+        (action.suppress_arguments, "suppressArguments"),
+        (action.preload_arguments, "preloadArguments"),
+        (action.suppress_this, "suppressThis"),
+        (action.preload_this, "preloadThis"),
+        (action.preload_global, "preloadGlobal"),
+      ];
+      for (_, flag) in flags.iter().filter(|(is_set, _)| *is_set) {
+        write!(writer, " {}", flag)?;
+      }
+      Ok(())
+    }
+    Action::Try(_) => writer.write_str("try"),
+    Action::With { .. } => writer.write_str("with"),
+    Action::Push { values } => {
+      writer.write_str("push")?;
+      write_list(writer, values.iter().map(|value| format_push_value(value, constants)))
+    }
+    Action::Jump { target } => {
+      writer.write_str("jump ")?;
+      write_target(writer, *target, labels)
+    }
+    Action::GetUrl2 {
+      method,
+      load_target,
+      load_variables,
+    } => {
+      let method = match method {
+        SendVarsMethod::None => "none",
+        SendVarsMethod::Get => "get",
+        SendVarsMethod::Post => "post",
+      };
+      write!(
+        writer,
+        "getUrl2 method={}, loadTarget={}, loadVariables={}",
+        method, load_target, load_variables
+      )
+    }
+    Action::DefineFunction(action) => {
+      write!(writer, "defineFunction {} (", quote(&action.name))?;
+      let parameters: Vec<String> = action.parameters.iter().map(|parameter| quote(parameter)).collect();
+      write!(writer, "{})", parameters.join(", "))
+    }
+    Action::If { target } => {
+      writer.write_str("if ")?;
+      write_target(writer, *target, labels)
+    }
+    Action::GotoFrame2 { play, scene_bias } => write!(writer, "gotoFrame2 play={}, sceneBias={}", play, scene_bias),
+    Action::Raw(action) => {
+      write!(writer, "raw {:#04x}", action.code)?;
+      if !action.data.is_empty() {
+        writer.write_str(" [")?;
+        for (index, byte) in action.data.iter().enumerate() {
+          let separator = if index == 0 { "" } else { " " };
+          write!(writer, "{}{:02x}", separator, byte)?;
+        }
+        writer.write_str("]")?;
+      }
+      Ok(())
+    }
+    action => writer.write_str(&get_simple_action_name(action)),
+  }
+}
+
+/// Returns the name of an action without arguments, in lower camel case.
+fn get_simple_action_name(action: &Action) -> String {
+  // The `Debug` representation of a unit variant is its name
+  let name = format!("{:?}", action);
+  let mut chars = name.chars();
+  match chars.next() {
+    Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+    None => name,
+  }
+}
+
+fn write_list<W: Write, I: Iterator<Item = String>>(writer: &mut W, items: I) -> fmt::Result {
+  for (index, item) in items.enumerate() {
+    let separator = if index == 0 { " " } else { ", " };
+    write!(writer, "{}{}", separator, item)?;
+  }
+  Ok(())
+}
+
+fn format_push_value(value: &PushValue, constants: &[String]) -> String {
+  match value {
+    PushValue::String(value) => quote(value),
+    PushValue::Float32(value) => format!("{:?}f", value),
+    PushValue::Null => String::from("null"),
+    PushValue::Undefined => String::from("undefined"),
+    PushValue::Register(register) => format!("r:{}", register),
+    PushValue::Boolean(value) => value.to_string(),
+    PushValue::Float64(value) => format!("{:?}", value),
+    PushValue::Sint32(value) => value.to_string(),
+    PushValue::Constant(index) => match constants.get(usize::from(*index)) {
+      Some(constant) => quote(constant),
+      None => format!("c:{}", index),
+    },
+  }
+}
+
+/// Quotes a string with single quotes, escaping special characters.
+fn quote(value: &str) -> String {
+  let mut quoted = String::with_capacity(value.len() + 2);
+  quoted.push('\'');
+  for c in value.chars() {
+    match c {
+      '\'' => quoted.push_str("\\'"),
+      '\\' => quoted.push_str("\\\\"),
+      '\n' => quoted.push_str("\\n"),
+      '\r' => quoted.push_str("\\r"),
+      '\t' => quoted.push_str("\\t"),
+      c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", u32::from(c))),
+      c => quoted.push(c),
+    }
+  }
+  quoted.push('\'');
+  quoted
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_disassemble_branches() {
+    let input: &[u8] = &[
+      0x88, 0x06, 0x00, 0x02, 0x00, b'a', 0x00, b'b', 0x00, // 0: ConstantPool ["a", "b"]
+      0x96, 0x09, 0x00, 0x08, 0x01, 0x08, 0x05, 0x00, b'\'', 0x00, 0x05, 0x01, // 9: Push c:1, c:5, "'", true
+      0x9d, 0x02, 0x00, 0x06, 0x00, // 21: If +6 (32)
+      0x06, // 26: Play
+      0x99, 0x02, 0x00, 0x01, 0x00, // 27: Jump +1 (33)
+      0x07, // 32: Stop
+      0x00, // 33: End
+    ];
+    let expected = "  constantPool 'a', 'b'
+  push 'b', c:5, '\\'', true
+  if label1
+  play
+  jump label2
+label1:
+  stop
+label2:
+";
+    assert_eq!(disassemble(input, 10), expected);
+  }
+
+  #[test]
+  fn test_disassemble_blocks() {
+    let input: &[u8] = &[
+      0x8e, 0x0c, 0x00, b'f', 0x00, 0x01, 0x00, 0x02, 0x08, 0x00, 0x01, b'x', 0x00, 0x14, 0x00, // DefineFunction2
+      0x8f, 0x08, 0x00, 0x07, 0x06, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01, // Try, catch in r:1
+      0x94, 0x02, 0x00, 0x01, 0x00, // try: With
+      0x06, // with: Play
+      0x17, // catch: Pop
+      0x07, // finally: Stop
+      0x3e, // Return
+      0x00, // End
+    ];
+    let expected = "  defineFunction2 'f' (r:1='x') registers=2 suppressArguments
+    try
+      with
+        play
+      end // of with
+    catch r:1
+      pop
+    finally
+      stop
+    end // of try
+    return
+  end // of function 'f'
+";
+    assert_eq!(disassemble(input, 10), expected);
+  }
+
+  #[test]
+  fn test_disassemble_invalid() {
+    let input: &[u8] = &[
+      0x99, 0x02, 0x00, 0xfe, 0xff, // Jump -2, in the middle of the jump
+      0x01, // Unknown
+      0x96, 0x02, 0x00, 0x00, // Truncated
+    ];
+    let expected = "  jump @0x3
+  raw 0x01
+  raw 0x96 [00]
+";
+    assert_eq!(disassemble(input, 10), expected);
+  }
+}
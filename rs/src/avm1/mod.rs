@@ -0,0 +1,12 @@
+pub(crate) mod action;
+pub(crate) mod cfg;
+pub(crate) mod disassembler;
+pub(crate) mod parser;
+
+pub use action::{
+  Action, ActionRecord, CatchTarget, DefineFunction, DefineFunction2, Parameter, PushValue, RawAction,
+  SendVarsMethod, Try,
+};
+pub use cfg::{build_cfg, BasicBlock, ControlFlowGraph};
+pub use disassembler::{disassemble, write_disassembly};
+pub use parser::{parse_actions, parse_actions_with_legacy_encoding, tag_actions};
@@ -0,0 +1,699 @@
+use crate::avm1::action::{
+  Action, ActionRecord, CatchTarget, DefineFunction, DefineFunction2, Parameter, PushValue, RawAction,
+  SendVarsMethod, Try,
+};
+use crate::legacy::LegacyEncoding;
+use crate::streaming::basic_data_types::parse_swf_c_string;
+use crate::streaming::error::{LocatedError, NomResult};
+use nom::number::complete::{
+  le_f32 as parse_le_f32, le_i16 as parse_le_i16, le_i32 as parse_le_i32, le_u16 as parse_le_u16,
+  le_u32 as parse_le_u32, le_u8 as parse_u8,
+};
+use std::ops::Range;
+use swf_types as ast;
+
+/// Returns the action strings of a tag: `DoAction`, `DoInitAction`, the actions of each `DefineButton`
+/// condition and the clip actions of `PlaceObject`.
+pub fn tag_actions(tag: &ast::Tag) -> Vec<&[u8]> {
+  match tag {
+    ast::Tag::DoAction(tag) => vec![&tag.actions[..]],
+    ast::Tag::DoInitAction(tag) => vec![&tag.actions[..]],
+    ast::Tag::DefineButton(tag) => tag.actions.iter().map(|cond_action| &cond_action.actions[..]).collect(),
+    ast::Tag::PlaceObject(tag) => match &tag.clip_actions {
+      Some(clip_actions) => clip_actions.iter().map(|clip_action| &clip_action.actions[..]).collect(),
+      None => Vec::new(),
+    },
+    _ => Vec::new(),
+  }
+}
+
+/// Parses a string of actions, up to the `End` action (included) or the end of the input.
+///
+/// Actions with an unknown code or invalid data are kept as `Action::Raw`. A truncated last action is kept as
+/// `Action::Raw` with the available data.
+/// The strings of SWF 5 (and earlier) movies are decoded as Windows-1252, see [[parse_actions_with_legacy_encoding]].
+///
+/// This function never panics.
+pub fn parse_actions(input: &[u8], swf_version: u8) -> Vec<ActionRecord> {
+  parse_actions_with_legacy_encoding(input, swf_version, LegacyEncoding::default())
+}
+
+/// Parses a string of actions, decoding the strings of SWF 5 (and earlier) movies with `encoding`.
+///
+/// See [[parse_actions]]. This function never panics.
+pub fn parse_actions_with_legacy_encoding(
+  input: &[u8],
+  swf_version: u8,
+  encoding: LegacyEncoding,
+) -> Vec<ActionRecord> {
+  let mut records: Vec<ActionRecord> = Vec::new();
+  let mut offset: usize = 0;
+  while offset < input.len() {
+    let record = parse_action_record(input, offset, swf_version, encoding);
+    let is_end = record.action == Action::End;
+    offset += record.len;
+    records.push(record);
+    if is_end {
+      break;
+    }
+  }
+  records
+}
+
+/// Parses the action at `offset` in the action string `input`, `offset` must be in bounds.
+fn parse_action_record(input: &[u8], offset: usize, swf_version: u8, encoding: LegacyEncoding) -> ActionRecord {
+  let code = input[offset];
+  if code < 0x80 {
+    let action = parse_action_without_data(code).unwrap_or(Action::Raw(RawAction { code, data: Vec::new() }));
+    return ActionRecord { offset, len: 1, action };
+  }
+  // Actions with a code of `0x80` or more have a 16-bit data length
+  let data_offset = offset + 3;
+  let data = input
+    .get((offset + 1)..data_offset)
+    .map(|len| usize::from(u16::from_le_bytes([len[0], len[1]])))
+    .and_then(|data_len| input.get(data_offset..(data_offset + data_len)));
+  let data = match data {
+    Some(data) => data,
+    None => {
+      let action = Action::Raw(RawAction {
+        code,
+        data: input.get(data_offset..).unwrap_or(&[]).to_vec(),
+      });
+      return ActionRecord {
+        offset,
+        len: input.len() - offset,
+        action,
+      };
+    }
+  };
+  let end = data_offset + data.len();
+  let strings = Strings { swf_version, encoding };
+  let action = match parse_action_data(data, code, end, input.len(), strings) {
+    Ok((_, action)) => action,
+    Err(_) => Action::Raw(RawAction {
+      code,
+      data: data.to_vec(),
+    }),
+  };
+  ActionRecord {
+    offset,
+    len: end - offset,
+    action,
+  }
+}
+
+fn parse_action_without_data(code: u8) -> Option<Action> {
+  let action = match code {
+    0x00 => Action::End,
+    0x04 => Action::NextFrame,
+    0x05 => Action::PreviousFrame,
+    0x06 => Action::Play,
+    0x07 => Action::Stop,
+    0x08 => Action::ToggleQuality,
+    0x09 => Action::StopSounds,
+    0x0a => Action::Add,
+    0x0b => Action::Subtract,
+    0x0c => Action::Multiply,
+    0x0d => Action::Divide,
+    0x0e => Action::Equals,
+    0x0f => Action::Less,
+    0x10 => Action::And,
+    0x11 => Action::Or,
+    0x12 => Action::Not,
+    0x13 => Action::StringEquals,
+    0x14 => Action::StringLength,
+    0x15 => Action::StringExtract,
+    0x17 => Action::Pop,
+    0x18 => Action::ToInteger,
+    0x1c => Action::GetVariable,
+    0x1d => Action::SetVariable,
+    0x20 => Action::SetTarget2,
+    0x21 => Action::StringAdd,
+    0x22 => Action::GetProperty,
+    0x23 => Action::SetProperty,
+    0x24 => Action::CloneSprite,
+    0x25 => Action::RemoveSprite,
+    0x26 => Action::Trace,
+    0x27 => Action::StartDrag,
+    0x28 => Action::EndDrag,
+    0x29 => Action::StringLess,
+    0x2a => Action::Throw,
+    0x2b => Action::CastOp,
+    0x2c => Action::ImplementsOp,
+    0x2d => Action::FsCommand2,
+    0x30 => Action::RandomNumber,
+    0x31 => Action::MbStringLength,
+    0x32 => Action::CharToAscii,
+    0x33 => Action::AsciiToChar,
+    0x34 => Action::GetTime,
+    0x35 => Action::MbStringExtract,
+    0x36 => Action::MbCharToAscii,
+    0x37 => Action::MbAsciiToChar,
+    0x3a => Action::Delete,
+    0x3b => Action::Delete2,
+    0x3c => Action::DefineLocal,
+    0x3d => Action::CallFunction,
+    0x3e => Action::Return,
+    0x3f => Action::Modulo,
+    0x40 => Action::NewObject,
+    0x41 => Action::DefineLocal2,
+    0x42 => Action::InitArray,
+    0x43 => Action::InitObject,
+    0x44 => Action::TypeOf,
+    0x45 => Action::TargetPath,
+    0x46 => Action::Enumerate,
+    0x47 => Action::Add2,
+    0x48 => Action::Less2,
+    0x49 => Action::Equals2,
+    0x4a => Action::ToNumber,
+    0x4b => Action::ToString,
+    0x4c => Action::PushDuplicate,
+    0x4d => Action::StackSwap,
+    0x4e => Action::GetMember,
+    0x4f => Action::SetMember,
+    0x50 => Action::Increment,
+    0x51 => Action::Decrement,
+    0x52 => Action::CallMethod,
+    0x53 => Action::NewMethod,
+    0x54 => Action::InstanceOf,
+    0x55 => Action::Enumerate2,
+    0x60 => Action::BitAnd,
+    0x61 => Action::BitOr,
+    0x62 => Action::BitXor,
+    0x63 => Action::BitLShift,
+    0x64 => Action::BitRShift,
+    0x65 => Action::BitURShift,
+    0x66 => Action::StrictEquals,
+    0x67 => Action::Greater,
+    0x68 => Action::StringGreater,
+    0x69 => Action::Extends,
+    _ => return None,
+  };
+  Some(action)
+}
+
+/// Parses the data of an action with a code of `0x80` or more.
+///
+/// `end` is the offset of the next action and `len` the length of the action string: they are used to resolve
+/// the jump targets and code blocks.
+fn parse_action_data(input: &[u8], code: u8, end: usize, len: usize, strings: Strings) -> NomResult<&[u8], Action> {
+  use nom::combinator::map;
+  match code {
+    0x81 => map(parse_le_u16, |frame| Action::GotoFrame { frame })(input),
+    0x83 => {
+      let (input, url) = strings.parse(input)?;
+      let (input, target) = strings.parse(input)?;
+      Ok((input, Action::GetUrl { url, target }))
+    }
+    0x87 => map(parse_u8, |register| Action::StoreRegister { register })(input),
+    0x88 => parse_constant_pool(input, strings),
+    0x89 => map(parse_u8, |mode| Action::StrictMode { is_strict: mode != 0 })(input),
+    0x8a => {
+      let (input, frame) = parse_le_u16(input)?;
+      let (input, skip_count) = parse_u8(input)?;
+      Ok((input, Action::WaitForFrame { frame, skip_count }))
+    }
+    0x8b => map(|i| strings.parse(i), |target_name| Action::SetTarget { target_name })(input),
+    0x8c => map(|i| strings.parse(i), |label| Action::GotoLabel { label })(input),
+    0x8d => map(parse_u8, |skip_count| Action::WaitForFrame2 { skip_count })(input),
+    0x8e => map(|i| parse_define_function2(i, end, len, strings), Action::DefineFunction2)(input),
+    0x8f => map(|i| parse_try(i, end, len, strings), Action::Try)(input),
+    0x94 => {
+      let (input, size) = parse_le_u16(input)?;
+      let body = get_block(input, end, usize::from(size), len)?;
+      Ok((input, Action::With { body }))
+    }
+    0x96 => parse_push(input, strings),
+    0x99 => {
+      let (input, target) = parse_branch_target(input, end)?;
+      Ok((input, Action::Jump { target }))
+    }
+    0x9a => parse_get_url2(input),
+    0x9b => map(|i| parse_define_function(i, end, len, strings), Action::DefineFunction)(input),
+    0x9d => {
+      let (input, target) = parse_branch_target(input, end)?;
+      Ok((input, Action::If { target }))
+    }
+    0x9e => Ok((input, Action::Call)),
+    0x9f => parse_goto_frame2(input),
+    _ => Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch))),
+  }
+}
+
+/// Decoder of the action strings, which use the legacy encoding in SWF 5 (and earlier) movies.
+#[derive(Copy, Clone)]
+struct Strings {
+  swf_version: u8,
+  encoding: LegacyEncoding,
+}
+
+impl Strings {
+  fn parse(self, input: &[u8]) -> NomResult<&[u8], String> {
+    parse_swf_c_string(input, self.swf_version, self.encoding)
+  }
+}
+
+/// Parses a branch offset and resolves it relative to `end`, the offset of the next action.
+fn parse_branch_target(input: &[u8], end: usize) -> NomResult<&[u8], usize> {
+  let (remaining, branch_offset) = parse_le_i16(input)?;
+  match isize::try_from(end).ok().and_then(|end| usize::try_from(end + isize::from(branch_offset)).ok()) {
+    Some(target) => Ok((remaining, target)),
+    None => Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Verify))),
+  }
+}
+
+/// Returns the range of a code block of `size` bytes starting at `start`, if it fits in the action string.
+fn get_block(input: &[u8], start: usize, size: usize, len: usize) -> Result<Range<usize>, nom::Err<LocatedError<&[u8]>>> {
+  if start + size <= len {
+    Ok(start..(start + size))
+  } else {
+    Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Eof)))
+  }
+}
+
+fn parse_constant_pool(input: &[u8], strings: Strings) -> NomResult<&[u8], Action> {
+  use nom::multi::count;
+  let (input, constant_count) = parse_le_u16(input)?;
+  let (input, pool) = count(|i| strings.parse(i), usize::from(constant_count))(input)?;
+  Ok((input, Action::ConstantPool { pool }))
+}
+
+fn parse_push(mut input: &[u8], strings: Strings) -> NomResult<&[u8], Action> {
+  let mut values: Vec<PushValue> = Vec::new();
+  while !input.is_empty() {
+    let (next_input, value) = parse_push_value(input, strings)?;
+    values.push(value);
+    input = next_input;
+  }
+  Ok((input, Action::Push { values }))
+}
+
+fn parse_push_value(input: &[u8], strings: Strings) -> NomResult<&[u8], PushValue> {
+  use nom::combinator::map;
+  let (input, value_type) = parse_u8(input)?;
+  match value_type {
+    0 => map(|i| strings.parse(i), PushValue::String)(input),
+    1 => map(parse_le_f32, PushValue::Float32)(input),
+    2 => Ok((input, PushValue::Null)),
+    3 => Ok((input, PushValue::Undefined)),
+    4 => map(parse_u8, PushValue::Register)(input),
+    5 => map(parse_u8, |value| PushValue::Boolean(value != 0))(input),
+    6 => map(parse_le_f64_swapped, PushValue::Float64)(input),
+    7 => map(parse_le_i32, PushValue::Sint32)(input),
+    8 => map(parse_u8, |index| PushValue::Constant(u16::from(index)))(input),
+    9 => map(parse_le_u16, PushValue::Constant)(input),
+    _ => Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch))),
+  }
+}
+
+/// Parses a double stored as two little-endian 32-bit words, the most significant word first.
+fn parse_le_f64_swapped(input: &[u8]) -> NomResult<&[u8], f64> {
+  let (input, high) = parse_le_u32(input)?;
+  let (input, low) = parse_le_u32(input)?;
+  Ok((input, f64::from_bits((u64::from(high) << 32) | u64::from(low))))
+}
+
+fn parse_get_url2(input: &[u8]) -> NomResult<&[u8], Action> {
+  let (remaining, flags) = parse_u8(input)?;
+  // The documented bit order is reversed: the method uses the least significant bits
+  let method = match flags & 0b11 {
+    0 => SendVarsMethod::None,
+    1 => SendVarsMethod::Get,
+    2 => SendVarsMethod::Post,
+    _ => return Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch))),
+  };
+  let load_target = (flags & (1 << 6)) != 0;
+  let load_variables = (flags & (1 << 7)) != 0;
+  Ok((
+    remaining,
+    Action::GetUrl2 {
+      method,
+      load_target,
+      load_variables,
+    },
+  ))
+}
+
+fn parse_goto_frame2(input: &[u8]) -> NomResult<&[u8], Action> {
+  let (input, flags) = parse_u8(input)?;
+  let play = (flags & (1 << 0)) != 0;
+  let has_scene_bias = (flags & (1 << 1)) != 0;
+  let (input, scene_bias) = if has_scene_bias {
+    parse_le_u16(input)?
+  } else {
+    (input, 0)
+  };
+  Ok((input, Action::GotoFrame2 { play, scene_bias }))
+}
+
+fn parse_define_function(input: &[u8], end: usize, len: usize, strings: Strings) -> NomResult<&[u8], DefineFunction> {
+  use nom::multi::count;
+  let (input, name) = strings.parse(input)?;
+  let (input, parameter_count) = parse_le_u16(input)?;
+  let (input, parameters) = count(|i| strings.parse(i), usize::from(parameter_count))(input)?;
+  let (input, body_size) = parse_le_u16(input)?;
+  let body = get_block(input, end, usize::from(body_size), len)?;
+  Ok((input, DefineFunction { name, parameters, body }))
+}
+
+fn parse_define_function2(
+  input: &[u8],
+  end: usize,
+  len: usize,
+  strings: Strings,
+) -> NomResult<&[u8], DefineFunction2> {
+  use nom::multi::count;
+  let (input, name) = strings.parse(input)?;
+  let (input, parameter_count) = parse_le_u16(input)?;
+  let (input, register_count) = parse_u8(input)?;
+  let (input, flags) = parse_le_u16(input)?;
+  let preload_this = (flags & (1 << 0)) != 0;
+  let suppress_this = (flags & (1 << 1)) != 0;
+  let preload_arguments = (flags & (1 << 2)) != 0;
+  let suppress_arguments = (flags & (1 << 3)) != 0;
+  let preload_super = (flags & (1 << 4)) != 0;
+  let suppress_super = (flags & (1 << 5)) != 0;
+  let preload_root = (flags & (1 << 6)) != 0;
+  let preload_parent = (flags & (1 << 7)) != 0;
+  let preload_global = (flags & (1 << 8)) != 0;
+  // Skip bits [9, 15]
+  let (input, parameters) = count(|i| parse_parameter(i, strings), usize::from(parameter_count))(input)?;
+  let (input, body_size) = parse_le_u16(input)?;
+  let body = get_block(input, end, usize::from(body_size), len)?;
+  Ok((
+    input,
+    DefineFunction2 {
+      name,
+      register_count,
+      preload_parent,
+      preload_root,
+      suppress_super,
+      preload_super,
+      suppress_arguments,
+      preload_arguments,
+      suppress_this,
+      preload_this,
+      preload_global,
+      parameters,
+      body,
+    },
+  ))
+}
+
+fn parse_parameter(input: &[u8], strings: Strings) -> NomResult<&[u8], Parameter> {
+  let (input, register) = parse_u8(input)?;
+  let (input, name) = strings.parse(input)?;
+  let register = if register == 0 { None } else { Some(register) };
+  Ok((input, Parameter { register, name }))
+}
+
+fn parse_try(input: &[u8], end: usize, len: usize, strings: Strings) -> NomResult<&[u8], Try> {
+  use nom::combinator::map;
+  let (input, flags) = parse_u8(input)?;
+  let has_catch_block = (flags & (1 << 0)) != 0;
+  let has_finally_block = (flags & (1 << 1)) != 0;
+  let catch_in_register = (flags & (1 << 2)) != 0;
+  // Skip bits [3, 7]
+  let (input, try_size) = parse_le_u16(input)?;
+  let (input, catch_size) = parse_le_u16(input)?;
+  let (input, finally_size) = parse_le_u16(input)?;
+  let (input, catch_target) = if catch_in_register {
+    map(parse_u8, CatchTarget::Register)(input)?
+  } else {
+    map(|i| strings.parse(i), CatchTarget::Variable)(input)?
+  };
+  // The catch and finally sizes are always present, the blocks follow each other
+  let try_body = get_block(input, end, usize::from(try_size), len)?;
+  let catch_body = get_block(input, try_body.end, usize::from(catch_size), len)?;
+  let finally_body = get_block(input, catch_body.end, usize::from(finally_size), len)?;
+  Ok((
+    input,
+    Try {
+      try_body,
+      catch_body: if has_catch_block { Some(catch_body) } else { None },
+      finally_body: if has_finally_block { Some(finally_body) } else { None },
+      catch_target,
+    },
+  ))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn actions(input: &[u8]) -> Vec<Action> {
+    parse_actions(input, 10).into_iter().map(|record| record.action).collect()
+  }
+
+  #[test]
+  fn test_parse_actions_simple() {
+    let input: &[u8] = &[
+      0x06, // Play
+      0x81, 0x02, 0x00, 0x05, 0x00, // GotoFrame 5
+      0x00, // End
+      0x07, // Ignored after `End`
+    ];
+    assert_eq!(
+      parse_actions(input, 10),
+      vec![
+        ActionRecord {
+          offset: 0,
+          len: 1,
+          action: Action::Play,
+        },
+        ActionRecord {
+          offset: 1,
+          len: 5,
+          action: Action::GotoFrame { frame: 5 },
+        },
+        ActionRecord {
+          offset: 6,
+          len: 1,
+          action: Action::End,
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_parse_constant_pool_and_push() {
+    let input: &[u8] = &[
+      0x88, 0x06, 0x00, 0x02, 0x00, b'a', 0x00, b'b', 0x00, // ConstantPool ["a", "b"]
+      0x96, 0x21, 0x00, // Push
+      0x00, b'x', 0x00, // "x"
+      0x01, 0x00, 0x00, 0x80, 0x3f, // 1f32
+      0x02, // null
+      0x03, // undefined
+      0x04, 0x01, // register 1
+      0x05, 0x01, // true
+      0x06, 0x00, 0x00, 0xf0, 0x3f, 0x00, 0x00, 0x00, 0x00, // 1f64
+      0x07, 0xff, 0xff, 0xff, 0xff, // -1i32
+      0x08, 0x01, // constant 1
+      0x09, 0x00, 0x01, // constant 256
+    ];
+    assert_eq!(
+      actions(input),
+      vec![
+        Action::ConstantPool {
+          pool: vec![String::from("a"), String::from("b")],
+        },
+        Action::Push {
+          values: vec![
+            PushValue::String(String::from("x")),
+            PushValue::Float32(1.0),
+            PushValue::Null,
+            PushValue::Undefined,
+            PushValue::Register(1),
+            PushValue::Boolean(true),
+            PushValue::Float64(1.0),
+            PushValue::Sint32(-1),
+            PushValue::Constant(1),
+            PushValue::Constant(256),
+          ],
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_parse_legacy_strings() {
+    let input: &[u8] = &[
+      0x88, 0x07, 0x00, 0x01, 0x00, b'c', b'a', b'f', 0xe9, 0x00, // ConstantPool ["café"]
+      0x96, 0x03, 0x00, 0x00, 0xe9, 0x00, // Push "é"
+      0x9b, 0x08, 0x00, 0xe9, 0x00, 0x01, 0x00, 0xe9, 0x00, 0x00, 0x00, // DefineFunction é(é), empty body
+      0x00, // End
+    ];
+    let expected = vec![
+      Action::ConstantPool {
+        pool: vec![String::from("café")],
+      },
+      Action::Push {
+        values: vec![PushValue::String(String::from("é"))],
+      },
+      Action::DefineFunction(DefineFunction {
+        name: String::from("é"),
+        parameters: vec![String::from("é")],
+        body: 27..27,
+      }),
+      Action::End,
+    ];
+    let records = parse_actions(input, 5);
+    assert_eq!(records.into_iter().map(|record| record.action).collect::<Vec<_>>(), expected);
+
+    let records = parse_actions_with_legacy_encoding(&input[10..16], 5, LegacyEncoding::Raw);
+    assert_eq!(
+      records[0].action,
+      Action::Push {
+        values: vec![PushValue::String(String::from("\u{e9}"))],
+      }
+    );
+
+    // SWF 6 and later strings are UTF-8
+    assert!(matches!(actions(input)[0], Action::Raw(_)));
+  }
+
+  #[test]
+  fn test_parse_branch_targets() {
+    let input: &[u8] = &[
+      0x9d, 0x02, 0x00, 0x05, 0x00, // If +5
+      0x99, 0x02, 0x00, 0xf6, 0xff, // Jump -10
+      0x99, 0x02, 0x00, 0xf0, 0xff, // Jump -16, before the start of the actions
+      0x00, // End
+    ];
+    assert_eq!(
+      actions(input),
+      vec![
+        Action::If { target: 10 },
+        Action::Jump { target: 0 },
+        Action::Raw(RawAction {
+          code: 0x99,
+          data: vec![0xf0, 0xff],
+        }),
+        Action::End,
+      ]
+    );
+  }
+
+  #[test]
+  fn test_parse_define_function2() {
+    let input: &[u8] = &[
+      0x8e, 0x0f, 0x00, // DefineFunction2
+      b'f', 0x00, // name
+      0x02, 0x00, // 2 parameters
+      0x04, // 4 registers
+      0x01, 0x01, // preload_this, preload_global
+      0x03, b'a', 0x00, // a: register 3
+      0x00, b'b', 0x00, // b: variable
+      0x02, 0x00, // body size
+      0x4c, 0x3e, // PushDuplicate, Return
+      0x00, // End
+    ];
+    assert_eq!(
+      actions(input),
+      vec![
+        Action::DefineFunction2(DefineFunction2 {
+          name: String::from("f"),
+          register_count: 4,
+          preload_parent: false,
+          preload_root: false,
+          suppress_super: false,
+          preload_super: false,
+          suppress_arguments: false,
+          preload_arguments: false,
+          suppress_this: false,
+          preload_this: true,
+          preload_global: true,
+          parameters: vec![
+            Parameter {
+              register: Some(3),
+              name: String::from("a"),
+            },
+            Parameter {
+              register: None,
+              name: String::from("b"),
+            },
+          ],
+          body: 18..20,
+        }),
+        Action::PushDuplicate,
+        Action::Return,
+        Action::End,
+      ]
+    );
+  }
+
+  #[test]
+  fn test_parse_try_and_with() {
+    let input: &[u8] = &[
+      0x8f, 0x09, 0x00, // Try
+      0x03, // catch block, finally block
+      0x01, 0x00, 0x01, 0x00, 0x01, 0x00, // sizes
+      b'e', 0x00, // catch variable
+      0x2a, // try: Throw
+      0x17, // catch: Pop
+      0x07, // finally: Stop
+      0x94, 0x02, 0x00, 0x01, 0x00, // With
+      0x06, // with: Play
+      0x00, // End
+    ];
+    assert_eq!(
+      actions(input),
+      vec![
+        Action::Try(Try {
+          try_body: 12..13,
+          catch_body: Some(13..14),
+          finally_body: Some(14..15),
+          catch_target: CatchTarget::Variable(String::from("e")),
+        }),
+        Action::Throw,
+        Action::Pop,
+        Action::Stop,
+        Action::With { body: 20..21 },
+        Action::Play,
+        Action::End,
+      ]
+    );
+  }
+
+  #[test]
+  fn test_parse_invalid_actions() {
+    let input: &[u8] = &[
+      0x01, // Unknown action without data
+      0xa0, 0x01, 0x00, 0xff, // Unknown action with data
+      0x94, 0x02, 0x00, 0xff, 0x00, // With, body out of bounds
+      0x96, 0x05, 0x00, 0x00, // Truncated
+    ];
+    assert_eq!(
+      actions(input),
+      vec![
+        Action::Raw(RawAction {
+          code: 0x01,
+          data: Vec::new(),
+        }),
+        Action::Raw(RawAction {
+          code: 0xa0,
+          data: vec![0xff],
+        }),
+        Action::Raw(RawAction {
+          code: 0x94,
+          data: vec![0xff, 0x00],
+        }),
+        Action::Raw(RawAction {
+          code: 0x96,
+          data: vec![0x00],
+        }),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_tag_actions() {
+    let tag = ast::Tag::DoInitAction(ast::tags::DoInitAction {
+      sprite_id: 1,
+      actions: vec![0x06, 0x00],
+    });
+    assert_eq!(tag_actions(&tag), vec![&[0x06, 0x00][..]]);
+    assert_eq!(tag_actions(&ast::Tag::ShowFrame), Vec::<&[u8]>::new());
+  }
+}
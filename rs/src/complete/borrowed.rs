@@ -0,0 +1,662 @@
+//! Borrowed representation of the tags carrying bulk binary payloads.
+//!
+//! The default parsers copy every payload (bitmaps, sounds, video frames, bytecode, binary data)
+//! into the owned `swf_types` AST. The parsers of this module return tags borrowing these payloads
+//! from the (decompressed) input instead, the other tags are parsed to their owned representation.
+//! The owned parsers of these tags convert the borrowed tags returned by this module.
+
+use crate::complete::base::skip;
+use crate::complete::movie::{parse_header, SwfParseError};
+use crate::complete::image::{
+  get_gif_image_dimensions, get_jpeg_image_dimensions, get_png_image_dimensions, is_sniffed_jpeg, sniff_image_type,
+  SniffedImageType,
+};
+use crate::complete::sound::{audio_coding_format_from_code, is_uncompressed_audio_coding_format, sound_rate_from_code};
+use crate::complete::tag::{split_tag, try_parse_tag_body, TagIter, TagSplit};
+use crate::legacy::LegacyEncoding;
+use crate::streaming::basic_data_types::parse_c_string;
+use crate::streaming::error::{LocatedError, NomResult};
+use nom::number::complete::{le_u16 as parse_le_u16, le_u32 as parse_le_u32, le_u8 as parse_u8};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use swf_types as ast;
+
+/// Tag with its binary payload borrowed from the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BorrowedTag<'a> {
+  DefineBinaryData(BorrowedDefineBinaryData<'a>),
+  DefineBitmap(BorrowedDefineBitmap<'a>),
+  DefineJpegTables(BorrowedDefineJpegTables<'a>),
+  DefineSound(BorrowedDefineSound<'a>),
+  DefineSprite(BorrowedDefineSprite<'a>),
+  DoAbc(BorrowedDoAbc<'a>),
+  DoAction(BorrowedDoAction<'a>),
+  DoInitAction(BorrowedDoInitAction<'a>),
+  Raw(BorrowedRaw<'a>),
+  RawBody(BorrowedRawBody<'a>),
+  SoundStreamBlock(BorrowedSoundStreamBlock<'a>),
+  VideoFrame(BorrowedVideoFrame<'a>),
+  /// Tag without bulk binary payload
+  Owned(ast::Tag),
+}
+
+impl BorrowedTag<'_> {
+  /// Converts the tag to the owned `swf_types` representation.
+  pub fn into_owned(self) -> ast::Tag {
+    match self {
+      BorrowedTag::DefineBinaryData(tag) => ast::Tag::DefineBinaryData(tag.into_owned()),
+      BorrowedTag::DefineBitmap(tag) => ast::Tag::DefineBitmap(tag.into_owned()),
+      BorrowedTag::DefineJpegTables(tag) => ast::Tag::DefineJpegTables(tag.into_owned()),
+      BorrowedTag::DefineSound(tag) => ast::Tag::DefineSound(tag.into_owned()),
+      BorrowedTag::DefineSprite(tag) => ast::Tag::DefineSprite(tag.into_owned()),
+      BorrowedTag::DoAbc(tag) => ast::Tag::DoAbc(tag.into_owned()),
+      BorrowedTag::DoAction(tag) => ast::Tag::DoAction(tag.into_owned()),
+      BorrowedTag::DoInitAction(tag) => ast::Tag::DoInitAction(tag.into_owned()),
+      BorrowedTag::Raw(tag) => ast::Tag::Raw(tag.into_owned()),
+      BorrowedTag::RawBody(tag) => ast::Tag::RawBody(tag.into_owned()),
+      BorrowedTag::SoundStreamBlock(tag) => ast::Tag::SoundStreamBlock(tag.into_owned()),
+      BorrowedTag::VideoFrame(tag) => ast::Tag::VideoFrame(tag.into_owned()),
+      BorrowedTag::Owned(tag) => tag,
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BorrowedDefineBinaryData<'a> {
+  pub id: u16,
+  pub data: Cow<'a, [u8]>,
+}
+
+impl BorrowedDefineBinaryData<'_> {
+  pub fn into_owned(self) -> ast::tags::DefineBinaryData {
+    ast::tags::DefineBinaryData {
+      id: self.id,
+      data: self.data.into_owned(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BorrowedDefineBitmap<'a> {
+  pub id: u16,
+  pub width: u16,
+  pub height: u16,
+  pub media_type: ast::ImageType,
+  pub data: Cow<'a, [u8]>,
+}
+
+impl BorrowedDefineBitmap<'_> {
+  pub fn into_owned(self) -> ast::tags::DefineBitmap {
+    ast::tags::DefineBitmap {
+      id: self.id,
+      width: self.width,
+      height: self.height,
+      media_type: self.media_type,
+      data: self.data.into_owned(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BorrowedDefineJpegTables<'a> {
+  pub data: Cow<'a, [u8]>,
+}
+
+impl BorrowedDefineJpegTables<'_> {
+  pub fn into_owned(self) -> ast::tags::DefineJpegTables {
+    ast::tags::DefineJpegTables {
+      data: self.data.into_owned(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BorrowedDefineSound<'a> {
+  pub id: u16,
+  pub sound_type: ast::SoundType,
+  pub sound_size: ast::SoundSize,
+  pub sound_rate: ast::SoundRate,
+  pub format: ast::AudioCodingFormat,
+  pub sample_count: u32,
+  pub data: Cow<'a, [u8]>,
+}
+
+impl BorrowedDefineSound<'_> {
+  pub fn into_owned(self) -> ast::tags::DefineSound {
+    ast::tags::DefineSound {
+      id: self.id,
+      sound_type: self.sound_type,
+      sound_size: self.sound_size,
+      sound_rate: self.sound_rate,
+      format: self.format,
+      sample_count: self.sample_count,
+      data: self.data.into_owned(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BorrowedDefineSprite<'a> {
+  pub id: u16,
+  pub frame_count: usize,
+  pub tags: Vec<BorrowedTag<'a>>,
+}
+
+impl BorrowedDefineSprite<'_> {
+  pub fn into_owned(self) -> ast::tags::DefineSprite {
+    ast::tags::DefineSprite {
+      id: self.id,
+      frame_count: self.frame_count,
+      tags: self.tags.into_iter().map(BorrowedTag::into_owned).collect(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BorrowedDoAbc<'a> {
+  pub header: Option<ast::AbcHeader>,
+  pub data: Cow<'a, [u8]>,
+}
+
+impl BorrowedDoAbc<'_> {
+  pub fn into_owned(self) -> ast::tags::DoAbc {
+    ast::tags::DoAbc {
+      header: self.header,
+      data: self.data.into_owned(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BorrowedDoAction<'a> {
+  pub actions: Cow<'a, [u8]>,
+}
+
+impl BorrowedDoAction<'_> {
+  pub fn into_owned(self) -> ast::tags::DoAction {
+    ast::tags::DoAction {
+      actions: self.actions.into_owned(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BorrowedDoInitAction<'a> {
+  pub sprite_id: u16,
+  pub actions: Cow<'a, [u8]>,
+}
+
+impl BorrowedDoInitAction<'_> {
+  pub fn into_owned(self) -> ast::tags::DoInitAction {
+    ast::tags::DoInitAction {
+      sprite_id: self.sprite_id,
+      actions: self.actions.into_owned(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BorrowedRaw<'a> {
+  pub data: Cow<'a, [u8]>,
+}
+
+impl BorrowedRaw<'_> {
+  pub fn into_owned(self) -> ast::tags::Raw {
+    ast::tags::Raw {
+      data: self.data.into_owned(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BorrowedRawBody<'a> {
+  pub code: u16,
+  pub data: Cow<'a, [u8]>,
+}
+
+impl BorrowedRawBody<'_> {
+  pub fn into_owned(self) -> ast::tags::RawBody {
+    ast::tags::RawBody {
+      code: self.code,
+      data: self.data.into_owned(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BorrowedSoundStreamBlock<'a> {
+  pub data: Cow<'a, [u8]>,
+}
+
+impl BorrowedSoundStreamBlock<'_> {
+  pub fn into_owned(self) -> ast::tags::SoundStreamBlock {
+    ast::tags::SoundStreamBlock {
+      data: self.data.into_owned(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BorrowedVideoFrame<'a> {
+  pub video_id: u16,
+  pub frame: u16,
+  pub packet: Cow<'a, [u8]>,
+}
+
+impl BorrowedVideoFrame<'_> {
+  pub fn into_owned(self) -> ast::tags::VideoFrame {
+    ast::tags::VideoFrame {
+      video_id: self.video_id,
+      frame: self.frame,
+      packet: self.packet.into_owned(),
+    }
+  }
+}
+
+/// Parses the movie header of a decompressed SWF payload and returns it with a lazy iterator over
+/// the tags, borrowing their binary payloads from `payload`.
+///
+/// The tag offsets are relative to the start of `payload`.
+/// See `crate::complete::decompress_swf` to get the payload of an SWF file.
+///
+/// ```no_run
+/// use swf_parser::{decompress_swf, parse_borrowed_movie, BorrowedTag};
+///
+/// let swf_bytes: Vec<u8> = ::std::fs::read("movie.swf").expect("Failed to read movie");
+/// let (signature, payload) = decompress_swf(&swf_bytes).expect("Failed to decompress SWF");
+/// let (_header, tags) = parse_borrowed_movie(&payload, signature.swf_version).expect("Failed to parse SWF header");
+/// for (_, _, tag) in tags {
+///   if let BorrowedTag::DefineBitmap(bitmap) = tag {
+///     println!("{}: {} bytes", bitmap.id, bitmap.data.len());
+///   }
+/// }
+/// ```
+pub fn parse_borrowed_movie(
+  payload: &[u8],
+  swf_version: u8,
+) -> Result<(ast::Header, BorrowedTagIter<'_>), SwfParseError> {
+  match parse_header(payload, swf_version) {
+    Ok((input, header)) => Ok((header, borrowed_tag_iter(input, payload.len() - input.len(), swf_version))),
+    Err(_) => Err(SwfParseError::InvalidHeader),
+  }
+}
+
+/// Returns a lazy iterator over the tags of `input`, borrowing their binary payloads.
+///
+/// This is the borrowed counterpart of `crate::complete::parse_swf_tags`.
+pub fn parse_borrowed_swf_tags(input: &[u8], swf_version: u8) -> BorrowedTagIter<'_> {
+  borrowed_tag_iter(input, 0, swf_version)
+}
+
+/// Lazy iterator over the tags of a completely loaded tag string, yielding borrowed tags.
+///
+/// See `parse_borrowed_swf_tags`.
+pub type BorrowedTagIter<'a> = TagIter<'a, BorrowedTag<'a>>;
+
+fn borrowed_tag_iter(input: &[u8], offset: usize, swf_version: u8) -> BorrowedTagIter<'_> {
+  TagIter::with_parser(input, offset, swf_version, parse_tag_body, |data| {
+    BorrowedTag::Raw(BorrowedRaw { data: data.into() })
+  })
+}
+
+/// Parses a tag body, borrowing its binary payload from `input`.
+///
+/// Malformed tags produce a `BorrowedTag::RawBody`, as with the owned parser.
+pub(crate) fn parse_tag_body(input: &[u8], code: u16, swf_version: u8, encoding: LegacyEncoding) -> BorrowedTag<'_> {
+  use nom::combinator::map;
+  let result = match code {
+    6 => map(|i| parse_define_bits(i, swf_version), BorrowedTag::DefineBitmap)(input),
+    8 => map(parse_define_jpeg_tables, BorrowedTag::DefineJpegTables)(input),
+    12 => map(parse_do_action, BorrowedTag::DoAction)(input),
+    14 => map(parse_define_sound, BorrowedTag::DefineSound)(input),
+    19 => map(parse_sound_stream_block, BorrowedTag::SoundStreamBlock)(input),
+    20 => map(parse_define_bits_lossless, BorrowedTag::DefineBitmap)(input),
+    21 => map(|i| parse_define_bits_jpeg2(i, swf_version), BorrowedTag::DefineBitmap)(input),
+    35 => map(|i| parse_define_bits_jpeg3(i, swf_version), BorrowedTag::DefineBitmap)(input),
+    36 => map(parse_define_bits_lossless2, BorrowedTag::DefineBitmap)(input),
+    39 => map(|i| parse_define_sprite(i, swf_version, encoding), BorrowedTag::DefineSprite)(input),
+    59 => map(parse_do_init_action, BorrowedTag::DoInitAction)(input),
+    61 => map(parse_video_frame, BorrowedTag::VideoFrame)(input),
+    72 => map(|i| parse_do_abc(i, false), BorrowedTag::DoAbc)(input),
+    82 => map(|i| parse_do_abc(i, true), BorrowedTag::DoAbc)(input),
+    87 => map(parse_define_binary_data, BorrowedTag::DefineBinaryData)(input),
+    90 => map(parse_define_bits_jpeg4, BorrowedTag::DefineBitmap)(input),
+    _ => map(|i| try_parse_tag_body(i, code, swf_version, encoding), BorrowedTag::Owned)(input),
+  };
+  match result {
+    Ok((_, tag)) => tag,
+    Err(_) => BorrowedTag::RawBody(BorrowedRawBody {
+      code,
+      data: input.into(),
+    }),
+  }
+}
+
+fn verify_error<T>(input: &[u8]) -> NomResult<&[u8], T> {
+  Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Verify)))
+}
+
+pub(crate) fn parse_define_binary_data(input: &[u8]) -> NomResult<&[u8], BorrowedDefineBinaryData<'_>> {
+  let (input, id) = parse_le_u16(input)?;
+  let (input, _reserved) = parse_le_u32(input)?; // TODO: assert reserved == 0
+  Ok((&[][..], BorrowedDefineBinaryData { id, data: input.into() }))
+}
+
+pub(crate) fn parse_define_bits(input: &[u8], swf_version: u8) -> NomResult<&[u8], BorrowedDefineBitmap<'_>> {
+  let (data, id) = parse_le_u16(input)?;
+  let input: &[u8] = &[][..];
+
+  if is_sniffed_jpeg(data, swf_version < 8) {
+    let image_dimensions = match get_jpeg_image_dimensions(data) {
+      Ok(dimensions) => dimensions,
+      Err(_) => return verify_error(input),
+    };
+    // TODO: avoid conversions
+    Ok((
+      input,
+      BorrowedDefineBitmap {
+        id,
+        width: image_dimensions.width as u16,
+        height: image_dimensions.height as u16,
+        media_type: ast::ImageType::SwfPartialJpeg,
+        data: data.into(),
+      },
+    ))
+  } else {
+    // UnknownBitmapType
+    // TODO: Better error
+    verify_error(input)
+  }
+}
+
+pub(crate) fn parse_define_bits_jpeg2(input: &[u8], swf_version: u8) -> NomResult<&[u8], BorrowedDefineBitmap<'_>> {
+  let (data, id) = parse_le_u16(input)?;
+  let input: &[u8] = &[][..];
+
+  let (media_type, dimensions) = match sniff_image_type(data, swf_version < 8) {
+    Ok(SniffedImageType::Jpeg) => (ast::ImageType::Jpeg, get_jpeg_image_dimensions(data)),
+    Ok(SniffedImageType::Png) => (ast::ImageType::Png, get_png_image_dimensions(data)),
+    Ok(SniffedImageType::Gif) => (ast::ImageType::Gif, get_gif_image_dimensions(data)),
+    // UnknownBitmapType
+    Err(()) => return verify_error(input),
+  };
+
+  let dimensions = match dimensions {
+    Ok(dimensions) => dimensions,
+    Err(_) => return verify_error(input),
+  };
+
+  Ok((
+    input,
+    BorrowedDefineBitmap {
+      id,
+      width: dimensions.width as u16,
+      height: dimensions.height as u16,
+      media_type,
+      data: data.into(),
+    },
+  ))
+}
+
+pub(crate) fn parse_define_bits_jpeg3(input: &[u8], swf_version: u8) -> NomResult<&[u8], BorrowedDefineBitmap<'_>> {
+  use nom::bytes::complete::take;
+  use nom::combinator::map;
+
+  let (ajpeg_data, id) = parse_le_u16(input)?;
+  let (input, data_len) = map(parse_le_u32, |x| x as usize)(ajpeg_data)?;
+  let (_, data) = take(data_len)(input)?;
+
+  let (media_type, dimensions, img_data) = match sniff_image_type(data, swf_version < 8) {
+    Ok(SniffedImageType::Jpeg) => {
+      let dimensions = get_jpeg_image_dimensions(data);
+      if input.len() > data_len {
+        (ast::ImageType::SwfJpeg3, dimensions, ajpeg_data)
+      } else {
+        (ast::ImageType::Jpeg, dimensions, data)
+      }
+    }
+    Ok(SniffedImageType::Png) => (ast::ImageType::Png, get_png_image_dimensions(data), data),
+    Ok(SniffedImageType::Gif) => (ast::ImageType::Gif, get_gif_image_dimensions(data), data),
+    // UnknownBitmapType
+    Err(()) => return verify_error(input),
+  };
+
+  let dimensions = match dimensions {
+    Ok(dimensions) => dimensions,
+    Err(_) => return verify_error(input),
+  };
+
+  Ok((
+    &[],
+    BorrowedDefineBitmap {
+      id,
+      width: dimensions.width as u16,
+      height: dimensions.height as u16,
+      media_type,
+      data: img_data.into(),
+    },
+  ))
+}
+
+pub(crate) fn parse_define_bits_jpeg4(input: &[u8]) -> NomResult<&[u8], BorrowedDefineBitmap<'_>> {
+  use nom::bytes::complete::take;
+  use nom::combinator::map;
+
+  let (djpeg_data, id) = parse_le_u16(input)?;
+  let (input, data_len) = map(parse_le_u32, |dl| usize::try_from(dl).unwrap())(djpeg_data)?;
+  let (input, _) = skip(2usize)(input)?; // Skip deblock
+  let (_, data) = take(data_len)(input)?;
+
+  let (media_type, dimensions, img_data) = match sniff_image_type(data, false) {
+    Ok(SniffedImageType::Jpeg) => (ast::ImageType::SwfJpeg4, get_jpeg_image_dimensions(data), djpeg_data),
+    Ok(SniffedImageType::Png) => (ast::ImageType::Png, get_png_image_dimensions(data), data),
+    Ok(SniffedImageType::Gif) => (ast::ImageType::Gif, get_gif_image_dimensions(data), data),
+    // UnknownBitmapType
+    Err(()) => return verify_error(input),
+  };
+
+  let dimensions = match dimensions {
+    Ok(dimensions) => dimensions,
+    Err(_) => return verify_error(input),
+  };
+
+  Ok((
+    &[],
+    BorrowedDefineBitmap {
+      id,
+      width: dimensions.width as u16,
+      height: dimensions.height as u16,
+      media_type,
+      data: img_data.into(),
+    },
+  ))
+}
+
+pub(crate) fn parse_define_bits_lossless(input: &[u8]) -> NomResult<&[u8], BorrowedDefineBitmap<'_>> {
+  parse_define_bits_lossless_any(input, ast::ImageType::SwfLossless1)
+}
+
+pub(crate) fn parse_define_bits_lossless2(input: &[u8]) -> NomResult<&[u8], BorrowedDefineBitmap<'_>> {
+  parse_define_bits_lossless_any(input, ast::ImageType::SwfLossless2)
+}
+
+pub(crate) fn parse_define_bits_lossless_any(input: &[u8], media_type: ast::ImageType) -> NomResult<&[u8], BorrowedDefineBitmap<'_>> {
+  let (data, id) = parse_le_u16(input)?;
+  let (input, _) = skip(1usize)(data)?; // BitmapFormat
+  let (input, width) = parse_le_u16(input)?;
+  let (_, height) = parse_le_u16(input)?;
+  let input: &[u8] = &[];
+
+  Ok((
+    input,
+    BorrowedDefineBitmap {
+      id,
+      width,
+      height,
+      media_type,
+      data: data.into(),
+    },
+  ))
+}
+
+pub(crate) fn parse_define_jpeg_tables(input: &[u8]) -> NomResult<&[u8], BorrowedDefineJpegTables<'_>> {
+  Ok((&[][..], BorrowedDefineJpegTables { data: input.into() }))
+}
+
+pub(crate) fn parse_define_sound(input: &[u8]) -> NomResult<&[u8], BorrowedDefineSound<'_>> {
+  let (input, id) = parse_le_u16(input)?;
+  let (input, flags) = parse_u8(input)?;
+  #[allow(clippy::identity_op)]
+  let sound_type = if (flags & (1 << 0)) != 0 {
+    ast::SoundType::Stereo
+  } else {
+    ast::SoundType::Mono
+  };
+  let sound_size = if (flags & (1 << 1)) != 0 {
+    ast::SoundSize::SoundSize16
+  } else {
+    ast::SoundSize::SoundSize8
+  };
+  let sound_rate =
+    sound_rate_from_code((flags >> 2) & 0b11).map_err(|_| nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch)))?;
+  let format = audio_coding_format_from_code((flags >> 4) & 0b1111)
+    .map_err(|_| nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch)))?;
+  let (data, sample_count) = parse_le_u32(input)?;
+
+  Ok((
+    &[],
+    BorrowedDefineSound {
+      id,
+      sound_type,
+      sound_size: if is_uncompressed_audio_coding_format(format) {
+        sound_size
+      } else {
+        ast::SoundSize::SoundSize16
+      },
+      sound_rate,
+      format,
+      sample_count,
+      data: data.into(),
+    },
+  ))
+}
+
+fn parse_define_sprite(
+  input: &[u8],
+  swf_version: u8,
+  encoding: LegacyEncoding,
+) -> NomResult<&[u8], BorrowedDefineSprite<'_>> {
+  use nom::combinator::map;
+
+  let (mut input, id) = parse_le_u16(input)?;
+  let (tag_input, frame_count) = map(parse_le_u16, usize::from)(input)?;
+  input = tag_input;
+  let mut tags: Vec<BorrowedTag> = Vec::new();
+  // Same semantics as the owned parser: the nested tags must be complete and terminated by `End`.
+  loop {
+    if input.is_empty() {
+      return Err(nom::Err::Incomplete(nom::Needed::Unknown));
+    }
+    input = match split_tag(input) {
+      (remaining, TagSplit::End) => return Ok((remaining, BorrowedDefineSprite { id, frame_count, tags })),
+      (remaining, TagSplit::Tag(header, body)) => {
+        tags.push(parse_tag_body(body, header.code, swf_version, encoding));
+        remaining
+      }
+      (_, TagSplit::Truncated(_)) => return Err(nom::Err::Incomplete(nom::Needed::Unknown)),
+    };
+  }
+}
+
+pub(crate) fn parse_do_abc(input: &[u8], has_header: bool) -> NomResult<&[u8], BorrowedDoAbc<'_>> {
+  let (input, header) = if has_header {
+    let (input, flags) = parse_le_u32(input)?;
+    let (input, name) = parse_c_string(input)?;
+    (input, Some(ast::AbcHeader { flags, name }))
+  } else {
+    (input, None)
+  };
+  Ok((&[][..], BorrowedDoAbc { header, data: input.into() }))
+}
+
+pub(crate) fn parse_do_action(input: &[u8]) -> NomResult<&[u8], BorrowedDoAction<'_>> {
+  Ok((&[][..], BorrowedDoAction { actions: input.into() }))
+}
+
+pub(crate) fn parse_do_init_action(input: &[u8]) -> NomResult<&[u8], BorrowedDoInitAction<'_>> {
+  let (input, sprite_id) = parse_le_u16(input)?;
+  Ok((
+    &[][..],
+    BorrowedDoInitAction {
+      sprite_id,
+      actions: input.into(),
+    },
+  ))
+}
+
+pub(crate) fn parse_sound_stream_block(input: &[u8]) -> NomResult<&[u8], BorrowedSoundStreamBlock<'_>> {
+  Ok((&[][..], BorrowedSoundStreamBlock { data: input.into() }))
+}
+
+pub(crate) fn parse_video_frame(input: &[u8]) -> NomResult<&[u8], BorrowedVideoFrame<'_>> {
+  let (input, video_id) = parse_le_u16(input)?;
+  let (input, frame) = parse_le_u16(input)?;
+  Ok((
+    &[][..],
+    BorrowedVideoFrame {
+      video_id,
+      frame,
+      packet: input.into(),
+    },
+  ))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::complete::parse_swf_tags;
+
+  #[test]
+  fn test_borrowed_tags_match_owned_tags() {
+    let input: &[u8] = &[
+      0x44, 0x11, 0x08, 0x00, 0x00, 0x00, // FileAttributes
+      0x03, 0x03, 0x0a, 0x0b, 0x00, // DoAction
+      0xc9, 0x09, 0x01, 0x00, 0x01, 0x00, // DefineSprite
+      0x01, 0x03, 0x00, // DoAction (nested)
+      0x00, 0x00, // End (nested)
+      0xc9, 0x15, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x02, 0x03, // DefineBinaryData
+      0x02, 0x02, 0xff, 0xd8, // DefineJpegTables
+      0x89, 0x03, 0x01, 0x00, 0x0f, 0x02, 0x00, 0x00, 0x00, 0xaa, 0xbb, // DefineSound
+      0xc2, 0x04, 0x12, 0x34, // SoundStreamBlock
+      0x09, 0x05, 0x01, 0x00, 0x03, 0x02, 0x00, 0x02, 0x00, 0xff, 0xff, // DefineBitsLossless
+      0xc3, 0x0e, 0x01, 0x00, 0x00, // DoInitAction
+      0x46, 0x0f, 0x01, 0x00, 0x02, 0x00, 0xcc, 0xdd, // VideoFrame
+      0x87, 0x14, 0x01, 0x00, 0x00, 0x00, 0x61, 0x00, 0xee, // DoAbc (with header)
+      0xc9, 0x15, 0x07, 0x00, // DefineBinaryData (truncated)
+    ];
+    let borrowed: Vec<(usize, Option<ast::TagHeader>, BorrowedTag)> = parse_borrowed_swf_tags(input, 10).collect();
+    assert!(matches!(
+      &borrowed[1].2,
+      BorrowedTag::DoAction(BorrowedDoAction { actions: Cow::Borrowed(_) })
+    ));
+    assert!(matches!(
+      &borrowed[2].2,
+      BorrowedTag::DefineSprite(BorrowedDefineSprite { tags, .. }) if matches!(tags[..], [BorrowedTag::DoAction(_)])
+    ));
+    assert!(matches!(
+      &borrowed[3].2,
+      BorrowedTag::DefineBinaryData(BorrowedDefineBinaryData { data: Cow::Borrowed(d), .. }) if d == &[1, 2, 3]
+    ));
+    assert!(matches!(&borrowed[6].2, BorrowedTag::SoundStreamBlock(_)));
+    assert!(matches!(&borrowed[7].2, BorrowedTag::DefineBitmap(_)));
+    assert!(matches!(&borrowed[10].2, BorrowedTag::DoAbc(BorrowedDoAbc { header: Some(_), .. })));
+    assert!(matches!(&borrowed[11].2, BorrowedTag::Raw(_)));
+    // `DefineSprite` has its own parser in each representation: check that they produce the same tags
+    let owned: Vec<(usize, Option<ast::TagHeader>, ast::Tag)> = borrowed
+      .into_iter()
+      .map(|(offset, header, tag)| (offset, header, tag.into_owned()))
+      .collect();
+    assert_eq!(owned, parse_swf_tags(input, 10).collect::<Vec<_>>());
+    assert!(owned.iter().all(|(_, _, tag)| !matches!(tag, ast::Tag::RawBody(_))));
+  }
+}
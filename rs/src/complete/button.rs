@@ -2,8 +2,8 @@ use crate::complete::base::skip;
 use crate::complete::display::{parse_blend_mode, parse_filter_list};
 use crate::complete::sound::parse_sound_info;
 use crate::streaming::basic_data_types::{parse_color_transform_with_alpha, parse_matrix};
+use crate::streaming::error::{LocatedError, NomResult};
 use nom::number::complete::{le_u16 as parse_le_u16, le_u8 as parse_u8};
-use nom::IResult as NomResult;
 use swf_types as swf;
 
 #[derive(PartialEq, Eq, Clone, Copy, Ord, PartialOrd)]
@@ -100,9 +100,19 @@ pub fn parse_button2_cond_action(input: &[u8]) -> NomResult<&[u8], (usize, swf::
   use nom::combinator::map;
   let (input, next_action_offset) = map(parse_le_u16, usize::from)(input)?;
   let (input, conditions) = parse_button_cond(input)?;
+  // The offset is relative to the start of the cond action, the last one extends to the end of the tag
+  let (input, actions) = if next_action_offset == 0 {
+    (&input[input.len()..], input)
+  } else {
+    let actions_size = match next_action_offset.checked_sub(4) {
+      Some(x) => x,
+      None => return Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Verify))),
+    };
+    nom::bytes::complete::take(actions_size)(input)?
+  };
   let value = swf::ButtonCondAction {
     conditions: Some(conditions),
-    actions: input.to_vec(),
+    actions: actions.to_vec(),
   };
   Ok((input, (next_action_offset, value)))
 }
@@ -128,7 +138,7 @@ pub fn parse_button_cond(input: &[u8]) -> NomResult<&[u8], swf::ButtonCond> {
   let idle_to_over_down = (flags & (1 << 7)) != 0;
   let over_down_to_idle = (flags & (1 << 8)) != 0;
   let key_press =
-    key_press_from_code((flags >> 9) & 0x7f).map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch)))?;
+    key_press_from_code((flags >> 9) & 0x7f).map_err(|_| nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch)))?;
 
   Ok((
     input,
@@ -156,3 +166,46 @@ pub fn parse_button_sound(input: &[u8]) -> NomResult<&[u8], Option<swf::ButtonSo
     Ok((input, Some(swf::ButtonSound { sound_id, sound_info })))
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_button2_cond_action_string() {
+    let cond = swf::ButtonCond {
+      idle_to_over_up: false,
+      over_up_to_idle: false,
+      over_up_to_over_down: false,
+      over_down_to_over_up: false,
+      over_down_to_out_down: false,
+      out_down_to_over_down: false,
+      out_down_to_idle: false,
+      idle_to_over_down: false,
+      over_down_to_idle: false,
+      key_press: None,
+    };
+    let input: &[u8] = &[
+      0x06, 0x00, 0x00, 0x00, 0x06, 0x00, // Offset to the next condition, conditions, `Play`, `End`
+      0x00, 0x00, 0x00, 0x00, 0x07, 0x00, // Last condition, extending to the end of the tag: `Stop`, `End`
+    ];
+    let expected = vec![
+      swf::ButtonCondAction {
+        conditions: Some(cond),
+        actions: vec![0x06, 0x00],
+      },
+      swf::ButtonCondAction {
+        conditions: Some(cond),
+        actions: vec![0x07, 0x00],
+      },
+    ];
+    let (_, actual) = parse_button2_cond_action_string(input).unwrap();
+    assert_eq!(actual, expected);
+  }
+
+  #[test]
+  fn test_parse_button2_cond_action_invalid_offset() {
+    let input: &[u8] = &[0x02, 0x00, 0x00, 0x00, 0x00, 0x00];
+    assert!(parse_button2_cond_action(input).is_err());
+  }
+}
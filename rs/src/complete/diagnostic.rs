@@ -0,0 +1,374 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::complete::tag::{split_tag, try_parse_tag_body_with, NestedTag, TagSplit};
+use crate::legacy::LegacyEncoding;
+use crate::streaming::error::LocatedError;
+use crate::streaming::tag::parse_tag_header;
+use swf_types as ast;
+
+/// Describes a tag that could not be parsed.
+///
+/// Such tags are kept in the movie as `Raw` (if the tag header or body is
+/// truncated) or `RawBody` (if the body parser failed).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TagDiagnostic {
+  /// Position of the tag in the movie.
+  ///
+  /// The first item is the index of the tag in the top-level tag list. Tags
+  /// nested in a `DefineSprite` have one more item per nesting level: the index
+  /// of the tag in the sprite's tag list.
+  pub path: Vec<usize>,
+
+  /// Tag code, or `None` if the tag header is truncated.
+  pub code: Option<u16>,
+
+  /// Offset of the start of the tag (its header) in the decompressed payload.
+  ///
+  /// The decompressed payload corresponds to all the bytes following the SWF
+  /// signature (the first 8 bytes of the file).
+  pub offset: usize,
+
+  /// Offset of the input where the failure occurred, in the decompressed payload.
+  ///
+  /// For bit-level parsers (shapes, matrices, etc.), this is the offset of the
+  /// byte containing the failing bit.
+  pub error_offset: usize,
+
+  /// Position of the failing bit in the byte at `error_offset`.
+  ///
+  /// `0` is the most significant bit. It is only non-zero for bit-level parsers.
+  pub error_bit: usize,
+
+  /// Name of the tag body parser that failed, or `None` if no parser was run.
+  pub parser: Option<&'static str>,
+
+  /// Name of the innermost named sub-parser that failed (for example `parse_matrix`),
+  /// or `None` if the failure is not inside a named sub-parser.
+  pub sub_parser: Option<&'static str>,
+
+  /// Reason of the failure.
+  pub kind: TagDiagnosticKind,
+}
+
+impl fmt::Display for TagDiagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("tag ")?;
+    for (i, index) in self.path.iter().enumerate() {
+      if i > 0 {
+        f.write_str(".")?;
+      }
+      write!(f, "{}", index)?;
+    }
+    if let Some(code) = self.code {
+      write!(f, " (code {})", code)?;
+    }
+    write!(f, " at offset {}", self.offset)?;
+    if let Some(parser) = self.parser {
+      write!(f, ": `{}` failed", parser)?;
+    }
+    if let Some(sub_parser) = self.sub_parser {
+      write!(f, " in `{}`", sub_parser)?;
+    }
+    write!(f, " at offset {}", self.error_offset)?;
+    if self.error_bit != 0 {
+      write!(f, " (bit {})", self.error_bit)?;
+    }
+    write!(f, ": {}", self.kind)
+  }
+}
+
+/// Reason why a tag could not be parsed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TagDiagnosticKind {
+  /// The input ends in the middle of the tag header.
+  IncompleteHeader,
+
+  /// The tag header announces a body longer than the available input.
+  IncompleteBody {
+    /// Body length announced by the tag header.
+    expected: usize,
+    /// Available length.
+    available: usize,
+  },
+
+  /// The tag code is not supported by the parser.
+  UnknownCode,
+
+  /// The body parser reached the end of the tag body before completing.
+  UnexpectedEndOfBody,
+
+  /// The body parser rejected the data.
+  ///
+  /// The value is the kind of the `nom` parser that failed, for example
+  /// `Switch` for an unknown enum code or `Verify` for invalid strings.
+  Invalid(nom::error::ErrorKind),
+}
+
+impl fmt::Display for TagDiagnosticKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TagDiagnosticKind::IncompleteHeader => f.write_str("incomplete tag header"),
+      TagDiagnosticKind::IncompleteBody { expected, available } => write!(
+        f,
+        "incomplete tag body: expected {} bytes, only {} available",
+        expected, available
+      ),
+      TagDiagnosticKind::UnknownCode => f.write_str("unknown tag code"),
+      TagDiagnosticKind::UnexpectedEndOfBody => f.write_str("unexpected end of tag body"),
+      TagDiagnosticKind::Invalid(nom::error::ErrorKind::Switch) => f.write_str("invalid enum value"),
+      TagDiagnosticKind::Invalid(nom::error::ErrorKind::Verify) => f.write_str("invalid value"),
+      TagDiagnosticKind::Invalid(kind) => write!(f, "invalid data ({})", kind.description()),
+    }
+  }
+}
+
+/// Parses the string of tags from a completely loaded input, collecting diagnostics.
+///
+/// This function mirrors `crate::complete::parse_swf_tags`:
+/// truncated tags produce a final `Raw` tag.
+///
+/// `offset` is the offset of `input` in the decompressed payload and `path` the
+/// path of the tag list containing the parsed tags.
+pub(crate) fn parse_tag_block_string_with_diagnostics(
+  mut input: &[u8],
+  mut offset: usize,
+  swf_version: u8,
+  legacy_encoding: LegacyEncoding,
+  path: &[usize],
+  diagnostics: &mut Vec<TagDiagnostic>,
+) -> Vec<ast::Tag> {
+  let mut tags: Vec<ast::Tag> = Vec::new();
+  loop {
+    let tag_path = [path, &[tags.len()]].concat();
+    let (remaining, tag) = match split_tag(input) {
+      (_, TagSplit::End) => break,
+      (remaining, TagSplit::Tag(header, body)) => {
+        let tag = NestedTag {
+          offset,
+          header_len: input.len() - remaining.len() - body.len(),
+          header,
+          body,
+        };
+        (remaining, parse_tag_with_diagnostics(tag, swf_version, legacy_encoding, tag_path, diagnostics))
+      }
+      (remaining, TagSplit::Truncated(header)) => {
+        let kind = match (header, parse_tag_header(input)) {
+          (Some(header), Ok((body, _))) => TagDiagnosticKind::IncompleteBody {
+            expected: usize::try_from(header.length).unwrap(),
+            available: body.len(),
+          },
+          _ => TagDiagnosticKind::IncompleteHeader,
+        };
+        diagnostics.push(TagDiagnostic {
+          path: tag_path,
+          code: header.map(|header| header.code),
+          offset,
+          error_offset: offset + input.len(),
+          error_bit: 0,
+          parser: None,
+          sub_parser: None,
+          kind,
+        });
+        (remaining, ast::Tag::Raw(ast::tags::Raw { data: input.to_vec() }))
+      }
+    };
+    tags.push(tag);
+    offset += input.len() - remaining.len();
+    input = remaining;
+  }
+  tags
+}
+
+/// Parses a tag body, pushing a diagnostic and returning a `RawBody` on failure.
+///
+/// `tag.offset` is the offset of the tag in the decompressed payload.
+/// The diagnostics of the tags nested in a `DefineSprite` are only kept if the sprite itself is valid.
+fn parse_tag_with_diagnostics(
+  tag: NestedTag,
+  swf_version: u8,
+  legacy_encoding: LegacyEncoding,
+  path: Vec<usize>,
+  diagnostics: &mut Vec<TagDiagnostic>,
+) -> ast::Tag {
+  let body_offset = tag.offset + tag.header_len;
+  let code = tag.header.code;
+  let mut nested_diagnostics: Vec<TagDiagnostic> = Vec::new();
+  let mut nested_count: usize = 0;
+  let result = try_parse_tag_body_with(tag.body, code, swf_version, legacy_encoding, &mut |nested: NestedTag| {
+    let nested_path = [&path[..], &[nested_count]].concat();
+    nested_count += 1;
+    let nested = NestedTag {
+      offset: body_offset + nested.offset,
+      ..nested
+    };
+    parse_tag_with_diagnostics(nested, swf_version, legacy_encoding, nested_path, &mut nested_diagnostics)
+  });
+  match result {
+    Ok((_, parsed)) => {
+      diagnostics.extend(nested_diagnostics);
+      parsed
+    }
+    Err(e) => {
+      let failure = TagFailure::new(e, tag.body, body_offset);
+      diagnostics.push(TagDiagnostic {
+        path,
+        code: Some(code),
+        offset: tag.offset,
+        error_offset: failure.offset,
+        error_bit: failure.bit,
+        parser: failure.parser,
+        sub_parser: failure.sub_parser,
+        kind: failure.kind,
+      });
+      ast::Tag::RawBody(ast::tags::RawBody {
+        code,
+        data: tag.body.to_vec(),
+      })
+    }
+  }
+}
+
+/// Failure of a tag body parser, located in the decompressed payload.
+struct TagFailure {
+  kind: TagDiagnosticKind,
+  offset: usize,
+  bit: usize,
+  parser: Option<&'static str>,
+  sub_parser: Option<&'static str>,
+}
+
+impl TagFailure {
+  /// Converts a `nom` error of a tag body parser.
+  ///
+  /// `body` is the tag body and `body_offset` its offset in the payload.
+  fn new(err: nom::Err<LocatedError<&[u8]>>, body: &[u8], body_offset: usize) -> Self {
+    let e = match err {
+      nom::Err::Error(e) | nom::Err::Failure(e) => e,
+      nom::Err::Incomplete(_) => {
+        // Tag body parsers report incomplete input as `Eof` errors, this is only a fallback.
+        return Self {
+          kind: TagDiagnosticKind::UnexpectedEndOfBody,
+          offset: body_offset + body.len(),
+          bit: 0,
+          parser: None,
+          sub_parser: None,
+        };
+      }
+    };
+    // The error input is usually a sub-slice of the body, fall back to the end of the body otherwise.
+    let body_start = body.as_ptr() as usize;
+    let error_start = e.input.as_ptr() as usize;
+    let offset = if body_start <= error_start && error_start + e.input.len() <= body_start + body.len() {
+      body_offset + (error_start - body_start)
+    } else {
+      body_offset + body.len()
+    };
+    let kind = match (e.root_parser, e.code) {
+      (None, _) => TagDiagnosticKind::UnknownCode,
+      (Some(_), nom::error::ErrorKind::Eof) | (Some(_), nom::error::ErrorKind::Complete) => {
+        TagDiagnosticKind::UnexpectedEndOfBody
+      }
+      (Some(_), code) => TagDiagnosticKind::Invalid(code),
+    };
+    Self {
+      kind,
+      offset,
+      bit: e.bit,
+      parser: e.root_parser,
+      sub_parser: e.parser.filter(|_| e.parser != e.root_parser),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::complete::parse_swf_tags;
+
+  #[test]
+  fn test_diagnostics_match_parse_swf_tags() {
+    let input: &[u8] = &[
+      // ShowFrame
+      0x40, 0x00, //
+      // DefineSprite (id 1, 1 frame), containing a truncated `SetBackgroundColor` and `ShowFrame`
+      0xcc, 0x09, 0x01, 0x00, 0x01, 0x00, 0x42, 0x02, 0xff, 0xff, 0x40, 0x00, 0x00, 0x00, //
+      // SetBackgroundColor with a body too short
+      0x42, 0x02, 0xff, 0xff, //
+      // Unknown tag
+      0xc1, 0xff, 0x01, //
+      // DefineFontAlignZones with an invalid CSM table hint (bits 0-1 of its third byte)
+      0x43, 0x12, 0x01, 0x00, 0xc0, //
+      // Truncated tag
+      0x43, 0x03, 0xff,
+    ];
+    let mut diagnostics: Vec<TagDiagnostic> = Vec::new();
+    let tags = parse_tag_block_string_with_diagnostics(input, 100, 10, LegacyEncoding::default(), &[], &mut diagnostics);
+
+    let expected: Vec<ast::Tag> = parse_swf_tags(input, 10).map(|(_, _, tag)| tag).collect();
+    assert_eq!(tags, expected);
+    assert_eq!(
+      diagnostics,
+      vec![
+        TagDiagnostic {
+          path: vec![1, 0],
+          code: Some(9),
+          offset: 108,
+          error_offset: 112,
+          error_bit: 0,
+          parser: Some("parse_set_background_color_tag"),
+          sub_parser: None,
+          kind: TagDiagnosticKind::UnexpectedEndOfBody,
+        },
+        TagDiagnostic {
+          path: vec![2],
+          code: Some(9),
+          offset: 116,
+          error_offset: 120,
+          error_bit: 0,
+          parser: Some("parse_set_background_color_tag"),
+          sub_parser: None,
+          kind: TagDiagnosticKind::UnexpectedEndOfBody,
+        },
+        TagDiagnostic {
+          path: vec![3],
+          code: Some(1023),
+          offset: 120,
+          error_offset: 122,
+          error_bit: 0,
+          parser: None,
+          sub_parser: None,
+          kind: TagDiagnosticKind::UnknownCode,
+        },
+        TagDiagnostic {
+          path: vec![4],
+          code: Some(73),
+          offset: 123,
+          error_offset: 127,
+          error_bit: 2,
+          parser: Some("parse_define_font_align_zones"),
+          sub_parser: Some("parse_csm_table_hint"),
+          kind: TagDiagnosticKind::Invalid(nom::error::ErrorKind::Switch),
+        },
+        TagDiagnostic {
+          path: vec![5],
+          code: Some(13),
+          offset: 128,
+          error_offset: 131,
+          error_bit: 0,
+          parser: None,
+          sub_parser: None,
+          kind: TagDiagnosticKind::IncompleteBody {
+            expected: 3,
+            available: 1,
+          },
+        },
+      ]
+    );
+    assert_eq!(
+      diagnostics[3].to_string(),
+      "tag 4 (code 73) at offset 123: `parse_define_font_align_zones` failed in `parse_csm_table_hint` at offset 127 \
+       (bit 2): invalid enum value"
+    );
+  }
+}
@@ -1,9 +1,9 @@
 use crate::complete::base::skip;
 use crate::streaming::basic_data_types::{parse_le_fixed16_p16, parse_le_fixed8_p8, parse_straight_s_rgba8};
+use crate::streaming::error::{LocatedError, NomResult};
 use nom::number::complete::{
   le_f32 as parse_le_f32, le_u16 as parse_le_u16, le_u32 as parse_le_u32, le_u8 as parse_u8,
 };
-use nom::IResult as NomResult;
 use swf_types as swf;
 
 #[allow(unused_variables)]
@@ -25,7 +25,7 @@ pub fn parse_blend_mode(input: &[u8]) -> NomResult<&[u8], swf::BlendMode> {
     12 => swf::BlendMode::Erase,
     13 => swf::BlendMode::Overlay,
     14 => swf::BlendMode::Hardlight,
-    _ => return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch))),
+    _ => return Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch))),
   };
   Ok((input, blend_mode))
 }
@@ -147,7 +147,7 @@ pub fn parse_filter(input: &[u8]) -> NomResult<&[u8], swf::Filter> {
     5 => map(parse_convolution_filter, swf::Filter::Convolution)(input),
     6 => map(parse_color_matrix_filter, swf::Filter::ColorMatrix)(input),
     7 => map(parse_gradient_bevel_filter, swf::Filter::GradientBevel)(input),
-    _ => Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch))),
+    _ => Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch))),
   }
 }
 
@@ -305,7 +305,7 @@ fn parse_filter_gradient(input: &[u8], color_count: usize) -> NomResult<&[u8], V
       Err(e) => return Err(e),
     };
   }
-  for mut color_stop in &mut result {
+  for color_stop in &mut result {
     match parse_u8(current_input) {
       Ok((next_input, ratio)) => {
         color_stop.ratio = ratio;
@@ -0,0 +1,211 @@
+use std::ops::Range;
+
+use crate::complete::span::TagSpan;
+use crate::complete::tag::{split_tag, try_parse_tag_body_with, NestedTag, TagSplit};
+use crate::legacy::LegacyEncoding;
+use crate::streaming::tag::parse_tag_header;
+use swf_types as ast;
+
+/// Encoding details of a parsed SWF file, see [[crate::complete::parse_swf_with_encoding]].
+///
+/// The AST does not keep how values were encoded (bit widths, tag header forms, padding, ...). This
+/// record keeps the original bytes so [[crate::emitter::emit_swf_with_encoding]] can reuse them for the
+/// parts of the movie that were not modified.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MovieEncoding {
+  /// Original SWF signature, including the declared uncompressed file length.
+  pub signature: ast::SwfSignature,
+  /// Original bytes following the signature for compressed movies, empty for uncompressed movies.
+  pub data: Vec<u8>,
+  /// Decompressed payload.
+  pub payload: Vec<u8>,
+  /// Movie header, as parsed.
+  pub header: ast::Header,
+  /// Length of the movie header in the payload.
+  pub header_len: usize,
+  /// Encoding of the top-level tags.
+  pub tags: TagStringEncoding,
+  /// Encoding of the strings of SWF 5 (and earlier) movies.
+  pub legacy_encoding: LegacyEncoding,
+}
+
+/// Encoding of a string of tags.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TagStringEncoding {
+  /// Encoding of each tag, in order.
+  pub tags: Vec<TagEncoding>,
+  /// Range of the `End` tag and of the bytes following it, in the payload.
+  ///
+  /// The range is empty if the tag string is not terminated.
+  pub end: Range<usize>,
+}
+
+/// Encoding of a single tag.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TagEncoding {
+  /// Location of the tag in the payload.
+  pub span: TagSpan,
+  /// Tag, as parsed.
+  pub tag: ast::Tag,
+  /// Encoding of the nested tags, for a valid `DefineSprite`.
+  pub nested: Option<TagStringEncoding>,
+}
+
+/// Parses the string of tags from a completely loaded input, collecting the encoding of each tag.
+///
+/// `offset` is the offset of `input` in the decompressed payload.
+pub(crate) fn parse_tag_block_string_with_encoding(
+  mut input: &[u8],
+  mut offset: usize,
+  swf_version: u8,
+  legacy_encoding: LegacyEncoding,
+) -> (Vec<ast::Tag>, TagStringEncoding) {
+  let mut tags: Vec<ast::Tag> = Vec::new();
+  let mut encodings: Vec<TagEncoding> = Vec::new();
+  loop {
+    let path = vec![tags.len()];
+    let (remaining, encoding) = match split_tag(input) {
+      (_, TagSplit::End) => break,
+      (remaining, TagSplit::Tag(header, body)) => {
+        let tag = NestedTag {
+          offset,
+          header_len: input.len() - remaining.len() - body.len(),
+          header,
+          body,
+        };
+        (remaining, parse_tag_with_encoding(tag, swf_version, legacy_encoding, path))
+      }
+      (remaining, TagSplit::Truncated(header)) => {
+        let span = TagSpan {
+          path,
+          code: header.map(|header| header.code),
+          offset,
+          header_len: parse_tag_header(input).ok().map(|(body, _)| input.len() - body.len()),
+          len: input.len(),
+        };
+        let tag = ast::Tag::Raw(ast::tags::Raw { data: input.to_vec() });
+        (remaining, TagEncoding { span, tag, nested: None })
+      }
+    };
+    tags.push(encoding.tag.clone());
+    encodings.push(encoding);
+    offset += input.len() - remaining.len();
+    input = remaining;
+  }
+  let encoding = TagStringEncoding {
+    tags: encodings,
+    end: offset..(offset + input.len()),
+  };
+  (tags, encoding)
+}
+
+/// Parses a complete tag and collects its encoding, including the encoding of its nested tags.
+///
+/// `tag.offset` is the offset of the tag in the decompressed payload.
+fn parse_tag_with_encoding(
+  tag: NestedTag,
+  swf_version: u8,
+  legacy_encoding: LegacyEncoding,
+  path: Vec<usize>,
+) -> TagEncoding {
+  let body_offset = tag.offset + tag.header_len;
+  let body_end = body_offset + tag.body.len();
+  let code = tag.header.code;
+  let span = TagSpan {
+    path: path.clone(),
+    code: Some(code),
+    offset: tag.offset,
+    header_len: Some(tag.header_len),
+    len: tag.header_len + tag.body.len(),
+  };
+  let mut nested_encodings: Vec<TagEncoding> = Vec::new();
+  let result = try_parse_tag_body_with(tag.body, code, swf_version, legacy_encoding, &mut |nested: NestedTag| {
+    let nested_path = [&path[..], &[nested_encodings.len()]].concat();
+    let nested = NestedTag {
+      offset: body_offset + nested.offset,
+      ..nested
+    };
+    let encoding = parse_tag_with_encoding(nested, swf_version, legacy_encoding, nested_path);
+    let tag = encoding.tag.clone();
+    nested_encodings.push(encoding);
+    tag
+  });
+  match result {
+    Ok((_, tag)) => {
+      let nested = match &tag {
+        ast::Tag::DefineSprite(_) => {
+          // The nested tags start after the sprite id and frame count
+          const SPRITE_HEADER_LEN: usize = 4;
+          let end_offset = match nested_encodings.last() {
+            Some(last) => last.span.range().end,
+            None => body_offset + SPRITE_HEADER_LEN,
+          };
+          Some(TagStringEncoding {
+            tags: nested_encodings,
+            end: end_offset..body_end,
+          })
+        }
+        _ => None,
+      };
+      TagEncoding { span, tag, nested }
+    }
+    Err(_) => TagEncoding {
+      span,
+      tag: ast::Tag::RawBody(ast::tags::RawBody {
+        code,
+        data: tag.body.to_vec(),
+      }),
+      nested: None,
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::complete::parse_swf_tags;
+
+  #[test]
+  fn test_encoding_of_sprite() {
+    let input: &[u8] = &[
+      // ShowFrame, using the long header form
+      0x7f, 0x00, 0x00, 0x00, 0x00, 0x00, //
+      // DefineSprite (id 1, 1 frame), containing `ShowFrame` and a padding byte after `End`
+      0xc9, 0x09, 0x01, 0x00, 0x01, 0x00, 0x40, 0x00, 0x00, 0x00, 0xff, //
+      // End, followed by padding
+      0x00, 0x00, 0x00, 0x00,
+    ];
+    let (tags, encoding) = parse_tag_block_string_with_encoding(input, 10, 10, LegacyEncoding::default());
+
+    let expected: Vec<ast::Tag> = parse_swf_tags(input, 10).map(|(_, _, tag)| tag).collect();
+    assert_eq!(tags, expected);
+    assert_eq!(encoding.end, 27..31);
+    assert_eq!(encoding.tags.len(), 2);
+    assert!(encoding.tags[0].span.is_long_header());
+    assert_eq!(encoding.tags[0].nested, None);
+    let sprite = &encoding.tags[1];
+    assert_eq!(sprite.span.range(), 16..27);
+    assert_eq!(sprite.tag, tags[1]);
+    let nested = sprite.nested.as_ref().unwrap();
+    assert_eq!(nested.end, 24..27);
+    assert_eq!(nested.tags.len(), 1);
+    assert_eq!(nested.tags[0].span.path, vec![1, 0]);
+    assert_eq!(nested.tags[0].span.range(), 22..24);
+    assert_eq!(nested.tags[0].tag, ast::Tag::ShowFrame);
+  }
+
+  #[test]
+  fn test_encoding_of_unterminated_string() {
+    let input: &[u8] = &[
+      // ShowFrame
+      0x40, 0x00, //
+      // Truncated tag
+      0x43, 0x03, 0xff, //
+    ];
+    let (tags, encoding) = parse_tag_block_string_with_encoding(input, 0, 10, LegacyEncoding::default());
+
+    assert_eq!(tags[1], ast::Tag::Raw(ast::tags::Raw { data: vec![0x43, 0x03, 0xff] }));
+    assert_eq!(encoding.end, 5..5);
+    assert_eq!(encoding.tags[1].span.range(), 2..5);
+  }
+}
@@ -1,6 +1,6 @@
 use crate::complete::base::skip;
+use crate::streaming::error::NomResult;
 use nom::number::complete::{be_u16 as parse_be_u16, be_u32 as parse_be_u32};
-use nom::IResult as NomResult;
 
 pub struct ImageDimensions {
   pub width: usize,
@@ -81,8 +81,8 @@ pub fn get_jpeg_image_dimensions(mut input: &[u8]) -> Result<ImageDimensions, ()
 /// There are two types of chunks:
 /// - Standalone markers: they consist in only the marker
 /// - Marker sequences: the marker is followed by a sequence. The sequence starts with a
-/// `sequence_size` field followed by data. The `sequence_size` is a big-endian U16, it includes the
-/// `sequence_size` field its and data, but not the sequence marker.
+///   `sequence_size` field followed by data. The `sequence_size` is a big-endian U16, it includes the
+///   `sequence_size` field its and data, but not the sequence marker.
 ///
 /// This functions returns the next JPEG chunk.
 /// If a chunk is found, preceding garbage is skipped and the result is `Ok(suffix, Some(chunk))`
@@ -111,7 +111,8 @@ fn take_next_jpeg_chunk(input: &[u8]) -> NomResult<&[u8], Option<&[u8]>> {
         return match marker_type {
           JpegMarkerType::Standalone => map(take(2usize), Some)(input),
           JpegMarkerType::Sequence => {
-            let (_, size) = parse_be_u16(input)?;
+            // The size follows the 2-byte marker
+            let (_, size) = parse_be_u16(&input[2..])?;
             map(take(2usize + usize::from(size)), Some)(input)
           }
         };
@@ -195,3 +196,43 @@ pub(crate) fn is_sniffed_gif(image_data: &[u8]) -> bool {
 fn test_image_start(image_data: &[u8], start_bytes: &[u8]) -> bool {
   image_data.len() >= start_bytes.len() && image_data[..start_bytes.len()] == *start_bytes
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_get_jpeg_image_dimensions() {
+    let input: &[u8] = &[
+      0xff, 0xd8, // SOI
+      0xff, 0xe0, 0x00, 0x04, 0x4a, 0x46, // APP0
+      0xff, 0xc0, 0x00, 0x08, 0x08, 0x00, 0x10, 0x00, 0x20, 0x00, // SOF0, 32x16
+      0xff, 0xd9, // EOI
+    ];
+    let dimensions = get_jpeg_image_dimensions(input).unwrap();
+    assert_eq!((dimensions.width, dimensions.height), (32, 16));
+  }
+
+  #[test]
+  fn test_take_next_jpeg_chunk() {
+    let input: &[u8] = &[
+      0xaa, 0xff, 0xff, // Garbage and padding
+      0xff, 0xe0, 0x00, 0x04, 0x4a, 0x46, // APP0: the size follows the marker and excludes it
+      0xff, 0xd9, // EOI
+    ];
+    let (input, chunk) = take_next_jpeg_chunk(input).unwrap();
+    assert_eq!(chunk, Some(&[0xff, 0xe0, 0x00, 0x04, 0x4a, 0x46][..]));
+    let (input, chunk) = take_next_jpeg_chunk(input).unwrap();
+    assert_eq!(chunk, Some(&[0xff, 0xd9][..]));
+    assert_eq!(take_next_jpeg_chunk(input).unwrap(), (&[][..], None));
+  }
+
+  #[test]
+  fn test_take_next_jpeg_chunk_truncated() {
+    // APP0 declaring 4 bytes, with only 3 available
+    assert!(take_next_jpeg_chunk(&[0xff, 0xe0, 0x00, 0x04, 0x4a]).is_err());
+    // Missing size
+    assert!(take_next_jpeg_chunk(&[0xff, 0xe0, 0x00]).is_err());
+    assert!(get_jpeg_image_dimensions(&[0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04]).is_err());
+  }
+}
@@ -1,16 +1,37 @@
 pub(crate) mod base;
+pub(crate) mod borrowed;
 pub(crate) mod button;
+pub(crate) mod diagnostic;
 pub(crate) mod display;
+pub(crate) mod encoding;
 pub(crate) mod gradient;
 pub(crate) mod image;
 pub(crate) mod morph_shape;
 pub(crate) mod movie;
 pub(crate) mod shape;
+pub(crate) mod skim;
 pub(crate) mod sound;
+pub(crate) mod span;
 pub(crate) mod tag;
 pub(crate) mod text;
 pub(crate) mod video;
 
+pub use borrowed::{
+  parse_borrowed_movie, parse_borrowed_swf_tags, BorrowedDefineBinaryData, BorrowedDefineBitmap,
+  BorrowedDefineJpegTables, BorrowedDefineSound, BorrowedDefineSprite, BorrowedDoAbc, BorrowedDoAction,
+  BorrowedDoInitAction, BorrowedRaw, BorrowedRawBody, BorrowedSoundStreamBlock, BorrowedTag, BorrowedTagIter,
+  BorrowedVideoFrame,
+};
+pub use diagnostic::{TagDiagnostic, TagDiagnosticKind};
+pub use encoding::{MovieEncoding, TagEncoding, TagStringEncoding};
+pub use movie::decompress_swf;
+pub use movie::parse_movie;
 pub use movie::parse_swf;
+pub use movie::parse_swf_with_diagnostics;
+pub use movie::parse_swf_with_encoding;
+pub use movie::parse_swf_with_legacy_encoding;
+pub use movie::parse_swf_with_spans;
 pub use movie::SwfParseError;
-pub use tag::parse_tag;
+pub use skim::{skim_tags, SkimIter, TagHandle};
+pub use span::TagSpan;
+pub use tag::{parse_swf_tags, parse_tag, parse_tag_with_legacy_encoding, TagIter};
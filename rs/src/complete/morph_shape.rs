@@ -6,8 +6,10 @@ use crate::streaming::basic_data_types::{
   do_parse_u16_bits, do_parse_u32_bits, parse_bool_bits, parse_i32_bits, parse_le_fixed8_p8, parse_matrix,
   parse_straight_s_rgba8, parse_u16_bits,
 };
+use crate::streaming::error::{LocatedError, NomResult};
+use nom::error::context;
 use nom::number::complete::{le_u16 as parse_le_u16, le_u8 as parse_u8};
-use nom::{IResult as NomResult, Needed};
+use nom::Needed;
 use std::convert::TryFrom;
 use swf_types as swf;
 
@@ -23,7 +25,7 @@ pub fn parse_morph_shape(input: &[u8], version: MorphShapeVersion) -> NomResult<
   // Skip offset to end records
   // TODO: Read this offset and assert that it is valid
   let (input, _end_offset) = take(4usize)(input)?;
-  bits(|i| parse_morph_shape_bits(i, version))(input)
+  context("parse_morph_shape", bits(|i| parse_morph_shape_bits(i, version)))(input)
 }
 
 pub fn parse_morph_shape_bits(
@@ -49,7 +51,7 @@ pub fn parse_morph_shape_bits(
 
 fn parse_style_bits_len(input: &[u8]) -> NomResult<&[u8], StyleBits> {
   use nom::bits::bits;
-  bits(parse_style_bits_len_bits)(input)
+  context("parse_style_bits_len", bits(parse_style_bits_len_bits))(input)
 }
 
 fn parse_style_bits_len_bits(input: (&[u8], usize)) -> NomResult<(&[u8], usize), StyleBits> {
@@ -204,7 +206,7 @@ fn parse_morph_shape_end_record_string_bits(
     match parse_u16_bits(current_input, 6) {
       Ok((_, 0)) => {
         // Missing morph shape end record
-        return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify)));
+        return Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Verify)));
       }
       Ok((_, _)) => {}
       Err(e) => return Err(e),
@@ -242,7 +244,7 @@ fn parse_morph_shape_end_record_string_bits(
       MixedShapeRecord::MorphStyleChange(style_change)
     };
     let morph_shape_record = as_morph_shape_record(start_record, end_record)
-      .map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch)))?;
+      .map_err(|_| nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch)))?;
     result.push(morph_shape_record);
   }
 
@@ -325,7 +327,7 @@ pub fn parse_morph_fill_style(input: &[u8]) -> NomResult<&[u8], swf::MorphFillSt
       |i| parse_morph_bitmap_fill(i, false, false),
       swf::MorphFillStyle::Bitmap,
     )(input),
-    _ => Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch))),
+    _ => Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch))),
   }
 }
 
@@ -459,16 +461,16 @@ pub fn parse_morph_line_style2(input: &[u8]) -> NomResult<&[u8], swf::MorphLineS
   let no_close = (flags & (1 << 10)) != 0;
   // (Skip bits [11, 15])
   let start_cap =
-    cap_style_from_code(start_cap_style_code).map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch)))?;
+    cap_style_from_code(start_cap_style_code).map_err(|_| nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch)))?;
   let end_cap =
-    cap_style_from_code(end_cap_style_code).map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch)))?;
+    cap_style_from_code(end_cap_style_code).map_err(|_| nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch)))?;
   let (input, join) = match join_style_code {
     0 => (input, swf::JoinStyle::Round),
     1 => (input, swf::JoinStyle::Bevel),
     2 => map(parse_le_u16, |limit| {
       swf::JoinStyle::Miter(swf::join_styles::Miter { limit })
     })(input)?,
-    _ => return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch))),
+    _ => return Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch))),
   };
   let (input, fill) = if has_fill {
     parse_morph_fill_style(input)?
@@ -1,10 +1,15 @@
+use std::borrow::Cow;
 use std::fmt;
 
-use crate::complete::parse_tag;
+use crate::complete::diagnostic::{parse_tag_block_string_with_diagnostics, TagDiagnostic};
+use crate::complete::encoding::{parse_tag_block_string_with_encoding, MovieEncoding};
+use crate::complete::span::{parse_tag_block_string_with_spans, TagSpan};
+use crate::complete::tag::TagIter;
+use crate::legacy::{sniff_legacy_encoding, LegacyEncoding};
 use crate::streaming::movie::parse_swf_signature;
 use crate::streaming::decompress;
+use crate::streaming::error::{LocatedError, NomResult};
 use ast::CompressionMethod;
-use nom::IResult as NomResult;
 use swf_types as ast;
 
 /// Represents the possible parse errors when parsing an SWF file.
@@ -63,6 +68,116 @@ impl fmt::Display for SwfParseError {
 ///
 /// This function never panics.
 pub fn parse_swf(input: &[u8]) -> Result<ast::Movie, SwfParseError> {
+  parse_swf_with_legacy_encoding(input, None)
+}
+
+/// Parses a completely loaded SWF file, decoding the strings of SWF 5 (and earlier) movies with `encoding`.
+///
+/// These movies don't use UTF-8 but the codepage of the system of their author. If `encoding` is `None`, it is
+/// guessed from the language of the fonts (see [[crate::legacy::default_legacy_encoding]]). Strings of SWF 6 and later
+/// movies are always UTF-8.
+///
+/// This function never panics.
+pub fn parse_swf_with_legacy_encoding(
+  input: &[u8],
+  encoding: Option<LegacyEncoding>,
+) -> Result<ast::Movie, SwfParseError> {
+  let (signature, payload) = decompress_swf(input)?;
+  let (header, tags) = parse_movie(&payload, signature.swf_version)?;
+  let encoding = encoding.unwrap_or_else(|| sniff_legacy_encoding(tags.remaining(), signature.swf_version));
+  let tags: Vec<ast::Tag> = tags.with_legacy_encoding(encoding).map(|(_, _, tag)| tag).collect();
+  Ok(ast::Movie { header, tags })
+}
+
+/// Parses a completely loaded SWF file and reports the tags that could not be parsed.
+///
+/// The returned movie is the same as the one produced by [[parse_swf]]. Each tag
+/// that was kept as `Raw` or `RawBody` has a corresponding [[TagDiagnostic]]
+/// describing where and why parsing failed.
+///
+/// This function never panics.
+pub fn parse_swf_with_diagnostics(input: &[u8]) -> Result<(ast::Movie, Vec<TagDiagnostic>), SwfParseError> {
+  let (signature, payload) = decompress_swf(input)?;
+
+  let (tags_input, header) =
+    parse_header(&payload, signature.swf_version).map_err(|_| SwfParseError::InvalidHeader)?;
+  let tags_offset = payload.len() - tags_input.len();
+  let legacy_encoding = sniff_legacy_encoding(tags_input, signature.swf_version);
+  let mut diagnostics: Vec<TagDiagnostic> = Vec::new();
+  let tags = parse_tag_block_string_with_diagnostics(
+    tags_input,
+    tags_offset,
+    signature.swf_version,
+    legacy_encoding,
+    &[],
+    &mut diagnostics,
+  );
+
+  Ok((ast::Movie { header, tags }, diagnostics))
+}
+
+/// Parses a completely loaded SWF file and reports the location of each tag.
+///
+/// The returned movie is the same as the one produced by [[parse_swf]]. Each tag, including the
+/// tags nested in a `DefineSprite`, has a corresponding [[TagSpan]] locating it in the decompressed
+/// payload (see [[decompress_swf]]). The spans are in the order of the tags, each `DefineSprite`
+/// being followed by the spans of its nested tags.
+///
+/// This function never panics.
+pub fn parse_swf_with_spans(input: &[u8]) -> Result<(ast::Movie, Vec<TagSpan>), SwfParseError> {
+  let (signature, payload) = decompress_swf(input)?;
+
+  let (tags_input, header) =
+    parse_header(&payload, signature.swf_version).map_err(|_| SwfParseError::InvalidHeader)?;
+  let tags_offset = payload.len() - tags_input.len();
+  let legacy_encoding = sniff_legacy_encoding(tags_input, signature.swf_version);
+  let mut spans: Vec<TagSpan> = Vec::new();
+  let tags =
+    parse_tag_block_string_with_spans(tags_input, tags_offset, signature.swf_version, legacy_encoding, &mut spans);
+
+  Ok((ast::Movie { header, tags }, spans))
+}
+
+/// Parses a completely loaded SWF file and records how it was encoded.
+///
+/// The returned movie is the same as the one produced by [[parse_swf]]. The [[MovieEncoding]] keeps the
+/// original bytes of the file: passing it to [[crate::emitter::emit_swf_with_encoding]] with the unmodified
+/// movie produces the original file, byte for byte.
+///
+/// This function never panics.
+pub fn parse_swf_with_encoding(input: &[u8]) -> Result<(ast::Movie, MovieEncoding), SwfParseError> {
+  let (signature, payload) = decompress_swf(input)?;
+
+  let (tags_input, header) =
+    parse_header(&payload, signature.swf_version).map_err(|_| SwfParseError::InvalidHeader)?;
+  let header_len = payload.len() - tags_input.len();
+  let legacy_encoding = sniff_legacy_encoding(tags_input, signature.swf_version);
+  let (tags, tags_encoding) =
+    parse_tag_block_string_with_encoding(tags_input, header_len, signature.swf_version, legacy_encoding);
+  let movie = ast::Movie { header, tags };
+
+  const SIGNATURE_LEN: usize = 8;
+  let data: Vec<u8> = match signature.compression_method {
+    CompressionMethod::None => Vec::new(),
+    _ => input[SIGNATURE_LEN..].to_vec(),
+  };
+  let encoding = MovieEncoding {
+    signature,
+    data,
+    payload: payload.into_owned(),
+    header,
+    header_len,
+    tags: tags_encoding,
+    legacy_encoding,
+  };
+  Ok((movie, encoding))
+}
+
+/// Reads the SWF signature of a completely loaded SWF file and returns it with the decompressed payload.
+///
+/// The payload is borrowed from `input` if it is not compressed. It can be passed to [[parse_movie]],
+/// or to [[crate::complete::parse_borrowed_movie]] to parse tags borrowing their data.
+pub fn decompress_swf(input: &[u8]) -> Result<(ast::SwfSignature, Cow<'_, [u8]>), SwfParseError> {
   let (input, signature) = match parse_swf_signature(input) {
     Ok(ok) => ok,
     Err(_) => return Err(SwfParseError::InvalidSignature),
@@ -73,7 +188,7 @@ pub fn parse_swf(input: &[u8]) -> Result<ast::Movie, SwfParseError> {
     #[cfg(feature="deflate")]
     CompressionMethod::Deflate => decompress::decompress_zlib(input),
     #[cfg(feature="lzma")]
-    CompressionMethod::Lzma => decompress::decompress_lzma(input),
+    CompressionMethod::Lzma => decompress::decompress_lzma(input, signature.uncompressed_file_length.saturating_sub(8)),
     #[allow(unreachable_patterns)]
     method => return Err(SwfParseError::UnsupportedCompression(method)),
   };
@@ -83,41 +198,27 @@ pub fn parse_swf(input: &[u8]) -> Result<ast::Movie, SwfParseError> {
   // TODO: should we check that the input was fully consumed?
   // TODO: check decompressed payload length against signature?
 
-  match parse_movie(&payload, signature.swf_version) {
-    Ok((_, movie)) => Ok(movie),
-    Err(_) => Err(SwfParseError::InvalidHeader),
-  }
+  Ok((signature, payload))
 }
 
-/// Parses a completely loaded movie.
+/// Parses the movie header of a decompressed SWF payload and returns it with a lazy iterator over
+/// the tags.
 ///
-/// The movie is the uncompressed payload of the SWF.
-fn parse_movie(input: &[u8], swf_version: u8) -> NomResult<&[u8], ast::Movie> {
-  let (input, header) = parse_header(input, swf_version)?;
-  let tags = parse_tag_block_string(input, swf_version);
-
-  Ok((&[][..], ast::Movie { header, tags }))
+/// The tag offsets are relative to the start of `payload`.
+/// See [[decompress_swf]] to get the payload of an SWF file.
+/// The strings of SWF 5 (and earlier) movies are decoded as Windows-1252, see [[TagIter::with_legacy_encoding]].
+pub fn parse_movie(payload: &[u8], swf_version: u8) -> Result<(ast::Header, TagIter<'_>), SwfParseError> {
+  match parse_header(payload, swf_version) {
+    Ok((input, header)) => Ok((header, TagIter::new(input, payload.len() - input.len(), swf_version))),
+    Err(_) => Err(SwfParseError::InvalidHeader),
+  }
 }
 
 /// Parses the movie header from a completely loaded input.
-fn parse_header(input: &[u8], swf_version: u8) -> NomResult<&[u8], ast::Header> {
+pub(crate) fn parse_header(input: &[u8], swf_version: u8) -> NomResult<&[u8], ast::Header> {
   match crate::streaming::movie::parse_header(input, swf_version) {
     Ok(ok) => Ok(ok),
-    Err(nom::Err::Incomplete(_)) => Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Complete))),
+    Err(nom::Err::Incomplete(_)) => Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Complete))),
     Err(e) => Err(e),
   }
 }
-
-/// Parses the string of tags from a completely loaded input.
-pub(crate) fn parse_tag_block_string(mut input: &[u8], swf_version: u8) -> Vec<ast::Tag> {
-  let mut tags: Vec<ast::Tag> = Vec::new();
-  loop {
-    input = match parse_tag(input, swf_version) {
-      (input, Some(tag)) => {
-        tags.push(tag);
-        input
-      }
-      (_, None) => return tags,
-    }
-  }
-}
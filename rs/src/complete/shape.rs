@@ -3,8 +3,10 @@ use crate::streaming::basic_data_types::{
   do_parse_i32_bits, do_parse_u16_bits, do_parse_u32_bits, parse_bool_bits, parse_i32_bits, parse_le_fixed8_p8,
   parse_matrix, parse_s_rgb8, parse_straight_s_rgba8, parse_u16_bits,
 };
+use crate::streaming::error::{LocatedError, NomResult};
+use nom::error::context;
 use nom::number::complete::{le_u16 as parse_le_u16, le_u8 as parse_u8};
-use nom::{IResult as NomResult, Needed};
+use nom::Needed;
 use std::convert::TryFrom;
 use swf_types as swf;
 
@@ -18,7 +20,7 @@ pub enum ShapeVersion {
 
 pub fn parse_glyph(input: &[u8]) -> NomResult<&[u8], swf::Glyph> {
   use nom::bits::bits;
-  bits(parse_glyph_bits)(input)
+  context("parse_glyph", bits(parse_glyph_bits))(input)
 }
 
 pub fn parse_glyph_bits(input: (&[u8], usize)) -> NomResult<(&[u8], usize), swf::Glyph> {
@@ -34,7 +36,7 @@ pub fn parse_glyph_bits(input: (&[u8], usize)) -> NomResult<(&[u8], usize), swf:
 
 pub fn parse_shape(input: &[u8], version: ShapeVersion) -> NomResult<&[u8], swf::Shape> {
   use nom::bits::bits;
-  bits(|i| parse_shape_bits(i, version))(input)
+  context("parse_shape", bits(|i| parse_shape_bits(i, version)))(input)
 }
 
 pub fn parse_shape_bits(input: (&[u8], usize), version: ShapeVersion) -> NomResult<(&[u8], usize), swf::Shape> {
@@ -284,7 +286,7 @@ pub fn parse_fill_style(input: &[u8], with_alpha: bool) -> NomResult<&[u8], swf:
     0x41 => map(|i| parse_bitmap_fill(i, false, true), swf::FillStyle::Bitmap)(input),
     0x42 => map(|i| parse_bitmap_fill(i, true, false), swf::FillStyle::Bitmap)(input),
     0x43 => map(|i| parse_bitmap_fill(i, false, false), swf::FillStyle::Bitmap)(input),
-    _ => Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch))),
+    _ => Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch))),
   }
 }
 
@@ -419,9 +421,9 @@ pub fn parse_line_style2(input: &[u8]) -> NomResult<&[u8], swf::LineStyle> {
   // (Skip bits [11, 15])
 
   let start_cap =
-    cap_style_from_code(start_cap_style_code).map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch)))?;
+    cap_style_from_code(start_cap_style_code).map_err(|_| nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch)))?;
   let end_cap =
-    cap_style_from_code(end_cap_style_code).map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch)))?;
+    cap_style_from_code(end_cap_style_code).map_err(|_| nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch)))?;
 
   let (input, join) = match join_style_code {
     0 => (input, swf::JoinStyle::Round),
@@ -430,7 +432,7 @@ pub fn parse_line_style2(input: &[u8]) -> NomResult<&[u8], swf::LineStyle> {
       let (input, limit) = parse_le_u16(input)?;
       (input, swf::JoinStyle::Miter(swf::join_styles::Miter { limit }))
     }
-    _ => return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch))),
+    _ => return Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch))),
   };
 
   let (input, fill) = if has_fill {
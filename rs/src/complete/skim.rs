@@ -0,0 +1,290 @@
+use crate::complete::tag::{parse_tag_body, split_tag, TagSplit};
+use crate::legacy::LegacyEncoding;
+use std::convert::TryFrom;
+use swf_types as ast;
+
+/// `DefineSprite` tag code
+const DEFINE_SPRITE_CODE: u16 = 39;
+/// Length of the `DefineSprite` fields before its nested tags (id and frame count)
+const DEFINE_SPRITE_PREFIX_LEN: usize = 4;
+
+/// Returns a lazy iterator over the tag headers of `input`, without parsing the tag bodies.
+///
+/// `input` is a completely loaded tag string, such as the part of the uncompressed SWF payload
+/// following the movie header.
+/// The tags nested in `DefineSprite` tags are yielded right after their sprite.
+/// Use `TagHandle::decode` to parse the tags you are interested in.
+///
+/// The tags nested in a malformed `DefineSprite` (with a truncated nested tag or missing its `End`
+/// tag) are still yielded, but the sprite itself decodes to a `Tag::RawBody`: see `TagHandle::is_orphan`.
+pub fn skim_tags(input: &[u8], swf_version: u8) -> SkimIter<'_> {
+  SkimIter {
+    stack: vec![SkimFrame {
+      input,
+      offset: 0,
+      parent: None,
+      is_orphan: false,
+    }],
+    swf_version,
+    encoding: LegacyEncoding::default(),
+  }
+}
+
+/// Lightweight reference to a tag found by `skim_tags`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TagHandle<'a> {
+  offset: usize,
+  header: Option<ast::TagHeader>,
+  depth: usize,
+  parent: Option<usize>,
+  data: &'a [u8],
+  body: Option<&'a [u8]>,
+  is_orphan: bool,
+  swf_version: u8,
+  encoding: LegacyEncoding,
+}
+
+impl<'a> TagHandle<'a> {
+  /// Tag code, or `None` if the tag header is truncated.
+  pub fn code(&self) -> Option<u16> {
+    self.header.map(|header| header.code)
+  }
+
+  /// Tag header, or `None` if it is truncated.
+  pub fn header(&self) -> Option<ast::TagHeader> {
+    self.header
+  }
+
+  /// Offset of the tag, relative to the start of the skimmed input.
+  pub fn offset(&self) -> usize {
+    self.offset
+  }
+
+  /// Length of the tag body as declared by its header, or `None` if the tag header is truncated.
+  pub fn length(&self) -> Option<usize> {
+    self.header.map(|header| usize::try_from(header.length).unwrap())
+  }
+
+  /// Count of `DefineSprite` tags enclosing this tag.
+  pub fn depth(&self) -> usize {
+    self.depth
+  }
+
+  /// Offset of the enclosing `DefineSprite` tag.
+  pub fn parent(&self) -> Option<usize> {
+    self.parent
+  }
+
+  /// Tag body, or `None` if the tag is truncated.
+  pub fn body(&self) -> Option<&'a [u8]> {
+    self.body
+  }
+
+  /// Returns `true` if the tag extends past the end of the input (or its enclosing sprite).
+  pub fn is_truncated(&self) -> bool {
+    self.body.is_none()
+  }
+
+  /// Returns `true` if an enclosing `DefineSprite` is malformed.
+  ///
+  /// Decoding such a sprite produces a `Tag::RawBody`, so this tag is not part of the tags of
+  /// any decoded sprite.
+  pub fn is_orphan(&self) -> bool {
+    self.is_orphan
+  }
+
+  /// Parses the tag, as `crate::complete::parse_swf_tags` would.
+  pub fn decode(&self) -> ast::Tag {
+    match (self.header, self.body) {
+      (Some(header), Some(body)) => parse_tag_body(body, header.code, self.swf_version, self.encoding),
+      _ => ast::Tag::Raw(ast::tags::Raw { data: self.data.to_vec() }),
+    }
+  }
+}
+
+/// Tag string being skimmed: the top-level string or the tags of a `DefineSprite`
+#[derive(Debug, Clone)]
+struct SkimFrame<'a> {
+  input: &'a [u8],
+  offset: usize,
+  parent: Option<usize>,
+  /// The tags of this frame are nested in a malformed sprite
+  is_orphan: bool,
+}
+
+/// Lazy iterator over the tag headers of a completely loaded tag string, see `skim_tags`.
+#[derive(Debug, Clone)]
+pub struct SkimIter<'a> {
+  stack: Vec<SkimFrame<'a>>,
+  swf_version: u8,
+  encoding: LegacyEncoding,
+}
+
+impl SkimIter<'_> {
+  /// Sets the encoding used by `TagHandle::decode` for the strings of SWF 5 (and earlier) movies, Windows-1252 by
+  /// default.
+  pub fn with_legacy_encoding(mut self, encoding: LegacyEncoding) -> Self {
+    self.encoding = encoding;
+    self
+  }
+}
+
+impl<'a> Iterator for SkimIter<'a> {
+  type Item = TagHandle<'a>;
+
+  fn next(&mut self) -> Option<TagHandle<'a>> {
+    loop {
+      let depth = self.stack.len().checked_sub(1)?;
+      let frame = self.stack.last_mut()?;
+      let input = frame.input;
+      let (remaining, header, body) = match split_tag(input) {
+        (_, TagSplit::End) => {
+          self.stack.pop();
+          continue;
+        }
+        (remaining, TagSplit::Tag(header, body)) => (remaining, Some(header), Some(body)),
+        (remaining, TagSplit::Truncated(header)) => (remaining, header, None),
+      };
+      let data = &input[..input.len() - remaining.len()];
+      let handle = TagHandle {
+        offset: frame.offset,
+        header,
+        depth,
+        parent: frame.parent,
+        data,
+        body,
+        is_orphan: frame.is_orphan,
+        swf_version: self.swf_version,
+        encoding: self.encoding,
+      };
+      frame.input = remaining;
+      frame.offset += data.len();
+
+      if let (Some(header), Some(body)) = (header, body) {
+        if header.code == DEFINE_SPRITE_CODE && body.len() >= DEFINE_SPRITE_PREFIX_LEN {
+          let header_len = data.len() - body.len();
+          let input = &body[DEFINE_SPRITE_PREFIX_LEN..];
+          self.stack.push(SkimFrame {
+            input,
+            offset: handle.offset + header_len + DEFINE_SPRITE_PREFIX_LEN,
+            parent: Some(handle.offset),
+            is_orphan: handle.is_orphan || !is_complete_tag_string(input),
+          });
+        }
+      }
+      return Some(handle);
+    }
+  }
+}
+
+impl std::iter::FusedIterator for SkimIter<'_> {}
+
+/// Checks that the tags nested in a sprite are complete and terminated by an `End` tag, as
+/// required by `crate::complete::tag::parse_define_sprite`.
+fn is_complete_tag_string(mut input: &[u8]) -> bool {
+  loop {
+    if input.is_empty() {
+      return false;
+    }
+    input = match split_tag(input) {
+      (_, TagSplit::End) => return true,
+      (remaining, TagSplit::Tag(..)) => remaining,
+      (_, TagSplit::Truncated(_)) => return false,
+    };
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::complete::parse_swf_tags;
+
+  fn describe(handle: TagHandle) -> (usize, Option<u16>, usize, Option<usize>, bool) {
+    (handle.offset(), handle.code(), handle.depth(), handle.parent(), handle.is_orphan())
+  }
+
+  #[test]
+  fn test_skim_nested_sprites() {
+    let input: &[u8] = &[
+      0xd3, 0x09, 0x01, 0x00, 0x01, 0x00, // DefineSprite (id 1)
+      0x40, 0x00, // ShowFrame (depth 1)
+      0xc9, 0x09, 0x02, 0x00, 0x01, 0x00, // DefineSprite (id 2, depth 1)
+      0x01, 0x03, 0x00, // DoAction (depth 2)
+      0x00, 0x00, // End (depth 2)
+      0x00, 0x00, // End (depth 1)
+      0x40, 0x00, // ShowFrame
+      0x00, 0x00, // End
+    ];
+    let handles: Vec<_> = skim_tags(input, 10).map(describe).collect();
+    assert_eq!(
+      handles,
+      vec![
+        (0, Some(39), 0, None, false),
+        (6, Some(1), 1, Some(0), false),
+        (8, Some(39), 1, Some(0), false),
+        (14, Some(12), 2, Some(8), false),
+        (21, Some(1), 0, None, false),
+      ]
+    );
+
+    let tags: Vec<ast::Tag> = parse_swf_tags(input, 10).map(|(_, _, tag)| tag).collect();
+    let top_level: Vec<ast::Tag> = skim_tags(input, 10).filter(|h| h.depth() == 0).map(|h| h.decode()).collect();
+    assert_eq!(top_level, tags);
+    let nested_sprite = skim_tags(input, 10).find(|h| h.offset() == 8).unwrap().decode();
+    match &tags[0] {
+      ast::Tag::DefineSprite(sprite) => assert_eq!(sprite.tags[1], nested_sprite),
+      tag => panic!("Expected DefineSprite, got {:?}", tag),
+    }
+  }
+
+  #[test]
+  fn test_skim_malformed_sprites() {
+    let input: &[u8] = &[
+      0xc9, 0x09, 0x01, 0x00, 0x01, 0x00, // DefineSprite (id 1)
+      0x40, 0x00, // ShowFrame (depth 1)
+      0x05, 0x03, 0xaa, // Truncated DoAction (depth 1)
+      0xc6, 0x09, 0x02, 0x00, 0x01, 0x00, // DefineSprite (id 2)
+      0x40, 0x00, // ShowFrame (depth 1), missing `End`
+      0x40, 0x00, // ShowFrame
+      0x00, 0x00, // End
+    ];
+    let handles: Vec<_> = skim_tags(input, 10).map(describe).collect();
+    assert_eq!(
+      handles,
+      vec![
+        (0, Some(39), 0, None, false),
+        (6, Some(1), 1, Some(0), true),
+        (8, Some(12), 1, Some(0), true),
+        (11, Some(39), 0, None, false),
+        (17, Some(1), 1, Some(11), true),
+        (19, Some(1), 0, None, false),
+      ]
+    );
+
+    let handles: Vec<TagHandle> = skim_tags(input, 10).collect();
+    assert!(handles[2].is_truncated());
+    assert_eq!(handles[2].decode(), ast::Tag::Raw(ast::tags::Raw { data: input[8..11].to_vec() }));
+    for sprite in [handles[0], handles[3]] {
+      assert!(matches!(sprite.decode(), ast::Tag::RawBody(ast::tags::RawBody { code: 39, .. })));
+    }
+  }
+
+  #[test]
+  fn test_skim_truncated_sprite() {
+    let input: &[u8] = &[
+      0xd3, 0x09, 0x01, 0x00, 0x01, 0x00, // DefineSprite (id 1), truncated
+      0x40, 0x00, // ShowFrame
+    ];
+    let handles: Vec<TagHandle> = skim_tags(input, 10).collect();
+    assert_eq!(handles.len(), 1);
+    assert!(handles[0].is_truncated());
+    assert_eq!(handles[0].length(), Some(19));
+    assert_eq!(handles[0].decode(), ast::Tag::Raw(ast::tags::Raw { data: input.to_vec() }));
+
+    // Truncated header
+    let handles: Vec<TagHandle> = skim_tags(&input[..1], 10).collect();
+    assert_eq!(handles.len(), 1);
+    assert_eq!(handles[0].header(), None);
+    assert!(handles[0].is_truncated());
+  }
+}
@@ -1,5 +1,5 @@
+use crate::streaming::error::NomResult;
 use nom::number::complete::{le_u16 as parse_le_u16, le_u32 as parse_le_u32, le_u8 as parse_u8};
-use nom::IResult as NomResult;
 use swf_types as swf;
 
 pub fn sound_rate_from_code(sound_rate_code: u8) -> Result<swf::SoundRate, ()> {
@@ -0,0 +1,237 @@
+use std::ops::Range;
+
+use crate::complete::tag::{split_tag, try_parse_tag_body_with, NestedTag, TagSplit};
+use crate::legacy::LegacyEncoding;
+use crate::streaming::tag::parse_tag_header;
+use swf_types as ast;
+
+/// Location of a tag in the decompressed payload.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TagSpan {
+  /// Position of the tag in the movie.
+  ///
+  /// The first item is the index of the tag in the top-level tag list. Tags
+  /// nested in a `DefineSprite` have one more item per nesting level: the index
+  /// of the tag in the sprite's tag list.
+  pub path: Vec<usize>,
+
+  /// Tag code, or `None` if the tag header is truncated.
+  pub code: Option<u16>,
+
+  /// Offset of the start of the tag (its header) in the decompressed payload.
+  ///
+  /// The decompressed payload corresponds to all the bytes following the SWF
+  /// signature (the first 8 bytes of the file).
+  pub offset: usize,
+
+  /// Length of the tag header: `2` for the short form, `6` for the long form.
+  ///
+  /// `None` if the tag header is truncated.
+  pub header_len: Option<usize>,
+
+  /// Length of the tag (header and body) in the decompressed payload.
+  ///
+  /// For truncated tags, it is the length of the available data.
+  pub len: usize,
+}
+
+impl TagSpan {
+  /// Byte range of the tag (header and body) in the decompressed payload.
+  pub fn range(&self) -> Range<usize> {
+    self.offset..(self.offset + self.len)
+  }
+
+  /// Byte range of the tag body in the decompressed payload, or `None` if the tag header is truncated.
+  pub fn body_range(&self) -> Option<Range<usize>> {
+    self
+      .header_len
+      .map(|header_len| (self.offset + header_len)..(self.offset + self.len))
+  }
+
+  /// Returns `true` if the tag uses the long header form (with a 32-bit length).
+  ///
+  /// The long form is required for bodies of 63 bytes or more, but may be used for any tag.
+  pub fn is_long_header(&self) -> bool {
+    self.header_len == Some(LONG_HEADER_LEN)
+  }
+}
+
+/// Length of a tag header using the long form
+const LONG_HEADER_LEN: usize = 6;
+
+/// Parses the string of tags from a completely loaded input, collecting the tag spans.
+///
+/// This function mirrors `crate::complete::parse_swf_tags`: the spans are in the same order as
+/// the tags, each `DefineSprite` being followed by the spans of its nested tags.
+///
+/// `offset` is the offset of `input` in the decompressed payload.
+pub(crate) fn parse_tag_block_string_with_spans(
+  mut input: &[u8],
+  mut offset: usize,
+  swf_version: u8,
+  legacy_encoding: LegacyEncoding,
+  spans: &mut Vec<TagSpan>,
+) -> Vec<ast::Tag> {
+  let mut tags: Vec<ast::Tag> = Vec::new();
+  loop {
+    let path = vec![tags.len()];
+    let (remaining, tag) = match split_tag(input) {
+      (_, TagSplit::End) => break,
+      (remaining, TagSplit::Tag(header, body)) => {
+        let tag = NestedTag {
+          offset,
+          header_len: input.len() - remaining.len() - body.len(),
+          header,
+          body,
+        };
+        (remaining, parse_tag_with_spans(tag, swf_version, legacy_encoding, path, spans))
+      }
+      (remaining, TagSplit::Truncated(header)) => {
+        spans.push(TagSpan {
+          path,
+          code: header.map(|header| header.code),
+          offset,
+          header_len: parse_tag_header(input).ok().map(|(body, _)| input.len() - body.len()),
+          len: input.len(),
+        });
+        (remaining, ast::Tag::Raw(ast::tags::Raw { data: input.to_vec() }))
+      }
+    };
+    tags.push(tag);
+    offset += input.len() - remaining.len();
+    input = remaining;
+  }
+  tags
+}
+
+/// Parses a complete tag, pushing its span followed by the spans of its nested tags.
+///
+/// `tag.offset` is the offset of the tag in the decompressed payload.
+/// The spans of the tags nested in a `DefineSprite` are only kept if the sprite itself is valid.
+pub(crate) fn parse_tag_with_spans(
+  tag: NestedTag,
+  swf_version: u8,
+  legacy_encoding: LegacyEncoding,
+  path: Vec<usize>,
+  spans: &mut Vec<TagSpan>,
+) -> ast::Tag {
+  let body_offset = tag.offset + tag.header_len;
+  let code = tag.header.code;
+  spans.push(TagSpan {
+    path: path.clone(),
+    code: Some(code),
+    offset: tag.offset,
+    header_len: Some(tag.header_len),
+    len: tag.header_len + tag.body.len(),
+  });
+  let mut nested_spans: Vec<TagSpan> = Vec::new();
+  let mut nested_count: usize = 0;
+  let result = try_parse_tag_body_with(tag.body, code, swf_version, legacy_encoding, &mut |nested: NestedTag| {
+    let nested_path = [&path[..], &[nested_count]].concat();
+    nested_count += 1;
+    let nested = NestedTag {
+      offset: body_offset + nested.offset,
+      ..nested
+    };
+    parse_tag_with_spans(nested, swf_version, legacy_encoding, nested_path, &mut nested_spans)
+  });
+  match result {
+    Ok((_, parsed)) => {
+      spans.extend(nested_spans);
+      parsed
+    }
+    Err(_) => ast::Tag::RawBody(ast::tags::RawBody {
+      code,
+      data: tag.body.to_vec(),
+    }),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::complete::parse_swf_tags;
+
+  #[test]
+  fn test_spans_match_parse_swf_tags() {
+    let input: &[u8] = &[
+      // ShowFrame, using the long header form
+      0x7f, 0x00, 0x00, 0x00, 0x00, 0x00, //
+      // DefineSprite (id 1, 1 frame), containing `SetBackgroundColor` and `ShowFrame`
+      0xcd, 0x09, 0x01, 0x00, 0x01, 0x00, 0x43, 0x02, 0xff, 0xff, 0xff, 0x40, 0x00, 0x00, 0x00, //
+      // DefineSprite (id 2, 1 frame), invalid: missing `End`
+      0xc6, 0x09, 0x02, 0x00, 0x01, 0x00, 0x40, 0x00, //
+      // Truncated tag
+      0x43, 0x03, 0xff, //
+    ];
+    let mut spans: Vec<TagSpan> = Vec::new();
+    let tags = parse_tag_block_string_with_spans(input, 100, 10, LegacyEncoding::default(), &mut spans);
+
+    let expected: Vec<ast::Tag> = parse_swf_tags(input, 10).map(|(_, _, tag)| tag).collect();
+    assert_eq!(tags, expected);
+    assert_eq!(
+      spans,
+      vec![
+        TagSpan {
+          path: vec![0],
+          code: Some(1),
+          offset: 100,
+          header_len: Some(6),
+          len: 6,
+        },
+        TagSpan {
+          path: vec![1],
+          code: Some(39),
+          offset: 106,
+          header_len: Some(2),
+          len: 15,
+        },
+        TagSpan {
+          path: vec![1, 0],
+          code: Some(9),
+          offset: 112,
+          header_len: Some(2),
+          len: 5,
+        },
+        TagSpan {
+          path: vec![1, 1],
+          code: Some(1),
+          offset: 117,
+          header_len: Some(2),
+          len: 2,
+        },
+        TagSpan {
+          path: vec![2],
+          code: Some(39),
+          offset: 121,
+          header_len: Some(2),
+          len: 8,
+        },
+        TagSpan {
+          path: vec![3],
+          code: Some(13),
+          offset: 129,
+          header_len: Some(2),
+          len: 3,
+        },
+      ]
+    );
+    assert!(spans[0].is_long_header());
+    assert_eq!(spans[0].body_range(), Some(106..106));
+    assert_eq!(spans[2].body_range(), Some(114..117));
+    assert_eq!(spans[5].range(), 129..132);
+
+    let mut spans: Vec<TagSpan> = Vec::new();
+    parse_tag_block_string_with_spans(&[0x43], 100, 10, LegacyEncoding::default(), &mut spans);
+    assert_eq!(
+      spans,
+      vec![TagSpan {
+        path: vec![0],
+        code: None,
+        offset: 100,
+        header_len: None,
+        len: 1,
+      }]
+    );
+  }
+}
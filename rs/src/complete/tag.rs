@@ -1,12 +1,9 @@
 use crate::complete::base::{offset_take, skip};
+use crate::complete::borrowed;
 use crate::complete::button::{
   parse_button2_cond_action_string, parse_button_record_string, parse_button_sound, ButtonVersion,
 };
 use crate::complete::display::{parse_blend_mode, parse_clip_actions_string, parse_filter_list};
-use crate::complete::image::{
-  get_gif_image_dimensions, get_jpeg_image_dimensions, get_png_image_dimensions, is_sniffed_jpeg, sniff_image_type,
-  SniffedImageType,
-};
 use crate::complete::morph_shape::{parse_morph_shape, MorphShapeVersion};
 use crate::complete::shape::{parse_glyph, parse_shape, ShapeVersion};
 use crate::complete::sound::{
@@ -17,20 +14,22 @@ use crate::complete::text::{
   parse_text_alignment, parse_text_record_string, text_renderer_from_code, FontInfoVersion, FontVersion, TextVersion,
 };
 use crate::complete::video::{parse_videoc_codec, video_deblocking_from_code};
+use crate::legacy::LegacyEncoding;
 use crate::streaming::basic_data_types::{
-  parse_block_c_string, parse_c_string, parse_color_transform, parse_color_transform_with_alpha, parse_language_code,
-  parse_leb128_u32, parse_matrix, parse_named_id, parse_rect, parse_s_rgb8, parse_straight_s_rgba8,
+  parse_c_string, parse_color_transform, parse_color_transform_with_alpha, parse_language_code, parse_leb128_u32,
+  parse_matrix, parse_named_id, parse_rect, parse_s_rgb8, parse_straight_s_rgba8, parse_swf_block_c_string,
+  parse_swf_c_string, parse_swf_named_id,
 };
-use crate::streaming::movie::parse_tag_block_string;
-use crate::streaming::tag::StreamingTagError;
+use crate::streaming::error::{LocatedError, NomResult};
+use crate::streaming::tag::{parse_tag_header, StreamingTagError};
+use nom::error::context;
 use nom::number::complete::{
   le_f32 as parse_le_f32, le_i16 as parse_le_i16, le_u16 as parse_le_u16, le_u32 as parse_le_u32, le_u8 as parse_u8,
 };
-use nom::IResult as NomResult;
 use std::convert::TryFrom;
 use swf_types as ast;
 use swf_types::text::FontAlignmentZone;
-use swf_types::{AbcHeader, ButtonCondAction, Glyph};
+use swf_types::{ButtonCondAction, Glyph};
 
 /// Parses the tag at the start of `input`.
 ///
@@ -39,12 +38,24 @@ use swf_types::{AbcHeader, ButtonCondAction, Glyph};
 /// This function returns the remaining input and the tag.
 /// `None` indicates the end of tags (empty input or `End` raw tag).
 /// This function always succeeds. Malformed tags produce a `Tag::Raw` containing the invalid bytes.
+/// The strings of SWF 5 (and earlier) movies are decoded as Windows-1252, see [[parse_tag_with_legacy_encoding]].
 pub fn parse_tag(input: &[u8], swf_version: u8) -> (&[u8], Option<ast::Tag>) {
+  parse_tag_with_legacy_encoding(input, swf_version, LegacyEncoding::default())
+}
+
+/// Parses the tag at the start of `input`, decoding the strings of SWF 5 (and earlier) movies with `encoding`.
+///
+/// See [[parse_tag]].
+pub fn parse_tag_with_legacy_encoding(
+  input: &[u8],
+  swf_version: u8,
+  encoding: LegacyEncoding,
+) -> (&[u8], Option<ast::Tag>) {
   if input.is_empty() {
     return (input, None);
   }
 
-  match crate::streaming::tag::parse_tag(input, swf_version) {
+  match crate::streaming::tag::parse_tag(input, swf_version, encoding) {
     Ok(ok) => ok,
     Err(StreamingTagError::IncompleteHeader) | Err(StreamingTagError::IncompleteTag(_)) => {
       (&[][..], Some(ast::Tag::Raw(ast::tags::Raw { data: input.to_vec() })))
@@ -52,84 +63,136 @@ pub fn parse_tag(input: &[u8], swf_version: u8) -> (&[u8], Option<ast::Tag>) {
   }
 }
 
-pub(crate) fn parse_tag_body(input: &[u8], code: u16, swf_version: u8) -> ast::Tag {
-  use nom::combinator::map;
-  let result = match code {
-    1 => Ok((input, ast::Tag::ShowFrame)),
-    2 => map(parse_define_shape, ast::Tag::DefineShape)(input),
-    4 => map(parse_place_object, ast::Tag::PlaceObject)(input),
-    5 => map(parse_remove_object, ast::Tag::RemoveObject)(input),
-    6 => map(|i| parse_define_bits(i, swf_version), ast::Tag::DefineBitmap)(input),
-    7 => map(parse_define_button, ast::Tag::DefineButton)(input),
-    8 => map(|i| parse_define_jpeg_tables(i, swf_version), ast::Tag::DefineJpegTables)(input),
-    9 => map(parse_set_background_color_tag, ast::Tag::SetBackgroundColor)(input),
-    10 => map(parse_define_font, ast::Tag::DefineGlyphFont)(input),
-    11 => map(parse_define_text, ast::Tag::DefineText)(input),
-    12 => map(parse_do_action, ast::Tag::DoAction)(input),
-    13 => map(parse_define_font_info, ast::Tag::DefineFontInfo)(input),
-    14 => map(parse_define_sound, ast::Tag::DefineSound)(input),
-    15 => map(parse_start_sound, ast::Tag::StartSound)(input),
-    17 => map(parse_define_button_sound, ast::Tag::DefineButtonSound)(input),
-    18 => map(parse_sound_stream_head, ast::Tag::SoundStreamHead)(input),
-    19 => map(parse_sound_stream_block, ast::Tag::SoundStreamBlock)(input),
-    20 => map(parse_define_bits_lossless, ast::Tag::DefineBitmap)(input),
-    21 => map(|i| parse_define_bits_jpeg2(i, swf_version), ast::Tag::DefineBitmap)(input),
-    22 => map(parse_define_shape2, ast::Tag::DefineShape)(input),
-    23 => map(
-      parse_define_button_color_transform,
-      ast::Tag::DefineButtonColorTransform,
-    )(input),
-    24 => map(parse_protect, ast::Tag::Protect)(input),
-    25 => Ok((input, ast::Tag::EnablePostscript)),
-    26 => map(|i| parse_place_object2(i, swf_version), ast::Tag::PlaceObject)(input),
-    28 => map(parse_remove_object2, ast::Tag::RemoveObject)(input),
-    32 => map(parse_define_shape3, ast::Tag::DefineShape)(input),
-    33 => map(parse_define_text2, ast::Tag::DefineText)(input),
-    34 => map(parse_define_button2, ast::Tag::DefineButton)(input),
-    35 => map(|i| parse_define_bits_jpeg3(i, swf_version), ast::Tag::DefineBitmap)(input),
-    36 => map(parse_define_bits_lossless2, ast::Tag::DefineBitmap)(input),
-    37 => map(parse_define_edit_text, ast::Tag::DefineDynamicText)(input),
-    39 => map(|i| parse_define_sprite(i, swf_version), ast::Tag::DefineSprite)(input),
-    43 => map(parse_frame_label, ast::Tag::FrameLabel)(input),
-    45 => map(parse_sound_stream_head2, ast::Tag::SoundStreamHead)(input),
-    46 => map(parse_define_morph_shape, ast::Tag::DefineMorphShape)(input),
-    48 => map(parse_define_font2, ast::Tag::DefineFont)(input),
-    56 => map(parse_export_assets, ast::Tag::ExportAssets)(input),
-    57 => map(parse_import_assets, ast::Tag::ImportAssets)(input),
-    58 => map(parse_enable_debugger, ast::Tag::EnableDebugger)(input),
-    59 => map(parse_do_init_action, ast::Tag::DoInitAction)(input),
-    60 => map(parse_define_video_stream, ast::Tag::DefineVideoStream)(input),
-    61 => map(parse_video_frame, ast::Tag::VideoFrame)(input),
-    62 => map(parse_define_font_info2, ast::Tag::DefineFontInfo)(input),
-    64 => map(parse_enable_debugger2, ast::Tag::EnableDebugger)(input),
-    65 => map(parse_script_limits, ast::Tag::ScriptLimits)(input),
-    66 => map(parse_set_tab_index, ast::Tag::SetTabIndex)(input),
-    69 => map(parse_file_attributes_tag, ast::Tag::FileAttributes)(input),
-    70 => map(|i| parse_place_object3(i, swf_version), ast::Tag::PlaceObject)(input),
-    71 => map(parse_import_assets2, ast::Tag::ImportAssets)(input),
-    72 => map(|i| parse_do_abc(i, false), ast::Tag::DoAbc)(input),
-    73 => map(parse_define_font_align_zones, ast::Tag::DefineFontAlignZones)(input),
-    74 => map(parse_csm_text_settings, ast::Tag::CsmTextSettings)(input),
-    75 => map(parse_define_font3, ast::Tag::DefineFont)(input),
-    76 => map(parse_symbol_class, ast::Tag::SymbolClass)(input),
-    77 => map(parse_metadata, ast::Tag::Metadata)(input),
-    78 => map(parse_define_scaling_grid, ast::Tag::DefineScalingGrid)(input),
-    82 => map(|i| parse_do_abc(i, true), ast::Tag::DoAbc)(input),
-    83 => map(parse_define_shape4, ast::Tag::DefineShape)(input),
-    84 => map(parse_define_morph_shape2, ast::Tag::DefineMorphShape)(input),
-    86 => map(
-      parse_define_scene_and_frame_label_data_tag,
-      ast::Tag::DefineSceneAndFrameLabelData,
-    )(input),
-    87 => map(parse_define_binary_data, ast::Tag::DefineBinaryData)(input),
-    88 => map(parse_define_font_name, ast::Tag::DefineFontName)(input),
-    89 => map(parse_start_sound2, ast::Tag::StartSound2)(input),
-    90 => map(parse_define_bits_jpeg4, ast::Tag::DefineBitmap)(input),
-    91 => map(parse_define_font4, ast::Tag::DefineCffFont)(input),
-    93 => map(parse_enable_telemetry, ast::Tag::Telemetry)(input),
-    _ => Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch))),
-  };
-  match result {
+/// Returns a lazy iterator over the tags of `input`.
+///
+/// `input` is a completely loaded tag string, such as the part of the uncompressed SWF payload
+/// following the movie header.
+/// The tags are only parsed when the iterator is advanced, so you can stop early without paying
+/// for the rest of the movie.
+/// The strings of SWF 5 (and earlier) movies are decoded as Windows-1252, see `TagIter::with_legacy_encoding`.
+pub fn parse_swf_tags(input: &[u8], swf_version: u8) -> TagIter<'_> {
+  TagIter::new(input, 0, swf_version)
+}
+
+/// Lazy iterator over the tags of a completely loaded tag string, see `parse_swf_tags`.
+///
+/// Each item is the offset of the tag (relative to the start of the tag string, or of the
+/// payload when created by `parse_movie`), its header and the parsed tag. The iterator ends at
+/// the `End` tag or at the end of the input.
+/// It yields the same tags as `parse_tag`: malformed tags produce a `Tag::Raw`. If the header
+/// itself is truncated, the yielded header is `None`.
+///
+/// The tag type `T` depends on the parser of the tag bodies, see `crate::complete::BorrowedTagIter`.
+#[derive(Debug, Clone)]
+pub struct TagIter<'a, T = ast::Tag> {
+  input: &'a [u8],
+  offset: usize,
+  swf_version: u8,
+  encoding: LegacyEncoding,
+  /// Parser of complete tag bodies, receiving the body, tag code, SWF version and legacy string encoding
+  parse_body: fn(&'a [u8], u16, u8, LegacyEncoding) -> T,
+  /// Parser of truncated tags, receiving the whole remaining input
+  parse_truncated: fn(&'a [u8]) -> T,
+}
+
+impl<'a> TagIter<'a> {
+  /// Creates an iterator over `input`, located at `offset`.
+  pub(crate) fn new(input: &'a [u8], offset: usize, swf_version: u8) -> Self {
+    Self::with_parser(input, offset, swf_version, parse_tag_body, |data| {
+      ast::Tag::Raw(ast::tags::Raw { data: data.to_vec() })
+    })
+  }
+}
+
+impl<'a, T> TagIter<'a, T> {
+  /// Creates an iterator over `input`, located at `offset`, with the provided tag parsers.
+  pub(crate) fn with_parser(
+    input: &'a [u8],
+    offset: usize,
+    swf_version: u8,
+    parse_body: fn(&'a [u8], u16, u8, LegacyEncoding) -> T,
+    parse_truncated: fn(&'a [u8]) -> T,
+  ) -> Self {
+    Self {
+      input,
+      offset,
+      swf_version,
+      encoding: LegacyEncoding::default(),
+      parse_body,
+      parse_truncated,
+    }
+  }
+
+  /// Sets the encoding of the strings of SWF 5 (and earlier) movies, Windows-1252 by default.
+  ///
+  /// See [[crate::legacy::LegacyEncoding]].
+  pub fn with_legacy_encoding(mut self, encoding: LegacyEncoding) -> Self {
+    self.encoding = encoding;
+    self
+  }
+
+  /// Returns the input that was not parsed yet.
+  pub fn remaining(&self) -> &'a [u8] {
+    self.input
+  }
+}
+
+impl<'a, T> Iterator for TagIter<'a, T> {
+  type Item = (usize, Option<ast::TagHeader>, T);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let input = self.input;
+    let (remaining, header, tag) = match split_tag(input) {
+      (_, TagSplit::End) => {
+        self.input = &[];
+        return None;
+      }
+      (remaining, TagSplit::Tag(header, body)) => {
+        (remaining, Some(header), (self.parse_body)(body, header.code, self.swf_version, self.encoding))
+      }
+      (remaining, TagSplit::Truncated(header)) => (remaining, header, (self.parse_truncated)(input)),
+    };
+    let offset = self.offset;
+    self.offset += input.len() - remaining.len();
+    self.input = remaining;
+    Some((offset, header, tag))
+  }
+}
+
+impl<T> std::iter::FusedIterator for TagIter<'_, T> {}
+
+/// Next tag of a completely loaded tag string, see `split_tag`.
+pub(crate) enum TagSplit<'a> {
+  /// `End` tag or empty input
+  End,
+  /// Complete tag, with its body
+  Tag(ast::TagHeader, &'a [u8]),
+  /// Truncated tag, the header is `None` if it is itself truncated
+  Truncated(Option<ast::TagHeader>),
+}
+
+/// Splits the tag at the start of `input`, returning the remaining input.
+///
+/// A truncated tag consumes the whole input.
+pub(crate) fn split_tag(input: &[u8]) -> (&[u8], TagSplit<'_>) {
+  if input.is_empty() {
+    return (input, TagSplit::End);
+  }
+  match parse_tag_header(input) {
+    Ok((remaining, header)) if header.code == 0 => (remaining, TagSplit::End),
+    Ok((body_input, header)) => match usize::try_from(header.length) {
+      Ok(body_len) if body_len <= body_input.len() => {
+        let (body, remaining) = body_input.split_at(body_len);
+        (remaining, TagSplit::Tag(header, body))
+      }
+      _ => (&[][..], TagSplit::Truncated(Some(header))),
+    },
+    Err(_) => (&[][..], TagSplit::Truncated(None)),
+  }
+}
+
+pub(crate) fn parse_tag_body(input: &[u8], code: u16, swf_version: u8, encoding: LegacyEncoding) -> ast::Tag {
+  match try_parse_tag_body(input, code, swf_version, encoding) {
     Ok((_, tag)) => tag,
     Err(_) => ast::Tag::RawBody(ast::tags::RawBody {
       code,
@@ -138,14 +201,144 @@ pub(crate) fn parse_tag_body(input: &[u8], code: u16, swf_version: u8) -> ast::T
   }
 }
 
+/// Parses the tag body, returning the parser error instead of falling back to `RawBody`.
+pub(crate) fn try_parse_tag_body(
+  input: &[u8],
+  code: u16,
+  swf_version: u8,
+  encoding: LegacyEncoding,
+) -> NomResult<&[u8], ast::Tag> {
+  try_parse_tag_body_with(input, code, swf_version, encoding, &mut |tag: NestedTag| {
+    parse_tag_body(tag.body, tag.header.code, swf_version, encoding)
+  })
+}
+
+/// Tag nested in a `DefineSprite`, see `parse_define_sprite`.
+pub(crate) struct NestedTag<'a> {
+  /// Offset of the tag in the sprite body
+  pub offset: usize,
+  /// Length of the tag header (short or long)
+  pub header_len: usize,
+  pub header: ast::TagHeader,
+  pub body: &'a [u8],
+}
+
+/// Callback parsing the tags nested in a `DefineSprite`.
+pub(crate) type NestedTagParser<'a> = dyn FnMut(NestedTag<'_>) -> ast::Tag + 'a;
+
+/// Parses the body of a tag with the provided parser, naming the parser in errors.
+///
+/// Tag bodies are complete: running out of input is reported as an `Eof` error at the end of
+/// the body, so it keeps the parser name.
+macro_rules! tag_body {
+  ($input:expr, $parser:ident $(($($arg:expr),*))? => $variant:ident) => {
+    nom::error::context(
+      stringify!($parser),
+      complete_body(map(|i| $parser(i $($(, $arg)*)?), ast::Tag::$variant)),
+    )($input)
+  };
+}
+
+/// Converts `Incomplete` errors of `parser` into `Eof` errors at the end of the input.
+fn complete_body<'a, O>(
+  mut parser: impl FnMut(&'a [u8]) -> NomResult<&'a [u8], O>,
+) -> impl FnMut(&'a [u8]) -> NomResult<&'a [u8], O> {
+  move |input: &'a [u8]| match parser(input) {
+    Err(nom::Err::Incomplete(_)) => Err(nom::Err::Error(LocatedError::new(&input[input.len()..], nom::error::ErrorKind::Eof))),
+    result => result,
+  }
+}
+
+/// Parses the tag body, using `parse_nested_tag` for the tags nested in a `DefineSprite`.
+///
+/// Errors are named after the tag body parser, see `crate::streaming::error::LocatedError`.
+pub(crate) fn try_parse_tag_body_with<'a>(
+  input: &'a [u8],
+  code: u16,
+  swf_version: u8,
+  encoding: LegacyEncoding,
+  parse_nested_tag: &mut NestedTagParser<'_>,
+) -> NomResult<&'a [u8], ast::Tag> {
+  use nom::combinator::map;
+  match code {
+    1 => Ok((input, ast::Tag::ShowFrame)),
+    2 => tag_body!(input, parse_define_shape => DefineShape),
+    4 => tag_body!(input, parse_place_object => PlaceObject),
+    5 => tag_body!(input, parse_remove_object => RemoveObject),
+    6 => tag_body!(input, parse_define_bits(swf_version) => DefineBitmap),
+    7 => tag_body!(input, parse_define_button => DefineButton),
+    8 => tag_body!(input, parse_define_jpeg_tables(swf_version) => DefineJpegTables),
+    9 => tag_body!(input, parse_set_background_color_tag => SetBackgroundColor),
+    10 => tag_body!(input, parse_define_font => DefineGlyphFont),
+    11 => tag_body!(input, parse_define_text => DefineText),
+    12 => tag_body!(input, parse_do_action => DoAction),
+    13 => tag_body!(input, parse_define_font_info(swf_version, encoding) => DefineFontInfo),
+    14 => tag_body!(input, parse_define_sound => DefineSound),
+    15 => tag_body!(input, parse_start_sound => StartSound),
+    17 => tag_body!(input, parse_define_button_sound => DefineButtonSound),
+    18 => tag_body!(input, parse_sound_stream_head => SoundStreamHead),
+    19 => tag_body!(input, parse_sound_stream_block => SoundStreamBlock),
+    20 => tag_body!(input, parse_define_bits_lossless => DefineBitmap),
+    21 => tag_body!(input, parse_define_bits_jpeg2(swf_version) => DefineBitmap),
+    22 => tag_body!(input, parse_define_shape2 => DefineShape),
+    23 => tag_body!(input, parse_define_button_color_transform => DefineButtonColorTransform),
+    24 => tag_body!(input, parse_protect(swf_version, encoding) => Protect),
+    25 => Ok((input, ast::Tag::EnablePostscript)),
+    26 => tag_body!(input, parse_place_object2(swf_version, encoding) => PlaceObject),
+    28 => tag_body!(input, parse_remove_object2 => RemoveObject),
+    32 => tag_body!(input, parse_define_shape3 => DefineShape),
+    33 => tag_body!(input, parse_define_text2 => DefineText),
+    34 => tag_body!(input, parse_define_button2 => DefineButton),
+    35 => tag_body!(input, parse_define_bits_jpeg3(swf_version) => DefineBitmap),
+    36 => tag_body!(input, parse_define_bits_lossless2 => DefineBitmap),
+    37 => tag_body!(input, parse_define_edit_text(swf_version, encoding) => DefineDynamicText),
+    39 => tag_body!(input, parse_define_sprite(&mut *parse_nested_tag) => DefineSprite),
+    43 => tag_body!(input, parse_frame_label(swf_version, encoding) => FrameLabel),
+    45 => tag_body!(input, parse_sound_stream_head2 => SoundStreamHead),
+    46 => tag_body!(input, parse_define_morph_shape => DefineMorphShape),
+    48 => tag_body!(input, parse_define_font2(swf_version, encoding) => DefineFont),
+    56 => tag_body!(input, parse_export_assets(swf_version, encoding) => ExportAssets),
+    57 => tag_body!(input, parse_import_assets(swf_version, encoding) => ImportAssets),
+    58 => tag_body!(input, parse_enable_debugger(swf_version, encoding) => EnableDebugger),
+    59 => tag_body!(input, parse_do_init_action => DoInitAction),
+    60 => tag_body!(input, parse_define_video_stream => DefineVideoStream),
+    61 => tag_body!(input, parse_video_frame => VideoFrame),
+    62 => tag_body!(input, parse_define_font_info2(swf_version, encoding) => DefineFontInfo),
+    64 => tag_body!(input, parse_enable_debugger2 => EnableDebugger),
+    65 => tag_body!(input, parse_script_limits => ScriptLimits),
+    66 => tag_body!(input, parse_set_tab_index => SetTabIndex),
+    69 => tag_body!(input, parse_file_attributes_tag => FileAttributes),
+    70 => tag_body!(input, parse_place_object3(swf_version, encoding) => PlaceObject),
+    71 => tag_body!(input, parse_import_assets2 => ImportAssets),
+    72 => tag_body!(input, parse_do_abc(false) => DoAbc),
+    73 => tag_body!(input, parse_define_font_align_zones => DefineFontAlignZones),
+    74 => tag_body!(input, parse_csm_text_settings => CsmTextSettings),
+    75 => tag_body!(input, parse_define_font3(swf_version, encoding) => DefineFont),
+    76 => tag_body!(input, parse_symbol_class => SymbolClass),
+    77 => tag_body!(input, parse_metadata => Metadata),
+    78 => tag_body!(input, parse_define_scaling_grid => DefineScalingGrid),
+    82 => tag_body!(input, parse_do_abc(true) => DoAbc),
+    83 => tag_body!(input, parse_define_shape4 => DefineShape),
+    84 => tag_body!(input, parse_define_morph_shape2 => DefineMorphShape),
+    86 => tag_body!(input, parse_define_scene_and_frame_label_data_tag => DefineSceneAndFrameLabelData),
+    87 => tag_body!(input, parse_define_binary_data => DefineBinaryData),
+    88 => tag_body!(input, parse_define_font_name => DefineFontName),
+    89 => tag_body!(input, parse_start_sound2 => StartSound2),
+    90 => tag_body!(input, parse_define_bits_jpeg4 => DefineBitmap),
+    91 => tag_body!(input, parse_define_font4 => DefineCffFont),
+    93 => tag_body!(input, parse_enable_telemetry => Telemetry),
+    _ => Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch))),
+  }
+}
+
 pub fn parse_csm_text_settings(input: &[u8]) -> NomResult<&[u8], ast::tags::CsmTextSettings> {
   let (input, text_id) = parse_le_u16(input)?;
   let (input, flags) = parse_u8(input)?;
   // Skip bits [0, 2]
   let fitting = grid_fitting_from_code((flags >> 3) & 0b111)
-    .map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch)))?;
+    .map_err(|_| nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch)))?;
   let renderer = text_renderer_from_code((flags >> 6) & 0b11)
-    .map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch)))?;
+    .map_err(|_| nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch)))?;
   let (input, thickness) = parse_le_f32(input)?;
   let (input, sharpness) = parse_le_f32(input)?;
   // TODO: Skip 1 byte / assert 1 byte is available
@@ -162,37 +355,11 @@ pub fn parse_csm_text_settings(input: &[u8]) -> NomResult<&[u8], ast::tags::CsmT
 }
 
 pub fn parse_define_binary_data(input: &[u8]) -> NomResult<&[u8], ast::tags::DefineBinaryData> {
-  let (input, id) = parse_le_u16(input)?;
-  let (input, _reserved) = parse_le_u32(input)?; // TODO: assert reserved == 0
-  let data = input.to_vec();
-  let input = &[][..];
-  Ok((input, ast::tags::DefineBinaryData { id, data }))
+  borrowed::parse_define_binary_data(input).map(|(input, tag)| (input, tag.into_owned()))
 }
 
 pub fn parse_define_bits(input: &[u8], swf_version: u8) -> NomResult<&[u8], ast::tags::DefineBitmap> {
-  let (input, id) = parse_le_u16(input)?;
-  let data: Vec<u8> = input.to_vec();
-  let input: &[u8] = &[][..];
-
-  if is_sniffed_jpeg(&data, swf_version < 8) {
-    let image_dimensions =
-      get_jpeg_image_dimensions(&data).map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify)))?;
-    // TODO: avoid conversions
-    Ok((
-      input,
-      ast::tags::DefineBitmap {
-        id,
-        width: image_dimensions.width as u16,
-        height: image_dimensions.height as u16,
-        media_type: ast::ImageType::SwfPartialJpeg,
-        data,
-      },
-    ))
-  } else {
-    // UnknownBitmapType
-    // TODO: Better error
-    Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify)))
-  }
+  borrowed::parse_define_bits(input, swf_version).map(|(input, tag)| (input, tag.into_owned()))
 }
 
 pub fn parse_define_button(input: &[u8]) -> NomResult<&[u8], ast::tags::DefineButton> {
@@ -271,138 +438,26 @@ pub fn parse_define_button_sound(input: &[u8]) -> NomResult<&[u8], ast::tags::De
 }
 
 pub fn parse_define_bits_jpeg2(input: &[u8], swf_version: u8) -> NomResult<&[u8], ast::tags::DefineBitmap> {
-  let (input, id) = parse_le_u16(input)?;
-  let data: Vec<u8> = input.to_vec();
-  let input: &[u8] = &[][..];
-
-  let (media_type, dimensions) = match sniff_image_type(&data, swf_version < 8) {
-    Ok(SniffedImageType::Jpeg) => (ast::ImageType::Jpeg, get_jpeg_image_dimensions(&data)),
-    Ok(SniffedImageType::Png) => (ast::ImageType::Png, get_png_image_dimensions(&data)),
-    Ok(SniffedImageType::Gif) => (ast::ImageType::Gif, get_gif_image_dimensions(&data)),
-    Err(()) => {
-      // UnknownBitmapType
-      return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify)));
-    }
-  };
-
-  let dimensions = dimensions.map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify)))?;
-
-  Ok((
-    input,
-    ast::tags::DefineBitmap {
-      id,
-      width: dimensions.width as u16,
-      height: dimensions.height as u16,
-      media_type,
-      data,
-    },
-  ))
+  borrowed::parse_define_bits_jpeg2(input, swf_version).map(|(input, tag)| (input, tag.into_owned()))
 }
 
 pub fn parse_define_bits_jpeg3(input: &[u8], swf_version: u8) -> NomResult<&[u8], ast::tags::DefineBitmap> {
-  use nom::bytes::complete::take;
-  use nom::combinator::map;
-
-  let (ajpeg_data, id) = parse_le_u16(input)?;
-  let (input, data_len) = map(parse_le_u32, |x| x as usize)(ajpeg_data)?;
-  let (_, data) = take(data_len)(input)?;
-
-  let (media_type, dimensions, img_data) = match sniff_image_type(data, swf_version < 8) {
-    Ok(SniffedImageType::Jpeg) => {
-      let dimensions = get_jpeg_image_dimensions(data);
-      if input.len() > data_len {
-        (ast::ImageType::SwfJpeg3, dimensions, ajpeg_data)
-      } else {
-        (ast::ImageType::Jpeg, dimensions, data)
-      }
-    }
-    Ok(SniffedImageType::Png) => (ast::ImageType::Png, get_png_image_dimensions(data), data),
-    Ok(SniffedImageType::Gif) => (ast::ImageType::Gif, get_gif_image_dimensions(data), data),
-    Err(()) => {
-      // UnknownBitmapType
-      return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify)));
-    }
-  };
-
-  let dimensions = dimensions.map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify)))?;
-
-  Ok((
-    &[],
-    ast::tags::DefineBitmap {
-      id,
-      width: dimensions.width as u16,
-      height: dimensions.height as u16,
-      media_type,
-      data: img_data.to_vec(),
-    },
-  ))
+  borrowed::parse_define_bits_jpeg3(input, swf_version).map(|(input, tag)| (input, tag.into_owned()))
 }
 
 pub fn parse_define_bits_jpeg4(input: &[u8]) -> NomResult<&[u8], ast::tags::DefineBitmap> {
-  use nom::bytes::complete::take;
-  use nom::combinator::map;
-
-  let (djpeg_data, id) = parse_le_u16(input)?;
-  let (input, data_len) = map(parse_le_u32, |dl| usize::try_from(dl).unwrap())(djpeg_data)?;
-  let (input, _) = skip(2usize)(input)?; // Skip deblock
-  let (_, data) = take(data_len)(input)?;
-
-  let (media_type, dimensions, img_data) = match sniff_image_type(data, false) {
-    Ok(SniffedImageType::Jpeg) => (ast::ImageType::SwfJpeg4, get_jpeg_image_dimensions(data), djpeg_data),
-    Ok(SniffedImageType::Png) => (ast::ImageType::Png, get_png_image_dimensions(data), data),
-    Ok(SniffedImageType::Gif) => (ast::ImageType::Gif, get_gif_image_dimensions(data), data),
-    Err(()) => {
-      // UnknownBitmapType
-      return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify)));
-    }
-  };
-
-  let dimensions = dimensions.map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify)))?;
-
-  Ok((
-    &[],
-    ast::tags::DefineBitmap {
-      id,
-      width: dimensions.width as u16,
-      height: dimensions.height as u16,
-      media_type,
-      data: img_data.to_vec(),
-    },
-  ))
+  borrowed::parse_define_bits_jpeg4(input).map(|(input, tag)| (input, tag.into_owned()))
 }
 
 pub fn parse_define_bits_lossless(input: &[u8]) -> NomResult<&[u8], ast::tags::DefineBitmap> {
-  parse_define_bits_lossless_any(input, ast::ImageType::SwfLossless1)
+  borrowed::parse_define_bits_lossless(input).map(|(input, tag)| (input, tag.into_owned()))
 }
 
 pub fn parse_define_bits_lossless2(input: &[u8]) -> NomResult<&[u8], ast::tags::DefineBitmap> {
-  parse_define_bits_lossless_any(input, ast::ImageType::SwfLossless2)
+  borrowed::parse_define_bits_lossless2(input).map(|(input, tag)| (input, tag.into_owned()))
 }
 
-fn parse_define_bits_lossless_any(
-  input: &[u8],
-  media_type: ast::ImageType,
-) -> NomResult<&[u8], ast::tags::DefineBitmap> {
-  let (input, id) = parse_le_u16(input)?;
-  let data: Vec<u8> = input.to_vec();
-  let (input, _) = skip(1usize)(input)?; // BitmapFormat
-  let (input, width) = parse_le_u16(input)?;
-  let (_, height) = parse_le_u16(input)?;
-  let input: &[u8] = &[];
-
-  Ok((
-    input,
-    ast::tags::DefineBitmap {
-      id,
-      width,
-      height,
-      media_type,
-      data,
-    },
-  ))
-}
-
-pub fn parse_define_edit_text(input: &[u8]) -> NomResult<&[u8], ast::tags::DefineDynamicText> {
+pub fn parse_define_edit_text(input: &[u8], swf_version: u8, encoding: LegacyEncoding) -> NomResult<&[u8], ast::tags::DefineDynamicText> {
   use nom::combinator::cond;
   use nom::combinator::map;
 
@@ -428,7 +483,7 @@ pub fn parse_define_edit_text(input: &[u8]) -> NomResult<&[u8], ast::tags::Defin
   let has_font_class = (flags & (1 << 15)) != 0;
 
   let (input, font_id) = cond(has_font, parse_le_u16)(input)?;
-  let (input, font_class) = cond(has_font_class, parse_c_string)(input)?;
+  let (input, font_class) = cond(has_font_class, |i| parse_swf_c_string(i, swf_version, encoding))(input)?;
   let (input, font_size) = cond(has_font, parse_le_u16)(input)?;
   let (input, color) = cond(has_color, parse_straight_s_rgba8)(input)?;
   let (input, max_length) = cond(has_max_length, map(parse_le_u16, usize::from))(input)?;
@@ -442,13 +497,13 @@ pub fn parse_define_edit_text(input: &[u8]) -> NomResult<&[u8], ast::tags::Defin
   } else {
     (input, ast::text::TextAlignment::Left, 0, 0, 0, 0)
   };
-  let (input, variable_name) = parse_c_string(input)?;
+  let (input, variable_name) = parse_swf_c_string(input, swf_version, encoding)?;
   let variable_name = if variable_name.is_empty() {
     None
   } else {
     Some(variable_name)
   };
-  let (input, text) = cond(has_text, parse_c_string)(input)?;
+  let (input, text) = cond(has_text, |i| parse_swf_c_string(i, swf_version, encoding))(input)?;
 
   Ok((
     input,
@@ -507,7 +562,7 @@ pub fn parse_define_font(input: &[u8]) -> NomResult<&[u8], ast::tags::DefineGlyp
         let end_offset = offsets.get(i + 1).cloned().unwrap_or(saved_input_len);
         let glyph_input_size: usize = match end_offset.checked_sub(start_offset) {
           Some(x) => x,
-          None => return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify))),
+          None => return Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Verify))),
         };
         let (_, glyph_input) = offset_take(start_offset, glyph_input_size)(saved_input)?;
         glyph_input
@@ -522,16 +577,21 @@ pub fn parse_define_font(input: &[u8]) -> NomResult<&[u8], ast::tags::DefineGlyp
   Ok((&[], ast::tags::DefineGlyphFont { id, glyphs }))
 }
 
-pub fn parse_define_font2(input: &[u8]) -> NomResult<&[u8], ast::tags::DefineFont> {
-  parse_define_font_any(input, FontVersion::Font2)
+pub fn parse_define_font2(input: &[u8], swf_version: u8, encoding: LegacyEncoding) -> NomResult<&[u8], ast::tags::DefineFont> {
+  parse_define_font_any(input, FontVersion::Font2, swf_version, encoding)
 }
 
-pub fn parse_define_font3(input: &[u8]) -> NomResult<&[u8], ast::tags::DefineFont> {
-  parse_define_font_any(input, FontVersion::Font3)
+pub fn parse_define_font3(input: &[u8], swf_version: u8, encoding: LegacyEncoding) -> NomResult<&[u8], ast::tags::DefineFont> {
+  parse_define_font_any(input, FontVersion::Font3, swf_version, encoding)
 }
 
 // https://github.com/mozilla/shumway/blob/16451d8836fa85f4b16eeda8b4bda2fa9e2b22b0/src/swf/parser/module.ts#L632
-fn parse_define_font_any(input: &[u8], version: FontVersion) -> NomResult<&[u8], ast::tags::DefineFont> {
+fn parse_define_font_any(
+  input: &[u8],
+  version: FontVersion,
+  swf_version: u8,
+  encoding: LegacyEncoding,
+) -> NomResult<&[u8], ast::tags::DefineFont> {
   use nom::bytes::complete::take;
   use nom::combinator::{cond, map};
   use nom::multi::count;
@@ -559,7 +619,7 @@ fn parse_define_font_any(input: &[u8], version: FontVersion) -> NomResult<&[u8],
   let (input, font_name) = {
     let (input, font_name_len) = map(parse_u8, usize::from)(input)?;
     let (input, font_name_bytes) = take(font_name_len)(input)?;
-    let (_, font_name) = parse_block_c_string(font_name_bytes)?;
+    let (_, font_name) = parse_swf_block_c_string(font_name_bytes, swf_version, encoding)?;
     (input, font_name)
   };
   let (input, glyph_count) = map(parse_le_u16, |x| x as usize)(input)?;
@@ -644,7 +704,7 @@ pub fn parse_define_font_align_zones(input: &[u8]) -> NomResult<&[u8], ast::tags
   use nom::bits::bits;
 
   let (input, font_id) = parse_le_u16(input)?;
-  let (mut input, csm_table_hint) = bits(parse_csm_table_hint_bits)(input)?;
+  let (mut input, csm_table_hint) = context("parse_csm_table_hint", bits(parse_csm_table_hint_bits))(input)?;
   let mut zones: Vec<FontAlignmentZone> = Vec::new();
   while !input.is_empty() {
     let (next_input, zone) = parse_font_alignment_zone(input)?;
@@ -662,15 +722,20 @@ pub fn parse_define_font_align_zones(input: &[u8]) -> NomResult<&[u8], ast::tags
   ))
 }
 
-pub fn parse_define_font_info(input: &[u8]) -> NomResult<&[u8], ast::tags::DefineFontInfo> {
-  parse_define_font_info_any(input, FontInfoVersion::FontInfo1)
+pub fn parse_define_font_info(input: &[u8], swf_version: u8, encoding: LegacyEncoding) -> NomResult<&[u8], ast::tags::DefineFontInfo> {
+  parse_define_font_info_any(input, FontInfoVersion::FontInfo1, swf_version, encoding)
 }
 
-pub fn parse_define_font_info2(input: &[u8]) -> NomResult<&[u8], ast::tags::DefineFontInfo> {
-  parse_define_font_info_any(input, FontInfoVersion::FontInfo2)
+pub fn parse_define_font_info2(input: &[u8], swf_version: u8, encoding: LegacyEncoding) -> NomResult<&[u8], ast::tags::DefineFontInfo> {
+  parse_define_font_info_any(input, FontInfoVersion::FontInfo2, swf_version, encoding)
 }
 
-fn parse_define_font_info_any(input: &[u8], version: FontInfoVersion) -> NomResult<&[u8], ast::tags::DefineFontInfo> {
+fn parse_define_font_info_any(
+  input: &[u8],
+  version: FontInfoVersion,
+  swf_version: u8,
+  encoding: LegacyEncoding,
+) -> NomResult<&[u8], ast::tags::DefineFontInfo> {
   use nom::bytes::complete::take;
   use nom::combinator::map;
 
@@ -697,7 +762,7 @@ fn parse_define_font_info_any(input: &[u8], version: FontInfoVersion) -> NomResu
   let (input, font_name) = {
     let (input, font_name_len) = map(parse_u8, usize::from)(input)?;
     let (input, font_name_bytes) = take(font_name_len)(input)?;
-    let (_, font_name) = parse_block_c_string(font_name_bytes)?;
+    let (_, font_name) = parse_swf_block_c_string(font_name_bytes, swf_version, encoding)?;
     (input, font_name)
   };
   let (input, flags) = parse_u8(input)?;
@@ -746,14 +811,7 @@ pub fn parse_define_font_name(input: &[u8]) -> NomResult<&[u8], ast::tags::Defin
 }
 
 pub fn parse_define_jpeg_tables(input: &[u8], _swf_version: u8) -> NomResult<&[u8], ast::tags::DefineJpegTables> {
-  let data: Vec<u8> = input.to_vec();
-  let input: &[u8] = &[][..];
-
-  //  if !(test_image_start(&data, &JPEG_START) || (swf_version < 8 && test_image_start(&data, &ERRONEOUS_JPEG_START))) {
-  //    panic!("InvalidJpegTablesSignature");
-  //  }
-
-  Ok((input, ast::tags::DefineJpegTables { data }))
+  borrowed::parse_define_jpeg_tables(input).map(|(input, tag)| (input, tag.into_owned()))
 }
 
 pub fn parse_define_morph_shape(input: &[u8]) -> NomResult<&[u8], ast::tags::DefineMorphShape> {
@@ -883,51 +941,44 @@ fn parse_bytes(input: &[u8]) -> NomResult<&[u8], Vec<u8>> {
 }
 
 fn parse_define_sound(input: &[u8]) -> NomResult<&[u8], ast::tags::DefineSound> {
-  let (input, id) = parse_le_u16(input)?;
-  let (input, flags) = parse_u8(input)?;
-  #[allow(clippy::identity_op)]
-  let sound_type = if (flags & (1 << 0)) != 0 {
-    ast::SoundType::Stereo
-  } else {
-    ast::SoundType::Mono
-  };
-  let sound_size = if (flags & (1 << 1)) != 0 {
-    ast::SoundSize::SoundSize16
-  } else {
-    ast::SoundSize::SoundSize8
-  };
-  let sound_rate =
-    sound_rate_from_code((flags >> 2) & 0b11).map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch)))?;
-  let format = audio_coding_format_from_code((flags >> 4) & 0b1111)
-    .map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch)))?;
-  let (input, sample_count) = parse_le_u32(input)?;
-  let (input, data) = parse_bytes(input)?;
-
-  Ok((
-    input,
-    ast::tags::DefineSound {
-      id,
-      sound_type,
-      sound_size: if is_uncompressed_audio_coding_format(format) {
-        sound_size
-      } else {
-        ast::SoundSize::SoundSize16
-      },
-      sound_rate,
-      format,
-      sample_count,
-      data,
-    },
-  ))
+  borrowed::parse_define_sound(input).map(|(input, tag)| (input, tag.into_owned()))
 }
 
-pub fn parse_define_sprite(input: &[u8], swf_version: u8) -> NomResult<&[u8], ast::tags::DefineSprite> {
+/// Parses a `DefineSprite` body, using `parse_nested_tag` to parse the body of each nested tag.
+///
+/// Nested tags are read in streaming mode: a missing `End` tag or a truncated nested tag
+/// invalidate the whole sprite.
+pub(crate) fn parse_define_sprite<'a>(
+  input: &'a [u8],
+  parse_nested_tag: &mut NestedTagParser<'_>,
+) -> NomResult<&'a [u8], ast::tags::DefineSprite> {
   use nom::combinator::map;
 
+  let body = input;
   let (input, id) = parse_le_u16(input)?;
-  let (input, frame_count) = map(parse_le_u16, usize::from)(input)?;
-  let (input, tags) = parse_tag_block_string(input, swf_version)?;
-  Ok((input, ast::tags::DefineSprite { id, frame_count, tags }))
+  let (mut input, frame_count) = map(parse_le_u16, usize::from)(input)?;
+  let mut tags: Vec<ast::Tag> = Vec::new();
+  loop {
+    if input.is_empty() {
+      return Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Eof)));
+    }
+    let offset = body.len() - input.len();
+    input = match split_tag(input) {
+      (input, TagSplit::End) => break Ok((input, ast::tags::DefineSprite { id, frame_count, tags })),
+      (remaining, TagSplit::Tag(header, tag_body)) => {
+        tags.push(parse_nested_tag(NestedTag {
+          offset,
+          header_len: input.len() - remaining.len() - tag_body.len(),
+          header,
+          body: tag_body,
+        }));
+        remaining
+      }
+      (_, TagSplit::Truncated(_)) => {
+        return Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Eof)))
+      }
+    };
+  }
 }
 
 pub fn parse_define_text(input: &[u8]) -> NomResult<&[u8], ast::tags::DefineText> {
@@ -948,7 +999,7 @@ pub fn parse_define_text_any(input: &[u8], version: TextVersion) -> NomResult<&[
   let (input, advance_bits) = map(parse_u8, usize::from)(input)?;
   let has_alpha = version >= TextVersion::Text2;
   if index_bits > 32 || advance_bits > 32 {
-    return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify)));
+    return Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Verify)));
   }
   let (input, records) = parse_text_record_string(input, has_alpha, index_bits, advance_bits)?;
 
@@ -974,7 +1025,7 @@ pub fn parse_define_video_stream(input: &[u8]) -> NomResult<&[u8], ast::tags::De
   #[allow(clippy::identity_op)]
   let use_smoothing = (flags & (1 << 0)) != 0;
   let deblocking = video_deblocking_from_code((flags >> 1) & 0b111)
-    .map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch)))?;
+    .map_err(|_| nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch)))?;
   // Bits [4, 7] are reserved
   let (input, codec) = parse_videoc_codec(input)?;
 
@@ -993,30 +1044,19 @@ pub fn parse_define_video_stream(input: &[u8]) -> NomResult<&[u8], ast::tags::De
 }
 
 fn parse_do_abc(input: &[u8], has_header: bool) -> NomResult<&[u8], ast::tags::DoAbc> {
-  let (input, header) = if has_header {
-    let (input, flags) = parse_le_u32(input)?;
-    let (input, name) = parse_c_string(input)?;
-    (input, Some(AbcHeader { flags, name }))
-  } else {
-    (input, None)
-  };
-  let (input, data) = parse_bytes(input)?;
-  Ok((input, ast::tags::DoAbc { header, data }))
+  borrowed::parse_do_abc(input, has_header).map(|(input, tag)| (input, tag.into_owned()))
 }
 
 pub fn parse_do_action(input: &[u8]) -> NomResult<&[u8], ast::tags::DoAction> {
-  let (input, actions) = parse_bytes(input)?;
-  Ok((input, ast::tags::DoAction { actions }))
+  borrowed::parse_do_action(input).map(|(input, tag)| (input, tag.into_owned()))
 }
 
 pub fn parse_do_init_action(input: &[u8]) -> NomResult<&[u8], ast::tags::DoInitAction> {
-  let (input, sprite_id) = parse_le_u16(input)?;
-  let (input, actions) = (&[][..], input.to_vec());
-  Ok((input, ast::tags::DoInitAction { sprite_id, actions }))
+  borrowed::parse_do_init_action(input).map(|(input, tag)| (input, tag.into_owned()))
 }
 
-pub fn parse_enable_debugger(input: &[u8]) -> NomResult<&[u8], ast::tags::EnableDebugger> {
-  let (input, password) = parse_c_string(input)?;
+pub fn parse_enable_debugger(input: &[u8], swf_version: u8, encoding: LegacyEncoding) -> NomResult<&[u8], ast::tags::EnableDebugger> {
+  let (input, password) = parse_swf_c_string(input, swf_version, encoding)?;
   Ok((input, ast::tags::EnableDebugger { password }))
 }
 
@@ -1040,11 +1080,11 @@ pub fn parse_enable_telemetry(input: &[u8]) -> NomResult<&[u8], ast::tags::Telem
   ))
 }
 
-pub fn parse_export_assets(input: &[u8]) -> NomResult<&[u8], ast::tags::ExportAssets> {
+pub fn parse_export_assets(input: &[u8], swf_version: u8, encoding: LegacyEncoding) -> NomResult<&[u8], ast::tags::ExportAssets> {
   use nom::combinator::map;
   use nom::multi::count;
   let (input, asset_count) = map(parse_le_u16, usize::from)(input)?;
-  let (input, assets) = count(parse_named_id, asset_count)(input)?;
+  let (input, assets) = count(|i| parse_swf_named_id(i, swf_version, encoding), asset_count)(input)?;
   Ok((input, ast::tags::ExportAssets { assets }))
 }
 
@@ -1073,8 +1113,8 @@ pub fn parse_file_attributes_tag(input: &[u8]) -> NomResult<&[u8], ast::tags::Fi
   ))
 }
 
-pub fn parse_frame_label(input: &[u8]) -> NomResult<&[u8], ast::tags::FrameLabel> {
-  let (input, name) = parse_c_string(input)?;
+pub fn parse_frame_label(input: &[u8], swf_version: u8, encoding: LegacyEncoding) -> NomResult<&[u8], ast::tags::FrameLabel> {
+  let (input, name) = parse_swf_c_string(input, swf_version, encoding)?;
   let (input, is_anchor) = if input.is_empty() {
     (input, false)
   } else {
@@ -1085,12 +1125,12 @@ pub fn parse_frame_label(input: &[u8]) -> NomResult<&[u8], ast::tags::FrameLabel
   Ok((input, ast::tags::FrameLabel { name, is_anchor }))
 }
 
-pub fn parse_import_assets(input: &[u8]) -> NomResult<&[u8], ast::tags::ImportAssets> {
+pub fn parse_import_assets(input: &[u8], swf_version: u8, encoding: LegacyEncoding) -> NomResult<&[u8], ast::tags::ImportAssets> {
   use nom::combinator::map;
   use nom::multi::count;
-  let (input, url) = parse_c_string(input)?;
+  let (input, url) = parse_swf_c_string(input, swf_version, encoding)?;
   let (input, asset_count) = map(parse_le_u16, usize::from)(input)?;
-  let (input, assets) = count(parse_named_id, asset_count)(input)?;
+  let (input, assets) = count(|i| parse_swf_named_id(i, swf_version, encoding), asset_count)(input)?;
   Ok((input, ast::tags::ImportAssets { url, assets }))
 }
 
@@ -1153,7 +1193,7 @@ pub fn parse_place_object(input: &[u8]) -> NomResult<&[u8], ast::tags::PlaceObje
   ))
 }
 
-pub fn parse_place_object2(input: &[u8], swf_version: u8) -> NomResult<&[u8], ast::tags::PlaceObject> {
+pub fn parse_place_object2(input: &[u8], swf_version: u8, encoding: LegacyEncoding) -> NomResult<&[u8], ast::tags::PlaceObject> {
   use nom::combinator::cond;
 
   let (input, flags) = parse_u8(input)?;
@@ -1171,7 +1211,7 @@ pub fn parse_place_object2(input: &[u8], swf_version: u8) -> NomResult<&[u8], as
   let (input, matrix) = cond(has_matrix, parse_matrix)(input)?;
   let (input, color_transform) = cond(has_color_transform, parse_color_transform_with_alpha)(input)?;
   let (input, ratio) = cond(has_ratio, parse_le_u16)(input)?;
-  let (input, name) = cond(has_name, parse_c_string)(input)?;
+  let (input, name) = cond(has_name, |i| parse_swf_c_string(i, swf_version, encoding))(input)?;
   let (input, clip_depth) = cond(has_clip_depth, parse_le_u16)(input)?;
   let (input, clip_actions) = cond(has_clip_actions, |i| parse_clip_actions_string(i, swf_version >= 6))(input)?;
 
@@ -1197,7 +1237,7 @@ pub fn parse_place_object2(input: &[u8], swf_version: u8) -> NomResult<&[u8], as
   ))
 }
 
-pub fn parse_place_object3(input: &[u8], swf_version: u8) -> NomResult<&[u8], ast::tags::PlaceObject> {
+pub fn parse_place_object3(input: &[u8], swf_version: u8, encoding: LegacyEncoding) -> NomResult<&[u8], ast::tags::PlaceObject> {
   use nom::combinator::{cond, map};
 
   let (input, flags) = parse_le_u16(input)?;
@@ -1225,7 +1265,7 @@ pub fn parse_place_object3(input: &[u8], swf_version: u8) -> NomResult<&[u8], as
   let (input, matrix) = cond(has_matrix, parse_matrix)(input)?;
   let (input, color_transform) = cond(has_color_transform, parse_color_transform_with_alpha)(input)?;
   let (input, ratio) = cond(has_ratio, parse_le_u16)(input)?;
-  let (input, name) = cond(has_name, parse_c_string)(input)?;
+  let (input, name) = cond(has_name, |i| parse_swf_c_string(i, swf_version, encoding))(input)?;
   let (input, clip_depth) = cond(has_clip_depth, parse_le_u16)(input)?;
   let (input, filters) = cond(has_filters, parse_filter_list)(input)?;
   let (input, blend_mode) = cond(has_blend_mode, parse_blend_mode)(input)?;
@@ -1257,8 +1297,8 @@ pub fn parse_place_object3(input: &[u8], swf_version: u8) -> NomResult<&[u8], as
   ))
 }
 
-fn parse_protect(input: &[u8]) -> NomResult<&[u8], ast::tags::Protect> {
-  let (input, password) = parse_block_c_string(input)?;
+fn parse_protect(input: &[u8], swf_version: u8, encoding: LegacyEncoding) -> NomResult<&[u8], ast::tags::Protect> {
+  let (input, password) = parse_swf_block_c_string(input, swf_version, encoding)?;
   Ok((input, ast::tags::Protect { password }))
 }
 
@@ -1304,8 +1344,7 @@ pub fn parse_set_tab_index(input: &[u8]) -> NomResult<&[u8], ast::tags::SetTabIn
 }
 
 fn parse_sound_stream_block(input: &[u8]) -> NomResult<&[u8], ast::tags::SoundStreamBlock> {
-  let (input, data) = parse_bytes(input)?;
-  Ok((input, ast::tags::SoundStreamBlock { data }))
+  borrowed::parse_sound_stream_block(input).map(|(input, tag)| (input, tag.into_owned()))
 }
 
 fn parse_sound_stream_head(input: &[u8]) -> NomResult<&[u8], ast::tags::SoundStreamHead> {
@@ -1331,7 +1370,7 @@ fn parse_sound_stream_head_any(input: &[u8]) -> NomResult<&[u8], ast::tags::Soun
     ast::SoundSize::SoundSize8
   };
   let playback_sound_rate = sound_rate_from_code(((flags >> 2) & 0b11) as u8)
-    .map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch)))?;
+    .map_err(|_| nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch)))?;
   // Bits [4, 7] are reserved
   let stream_sound_type = if (flags & (1 << 8)) != 0 {
     ast::SoundType::Stereo
@@ -1344,9 +1383,9 @@ fn parse_sound_stream_head_any(input: &[u8]) -> NomResult<&[u8], ast::tags::Soun
     ast::SoundSize::SoundSize8
   };
   let stream_sound_rate = sound_rate_from_code(((flags >> 10) & 0b11) as u8)
-    .map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch)))?;
+    .map_err(|_| nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch)))?;
   let stream_format = audio_coding_format_from_code(((flags >> 12) & 0b1111) as u8)
-    .map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch)))?;
+    .map_err(|_| nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch)))?;
   let (input, stream_sample_count) = parse_le_u16(input)?;
   let (input, latency_seek) = cond(stream_format == ast::AudioCodingFormat::Mp3, parse_le_i16)(input)?;
   Ok((
@@ -1396,24 +1435,16 @@ pub fn parse_symbol_class(input: &[u8]) -> NomResult<&[u8], ast::tags::SymbolCla
 }
 
 pub fn parse_video_frame(input: &[u8]) -> NomResult<&[u8], ast::tags::VideoFrame> {
-  let (input, video_id) = parse_le_u16(input)?;
-  let (input, frame) = parse_le_u16(input)?;
-  let (input, packet) = parse_bytes(input)?;
-  Ok((
-    input,
-    ast::tags::VideoFrame {
-      video_id,
-      frame,
-      packet,
-    },
-  ))
+  borrowed::parse_video_frame(input).map(|(input, tag)| (input, tag.into_owned()))
 }
 
 #[cfg(test)]
 mod tests {
-  use super::parse_tag;
+  use super::{parse_swf_tags, parse_tag};
+  use crate::complete::{decompress_swf, parse_movie, parse_swf};
   use std::path::Path;
-  use swf_types::Tag;
+  use swf_types::tags::Raw;
+  use swf_types::{Tag, TagHeader};
   use test_generator::test_resources;
 
   #[test_resources("../tests/tags/*/*/")]
@@ -1422,7 +1453,7 @@ mod tests {
     let path: &Path = Path::new(path);
     let name = path
       .components()
-      .last()
+      .next_back()
       .unwrap()
       .as_os_str()
       .to_str()
@@ -1446,6 +1477,67 @@ mod tests {
     assert_eq!(remaining_bytes, &[] as &[u8], "Assert all input is consumed");
   }
 
+  #[test]
+  fn test_parse_swf_tags() {
+    let input: &[u8] = &[
+      0x44, 0x11, 0x08, 0x00, 0x00, 0x00, // FileAttributes
+      0x43, 0x02, 0xff, 0xff, 0xff, // SetBackgroundColor
+      0x40, 0x00, // ShowFrame
+      0x00, 0x00, // End
+      0x40, 0x00, // Ignored
+    ];
+    let tags: Vec<(usize, Option<(u16, u32)>)> = parse_swf_tags(input, 10)
+      .map(|(offset, header, _)| (offset, header.map(|h| (h.code, h.length))))
+      .collect();
+    assert_eq!(tags, vec![(0, Some((69, 4))), (6, Some((9, 3))), (11, Some((1, 0)))]);
+
+    let mut iter = parse_swf_tags(input, 10);
+    assert_eq!(iter.nth(1).map(|(_, _, tag)| tag.clone()), parse_tag(&input[6..], 10).1);
+    assert_eq!(iter.remaining(), &input[11..]);
+
+    let truncated: Vec<(usize, Option<(u16, u32)>)> = parse_swf_tags(&input[..9], 10)
+      .map(|(offset, header, _)| (offset, header.map(|h| (h.code, h.length))))
+      .collect();
+    assert_eq!(truncated, vec![(0, Some((69, 4))), (6, Some((9, 3)))]);
+
+    // Truncated tag header
+    let truncated: Vec<(usize, Option<TagHeader>, Tag)> = parse_swf_tags(&input[..7], 10).skip(1).collect();
+    assert_eq!(truncated, vec![(6, None, Tag::Raw(Raw { data: input[6..7].to_vec() }))]);
+  }
+
+  #[test]
+  fn test_parse_define_bits_jpeg2_dimensions() {
+    let input: &[u8] = &[
+      0x7f, 0x05, 0x16, 0x00, 0x00, 0x00, // DefineBitsJpeg2, long header
+      0x01, 0x00, // id
+      0xff, 0xd8, // SOI
+      0xff, 0xe0, 0x00, 0x04, 0x4a, 0x46, // APP0, skipped using its size
+      0xff, 0xc0, 0x00, 0x08, 0x08, 0x00, 0x10, 0x00, 0x20, 0x00, // SOF0, 32x16
+      0xff, 0xd9, // EOI
+    ];
+    match parse_tag(input, 10).1 {
+      Some(Tag::DefineBitmap(bitmap)) => {
+        assert_eq!((bitmap.width, bitmap.height), (32, 16));
+        assert_eq!(bitmap.data, input[8..].to_vec());
+      }
+      tag => panic!("Expected a bitmap, got: {:?}", tag),
+    }
+  }
+
+  #[test]
+  fn test_parse_movie() {
+    let movie_bytes: &[u8] = include_bytes!("../../../tests/movies/blank/main.swf");
+    let (signature, payload) = decompress_swf(movie_bytes).unwrap();
+    let (header, tags) = parse_movie(&payload, signature.swf_version).unwrap();
+    let expected = parse_swf(movie_bytes).unwrap();
+    assert_eq!(header, expected.header);
+    let tags: Vec<(usize, Option<TagHeader>, Tag)> = tags.collect();
+    // The tags follow the 13 bytes of the movie header
+    assert_eq!(tags[0].0, 13);
+    assert_eq!(tags[1].0, 13 + 6);
+    assert_eq!(tags.into_iter().map(|(_, _, tag)| tag).collect::<Vec<_>>(), expected.tags);
+  }
+
   //  #[test]
   //  fn test_fuzzing() {
   //    let artifact: &[u8] = include_bytes!("../../fuzz/artifacts/tag/crash-03fe96da7c46bbe615f27f3e6dbe7f481246e67f");
@@ -3,10 +3,11 @@ use crate::complete::shape::parse_glyph;
 use crate::streaming::basic_data_types::{
   do_parse_u32_bits, parse_i32_bits, parse_le_f16, parse_rect, parse_s_rgb8, parse_straight_s_rgba8, parse_u32_bits,
 };
+use crate::streaming::error::{LocatedError, NomResult};
+use nom::error::context;
 use nom::number::complete::{
   le_i16 as parse_le_i16, le_u16 as parse_le_u16, le_u32 as parse_le_u32, le_u8 as parse_u8,
 };
-use nom::IResult as NomResult;
 use std::convert::TryFrom;
 use swf_types as swf;
 
@@ -42,7 +43,7 @@ pub(crate) fn grid_fitting_from_code(grid_fitting_code: u8) -> Result<swf::text:
 pub fn parse_csm_table_hint_bits(input: (&[u8], usize)) -> NomResult<(&[u8], usize), swf::text::CsmTableHint> {
   let (input, code) = parse_u32_bits(input, 2)?;
   let csm_table_hint =
-    csm_table_hint_from_code(code).map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch)))?;
+    csm_table_hint_from_code(code).map_err(|_| nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch)))?;
   Ok((input, csm_table_hint))
 }
 
@@ -150,7 +151,10 @@ pub fn parse_text_record(
   let (input, offset_y) = cond(has_offset_y, parse_le_i16)(input)?;
   let (input, font_size) = cond(has_font, parse_le_u16)(input)?;
   let (input, entry_count) = parse_u8(input)?;
-  let (input, entries) = bits(|i| parse_glyph_entries(i, entry_count, index_bits, advance_bits))(input)?;
+  let (input, entries) = context(
+    "parse_glyph_entries",
+    bits(|i| parse_glyph_entries(i, entry_count, index_bits, advance_bits)),
+  )(input)?;
 
   Ok((
     input,
@@ -216,7 +220,7 @@ pub fn parse_offset_glyphs(
       let end_offset = offsets.get(i + 1).cloned().unwrap_or(end_offset);
       let glyph_input_size: usize = match end_offset.checked_sub(start_offset) {
         Some(x) => x,
-        None => return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify))),
+        None => return Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Verify))),
       };
       let (_, glyph_input) = offset_take(start_offset, glyph_input_size)(input)?;
       glyph_input
@@ -276,6 +280,6 @@ pub fn parse_text_alignment(input: &[u8]) -> NomResult<&[u8], swf::text::TextAli
     1 => Ok((input, swf::text::TextAlignment::Right)),
     2 => Ok((input, swf::text::TextAlignment::Center)),
     3 => Ok((input, swf::text::TextAlignment::Justify)),
-    _ => Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch))),
+    _ => Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch))),
   }
 }
@@ -0,0 +1,134 @@
+use std::convert::TryFrom;
+use std::io;
+
+/// Creates the error reported for AST values that can't be represented in the SWF format.
+pub(crate) fn invalid_value(message: &'static str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// Converts a length or count to a `u16`, failing with `message` if it is too large.
+pub(crate) fn to_u16(value: usize, message: &'static str) -> io::Result<u16> {
+  u16::try_from(value).map_err(|_| invalid_value(message))
+}
+
+/// Converts a length or count to a `u8`, failing with `message` if it is too large.
+pub(crate) fn to_u8(value: usize, message: &'static str) -> io::Result<u8> {
+  u8::try_from(value).map_err(|_| invalid_value(message))
+}
+
+/// Returns the minimum number of bits to represent `value` as an unsigned integer
+pub(crate) fn get_u32_bit_count(value: u32) -> u32 {
+  32 - value.leading_zeros()
+}
+
+/// Returns the minimum number of bits to represent `value` as a signed (two's complement) integer
+pub(crate) fn get_i32_bit_count(value: i32) -> u32 {
+  match value {
+    0 => 0,
+    v if v < 0 => 33 - (!v).leading_zeros(),
+    v => 33 - v.leading_zeros(),
+  }
+}
+
+/// Returns the minimum number of bits to represent all the `values` as signed integers
+pub(crate) fn get_i32_max_bit_count(values: &[i32]) -> u32 {
+  values.iter().map(|v| get_i32_bit_count(*v)).max().unwrap_or(0)
+}
+
+/// Bit-level writer, the counterpart of the `nom::bits` parsers.
+///
+/// Bits are written from the most significant bit of each byte. The last partial byte is padded
+/// with zeros by `align` (or `finish`).
+pub(crate) struct BitsWriter<'a, W: io::Write + ?Sized> {
+  inner: &'a mut W,
+  /// Pending bits, starting at the most significant bit
+  buffer: u8,
+  /// Number of pending bits
+  len: u32,
+}
+
+impl<'a, W: io::Write + ?Sized> BitsWriter<'a, W> {
+  pub(crate) fn new(inner: &'a mut W) -> Self {
+    Self {
+      inner,
+      buffer: 0,
+      len: 0,
+    }
+  }
+
+  pub(crate) fn write_bool(&mut self, value: bool) -> io::Result<()> {
+    if value {
+      self.buffer |= 1 << (7 - self.len);
+    }
+    self.len += 1;
+    if self.len == 8 {
+      self.inner.write_all(&[self.buffer])?;
+      self.buffer = 0;
+      self.len = 0;
+    }
+    Ok(())
+  }
+
+  /// Writes the `n` least significant bits of `value` (big-endian)
+  pub(crate) fn write_u32_bits(&mut self, n: u32, value: u32) -> io::Result<()> {
+    debug_assert!(n <= 32);
+    debug_assert!(n == 32 || value >> n == 0);
+    for i in (0..n).rev() {
+      self.write_bool((value >> i) & 1 != 0)?;
+    }
+    Ok(())
+  }
+
+  /// Writes `value` as a signed integer over `n` bits (big-endian)
+  pub(crate) fn write_i32_bits(&mut self, n: u32, value: i32) -> io::Result<()> {
+    debug_assert!(get_i32_bit_count(value) <= n);
+    let mask: u32 = if n == 32 { u32::MAX } else { (1 << n) - 1 };
+    self.write_u32_bits(n, (value as u32) & mask)
+  }
+
+  /// Pads the current byte with zeros and returns the underlying byte writer.
+  pub(crate) fn align(&mut self) -> io::Result<&mut W> {
+    if self.len > 0 {
+      self.inner.write_all(&[self.buffer])?;
+      self.buffer = 0;
+      self.len = 0;
+    }
+    Ok(self.inner)
+  }
+
+  /// Pads the last byte with zeros.
+  pub(crate) fn finish(mut self) -> io::Result<()> {
+    self.align().map(drop)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_bit_counts() {
+    assert_eq!(get_u32_bit_count(0), 0);
+    assert_eq!(get_u32_bit_count(1), 1);
+    assert_eq!(get_u32_bit_count(255), 8);
+    assert_eq!(get_i32_bit_count(0), 0);
+    assert_eq!(get_i32_bit_count(-1), 1);
+    assert_eq!(get_i32_bit_count(1), 2);
+    assert_eq!(get_i32_bit_count(-128), 8);
+    assert_eq!(get_i32_bit_count(127), 8);
+    assert_eq!(get_i32_bit_count(128), 9);
+    assert_eq!(get_i32_bit_count(i32::MIN), 32);
+    assert_eq!(get_i32_bit_count(i32::MAX), 32);
+  }
+
+  #[test]
+  fn test_bits_writer() {
+    let mut out: Vec<u8> = Vec::new();
+    let mut bits = BitsWriter::new(&mut out);
+    bits.write_bool(true).unwrap();
+    bits.write_u32_bits(3, 0b010).unwrap();
+    bits.write_i32_bits(6, -2).unwrap();
+    bits.finish().unwrap();
+    assert_eq!(out, vec![0b1010_1111, 0b1000_0000]);
+  }
+}
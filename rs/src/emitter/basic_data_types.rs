@@ -0,0 +1,219 @@
+use crate::emitter::base::{get_i32_bit_count, get_i32_max_bit_count, invalid_value, BitsWriter};
+use crate::legacy::LegacyEncoding;
+use crate::streaming::basic_data_types::LAST_LEGACY_STRING_VERSION;
+use half::f16;
+use std::borrow::Cow;
+use std::io;
+use swf_fixed::{Sfixed16P16, Sfixed8P8};
+use swf_types as swf;
+
+/// Emits a null-terminated string
+pub fn emit_c_string<W: io::Write + ?Sized>(writer: &mut W, value: &str) -> io::Result<()> {
+  if value.as_bytes().contains(&0) {
+    return Err(invalid_value("string contains a NUL byte"));
+  }
+  writer.write_all(value.as_bytes())?;
+  writer.write_all(&[0])
+}
+
+/// Encodes a string for the SWF version: legacy (SWF 5 and earlier) strings use `encoding`, later strings UTF-8.
+pub(crate) fn encode_swf_string(value: &str, swf_version: u8, encoding: LegacyEncoding) -> io::Result<Cow<'_, [u8]>> {
+  if swf_version > LAST_LEGACY_STRING_VERSION {
+    return Ok(Cow::Borrowed(value.as_bytes()));
+  }
+  encoding
+    .encode(value)
+    .ok_or_else(|| invalid_value("string not representable in the legacy encoding"))
+}
+
+/// Version-aware [[emit_c_string]]: encodes legacy (SWF 5 and earlier) strings with `encoding`.
+pub fn emit_swf_c_string<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &str,
+  swf_version: u8,
+  encoding: LegacyEncoding,
+) -> io::Result<()> {
+  let bytes = encode_swf_string(value, swf_version, encoding)?;
+  if bytes.contains(&0) {
+    return Err(invalid_value("string contains a NUL byte"));
+  }
+  writer.write_all(&bytes)?;
+  writer.write_all(&[0])
+}
+
+/// Emits the variable-length encoded little-endian representation of an unsigned 32-bit integer
+pub fn emit_leb128_u32<W: io::Write + ?Sized>(writer: &mut W, mut value: u32) -> io::Result<()> {
+  loop {
+    let next_byte = (value & 0x7f) as u8;
+    value >>= 7;
+    if value == 0 {
+      return writer.write_all(&[next_byte]);
+    }
+    writer.write_all(&[next_byte | 0x80])?;
+  }
+}
+
+pub fn emit_le_f16<W: io::Write + ?Sized>(writer: &mut W, value: f32) -> io::Result<()> {
+  writer.write_all(&f16::from_f32(value).to_bits().to_le_bytes())
+}
+
+/// Returns the bit count of a field whose width is stored in `width_bits` bits
+fn checked_bit_count(bit_count: u32, width_bits: u32) -> io::Result<u32> {
+  if bit_count < (1 << width_bits) {
+    Ok(bit_count)
+  } else {
+    Err(invalid_value("value too large for its bit-encoded representation"))
+  }
+}
+
+pub fn emit_rect<W: io::Write + ?Sized>(writer: &mut W, value: &swf::Rect) -> io::Result<()> {
+  let mut bits = BitsWriter::new(writer);
+  emit_rect_bits(&mut bits, value)?;
+  bits.finish()
+}
+
+pub fn emit_rect_bits<W: io::Write + ?Sized>(bits: &mut BitsWriter<W>, value: &swf::Rect) -> io::Result<()> {
+  let n_bits = checked_bit_count(
+    get_i32_max_bit_count(&[value.x_min, value.x_max, value.y_min, value.y_max]),
+    5,
+  )?;
+  bits.write_u32_bits(5, n_bits)?;
+  bits.write_i32_bits(n_bits, value.x_min)?;
+  bits.write_i32_bits(n_bits, value.x_max)?;
+  bits.write_i32_bits(n_bits, value.y_min)?;
+  bits.write_i32_bits(n_bits, value.y_max)
+}
+
+pub fn emit_s_rgb8<W: io::Write + ?Sized>(writer: &mut W, value: swf::SRgb8) -> io::Result<()> {
+  writer.write_all(&[value.r, value.g, value.b])
+}
+
+pub fn emit_straight_s_rgba8<W: io::Write + ?Sized>(writer: &mut W, value: swf::StraightSRgba8) -> io::Result<()> {
+  writer.write_all(&[value.r, value.g, value.b, value.a])
+}
+
+/// Emits an opaque color as `SRgb8`, failing if it has transparency.
+pub fn emit_opaque_s_rgba8<W: io::Write + ?Sized>(writer: &mut W, value: swf::StraightSRgba8) -> io::Result<()> {
+  if value.a != u8::MAX {
+    return Err(invalid_value("transparent color in an RGB context"));
+  }
+  writer.write_all(&[value.r, value.g, value.b])
+}
+
+pub fn emit_language_code<W: io::Write + ?Sized>(writer: &mut W, value: swf::LanguageCode) -> io::Result<()> {
+  let code: u8 = match value {
+    swf::LanguageCode::Auto => 0,
+    swf::LanguageCode::Latin => 1,
+    swf::LanguageCode::Japanese => 2,
+    swf::LanguageCode::Korean => 3,
+    swf::LanguageCode::SimplifiedChinese => 4,
+    swf::LanguageCode::TraditionalChinese => 5,
+  };
+  writer.write_all(&[code])
+}
+
+pub fn emit_matrix<W: io::Write + ?Sized>(writer: &mut W, value: &swf::Matrix) -> io::Result<()> {
+  let mut bits = BitsWriter::new(writer);
+  emit_matrix_bits(&mut bits, value)?;
+  bits.finish()
+}
+
+pub fn emit_matrix_bits<W: io::Write + ?Sized>(bits: &mut BitsWriter<W>, value: &swf::Matrix) -> io::Result<()> {
+  let has_scale = value.scale_x != Sfixed16P16::ONE || value.scale_y != Sfixed16P16::ONE;
+  bits.write_bool(has_scale)?;
+  if has_scale {
+    let scale_bits = checked_bit_count(
+      get_i32_max_bit_count(&[value.scale_x.epsilons, value.scale_y.epsilons]),
+      5,
+    )?;
+    bits.write_u32_bits(5, scale_bits)?;
+    bits.write_i32_bits(scale_bits, value.scale_x.epsilons)?;
+    bits.write_i32_bits(scale_bits, value.scale_y.epsilons)?;
+  }
+  let has_skew = value.rotate_skew0 != Sfixed16P16::ZERO || value.rotate_skew1 != Sfixed16P16::ZERO;
+  bits.write_bool(has_skew)?;
+  if has_skew {
+    let skew_bits = checked_bit_count(
+      get_i32_max_bit_count(&[value.rotate_skew0.epsilons, value.rotate_skew1.epsilons]),
+      5,
+    )?;
+    bits.write_u32_bits(5, skew_bits)?;
+    bits.write_i32_bits(skew_bits, value.rotate_skew0.epsilons)?;
+    bits.write_i32_bits(skew_bits, value.rotate_skew1.epsilons)?;
+  }
+  let translate_bits = checked_bit_count(get_i32_max_bit_count(&[value.translate_x, value.translate_y]), 5)?;
+  bits.write_u32_bits(5, translate_bits)?;
+  bits.write_i32_bits(translate_bits, value.translate_x)?;
+  bits.write_i32_bits(translate_bits, value.translate_y)
+}
+
+pub fn emit_named_id<W: io::Write + ?Sized>(writer: &mut W, value: &swf::NamedId) -> io::Result<()> {
+  writer.write_all(&value.id.to_le_bytes())?;
+  emit_c_string(writer, &value.name)
+}
+
+/// Version-aware [[emit_named_id]]: encodes legacy (SWF 5 and earlier) names with `encoding`.
+pub fn emit_swf_named_id<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::NamedId,
+  swf_version: u8,
+  encoding: LegacyEncoding,
+) -> io::Result<()> {
+  writer.write_all(&value.id.to_le_bytes())?;
+  emit_swf_c_string(writer, &value.name, swf_version, encoding)
+}
+
+pub fn emit_color_transform<W: io::Write + ?Sized>(writer: &mut W, value: &swf::ColorTransform) -> io::Result<()> {
+  let mut bits = BitsWriter::new(writer);
+  emit_color_transform_bits(
+    &mut bits,
+    &[value.red_mult, value.green_mult, value.blue_mult],
+    &[value.red_add, value.green_add, value.blue_add],
+  )?;
+  bits.finish()
+}
+
+pub fn emit_color_transform_with_alpha<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::ColorTransformWithAlpha,
+) -> io::Result<()> {
+  let mut bits = BitsWriter::new(writer);
+  emit_color_transform_bits(
+    &mut bits,
+    &[value.red_mult, value.green_mult, value.blue_mult, value.alpha_mult],
+    &[value.red_add, value.green_add, value.blue_add, value.alpha_add],
+  )?;
+  bits.finish()
+}
+
+/// Emits a color transform with or without alpha, depending on the number of channels
+fn emit_color_transform_bits<W: io::Write + ?Sized>(
+  bits: &mut BitsWriter<W>,
+  mult: &[Sfixed8P8],
+  add: &[i16],
+) -> io::Result<()> {
+  let has_mult = mult.iter().any(|m| *m != Sfixed8P8::ONE);
+  let has_add = add.iter().any(|a| *a != 0);
+  let mut n_bits: u32 = 0;
+  if has_mult {
+    n_bits = mult.iter().map(|m| get_i32_bit_count(i32::from(m.epsilons))).fold(n_bits, u32::max);
+  }
+  if has_add {
+    n_bits = add.iter().map(|a| get_i32_bit_count(i32::from(*a))).fold(n_bits, u32::max);
+  }
+  let n_bits = checked_bit_count(n_bits, 4)?;
+  bits.write_bool(has_add)?;
+  bits.write_bool(has_mult)?;
+  bits.write_u32_bits(4, n_bits)?;
+  if has_mult {
+    for m in mult {
+      bits.write_i32_bits(n_bits, i32::from(m.epsilons))?;
+    }
+  }
+  if has_add {
+    for a in add {
+      bits.write_i32_bits(n_bits, i32::from(*a))?;
+    }
+  }
+  Ok(())
+}
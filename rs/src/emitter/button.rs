@@ -0,0 +1,191 @@
+use crate::complete::button::ButtonVersion;
+use crate::emitter::base::{invalid_value, to_u16};
+use crate::emitter::basic_data_types::{emit_color_transform_with_alpha, emit_matrix};
+use crate::emitter::display::{emit_blend_mode, emit_filter_list};
+use std::io;
+use swf_types as swf;
+
+/// Checks if the button record only uses the features of `DefineButton` records
+pub(crate) fn is_button1_record(value: &swf::ButtonRecord) -> bool {
+  value.color_transform == swf::ColorTransformWithAlpha::default()
+    && value.filters.is_empty()
+    && value.blend_mode == swf::BlendMode::Normal
+}
+
+pub fn emit_button_record_string<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &[swf::ButtonRecord],
+  version: ButtonVersion,
+) -> io::Result<()> {
+  for button_record in value {
+    emit_button_record(writer, button_record, version)?;
+  }
+  writer.write_all(&[0])
+}
+
+pub fn emit_button_record<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::ButtonRecord,
+  version: ButtonVersion,
+) -> io::Result<()> {
+  if version < ButtonVersion::Button2 && !is_button1_record(value) {
+    return Err(invalid_value("button record requires `DefineButton2`"));
+  }
+  let has_filter_list = !value.filters.is_empty();
+  let has_blend_mode = value.blend_mode != swf::BlendMode::Normal;
+  let has_state = value.state_up || value.state_over || value.state_down || value.state_hit_test;
+  if !has_state && !has_filter_list && !has_blend_mode {
+    // The flags byte would be read as the end of the record string
+    return Err(invalid_value("button record without state"));
+  }
+  #[allow(clippy::identity_op)]
+  let flags: u8 = 0
+    | (if value.state_up { 1 << 0 } else { 0 })
+    | (if value.state_over { 1 << 1 } else { 0 })
+    | (if value.state_down { 1 << 2 } else { 0 })
+    | (if value.state_hit_test { 1 << 3 } else { 0 })
+    | (if has_filter_list { 1 << 4 } else { 0 })
+    | (if has_blend_mode { 1 << 5 } else { 0 });
+  writer.write_all(&[flags])?;
+  writer.write_all(&value.character_id.to_le_bytes())?;
+  writer.write_all(&value.depth.to_le_bytes())?;
+  emit_matrix(writer, &value.matrix)?;
+  if version >= ButtonVersion::Button2 {
+    emit_color_transform_with_alpha(writer, &value.color_transform)?;
+    if has_filter_list {
+      emit_filter_list(writer, &value.filters)?;
+    }
+    if has_blend_mode {
+      emit_blend_mode(writer, value.blend_mode)?;
+    }
+  }
+  Ok(())
+}
+
+pub fn emit_button2_cond_action_string<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &[swf::ButtonCondAction],
+) -> io::Result<()> {
+  for (index, cond_action) in value.iter().enumerate() {
+    let is_last = index + 1 == value.len();
+    emit_button2_cond_action(writer, cond_action, is_last)?;
+  }
+  Ok(())
+}
+
+pub fn emit_button2_cond_action<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::ButtonCondAction,
+  is_last: bool,
+) -> io::Result<()> {
+  let conditions = match &value.conditions {
+    Some(conditions) => conditions,
+    None => return Err(invalid_value("`DefineButton2` actions require conditions")),
+  };
+  let next_action_offset: u16 = if is_last {
+    0
+  } else {
+    to_u16(value.actions.len() + 4, "button actions too large")?
+  };
+  writer.write_all(&next_action_offset.to_le_bytes())?;
+  emit_button_cond(writer, conditions)?;
+  writer.write_all(&value.actions)
+}
+
+pub fn emit_button_cond<W: io::Write + ?Sized>(writer: &mut W, value: &swf::ButtonCond) -> io::Result<()> {
+  let key_press_code: u16 = match value.key_press {
+    None => 0,
+    Some(k @ 1..=6) | Some(k @ 8) | Some(k @ 13..=19) | Some(k @ 32..=126) => k as u16,
+    Some(_) => return Err(invalid_value("invalid button key press code")),
+  };
+  #[allow(clippy::identity_op)]
+  let flags: u16 = 0
+    | (if value.idle_to_over_up { 1 << 0 } else { 0 })
+    | (if value.over_up_to_idle { 1 << 1 } else { 0 })
+    | (if value.over_up_to_over_down { 1 << 2 } else { 0 })
+    | (if value.over_down_to_over_up { 1 << 3 } else { 0 })
+    | (if value.over_down_to_out_down { 1 << 4 } else { 0 })
+    | (if value.out_down_to_over_down { 1 << 5 } else { 0 })
+    | (if value.out_down_to_idle { 1 << 6 } else { 0 })
+    | (if value.idle_to_over_down { 1 << 7 } else { 0 })
+    | (if value.over_down_to_idle { 1 << 8 } else { 0 })
+    | (key_press_code << 9);
+  writer.write_all(&flags.to_le_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::complete::button::{parse_button2_cond_action_string, parse_button_record_string};
+
+  fn cond(key_press: Option<u32>) -> swf::ButtonCond {
+    swf::ButtonCond {
+      idle_to_over_up: false,
+      over_up_to_idle: true,
+      over_up_to_over_down: false,
+      over_down_to_over_up: false,
+      over_down_to_out_down: false,
+      out_down_to_over_down: false,
+      out_down_to_idle: false,
+      idle_to_over_down: false,
+      over_down_to_idle: true,
+      key_press,
+    }
+  }
+
+  #[test]
+  fn test_emit_button_record_string() {
+    let record = swf::ButtonRecord {
+      state_up: true,
+      state_over: false,
+      state_down: false,
+      state_hit_test: true,
+      character_id: 3,
+      depth: 2,
+      matrix: swf::Matrix::default(),
+      color_transform: swf::ColorTransformWithAlpha::default(),
+      filters: Vec::new(),
+      blend_mode: swf::BlendMode::Normal,
+    };
+    let records = vec![record.clone()];
+    let mut out: Vec<u8> = Vec::new();
+    emit_button_record_string(&mut out, &records, ButtonVersion::Button1).unwrap();
+    assert_eq!(out, vec![0b0000_1001, 0x03, 0x00, 0x02, 0x00, 0x00, 0x00]);
+    assert_eq!(parse_button_record_string(&out, ButtonVersion::Button1), Ok((&[][..], records)));
+
+    let records = vec![swf::ButtonRecord {
+      blend_mode: swf::BlendMode::Screen,
+      ..record
+    }];
+    let mut out: Vec<u8> = Vec::new();
+    emit_button_record_string(&mut out, &records, ButtonVersion::Button2).unwrap();
+    assert_eq!(parse_button_record_string(&out, ButtonVersion::Button2), Ok((&[][..], records)));
+  }
+
+  #[test]
+  fn test_emit_button2_cond_action_string() {
+    let actions = vec![
+      swf::ButtonCondAction {
+        conditions: Some(cond(None)),
+        actions: vec![0x06, 0x00],
+      },
+      swf::ButtonCondAction {
+        conditions: Some(cond(Some(u32::from(b'a')))),
+        actions: vec![0x07, 0x00],
+      },
+    ];
+    let mut out: Vec<u8> = Vec::new();
+    emit_button2_cond_action_string(&mut out, &actions).unwrap();
+    // Only the last condition has a null offset to the next condition
+    assert_eq!(&out[..2], &[0x06, 0x00]);
+    assert_eq!(&out[6..8], &[0x00, 0x00]);
+    assert_eq!(parse_button2_cond_action_string(&out).map(|(_, value)| value), Ok(actions));
+
+    let mut out: Vec<u8> = Vec::new();
+    let invalid_key = swf::ButtonCondAction {
+      conditions: Some(cond(Some(7))),
+      actions: Vec::new(),
+    };
+    assert!(emit_button2_cond_action(&mut out, &invalid_key, true).is_err());
+  }
+}
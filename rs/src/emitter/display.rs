@@ -0,0 +1,331 @@
+use crate::emitter::base::{invalid_value, to_u8};
+use crate::emitter::basic_data_types::emit_straight_s_rgba8;
+use std::convert::TryFrom;
+use std::io;
+use swf_fixed::{Sfixed16P16, Sfixed8P8};
+use swf_types as swf;
+
+pub fn emit_blend_mode<W: io::Write + ?Sized>(writer: &mut W, value: swf::BlendMode) -> io::Result<()> {
+  let code: u8 = match value {
+    swf::BlendMode::Normal => 1,
+    swf::BlendMode::Layer => 2,
+    swf::BlendMode::Multiply => 3,
+    swf::BlendMode::Screen => 4,
+    swf::BlendMode::Lighten => 5,
+    swf::BlendMode::Darken => 6,
+    swf::BlendMode::Difference => 7,
+    swf::BlendMode::Add => 8,
+    swf::BlendMode::Subtract => 9,
+    swf::BlendMode::Invert => 10,
+    swf::BlendMode::Alpha => 11,
+    swf::BlendMode::Erase => 12,
+    swf::BlendMode::Overlay => 13,
+    swf::BlendMode::Hardlight => 14,
+  };
+  writer.write_all(&[code])
+}
+
+pub(crate) fn get_clip_event_flags_bits(value: &swf::ClipEventFlags) -> u32 {
+  #[allow(clippy::identity_op)]
+  let flags: u32 = 0
+    | (if value.load { 1 << 0 } else { 0 })
+    | (if value.enter_frame { 1 << 1 } else { 0 })
+    | (if value.unload { 1 << 2 } else { 0 })
+    | (if value.mouse_move { 1 << 3 } else { 0 })
+    | (if value.mouse_down { 1 << 4 } else { 0 })
+    | (if value.mouse_up { 1 << 5 } else { 0 })
+    | (if value.key_down { 1 << 6 } else { 0 })
+    | (if value.key_up { 1 << 7 } else { 0 })
+    | (if value.data { 1 << 8 } else { 0 })
+    | (if value.initialize { 1 << 9 } else { 0 })
+    | (if value.press { 1 << 10 } else { 0 })
+    | (if value.release { 1 << 11 } else { 0 })
+    | (if value.release_outside { 1 << 12 } else { 0 })
+    | (if value.roll_over { 1 << 13 } else { 0 })
+    | (if value.roll_out { 1 << 14 } else { 0 })
+    | (if value.drag_over { 1 << 15 } else { 0 })
+    | (if value.drag_out { 1 << 16 } else { 0 })
+    | (if value.key_press { 1 << 17 } else { 0 })
+    | (if value.construct { 1 << 18 } else { 0 });
+  flags
+}
+
+fn emit_clip_event_flags_bits<W: io::Write + ?Sized>(writer: &mut W, flags: u32, extended_events: bool) -> io::Result<()> {
+  if extended_events {
+    writer.write_all(&flags.to_le_bytes())
+  } else {
+    let flags = u16::try_from(flags).map_err(|_| invalid_value("clip event requires SWF 6"))?;
+    writer.write_all(&flags.to_le_bytes())
+  }
+}
+
+pub fn emit_clip_event_flags<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::ClipEventFlags,
+  extended_events: bool,
+) -> io::Result<()> {
+  emit_clip_event_flags_bits(writer, get_clip_event_flags_bits(value), extended_events)
+}
+
+pub fn emit_clip_actions_string<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &[swf::ClipAction],
+  extended_events: bool,
+) -> io::Result<()> {
+  let all_events = value
+    .iter()
+    .map(|clip_action| get_clip_event_flags_bits(&clip_action.events))
+    .fold(0, |acc, flags| acc | flags);
+  writer.write_all(&[0, 0])?; // Reserved
+  emit_clip_event_flags_bits(writer, all_events, extended_events)?;
+  for clip_action in value {
+    emit_clip_actions(writer, clip_action, extended_events)?;
+  }
+  emit_clip_event_flags_bits(writer, 0, extended_events)
+}
+
+pub fn emit_clip_actions<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::ClipAction,
+  extended_events: bool,
+) -> io::Result<()> {
+  if get_clip_event_flags_bits(&value.events) == 0 {
+    return Err(invalid_value("clip action without events"));
+  }
+  if value.events.key_press != value.key_code.is_some() {
+    return Err(invalid_value("clip action key code must be set for key press events only"));
+  }
+  emit_clip_event_flags(writer, &value.events, extended_events)?;
+  let key_code_size = if value.key_code.is_some() { 1 } else { 0 };
+  let actions_size =
+    u32::try_from(value.actions.len() + key_code_size).map_err(|_| invalid_value("clip actions too large"))?;
+  writer.write_all(&actions_size.to_le_bytes())?;
+  if let Some(key_code) = value.key_code {
+    writer.write_all(&[key_code])?;
+  }
+  writer.write_all(&value.actions)
+}
+
+pub fn emit_filter_list<W: io::Write + ?Sized>(writer: &mut W, value: &[swf::Filter]) -> io::Result<()> {
+  writer.write_all(&[to_u8(value.len(), "too many filters")?])?;
+  for filter in value {
+    emit_filter(writer, filter)?;
+  }
+  Ok(())
+}
+
+pub fn emit_filter<W: io::Write + ?Sized>(writer: &mut W, value: &swf::Filter) -> io::Result<()> {
+  match value {
+    swf::Filter::DropShadow(filter) => {
+      writer.write_all(&[0])?;
+      emit_drop_shadow_filter(writer, filter)
+    }
+    swf::Filter::Blur(filter) => {
+      writer.write_all(&[1])?;
+      emit_blur_filter(writer, filter)
+    }
+    swf::Filter::Glow(filter) => {
+      writer.write_all(&[2])?;
+      emit_glow_filter(writer, filter)
+    }
+    swf::Filter::Bevel(filter) => {
+      writer.write_all(&[3])?;
+      emit_bevel_filter(writer, filter)
+    }
+    swf::Filter::GradientGlow(filter) => {
+      writer.write_all(&[4])?;
+      emit_gradient_glow_filter(writer, filter)
+    }
+    swf::Filter::Convolution(filter) => {
+      writer.write_all(&[5])?;
+      emit_convolution_filter(writer, filter)
+    }
+    swf::Filter::ColorMatrix(filter) => {
+      writer.write_all(&[6])?;
+      emit_color_matrix_filter(writer, filter)
+    }
+    swf::Filter::GradientBevel(filter) => {
+      writer.write_all(&[7])?;
+      emit_gradient_bevel_filter(writer, filter)
+    }
+  }
+}
+
+fn emit_le_fixed16_p16<W: io::Write + ?Sized>(writer: &mut W, value: Sfixed16P16) -> io::Result<()> {
+  writer.write_all(&value.epsilons.to_le_bytes())
+}
+
+fn emit_le_fixed8_p8<W: io::Write + ?Sized>(writer: &mut W, value: Sfixed8P8) -> io::Result<()> {
+  writer.write_all(&value.epsilons.to_le_bytes())
+}
+
+/// Returns the flags shared by the shadow filters, `passes_bits` is the width of the `passes` field
+fn get_shadow_filter_flags(
+  passes: u8,
+  passes_bits: u32,
+  on_top: bool,
+  composite_source: bool,
+  knockout: bool,
+  inner: bool,
+) -> io::Result<u8> {
+  if u32::from(passes) >= (1 << passes_bits) {
+    return Err(invalid_value("too many filter passes"));
+  }
+  Ok(
+    passes
+      | (if on_top { 1 << 4 } else { 0 })
+      | (if composite_source { 1 << 5 } else { 0 })
+      | (if knockout { 1 << 6 } else { 0 })
+      | (if inner { 1 << 7 } else { 0 }),
+  )
+}
+
+pub fn emit_bevel_filter<W: io::Write + ?Sized>(writer: &mut W, value: &swf::filters::Bevel) -> io::Result<()> {
+  emit_straight_s_rgba8(writer, value.shadow_color)?;
+  emit_straight_s_rgba8(writer, value.highlight_color)?;
+  emit_le_fixed16_p16(writer, value.blur_x)?;
+  emit_le_fixed16_p16(writer, value.blur_y)?;
+  emit_le_fixed16_p16(writer, value.angle)?;
+  emit_le_fixed16_p16(writer, value.distance)?;
+  emit_le_fixed8_p8(writer, value.strength)?;
+  let flags = get_shadow_filter_flags(
+    value.passes,
+    4,
+    value.on_top,
+    value.composite_source,
+    value.knockout,
+    value.inner,
+  )?;
+  writer.write_all(&[flags])
+}
+
+pub fn emit_blur_filter<W: io::Write + ?Sized>(writer: &mut W, value: &swf::filters::Blur) -> io::Result<()> {
+  emit_le_fixed16_p16(writer, value.blur_x)?;
+  emit_le_fixed16_p16(writer, value.blur_y)?;
+  if value.passes >= (1 << 5) {
+    return Err(invalid_value("too many filter passes"));
+  }
+  writer.write_all(&[value.passes << 3])
+}
+
+pub fn emit_color_matrix_filter<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::filters::ColorMatrix,
+) -> io::Result<()> {
+  for matrix_value in &value.matrix {
+    writer.write_all(&matrix_value.to_le_bytes())?;
+  }
+  Ok(())
+}
+
+pub fn emit_convolution_filter<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::filters::Convolution,
+) -> io::Result<()> {
+  if value.matrix.len() != value.matrix_width * value.matrix_height {
+    return Err(invalid_value("convolution matrix size mismatch"));
+  }
+  writer.write_all(&[
+    to_u8(value.matrix_width, "convolution matrix too wide")?,
+    to_u8(value.matrix_height, "convolution matrix too high")?,
+  ])?;
+  writer.write_all(&value.divisor.to_le_bytes())?;
+  writer.write_all(&value.bias.to_le_bytes())?;
+  for matrix_value in &value.matrix {
+    writer.write_all(&matrix_value.to_le_bytes())?;
+  }
+  emit_straight_s_rgba8(writer, value.default_color)?;
+  #[allow(clippy::identity_op)]
+  let flags: u8 = 0 | (if value.preserve_alpha { 1 << 0 } else { 0 }) | (if value.clamp { 1 << 1 } else { 0 });
+  writer.write_all(&[flags])
+}
+
+pub fn emit_drop_shadow_filter<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::filters::DropShadow,
+) -> io::Result<()> {
+  emit_straight_s_rgba8(writer, value.color)?;
+  emit_le_fixed16_p16(writer, value.blur_x)?;
+  emit_le_fixed16_p16(writer, value.blur_y)?;
+  emit_le_fixed16_p16(writer, value.angle)?;
+  emit_le_fixed16_p16(writer, value.distance)?;
+  emit_le_fixed8_p8(writer, value.strength)?;
+  let flags = get_shadow_filter_flags(
+    value.passes,
+    5,
+    false,
+    value.composite_source,
+    value.knockout,
+    value.inner,
+  )?;
+  writer.write_all(&[flags])
+}
+
+pub fn emit_glow_filter<W: io::Write + ?Sized>(writer: &mut W, value: &swf::filters::Glow) -> io::Result<()> {
+  emit_straight_s_rgba8(writer, value.color)?;
+  emit_le_fixed16_p16(writer, value.blur_x)?;
+  emit_le_fixed16_p16(writer, value.blur_y)?;
+  emit_le_fixed8_p8(writer, value.strength)?;
+  let flags = get_shadow_filter_flags(
+    value.passes,
+    5,
+    false,
+    value.composite_source,
+    value.knockout,
+    value.inner,
+  )?;
+  writer.write_all(&[flags])
+}
+
+fn emit_filter_gradient<W: io::Write + ?Sized>(writer: &mut W, value: &[swf::ColorStop]) -> io::Result<()> {
+  writer.write_all(&[to_u8(value.len(), "too many filter gradient colors")?])?;
+  for color_stop in value {
+    emit_straight_s_rgba8(writer, color_stop.color)?;
+  }
+  for color_stop in value {
+    writer.write_all(&[color_stop.ratio])?;
+  }
+  Ok(())
+}
+
+pub fn emit_gradient_bevel_filter<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::filters::GradientBevel,
+) -> io::Result<()> {
+  emit_filter_gradient(writer, &value.gradient)?;
+  emit_le_fixed16_p16(writer, value.blur_x)?;
+  emit_le_fixed16_p16(writer, value.blur_y)?;
+  emit_le_fixed16_p16(writer, value.angle)?;
+  emit_le_fixed16_p16(writer, value.distance)?;
+  emit_le_fixed8_p8(writer, value.strength)?;
+  let flags = get_shadow_filter_flags(
+    value.passes,
+    4,
+    value.on_top,
+    value.composite_source,
+    value.knockout,
+    value.inner,
+  )?;
+  writer.write_all(&[flags])
+}
+
+pub fn emit_gradient_glow_filter<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::filters::GradientGlow,
+) -> io::Result<()> {
+  emit_filter_gradient(writer, &value.gradient)?;
+  emit_le_fixed16_p16(writer, value.blur_x)?;
+  emit_le_fixed16_p16(writer, value.blur_y)?;
+  emit_le_fixed16_p16(writer, value.angle)?;
+  emit_le_fixed16_p16(writer, value.distance)?;
+  emit_le_fixed8_p8(writer, value.strength)?;
+  let flags = get_shadow_filter_flags(
+    value.passes,
+    4,
+    value.on_top,
+    value.composite_source,
+    value.knockout,
+    value.inner,
+  )?;
+  writer.write_all(&[flags])
+}
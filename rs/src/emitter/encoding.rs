@@ -0,0 +1,221 @@
+use crate::complete::encoding::{MovieEncoding, TagEncoding, TagStringEncoding};
+use crate::emitter::base::{invalid_value, to_u16};
+use crate::emitter::movie::{emit_header, emit_swf_payload, emit_swf_signature, SwfEmitError};
+use crate::emitter::tag::{emit_end_tag, emit_tag_body, emit_tag_header_with_form, emit_tag_with_legacy_encoding};
+use crate::legacy::LegacyEncoding;
+use std::convert::TryFrom;
+use std::io;
+use std::mem::discriminant;
+use swf_types as ast;
+
+/// Emits a complete SWF file, reusing the original encoding of the parts that were not modified.
+///
+/// `encoding` is the encoding of the original movie, see [[crate::complete::parse_swf_with_encoding]].
+/// The movie is compressed with the original compression method. If `value` is unmodified, the
+/// original file is returned byte for byte.
+///
+/// Unmodified tags keep their original bytes, even if they were moved, and the bytes following the
+/// `End` tag are kept. A modified tag replacing an original tag of the same type keeps its header form
+/// (short or long), but its body is emitted as by [[crate::emitter::emit_tag]]. Modified sprites reuse the
+/// encoding of their nested tags. The strings of SWF 5 (and earlier) movies are encoded with the original
+/// legacy encoding.
+pub fn emit_swf_with_encoding(value: &ast::Movie, encoding: &MovieEncoding) -> Result<Vec<u8>, SwfEmitError> {
+  let swf_version = value.header.swf_version;
+  let mut payload: Vec<u8> = Vec::new();
+  emit_header_with_encoding(&mut payload, &value.header, encoding)?;
+  emit_tag_string_with_encoding(
+    &mut payload,
+    &value.tags,
+    &encoding.tags,
+    &encoding.payload,
+    swf_version,
+    encoding.legacy_encoding,
+  )?;
+
+  if payload == encoding.payload && swf_version == encoding.signature.swf_version {
+    let mut swf: Vec<u8> = Vec::new();
+    emit_swf_signature(&mut swf, &encoding.signature)?;
+    match encoding.signature.compression_method {
+      ast::CompressionMethod::None => swf.extend_from_slice(&payload),
+      _ => swf.extend_from_slice(&encoding.data),
+    }
+    return Ok(swf);
+  }
+  emit_swf_payload(&payload, encoding.signature.compression_method, swf_version)
+}
+
+/// Emits the movie header, keeping the original encoding of the frame size if it was not modified.
+fn emit_header_with_encoding(writer: &mut Vec<u8>, value: &ast::Header, encoding: &MovieEncoding) -> io::Result<()> {
+  if value.frame_size != encoding.header.frame_size {
+    return emit_header(writer, value);
+  }
+  // The frame size is followed by the frame rate and frame count (2 bytes each)
+  let frame_size_len = encoding.header_len - 4;
+  writer.extend_from_slice(&encoding.payload[..frame_size_len]);
+  writer.extend_from_slice(&value.frame_rate.epsilons.to_le_bytes());
+  writer.extend_from_slice(&value.frame_count.to_le_bytes());
+  Ok(())
+}
+
+/// Emits a string of tags, followed by the original `End` tag and trailing bytes.
+///
+/// Each tag is matched with the next original tag equal to it: the original tags skipped this way
+/// were removed. Tags without an equal original tag are matched with the next original tag if it has
+/// the same type, otherwise they were inserted.
+fn emit_tag_string_with_encoding(
+  writer: &mut Vec<u8>,
+  value: &[ast::Tag],
+  encoding: &TagStringEncoding,
+  payload: &[u8],
+  swf_version: u8,
+  legacy_encoding: LegacyEncoding,
+) -> io::Result<()> {
+  let mut originals: &[TagEncoding] = &encoding.tags;
+  // Whether the tags are the original tags, emitted from their original bytes
+  let mut is_verbatim = true;
+  for tag in value {
+    if let Some(index) = originals.iter().position(|original| original.tag == *tag) {
+      writer.extend_from_slice(&payload[originals[index].span.range()]);
+      is_verbatim = is_verbatim && index == 0;
+      originals = &originals[(index + 1)..];
+      continue;
+    }
+    is_verbatim = false;
+    match originals.first() {
+      Some(original) if discriminant(&original.tag) == discriminant(tag) => {
+        emit_tag_with_encoding(writer, tag, original, payload, swf_version, legacy_encoding)?;
+        originals = &originals[1..];
+      }
+      _ => emit_tag_with_legacy_encoding(writer, tag, swf_version, legacy_encoding)?,
+    }
+  }
+  is_verbatim = is_verbatim && originals.is_empty();
+  if encoding.end.is_empty() && !is_verbatim && value.last().map_or(true, |tag| !matches!(tag, ast::Tag::Raw(_))) {
+    // The original string was truncated, but it was modified and the last tag is complete
+    return emit_end_tag(writer);
+  }
+  writer.extend_from_slice(&payload[encoding.end.clone()]);
+  Ok(())
+}
+
+/// Emits a modified tag, keeping the header form of the original tag it replaces.
+fn emit_tag_with_encoding(
+  writer: &mut Vec<u8>,
+  value: &ast::Tag,
+  original: &TagEncoding,
+  payload: &[u8],
+  swf_version: u8,
+  legacy_encoding: LegacyEncoding,
+) -> io::Result<()> {
+  let mut body: Vec<u8> = Vec::new();
+  let code = match (value, &original.nested) {
+    (ast::Tag::Raw(tag), _) => {
+      writer.extend_from_slice(&tag.data);
+      return Ok(());
+    }
+    (ast::Tag::DefineSprite(tag), Some(nested)) => {
+      body.extend_from_slice(&tag.id.to_le_bytes());
+      body.extend_from_slice(&to_u16(tag.frame_count, "sprite frame count too large")?.to_le_bytes());
+      emit_tag_string_with_encoding(&mut body, &tag.tags, nested, payload, swf_version, legacy_encoding)?;
+      39
+    }
+    _ => emit_tag_body(&mut body, value, swf_version, legacy_encoding)?,
+  };
+  let length = u32::try_from(body.len()).map_err(|_| invalid_value("tag too large"))?;
+  emit_tag_header_with_form(writer, ast::TagHeader { code, length }, original.span.is_long_header())?;
+  writer.extend_from_slice(&body);
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::emit_swf_with_encoding;
+  use crate::complete::{parse_swf, parse_swf_with_encoding};
+  use crate::emitter::emit_swf;
+  use std::path::Path;
+  use swf_types as ast;
+  use test_generator::test_resources;
+
+  #[test_resources("../tests/movies/*/")]
+  fn test_emit_swf_with_encoding(path: &str) {
+    let path: &Path = Path::new(path);
+    let movie_bytes: Vec<u8> = ::std::fs::read(path.join("main.swf")).expect("Failed to read movie");
+    let (movie, encoding) = parse_swf_with_encoding(&movie_bytes).expect("Failed to parse movie");
+    assert_eq!(parse_swf(&movie_bytes), Ok(movie.clone()));
+    assert_eq!(emit_swf_with_encoding(&movie, &encoding), Ok(movie_bytes));
+  }
+
+  #[test]
+  fn test_emit_swf_with_encoding_compressed() {
+    let movie_bytes: &[u8] = include_bytes!("../../../tests/movies/blank/main.swf");
+    let movie = parse_swf(movie_bytes).unwrap();
+    let compressed = emit_swf(&movie, ast::CompressionMethod::Deflate).unwrap();
+    let (movie, encoding) = parse_swf_with_encoding(&compressed).unwrap();
+    assert_eq!(emit_swf_with_encoding(&movie, &encoding), Ok(compressed));
+  }
+
+  #[test]
+  fn test_emit_swf_with_encoding_modified() {
+    let movie_bytes: &[u8] = include_bytes!("../../../tests/movies/blank/main.swf");
+    let (mut movie, encoding) = parse_swf_with_encoding(movie_bytes).unwrap();
+    movie.header.frame_count = 2;
+    movie.tags.remove(0);
+    match &mut movie.tags[1] {
+      ast::Tag::RawBody(tag) => tag.data = b"<rdf:RDF></rdf:RDF>\0".to_vec(),
+      _ => panic!("Expected `RawBody` tag"),
+    }
+    movie.tags.push(ast::Tag::ShowFrame);
+    let expected: &[u8] = &[
+      0x46, 0x57, 0x53, 0x0a, 0x3a, 0x00, 0x00, 0x00, // Signature
+      0x80, 0x00, 0x01, 0x57, 0xc0, 0x00, 0x00, 0xfa, 0x00, 0x00, 0x18, 0x02, 0x00, // Header
+      0x43, 0x02, 0xff, 0xff, 0xff, // SetBackgroundColor
+      0x3f, 0x13, 0x14, 0x00, 0x00, 0x00, // RawBody (code 76), keeping the long header form
+      0x3c, 0x72, 0x64, 0x66, 0x3a, 0x52, 0x44, 0x46, 0x3e, 0x3c, 0x2f, 0x72, 0x64, 0x66, 0x3a, 0x52, 0x44, 0x46,
+      0x3e, 0x00, //
+      0x40, 0x00, // ShowFrame
+      0x40, 0x00, // ShowFrame
+      0x00, 0x00, // End
+    ];
+    assert_eq!(emit_swf_with_encoding(&movie, &encoding), Ok(expected.to_vec()));
+  }
+
+  #[test]
+  fn test_emit_swf_with_encoding_modified_sprite() {
+    let payload: &[u8] = &[
+      0x00, 0x00, 0x19, 0x01, 0x00, // Header: empty frame size, 25 fps, 1 frame
+      // DefineSprite (id 1, 1 frame), using the long header form, containing `ShowFrame` (long form)
+      0xff, 0x09, 0x0c, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x7f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+      0x00, 0x00, // End
+    ];
+    let movie_bytes: Vec<u8> = [&b"FWS\x0a\x21\x00\x00\x00"[..], payload].concat();
+    let (mut movie, encoding) = parse_swf_with_encoding(&movie_bytes).unwrap();
+    match &mut movie.tags[0] {
+      ast::Tag::DefineSprite(tag) => tag.tags.push(ast::Tag::ShowFrame),
+      _ => panic!("Expected `DefineSprite` tag"),
+    }
+    let expected: Vec<u8> = [
+      &b"FWS\x0a\x23\x00\x00\x00"[..],
+      &[
+        0x00, 0x00, 0x19, 0x01, 0x00, //
+        0xff, 0x09, 0x0e, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x7f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00,
+        0x00, 0x00, //
+        0x00, 0x00,
+      ],
+    ]
+    .concat();
+    assert_eq!(emit_swf_with_encoding(&movie, &encoding), Ok(expected));
+  }
+
+  #[test]
+  fn test_emit_swf_with_encoding_without_end() {
+    // Header: empty frame size, 25 fps, 1 frame, followed by `ShowFrame` without `End` tag
+    let movie_bytes: &[u8] = b"FWS\x0a\x0f\x00\x00\x00\x00\x00\x19\x01\x00\x40\x00";
+    let (mut movie, encoding) = parse_swf_with_encoding(movie_bytes).unwrap();
+    assert_eq!(movie.tags, vec![ast::Tag::ShowFrame]);
+    assert_eq!(emit_swf_with_encoding(&movie, &encoding), Ok(movie_bytes.to_vec()));
+
+    movie.tags.push(ast::Tag::ShowFrame);
+    let expected: &[u8] = b"FWS\x0a\x13\x00\x00\x00\x00\x00\x19\x01\x00\x40\x00\x40\x00\x00\x00";
+    assert_eq!(emit_swf_with_encoding(&movie, &encoding), Ok(expected.to_vec()));
+  }
+}
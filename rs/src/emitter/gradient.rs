@@ -0,0 +1,81 @@
+use crate::emitter::base::invalid_value;
+use crate::emitter::basic_data_types::{emit_opaque_s_rgba8, emit_straight_s_rgba8};
+use std::io;
+use swf_types as swf;
+
+pub fn emit_color_stop<W: io::Write + ?Sized>(writer: &mut W, value: &swf::ColorStop, with_alpha: bool) -> io::Result<()> {
+  writer.write_all(&[value.ratio])?;
+  if with_alpha {
+    emit_straight_s_rgba8(writer, value.color)
+  } else {
+    emit_opaque_s_rgba8(writer, value.color)
+  }
+}
+
+/// Returns the gradient flags byte: spread, color space and color count
+fn get_gradient_flags(
+  spread: swf::GradientSpread,
+  color_space: swf::ColorSpace,
+  color_count: usize,
+) -> io::Result<u8> {
+  const MAX_COLOR_COUNT: usize = (1 << 4) - 1;
+
+  let spread_code: u8 = match spread {
+    swf::GradientSpread::Pad => 0,
+    swf::GradientSpread::Reflect => 1,
+    swf::GradientSpread::Repeat => 2,
+  };
+  let color_space_code: u8 = match color_space {
+    swf::ColorSpace::SRgb => 0,
+    swf::ColorSpace::LinearRgb => 1,
+  };
+  if color_count > MAX_COLOR_COUNT {
+    return Err(invalid_value("too many gradient colors"));
+  }
+  Ok((spread_code << 6) | (color_space_code << 4) | (color_count as u8))
+}
+
+pub fn emit_gradient<W: io::Write + ?Sized>(writer: &mut W, value: &swf::Gradient, with_alpha: bool) -> io::Result<()> {
+  let flags = get_gradient_flags(value.spread, value.color_space, value.colors.len())?;
+  writer.write_all(&[flags])?;
+  for color_stop in &value.colors {
+    emit_color_stop(writer, color_stop, with_alpha)?;
+  }
+  Ok(())
+}
+
+pub fn emit_morph_color_stop<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::MorphColorStop,
+  with_alpha: bool,
+) -> io::Result<()> {
+  emit_color_stop(
+    writer,
+    &swf::ColorStop {
+      ratio: value.ratio,
+      color: value.color,
+    },
+    with_alpha,
+  )?;
+  emit_color_stop(
+    writer,
+    &swf::ColorStop {
+      ratio: value.morph_ratio,
+      color: value.morph_color,
+    },
+    with_alpha,
+  )
+}
+
+pub fn emit_morph_gradient<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::MorphGradient,
+  with_alpha: bool,
+) -> io::Result<()> {
+  let flags = get_gradient_flags(value.spread, value.color_space, value.colors.len())?;
+  writer.write_all(&[flags])?;
+  for color_stop in &value.colors {
+    emit_morph_color_stop(writer, color_stop, with_alpha)?;
+  }
+  Ok(())
+}
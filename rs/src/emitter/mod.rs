@@ -0,0 +1,17 @@
+pub(crate) mod base;
+pub(crate) mod basic_data_types;
+pub(crate) mod button;
+pub(crate) mod display;
+pub(crate) mod encoding;
+pub(crate) mod gradient;
+pub(crate) mod morph_shape;
+pub(crate) mod movie;
+pub(crate) mod shape;
+pub(crate) mod sound;
+pub(crate) mod tag;
+pub(crate) mod text;
+pub(crate) mod video;
+
+pub use encoding::emit_swf_with_encoding;
+pub use movie::{emit_header, emit_movie, emit_swf, emit_swf_signature, emit_swf_with_legacy_encoding, SwfEmitError};
+pub use tag::{emit_end_tag, emit_tag, emit_tag_header, emit_tag_with_legacy_encoding};
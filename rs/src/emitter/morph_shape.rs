@@ -0,0 +1,323 @@
+use crate::complete::morph_shape::MorphShapeVersion;
+use crate::complete::shape::StyleBits;
+use crate::emitter::base::{invalid_value, BitsWriter};
+use crate::emitter::basic_data_types::{emit_matrix, emit_straight_s_rgba8};
+use crate::emitter::gradient::emit_morph_gradient;
+use crate::emitter::shape::{
+  emit_edge_bits, emit_line_style2_flags, emit_list_length, emit_style_change_head_bits, get_bitmap_fill_code,
+  get_style_bits, LineStyle2Flags, StyleChangeIndices,
+};
+use std::convert::TryFrom;
+use std::io;
+use swf_types as swf;
+
+fn get_morph_shape_style_changes(
+  records: &[swf::MorphShapeRecord],
+) -> impl Iterator<Item = StyleChangeIndices> + '_ {
+  records.iter().filter_map(|record| match record {
+    swf::MorphShapeRecord::StyleChange(change) => Some(StyleChangeIndices {
+      left_fill: change.left_fill,
+      right_fill: change.right_fill,
+      line_style: change.line_style,
+      has_new_styles: change.new_styles.is_some(),
+    }),
+    swf::MorphShapeRecord::Edge(_) => None,
+  })
+}
+
+/// Returns the oldest morph shape version able to represent the styles of `shape`.
+pub(crate) fn get_morph_shape_version(shape: &swf::MorphShape) -> MorphShapeVersion {
+  let new_styles = shape.records.iter().filter_map(|record| match record {
+    swf::MorphShapeRecord::StyleChange(change) => change.new_styles.as_ref(),
+    swf::MorphShapeRecord::Edge(_) => None,
+  });
+  let needs_line_style2 = std::iter::once(&shape.initial_styles)
+    .chain(new_styles)
+    .any(|styles| !styles.line.iter().all(is_morph_line_style1));
+  if needs_line_style2 {
+    MorphShapeVersion::MorphShape2
+  } else {
+    MorphShapeVersion::MorphShape1
+  }
+}
+
+/// Checks if the line style only uses the features of `MorphLineStyle` (as opposed to `MorphLineStyle2`)
+fn is_morph_line_style1(value: &swf::MorphLineStyle) -> bool {
+  value.start_cap == swf::CapStyle::Round
+    && value.end_cap == swf::CapStyle::Round
+    && value.join == swf::JoinStyle::Round
+    && !value.no_h_scale
+    && !value.no_v_scale
+    && !value.no_close
+    && !value.pixel_hinting
+    && matches!(value.fill, swf::MorphFillStyle::Solid(_))
+}
+
+/// Emits a morph shape, starting with the offset to the end records
+pub fn emit_morph_shape<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::MorphShape,
+  version: MorphShapeVersion,
+) -> io::Result<()> {
+  let mut start_bytes: Vec<u8> = Vec::new();
+  let mut bits = BitsWriter::new(&mut start_bytes);
+  let style_bits = emit_morph_shape_styles_bits(&mut bits, &value.initial_styles, &value.records, version)?;
+  emit_morph_shape_start_record_string_bits(&mut bits, &value.records, style_bits, version)?;
+  bits.finish()?;
+
+  let end_offset = u32::try_from(start_bytes.len()).map_err(|_| invalid_value("morph shape too large"))?;
+  writer.write_all(&end_offset.to_le_bytes())?;
+  writer.write_all(&start_bytes)?;
+
+  // The end records never use style indices
+  writer.write_all(&[0])?;
+  let mut bits = BitsWriter::new(writer);
+  emit_morph_shape_end_record_string_bits(&mut bits, &value.records)?;
+  bits.finish()
+}
+
+pub fn emit_morph_shape_styles_bits<W: io::Write + ?Sized>(
+  bits: &mut BitsWriter<W>,
+  value: &swf::MorphShapeStyles,
+  records: &[swf::MorphShapeRecord],
+  version: MorphShapeVersion,
+) -> io::Result<StyleBits> {
+  let style_bits = get_style_bits(value.fill.len(), value.line.len(), get_morph_shape_style_changes(records))?;
+  let writer = bits.align()?;
+  emit_morph_fill_style_list(writer, &value.fill)?;
+  emit_morph_line_style_list(writer, &value.line, version)?;
+  bits.write_u32_bits(4, style_bits.fill as u32)?;
+  bits.write_u32_bits(4, style_bits.line as u32)?;
+  Ok(style_bits)
+}
+
+fn emit_morph_shape_start_record_string_bits<W: io::Write + ?Sized>(
+  bits: &mut BitsWriter<W>,
+  records: &[swf::MorphShapeRecord],
+  mut style_bits: StyleBits,
+  version: MorphShapeVersion,
+) -> io::Result<()> {
+  for (index, record) in records.iter().enumerate() {
+    match record {
+      swf::MorphShapeRecord::Edge(edge) => {
+        bits.write_bool(true)?;
+        emit_edge_bits(
+          bits,
+          &swf::shape_records::Edge {
+            delta: edge.delta,
+            control_delta: edge.control_delta,
+          },
+        )?;
+      }
+      swf::MorphShapeRecord::StyleChange(style_change) => {
+        let indices = StyleChangeIndices {
+          left_fill: style_change.left_fill,
+          right_fill: style_change.right_fill,
+          line_style: style_change.line_style,
+          has_new_styles: style_change.new_styles.is_some(),
+        };
+        bits.write_bool(false)?;
+        emit_style_change_head_bits(bits, &indices, style_change.move_to, style_bits)?;
+        if let Some(new_styles) = &style_change.new_styles {
+          style_bits = emit_morph_shape_styles_bits(bits, new_styles, &records[index + 1..], version)?;
+        }
+      }
+    }
+  }
+  bits.write_u32_bits(6, 0)
+}
+
+/// Emits the end records: the end edges and the `morph_move_to` of the style changes moving the pen
+fn emit_morph_shape_end_record_string_bits<W: io::Write + ?Sized>(
+  bits: &mut BitsWriter<W>,
+  records: &[swf::MorphShapeRecord],
+) -> io::Result<()> {
+  let no_style_bits = StyleBits { fill: 0, line: 0 };
+  let move_only = StyleChangeIndices {
+    left_fill: None,
+    right_fill: None,
+    line_style: None,
+    has_new_styles: false,
+  };
+  for record in records {
+    match record {
+      swf::MorphShapeRecord::Edge(edge) => {
+        bits.write_bool(true)?;
+        emit_edge_bits(
+          bits,
+          &swf::shape_records::Edge {
+            delta: edge.morph_delta,
+            control_delta: edge.morph_control_delta,
+          },
+        )?;
+      }
+      swf::MorphShapeRecord::StyleChange(style_change) => match (style_change.move_to, style_change.morph_move_to) {
+        (None, None) => {}
+        (Some(_), Some(morph_move_to)) => {
+          bits.write_bool(false)?;
+          emit_style_change_head_bits(bits, &move_only, Some(morph_move_to), no_style_bits)?;
+        }
+        _ => return Err(invalid_value("morph style change must move in both states or neither")),
+      },
+    }
+  }
+  bits.write_u32_bits(6, 0)
+}
+
+pub fn emit_morph_fill_style_list<W: io::Write + ?Sized>(writer: &mut W, value: &[swf::MorphFillStyle]) -> io::Result<()> {
+  emit_list_length(writer, value.len(), true)?;
+  for fill_style in value {
+    emit_morph_fill_style(writer, fill_style)?;
+  }
+  Ok(())
+}
+
+pub fn emit_morph_fill_style<W: io::Write + ?Sized>(writer: &mut W, value: &swf::MorphFillStyle) -> io::Result<()> {
+  match value {
+    swf::MorphFillStyle::Bitmap(fill) => {
+      writer.write_all(&[get_bitmap_fill_code(fill.repeating, fill.smoothed)])?;
+      writer.write_all(&fill.bitmap_id.to_le_bytes())?;
+      emit_matrix(writer, &fill.matrix)?;
+      emit_matrix(writer, &fill.morph_matrix)
+    }
+    swf::MorphFillStyle::FocalGradient(fill) => {
+      writer.write_all(&[0x13])?;
+      emit_matrix(writer, &fill.matrix)?;
+      emit_matrix(writer, &fill.morph_matrix)?;
+      emit_morph_gradient(writer, &fill.gradient, true)?;
+      writer.write_all(&fill.focal_point.epsilons.to_le_bytes())?;
+      writer.write_all(&fill.morph_focal_point.epsilons.to_le_bytes())
+    }
+    swf::MorphFillStyle::LinearGradient(fill) => {
+      writer.write_all(&[0x10])?;
+      emit_matrix(writer, &fill.matrix)?;
+      emit_matrix(writer, &fill.morph_matrix)?;
+      emit_morph_gradient(writer, &fill.gradient, true)
+    }
+    swf::MorphFillStyle::RadialGradient(fill) => {
+      writer.write_all(&[0x12])?;
+      emit_matrix(writer, &fill.matrix)?;
+      emit_matrix(writer, &fill.morph_matrix)?;
+      emit_morph_gradient(writer, &fill.gradient, true)
+    }
+    swf::MorphFillStyle::Solid(fill) => {
+      writer.write_all(&[0x00])?;
+      emit_morph_solid_colors(writer, fill)
+    }
+  }
+}
+
+fn emit_morph_solid_colors<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::fill_styles::MorphSolid,
+) -> io::Result<()> {
+  emit_straight_s_rgba8(writer, value.color)?;
+  emit_straight_s_rgba8(writer, value.morph_color)
+}
+
+pub fn emit_morph_line_style_list<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &[swf::MorphLineStyle],
+  version: MorphShapeVersion,
+) -> io::Result<()> {
+  emit_list_length(writer, value.len(), true)?;
+  for line_style in value {
+    if version >= MorphShapeVersion::MorphShape2 {
+      emit_morph_line_style2(writer, line_style)?;
+    } else {
+      emit_morph_line_style1(writer, line_style)?;
+    }
+  }
+  Ok(())
+}
+
+pub fn emit_morph_line_style1<W: io::Write + ?Sized>(writer: &mut W, value: &swf::MorphLineStyle) -> io::Result<()> {
+  let fill = match &value.fill {
+    swf::MorphFillStyle::Solid(fill) if is_morph_line_style1(value) => fill,
+    _ => return Err(invalid_value("morph line style requires `DefineMorphShape2`")),
+  };
+  writer.write_all(&value.width.to_le_bytes())?;
+  writer.write_all(&value.morph_width.to_le_bytes())?;
+  emit_morph_solid_colors(writer, fill)
+}
+
+pub fn emit_morph_line_style2<W: io::Write + ?Sized>(writer: &mut W, value: &swf::MorphLineStyle) -> io::Result<()> {
+  let has_fill = !matches!(value.fill, swf::MorphFillStyle::Solid(_));
+  writer.write_all(&value.width.to_le_bytes())?;
+  writer.write_all(&value.morph_width.to_le_bytes())?;
+  emit_line_style2_flags(
+    writer,
+    &LineStyle2Flags {
+      start_cap: value.start_cap,
+      end_cap: value.end_cap,
+      join: value.join,
+      no_h_scale: value.no_h_scale,
+      no_v_scale: value.no_v_scale,
+      no_close: value.no_close,
+      pixel_hinting: value.pixel_hinting,
+      has_fill,
+    },
+  )?;
+  match &value.fill {
+    swf::MorphFillStyle::Solid(fill) => emit_morph_solid_colors(writer, fill),
+    fill => emit_morph_fill_style(writer, fill),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::complete::morph_shape::parse_morph_shape;
+
+  fn vector(x: i32, y: i32) -> swf::Vector2D {
+    swf::Vector2D { x, y }
+  }
+
+  fn morph_edge(delta: (i32, i32), morph_delta: (i32, i32), curved: bool) -> swf::MorphShapeRecord {
+    let half = |(x, y): (i32, i32)| vector(x / 2, y / 2);
+    swf::MorphShapeRecord::Edge(swf::shape_records::MorphEdge {
+      delta: vector(delta.0, delta.1),
+      morph_delta: vector(morph_delta.0, morph_delta.1),
+      control_delta: if curved { Some(half(delta)) } else { None },
+      morph_control_delta: if curved { Some(half(morph_delta)) } else { None },
+    })
+  }
+
+  #[test]
+  fn test_emit_morph_shape() {
+    let solid = swf::MorphFillStyle::Solid(swf::fill_styles::MorphSolid {
+      color: swf::StraightSRgba8 { r: 255, g: 0, b: 0, a: 255 },
+      morph_color: swf::StraightSRgba8 { r: 0, g: 0, b: 255, a: 0 },
+    });
+    let shape = swf::MorphShape {
+      initial_styles: swf::MorphShapeStyles {
+        fill: vec![solid.clone()],
+        line: Vec::new(),
+      },
+      records: vec![
+        swf::MorphShapeRecord::StyleChange(swf::shape_records::MorphStyleChange {
+          move_to: Some(vector(0, 0)),
+          morph_move_to: Some(vector(-10, 10)),
+          left_fill: Some(1),
+          right_fill: None,
+          line_style: None,
+          new_styles: None,
+        }),
+        // A straight edge in one shape can morph into a curved edge in the other
+        morph_edge((100, 0), (0, -200), false),
+        swf::MorphShapeRecord::Edge(swf::shape_records::MorphEdge {
+          delta: vector(0, 100),
+          morph_delta: vector(200, 0),
+          control_delta: None,
+          morph_control_delta: Some(vector(100, 100)),
+        }),
+        morph_edge((-100, -100), (-200, 200), true),
+      ],
+    };
+    for version in [MorphShapeVersion::MorphShape1, MorphShapeVersion::MorphShape2] {
+      let mut out: Vec<u8> = Vec::new();
+      emit_morph_shape(&mut out, &shape, version).unwrap();
+      assert_eq!(parse_morph_shape(&out, version), Ok((&[][..], shape.clone())));
+    }
+  }
+}
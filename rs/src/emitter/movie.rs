@@ -0,0 +1,196 @@
+use crate::emitter::base::invalid_value;
+use crate::emitter::basic_data_types::emit_rect;
+use crate::emitter::tag::{emit_end_tag, emit_tag_with_legacy_encoding};
+use crate::legacy::{default_legacy_encoding, LegacyEncoding};
+use ast::CompressionMethod;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use swf_types as ast;
+
+/// Represents the possible errors when emitting an SWF file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SwfEmitError {
+  /// Indicates that the requested compression method isn't supported.
+  ///
+  /// This can only happen when the corresponding Cargo feature is disabled.
+  UnsupportedCompression(CompressionMethod),
+
+  /// Indicates that the movie contains a value that can't be represented in the SWF format.
+  ///
+  /// For example a `DefineShape` tag with a style index exceeding its style lists, or a movie larger than 4 GiB.
+  InvalidValue(String),
+}
+
+impl std::error::Error for SwfEmitError {}
+
+impl fmt::Display for SwfEmitError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SwfEmitError::UnsupportedCompression(comp) => {
+        f.write_str("unsupported SWF compression: ")?;
+        fmt::Debug::fmt(comp, f)
+      }
+      SwfEmitError::InvalidValue(msg) => {
+        f.write_str("invalid SWF value: ")?;
+        f.write_str(msg)
+      }
+    }
+  }
+}
+
+impl From<io::Error> for SwfEmitError {
+  fn from(e: io::Error) -> Self {
+    SwfEmitError::InvalidValue(e.to_string())
+  }
+}
+
+/// Emits a complete SWF file, compressing its payload with `compression_method`.
+///
+/// The SWF version is read from the movie header. Each tag is emitted with the oldest tag code able to
+/// represent it and with minimal bit widths: emitting a parsed movie produces an equivalent movie, but not
+/// necessarily the same bytes.
+pub fn emit_swf(value: &ast::Movie, compression_method: CompressionMethod) -> Result<Vec<u8>, SwfEmitError> {
+  emit_swf_with_legacy_encoding(value, compression_method, None)
+}
+
+/// Emits a complete SWF file, encoding the strings of SWF 5 (and earlier) movies with `encoding`.
+///
+/// If `encoding` is `None`, it is guessed from the language of the fonts, as when parsing (see
+/// [[crate::legacy::default_legacy_encoding]]). Strings with characters missing from the encoding are rejected.
+pub fn emit_swf_with_legacy_encoding(
+  value: &ast::Movie,
+  compression_method: CompressionMethod,
+  encoding: Option<LegacyEncoding>,
+) -> Result<Vec<u8>, SwfEmitError> {
+  let encoding = encoding.unwrap_or_else(|| default_legacy_encoding(&value.tags));
+  let mut payload: Vec<u8> = Vec::new();
+  emit_movie_with_legacy_encoding(&mut payload, value, encoding)?;
+  emit_swf_payload(&payload, compression_method, value.header.swf_version)
+}
+
+/// Emits a complete SWF file from its uncompressed payload.
+pub(crate) fn emit_swf_payload(
+  payload: &[u8],
+  compression_method: CompressionMethod,
+  swf_version: u8,
+) -> Result<Vec<u8>, SwfEmitError> {
+  let uncompressed_file_length = payload.len() + 8;
+  if u32::try_from(uncompressed_file_length).is_err() {
+    return Err(SwfEmitError::InvalidValue(String::from("movie too large")));
+  }
+  let signature = ast::SwfSignature {
+    compression_method,
+    swf_version,
+    uncompressed_file_length,
+  };
+
+  let mut swf: Vec<u8> = Vec::new();
+  emit_swf_signature(&mut swf, &signature)?;
+  match compression_method {
+    CompressionMethod::None => swf.extend_from_slice(payload),
+    #[cfg(feature = "deflate")]
+    CompressionMethod::Deflate => swf.extend_from_slice(&deflate::deflate_bytes_zlib(payload)),
+    #[cfg(feature = "lzma")]
+    CompressionMethod::Lzma => emit_lzma_payload(&mut swf, payload)?,
+    #[allow(unreachable_patterns)]
+    method => return Err(SwfEmitError::UnsupportedCompression(method)),
+  }
+  Ok(swf)
+}
+
+/// Emits the LZMA payload of an SWF file: compressed length, LZMA properties and compressed data.
+#[cfg(feature = "lzma")]
+fn emit_lzma_payload<W: io::Write + ?Sized>(writer: &mut W, payload: &[u8]) -> io::Result<()> {
+  use lzma_rs::compress::{Options, UnpackedSize};
+
+  // The uncompressed length is only stored in the SWF signature
+  let options = Options {
+    unpacked_size: UnpackedSize::SkipWritingToHeader,
+  };
+  let mut compressed: Vec<u8> = Vec::new();
+  lzma_rs::lzma_compress_with_options(&mut &payload[..], &mut compressed, &options)?;
+  const PROPERTIES_SIZE: usize = 5;
+  let compressed_len = u32::try_from(compressed.len() - PROPERTIES_SIZE).map_err(|_| invalid_value("movie too large"))?;
+  writer.write_all(&compressed_len.to_le_bytes())?;
+  writer.write_all(&compressed)
+}
+
+pub fn emit_swf_signature<W: io::Write + ?Sized>(writer: &mut W, value: &ast::SwfSignature) -> io::Result<()> {
+  let compression_method: &[u8; 3] = match value.compression_method {
+    CompressionMethod::None => b"FWS",
+    CompressionMethod::Deflate => b"CWS",
+    CompressionMethod::Lzma => b"ZWS",
+  };
+  writer.write_all(compression_method)?;
+  writer.write_all(&[value.swf_version])?;
+  let uncompressed_file_length =
+    u32::try_from(value.uncompressed_file_length).map_err(|_| invalid_value("movie too large"))?;
+  writer.write_all(&uncompressed_file_length.to_le_bytes())
+}
+
+pub fn emit_header<W: io::Write + ?Sized>(writer: &mut W, value: &ast::Header) -> io::Result<()> {
+  emit_rect(writer, &value.frame_size)?;
+  writer.write_all(&value.frame_rate.epsilons.to_le_bytes())?;
+  writer.write_all(&value.frame_count.to_le_bytes())
+}
+
+/// Emits the uncompressed payload of an SWF file: the movie header followed by the tags and the `End` tag.
+///
+/// The strings of SWF 5 (and earlier) movies use the encoding guessed from the fonts, see [[emit_swf]].
+pub fn emit_movie<W: io::Write + ?Sized>(writer: &mut W, value: &ast::Movie) -> io::Result<()> {
+  emit_movie_with_legacy_encoding(writer, value, default_legacy_encoding(&value.tags))
+}
+
+fn emit_movie_with_legacy_encoding<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::Movie,
+  encoding: LegacyEncoding,
+) -> io::Result<()> {
+  emit_header(writer, &value.header)?;
+  for tag in &value.tags {
+    emit_tag_with_legacy_encoding(writer, tag, value.header.swf_version, encoding)?;
+  }
+  emit_end_tag(writer)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::emit_swf;
+  use crate::complete::{decompress_swf, parse_swf};
+  use std::path::Path;
+  use swf_types::CompressionMethod;
+  use test_generator::test_resources;
+
+  #[test_resources("../tests/movies/*/")]
+  fn test_emit_swf(path: &str) {
+    let path: &Path = Path::new(path);
+    let movie_bytes: Vec<u8> = ::std::fs::read(path.join("main.swf")).expect("Failed to read movie");
+    let movie = parse_swf(&movie_bytes).expect("Failed to parse movie");
+
+    for compression_method in [CompressionMethod::None, CompressionMethod::Deflate, CompressionMethod::Lzma] {
+      let bytes = emit_swf(&movie, compression_method).expect("Failed to emit movie");
+      let (signature, payload) = decompress_swf(&bytes).expect("Failed to decompress emitted movie");
+      assert_eq!(signature.compression_method, compression_method);
+      assert_eq!(signature.uncompressed_file_length, payload.len() + 8);
+      assert_eq!(parse_swf(&bytes), Ok(movie.clone()));
+    }
+  }
+
+  #[test]
+  fn test_emit_swf_blank() {
+    let movie_bytes: &[u8] = include_bytes!("../../../tests/movies/blank/main.swf");
+    let movie = parse_swf(movie_bytes).unwrap();
+    // The original movie uses 16-bit frame size coordinates and a long `Metadata` header
+    let expected: &[u8] = &[
+      0x46, 0x57, 0x53, 0x0a, 0x31, 0x00, 0x00, 0x00, // Signature
+      0x78, 0x00, 0x05, 0x5f, 0x00, 0x00, 0x0f, 0xa0, 0x00, 0x00, 0x18, 0x01, 0x00, // Header
+      0x44, 0x11, 0x08, 0x00, 0x00, 0x00, // FileAttributes
+      0x43, 0x02, 0xff, 0xff, 0xff, // SetBackgroundColor
+      0x0b, 0x13, 0x3c, 0x72, 0x64, 0x66, 0x3a, 0x52, 0x44, 0x46, 0x2f, 0x3e, 0x00, // Metadata
+      0x40, 0x00, // ShowFrame
+      0x00, 0x00, // End
+    ];
+    assert_eq!(emit_swf(&movie, CompressionMethod::None).unwrap(), expected);
+  }
+}
@@ -0,0 +1,519 @@
+use crate::complete::shape::{ShapeVersion, StyleBits};
+use crate::emitter::base::{get_i32_max_bit_count, get_u32_bit_count, invalid_value, to_u16, BitsWriter};
+use crate::emitter::basic_data_types::{emit_matrix, emit_opaque_s_rgba8, emit_straight_s_rgba8};
+use crate::emitter::gradient::emit_gradient;
+use std::convert::TryFrom;
+use std::io;
+use swf_types as swf;
+
+/// Style indices used by a style change record, see `get_style_bits`.
+pub(crate) struct StyleChangeIndices {
+  pub left_fill: Option<usize>,
+  pub right_fill: Option<usize>,
+  pub line_style: Option<usize>,
+  pub has_new_styles: bool,
+}
+
+/// Returns the number of bits to encode the style indices, until the next style list.
+///
+/// `changes` are the style change records following the style lists, the indices of the record
+/// defining new styles are still encoded with the current bits.
+pub(crate) fn get_style_bits(
+  fill_count: usize,
+  line_count: usize,
+  changes: impl Iterator<Item = StyleChangeIndices>,
+) -> io::Result<StyleBits> {
+  let mut max_fill = fill_count;
+  let mut max_line = line_count;
+  for change in changes {
+    max_fill = max_fill.max(change.left_fill.unwrap_or(0)).max(change.right_fill.unwrap_or(0));
+    max_line = max_line.max(change.line_style.unwrap_or(0));
+    if change.has_new_styles {
+      break;
+    }
+  }
+  let to_bits = |max: usize| -> io::Result<usize> {
+    let bits = u32::try_from(max).map(get_u32_bit_count).unwrap_or(u32::MAX);
+    if bits < (1 << 4) {
+      Ok(bits as usize)
+    } else {
+      Err(invalid_value("style index too large"))
+    }
+  };
+  Ok(StyleBits {
+    fill: to_bits(max_fill)?,
+    line: to_bits(max_line)?,
+  })
+}
+
+fn get_shape_style_changes(records: &[swf::ShapeRecord]) -> impl Iterator<Item = StyleChangeIndices> + '_ {
+  records.iter().filter_map(|record| match record {
+    swf::ShapeRecord::StyleChange(change) => Some(StyleChangeIndices {
+      left_fill: change.left_fill,
+      right_fill: change.right_fill,
+      line_style: change.line_style,
+      has_new_styles: change.new_styles.is_some(),
+    }),
+    swf::ShapeRecord::Edge(_) => None,
+  })
+}
+
+/// Returns the oldest shape version able to represent `shape`.
+///
+/// It only depends on the styles, the `DefineShape4` fields are checked by the tag emitter.
+pub(crate) fn get_shape_version(shape: &swf::Shape) -> ShapeVersion {
+  let new_styles = shape.records.iter().filter_map(|record| match record {
+    swf::ShapeRecord::StyleChange(change) => change.new_styles.as_ref(),
+    swf::ShapeRecord::Edge(_) => None,
+  });
+  let has_new_styles = new_styles.clone().next().is_some();
+  let mut version = if has_new_styles {
+    ShapeVersion::Shape2
+  } else {
+    ShapeVersion::Shape1
+  };
+  for styles in std::iter::once(&shape.initial_styles).chain(new_styles) {
+    if styles.fill.len() >= 0xff || styles.line.len() >= 0xff {
+      version = version.max(ShapeVersion::Shape2);
+    }
+    let has_alpha = styles.fill.iter().any(has_fill_style_alpha)
+      || styles.line.iter().any(|line_style| has_fill_style_alpha(&line_style.fill));
+    if has_alpha {
+      version = version.max(ShapeVersion::Shape3);
+    }
+    if !styles.line.iter().all(is_line_style1) {
+      version = version.max(ShapeVersion::Shape4);
+    }
+  }
+  version
+}
+
+fn has_fill_style_alpha(value: &swf::FillStyle) -> bool {
+  let has_gradient_alpha = |gradient: &swf::Gradient| gradient.colors.iter().any(|stop| stop.color.a != u8::MAX);
+  match value {
+    swf::FillStyle::Bitmap(_) => false,
+    swf::FillStyle::FocalGradient(fill) => has_gradient_alpha(&fill.gradient),
+    swf::FillStyle::LinearGradient(fill) => has_gradient_alpha(&fill.gradient),
+    swf::FillStyle::RadialGradient(fill) => has_gradient_alpha(&fill.gradient),
+    swf::FillStyle::Solid(fill) => fill.color.a != u8::MAX,
+  }
+}
+
+/// Checks if the line style only uses the features of `LineStyle` (as opposed to `LineStyle2`)
+fn is_line_style1(value: &swf::LineStyle) -> bool {
+  value.start_cap == swf::CapStyle::Round
+    && value.end_cap == swf::CapStyle::Round
+    && value.join == swf::JoinStyle::Round
+    && !value.no_h_scale
+    && !value.no_v_scale
+    && !value.no_close
+    && !value.pixel_hinting
+    && matches!(value.fill, swf::FillStyle::Solid(_))
+}
+
+pub fn emit_glyph<W: io::Write + ?Sized>(writer: &mut W, value: &swf::Glyph) -> io::Result<()> {
+  let style_bits = get_style_bits(0, 0, get_shape_style_changes(&value.records))?;
+  let mut bits = BitsWriter::new(writer);
+  bits.write_u32_bits(4, style_bits.fill as u32)?;
+  bits.write_u32_bits(4, style_bits.line as u32)?;
+  emit_shape_record_string_bits(&mut bits, &value.records, style_bits, ShapeVersion::Shape1)?;
+  bits.finish()
+}
+
+pub fn emit_shape<W: io::Write + ?Sized>(writer: &mut W, value: &swf::Shape, version: ShapeVersion) -> io::Result<()> {
+  let mut bits = BitsWriter::new(writer);
+  let style_bits = emit_shape_styles_bits(&mut bits, &value.initial_styles, &value.records, version)?;
+  emit_shape_record_string_bits(&mut bits, &value.records, style_bits, version)?;
+  bits.finish()
+}
+
+/// Emits the style lists followed by the bit counts of the style indices used by `records`.
+pub fn emit_shape_styles_bits<W: io::Write + ?Sized>(
+  bits: &mut BitsWriter<W>,
+  value: &swf::ShapeStyles,
+  records: &[swf::ShapeRecord],
+  version: ShapeVersion,
+) -> io::Result<StyleBits> {
+  let style_bits = get_style_bits(value.fill.len(), value.line.len(), get_shape_style_changes(records))?;
+  let writer = bits.align()?;
+  emit_fill_style_list(writer, &value.fill, version)?;
+  emit_line_style_list(writer, &value.line, version)?;
+  bits.write_u32_bits(4, style_bits.fill as u32)?;
+  bits.write_u32_bits(4, style_bits.line as u32)?;
+  Ok(style_bits)
+}
+
+pub fn emit_shape_record_string_bits<W: io::Write + ?Sized>(
+  bits: &mut BitsWriter<W>,
+  records: &[swf::ShapeRecord],
+  mut style_bits: StyleBits,
+  version: ShapeVersion,
+) -> io::Result<()> {
+  for (index, record) in records.iter().enumerate() {
+    match record {
+      swf::ShapeRecord::Edge(edge) => {
+        bits.write_bool(true)?;
+        emit_edge_bits(bits, edge)?;
+      }
+      swf::ShapeRecord::StyleChange(style_change) => {
+        bits.write_bool(false)?;
+        style_bits = emit_style_change_bits(bits, style_change, style_bits, &records[index + 1..], version)?;
+      }
+    }
+  }
+  // End of shape: non-edge record without flags
+  bits.write_u32_bits(6, 0)
+}
+
+/// Returns the bit count of the coordinates of an edge (between 2 and 17)
+fn get_edge_bit_count(values: &[i32]) -> io::Result<u32> {
+  let n_bits = get_i32_max_bit_count(values).max(2);
+  if n_bits - 2 < (1 << 4) {
+    Ok(n_bits)
+  } else {
+    Err(invalid_value("edge too large"))
+  }
+}
+
+/// Emits an edge (without the leading `is_edge` flag)
+pub fn emit_edge_bits<W: io::Write + ?Sized>(
+  bits: &mut BitsWriter<W>,
+  value: &swf::shape_records::Edge,
+) -> io::Result<()> {
+  match value.control_delta {
+    Some(control) => {
+      let anchor_x = value.delta.x.wrapping_sub(control.x);
+      let anchor_y = value.delta.y.wrapping_sub(control.y);
+      let n_bits = get_edge_bit_count(&[control.x, control.y, anchor_x, anchor_y])?;
+      bits.write_bool(false)?;
+      bits.write_u32_bits(4, n_bits - 2)?;
+      bits.write_i32_bits(n_bits, control.x)?;
+      bits.write_i32_bits(n_bits, control.y)?;
+      bits.write_i32_bits(n_bits, anchor_x)?;
+      bits.write_i32_bits(n_bits, anchor_y)
+    }
+    None => {
+      let swf::Vector2D { x, y } = value.delta;
+      let n_bits = get_edge_bit_count(&[x, y])?;
+      bits.write_bool(true)?;
+      bits.write_u32_bits(4, n_bits - 2)?;
+      let is_diagonal = x != 0 && y != 0;
+      bits.write_bool(is_diagonal)?;
+      if is_diagonal {
+        bits.write_i32_bits(n_bits, x)?;
+        bits.write_i32_bits(n_bits, y)
+      } else {
+        let is_vertical = x == 0 && y != 0;
+        bits.write_bool(is_vertical)?;
+        bits.write_i32_bits(n_bits, if is_vertical { y } else { x })
+      }
+    }
+  }
+}
+
+/// Emits the style change flags, `move_to` and style indices (without the leading `is_edge` flag)
+pub(crate) fn emit_style_change_head_bits<W: io::Write + ?Sized>(
+  bits: &mut BitsWriter<W>,
+  indices: &StyleChangeIndices,
+  move_to: Option<swf::Vector2D>,
+  style_bits: StyleBits,
+) -> io::Result<()> {
+  if !indices.has_new_styles
+    && indices.line_style.is_none()
+    && indices.right_fill.is_none()
+    && indices.left_fill.is_none()
+    && move_to.is_none()
+  {
+    return Err(invalid_value("style change record without changes"));
+  }
+  bits.write_bool(indices.has_new_styles)?;
+  bits.write_bool(indices.line_style.is_some())?;
+  bits.write_bool(indices.right_fill.is_some())?;
+  bits.write_bool(indices.left_fill.is_some())?;
+  bits.write_bool(move_to.is_some())?;
+  if let Some(move_to) = move_to {
+    let move_to_bits = get_i32_max_bit_count(&[move_to.x, move_to.y]);
+    if move_to_bits >= (1 << 5) {
+      return Err(invalid_value("move_to too large"));
+    }
+    bits.write_u32_bits(5, move_to_bits)?;
+    bits.write_i32_bits(move_to_bits, move_to.x)?;
+    bits.write_i32_bits(move_to_bits, move_to.y)?;
+  }
+  // `get_style_bits` guarantees that the indices fit
+  if let Some(left_fill) = indices.left_fill {
+    bits.write_u32_bits(style_bits.fill as u32, left_fill as u32)?;
+  }
+  if let Some(right_fill) = indices.right_fill {
+    bits.write_u32_bits(style_bits.fill as u32, right_fill as u32)?;
+  }
+  if let Some(line_style) = indices.line_style {
+    bits.write_u32_bits(style_bits.line as u32, line_style as u32)?;
+  }
+  Ok(())
+}
+
+fn emit_style_change_bits<W: io::Write + ?Sized>(
+  bits: &mut BitsWriter<W>,
+  value: &swf::shape_records::StyleChange,
+  style_bits: StyleBits,
+  next_records: &[swf::ShapeRecord],
+  version: ShapeVersion,
+) -> io::Result<StyleBits> {
+  let indices = StyleChangeIndices {
+    left_fill: value.left_fill,
+    right_fill: value.right_fill,
+    line_style: value.line_style,
+    has_new_styles: value.new_styles.is_some(),
+  };
+  emit_style_change_head_bits(bits, &indices, value.move_to, style_bits)?;
+  match &value.new_styles {
+    Some(new_styles) => emit_shape_styles_bits(bits, new_styles, next_records, version),
+    None => Ok(style_bits),
+  }
+}
+
+/// Emits the length of a style list, using the extended form when `allow_extended` is set
+pub(crate) fn emit_list_length<W: io::Write + ?Sized>(
+  writer: &mut W,
+  length: usize,
+  allow_extended: bool,
+) -> io::Result<()> {
+  if length < 0xff {
+    writer.write_all(&[length as u8])
+  } else if allow_extended {
+    writer.write_all(&[0xff])?;
+    writer.write_all(&to_u16(length, "style list too long")?.to_le_bytes())
+  } else {
+    Err(invalid_value("style list too long"))
+  }
+}
+
+pub fn emit_fill_style_list<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &[swf::FillStyle],
+  version: ShapeVersion,
+) -> io::Result<()> {
+  emit_list_length(writer, value.len(), version >= ShapeVersion::Shape2)?;
+  for fill_style in value {
+    emit_fill_style(writer, fill_style, version >= ShapeVersion::Shape3)?;
+  }
+  Ok(())
+}
+
+/// Returns the code of a bitmap fill style from its flags
+pub(crate) fn get_bitmap_fill_code(repeating: bool, smoothed: bool) -> u8 {
+  0x40 | (if repeating { 0 } else { 1 }) | (if smoothed { 0 } else { 2 })
+}
+
+pub fn emit_fill_style<W: io::Write + ?Sized>(writer: &mut W, value: &swf::FillStyle, with_alpha: bool) -> io::Result<()> {
+  match value {
+    swf::FillStyle::Bitmap(fill) => {
+      writer.write_all(&[get_bitmap_fill_code(fill.repeating, fill.smoothed)])?;
+      writer.write_all(&fill.bitmap_id.to_le_bytes())?;
+      emit_matrix(writer, &fill.matrix)
+    }
+    swf::FillStyle::FocalGradient(fill) => {
+      writer.write_all(&[0x13])?;
+      emit_matrix(writer, &fill.matrix)?;
+      emit_gradient(writer, &fill.gradient, with_alpha)?;
+      writer.write_all(&fill.focal_point.epsilons.to_le_bytes())
+    }
+    swf::FillStyle::LinearGradient(fill) => {
+      writer.write_all(&[0x10])?;
+      emit_matrix(writer, &fill.matrix)?;
+      emit_gradient(writer, &fill.gradient, with_alpha)
+    }
+    swf::FillStyle::RadialGradient(fill) => {
+      writer.write_all(&[0x12])?;
+      emit_matrix(writer, &fill.matrix)?;
+      emit_gradient(writer, &fill.gradient, with_alpha)
+    }
+    swf::FillStyle::Solid(fill) => {
+      writer.write_all(&[0x00])?;
+      if with_alpha {
+        emit_straight_s_rgba8(writer, fill.color)
+      } else {
+        emit_opaque_s_rgba8(writer, fill.color)
+      }
+    }
+  }
+}
+
+pub fn emit_line_style_list<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &[swf::LineStyle],
+  version: ShapeVersion,
+) -> io::Result<()> {
+  emit_list_length(writer, value.len(), version >= ShapeVersion::Shape2)?;
+  for line_style in value {
+    if version >= ShapeVersion::Shape4 {
+      emit_line_style2(writer, line_style)?;
+    } else {
+      emit_line_style1(writer, line_style, version >= ShapeVersion::Shape3)?;
+    }
+  }
+  Ok(())
+}
+
+pub fn emit_line_style1<W: io::Write + ?Sized>(writer: &mut W, value: &swf::LineStyle, with_alpha: bool) -> io::Result<()> {
+  let color = match &value.fill {
+    swf::FillStyle::Solid(fill) if is_line_style1(value) => fill.color,
+    _ => return Err(invalid_value("line style requires `DefineShape4`")),
+  };
+  writer.write_all(&value.width.to_le_bytes())?;
+  if with_alpha {
+    emit_straight_s_rgba8(writer, color)
+  } else {
+    emit_opaque_s_rgba8(writer, color)
+  }
+}
+
+pub(crate) fn get_cap_style_code(value: swf::CapStyle) -> u16 {
+  match value {
+    swf::CapStyle::Round => 0,
+    swf::CapStyle::None => 1,
+    swf::CapStyle::Square => 2,
+  }
+}
+
+/// Fields shared by `LineStyle2` and `MorphLineStyle2`, stored after the width
+pub(crate) struct LineStyle2Flags {
+  pub start_cap: swf::CapStyle,
+  pub end_cap: swf::CapStyle,
+  pub join: swf::JoinStyle,
+  pub no_h_scale: bool,
+  pub no_v_scale: bool,
+  pub no_close: bool,
+  pub pixel_hinting: bool,
+  pub has_fill: bool,
+}
+
+/// Emits the `LineStyle2` flags and the optional miter limit
+pub(crate) fn emit_line_style2_flags<W: io::Write + ?Sized>(writer: &mut W, value: &LineStyle2Flags) -> io::Result<()> {
+  let (join_code, miter_limit): (u16, Option<u16>) = match value.join {
+    swf::JoinStyle::Round => (0, None),
+    swf::JoinStyle::Bevel => (1, None),
+    swf::JoinStyle::Miter(miter) => (2, Some(miter.limit)),
+  };
+  #[allow(clippy::identity_op)]
+  let flags: u16 = 0
+    | (if value.pixel_hinting { 1 << 0 } else { 0 })
+    | (if value.no_v_scale { 1 << 1 } else { 0 })
+    | (if value.no_h_scale { 1 << 2 } else { 0 })
+    | (if value.has_fill { 1 << 3 } else { 0 })
+    | (join_code << 4)
+    | (get_cap_style_code(value.start_cap) << 6)
+    | (get_cap_style_code(value.end_cap) << 8)
+    | (if value.no_close { 1 << 10 } else { 0 });
+  writer.write_all(&flags.to_le_bytes())?;
+  match miter_limit {
+    Some(miter_limit) => writer.write_all(&miter_limit.to_le_bytes()),
+    None => Ok(()),
+  }
+}
+
+pub fn emit_line_style2<W: io::Write + ?Sized>(writer: &mut W, value: &swf::LineStyle) -> io::Result<()> {
+  let has_fill = !matches!(value.fill, swf::FillStyle::Solid(_));
+  writer.write_all(&value.width.to_le_bytes())?;
+  emit_line_style2_flags(
+    writer,
+    &LineStyle2Flags {
+      start_cap: value.start_cap,
+      end_cap: value.end_cap,
+      join: value.join,
+      no_h_scale: value.no_h_scale,
+      no_v_scale: value.no_v_scale,
+      no_close: value.no_close,
+      pixel_hinting: value.pixel_hinting,
+      has_fill,
+    },
+  )?;
+  match &value.fill {
+    swf::FillStyle::Solid(fill) => emit_straight_s_rgba8(writer, fill.color),
+    fill => emit_fill_style(writer, fill, true),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::complete::shape::parse_shape;
+
+  fn edge(x: i32, y: i32, control: Option<(i32, i32)>) -> swf::ShapeRecord {
+    swf::ShapeRecord::Edge(swf::shape_records::Edge {
+      delta: swf::Vector2D { x, y },
+      control_delta: control.map(|(x, y)| swf::Vector2D { x, y }),
+    })
+  }
+
+  fn emit_edge(record: &swf::ShapeRecord) -> Vec<u8> {
+    let edge = match record {
+      swf::ShapeRecord::Edge(edge) => edge,
+      _ => unreachable!(),
+    };
+    let mut out: Vec<u8> = Vec::new();
+    let mut bits = BitsWriter::new(&mut out);
+    emit_edge_bits(&mut bits, edge).unwrap();
+    bits.finish().unwrap();
+    out
+  }
+
+  #[test]
+  fn test_emit_edge_bits() {
+    // Straight flag, bit count (2 bits), horizontal/vertical line flags, coordinate
+    assert_eq!(emit_edge(&edge(1, 0, None)), vec![0b1000_0000, 0b1000_0000]);
+    assert_eq!(emit_edge(&edge(0, -1, None)), vec![0b1000_0011, 0b1000_0000]);
+    // The anchor of curved edges is relative to the control point
+    assert_eq!(emit_edge(&edge(2, 0, Some((1, 1)))), vec![0b0000_0010, 0b1011_1000]);
+    // Coordinates are limited to 17 bits
+    let mut out: Vec<u8> = Vec::new();
+    let mut bits = BitsWriter::new(&mut out);
+    let too_large = swf::shape_records::Edge {
+      delta: swf::Vector2D { x: 70000, y: 0 },
+      control_delta: None,
+    };
+    assert!(emit_edge_bits(&mut bits, &too_large).is_err());
+  }
+
+  #[test]
+  fn test_emit_shape() {
+    let solid = |r: u8| {
+      swf::FillStyle::Solid(swf::fill_styles::Solid {
+        color: swf::StraightSRgba8 { r, g: 0, b: 0, a: 255 },
+      })
+    };
+    let shape = swf::Shape {
+      initial_styles: swf::ShapeStyles {
+        fill: vec![solid(255)],
+        line: Vec::new(),
+      },
+      records: vec![
+        swf::ShapeRecord::StyleChange(swf::shape_records::StyleChange {
+          move_to: Some(swf::Vector2D { x: -20, y: 30 }),
+          left_fill: None,
+          right_fill: Some(1),
+          line_style: None,
+          new_styles: None,
+        }),
+        edge(1000, 0, None),
+        edge(0, -60000, None),
+        edge(-1000, 60000, Some((-500, 0))),
+        swf::ShapeRecord::StyleChange(swf::shape_records::StyleChange {
+          move_to: None,
+          left_fill: Some(2),
+          right_fill: None,
+          line_style: None,
+          new_styles: Some(swf::ShapeStyles {
+            fill: vec![solid(0), solid(1), solid(2)],
+            line: Vec::new(),
+          }),
+        }),
+        edge(5, 5, None),
+      ],
+    };
+    let mut out: Vec<u8> = Vec::new();
+    emit_shape(&mut out, &shape, ShapeVersion::Shape2).unwrap();
+    assert_eq!(parse_shape(&out, ShapeVersion::Shape2), Ok((&[][..], shape)));
+  }
+}
@@ -0,0 +1,87 @@
+use crate::emitter::base::{invalid_value, to_u8};
+use std::io;
+use swf_types as swf;
+
+pub(crate) fn get_sound_rate_code(value: swf::SoundRate) -> u8 {
+  match value {
+    swf::SoundRate::SoundRate5500 => 0,
+    swf::SoundRate::SoundRate11000 => 1,
+    swf::SoundRate::SoundRate22000 => 2,
+    swf::SoundRate::SoundRate44000 => 3,
+  }
+}
+
+pub(crate) fn get_audio_coding_format_code(value: swf::AudioCodingFormat) -> u8 {
+  match value {
+    swf::AudioCodingFormat::UncompressedNativeEndian => 0,
+    swf::AudioCodingFormat::Adpcm => 1,
+    swf::AudioCodingFormat::Mp3 => 2,
+    swf::AudioCodingFormat::UncompressedLittleEndian => 3,
+    swf::AudioCodingFormat::Nellymoser16 => 4,
+    swf::AudioCodingFormat::Nellymoser8 => 5,
+    swf::AudioCodingFormat::Nellymoser => 6,
+    swf::AudioCodingFormat::Speex => 11,
+  }
+}
+
+pub(crate) fn get_sound_size_code(value: swf::SoundSize) -> u8 {
+  match value {
+    swf::SoundSize::SoundSize8 => 0,
+    swf::SoundSize::SoundSize16 => 1,
+  }
+}
+
+pub(crate) fn get_sound_type_code(value: swf::SoundType) -> u8 {
+  match value {
+    swf::SoundType::Mono => 0,
+    swf::SoundType::Stereo => 1,
+  }
+}
+
+pub fn emit_sound_info<W: io::Write + ?Sized>(writer: &mut W, value: &swf::SoundInfo) -> io::Result<()> {
+  #[allow(clippy::identity_op)]
+  let flags: u8 = 0
+    | (if value.in_point.is_some() { 1 << 0 } else { 0 })
+    | (if value.out_point.is_some() { 1 << 1 } else { 0 })
+    | (if value.loop_count.is_some() { 1 << 2 } else { 0 })
+    | (if value.envelope_records.is_some() { 1 << 3 } else { 0 })
+    | (if value.sync_no_multiple { 1 << 4 } else { 0 })
+    | (if value.sync_stop { 1 << 5 } else { 0 });
+  writer.write_all(&[flags])?;
+  if let Some(in_point) = value.in_point {
+    writer.write_all(&in_point.to_le_bytes())?;
+  }
+  if let Some(out_point) = value.out_point {
+    writer.write_all(&out_point.to_le_bytes())?;
+  }
+  if let Some(loop_count) = value.loop_count {
+    writer.write_all(&loop_count.to_le_bytes())?;
+  }
+  if let Some(envelope_records) = &value.envelope_records {
+    writer.write_all(&[to_u8(envelope_records.len(), "too many sound envelope records")?])?;
+    for envelope_record in envelope_records {
+      emit_sound_envelope(writer, envelope_record)?;
+    }
+  }
+  Ok(())
+}
+
+pub fn emit_sound_envelope<W: io::Write + ?Sized>(writer: &mut W, value: &swf::SoundEnvelope) -> io::Result<()> {
+  writer.write_all(&value.pos44.to_le_bytes())?;
+  writer.write_all(&value.left_level.to_le_bytes())?;
+  writer.write_all(&value.right_level.to_le_bytes())
+}
+
+/// Emits the sound id of a button state, or `0` if it has no sound
+pub fn emit_button_sound<W: io::Write + ?Sized>(writer: &mut W, value: &Option<swf::ButtonSound>) -> io::Result<()> {
+  match value {
+    None => writer.write_all(&0u16.to_le_bytes()),
+    Some(button_sound) => {
+      if button_sound.sound_id == 0 {
+        return Err(invalid_value("button sound id must not be 0"));
+      }
+      writer.write_all(&button_sound.sound_id.to_le_bytes())?;
+      emit_sound_info(writer, &button_sound.sound_info)
+    }
+  }
+}
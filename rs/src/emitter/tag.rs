@@ -0,0 +1,2058 @@
+use crate::complete::button::ButtonVersion;
+use crate::complete::morph_shape::MorphShapeVersion;
+use crate::complete::shape::ShapeVersion;
+use crate::complete::text::{FontInfoVersion, FontVersion, TextVersion};
+use crate::emitter::base::{invalid_value, to_u16, to_u8};
+use crate::emitter::basic_data_types::{
+  emit_c_string, emit_color_transform, emit_color_transform_with_alpha, emit_language_code, emit_leb128_u32,
+  emit_matrix, emit_named_id, emit_rect, emit_s_rgb8, emit_straight_s_rgba8, emit_swf_c_string, emit_swf_named_id,
+  encode_swf_string,
+};
+use crate::emitter::button::{
+  emit_button2_cond_action_string, emit_button_record_string, is_button1_record,
+};
+use crate::emitter::display::{emit_blend_mode, emit_clip_actions_string, emit_filter_list};
+use crate::emitter::morph_shape::{emit_morph_shape, get_morph_shape_version};
+use crate::emitter::shape::{emit_glyph, emit_shape, get_shape_version};
+use crate::emitter::sound::{
+  emit_button_sound, emit_sound_info, get_audio_coding_format_code, get_sound_rate_code, get_sound_size_code,
+  get_sound_type_code,
+};
+use crate::emitter::text::{
+  emit_font_alignment_zone, emit_font_layout, emit_offset_glyphs, emit_text_alignment, emit_text_record_string,
+  get_csm_table_hint_code, get_glyph_entry_bits, get_grid_fitting_code, get_text_renderer_code,
+  requires_wide_offsets,
+};
+use crate::emitter::video::{get_video_codec_code, get_video_deblocking_code};
+use crate::legacy::LegacyEncoding;
+use std::convert::TryFrom;
+use std::io;
+use swf_types as ast;
+
+/// Emits a tag header, using the short form when the length fits in 6 bits.
+pub fn emit_tag_header<W: io::Write + ?Sized>(writer: &mut W, value: ast::TagHeader) -> io::Result<()> {
+  emit_tag_header_with_form(writer, value, false)
+}
+
+/// Emits a tag header, using the long form if `long` is set or if the length does not fit in 6 bits.
+pub(crate) fn emit_tag_header_with_form<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: ast::TagHeader,
+  long: bool,
+) -> io::Result<()> {
+  const MAX_SHORT_LENGTH: u32 = (1 << 6) - 1;
+  if value.code > 0x3ff {
+    return Err(invalid_value("tag code too large"));
+  }
+  if !long && value.length < MAX_SHORT_LENGTH {
+    let code_and_length: u16 = (value.code << 6) | (value.length as u16);
+    writer.write_all(&code_and_length.to_le_bytes())
+  } else {
+    let code_and_length: u16 = (value.code << 6) | (MAX_SHORT_LENGTH as u16);
+    writer.write_all(&code_and_length.to_le_bytes())?;
+    writer.write_all(&value.length.to_le_bytes())
+  }
+}
+
+/// Emits the `End` tag terminating a tag string.
+pub fn emit_end_tag<W: io::Write + ?Sized>(writer: &mut W) -> io::Result<()> {
+  writer.write_all(&[0, 0])
+}
+
+/// Emits a tag, with its header.
+///
+/// The tag code is the oldest one able to represent `value` (for example `DefineShape` is only emitted as
+/// `DefineShape3` if it uses transparent colors). `Raw` tags are emitted as-is.
+/// The strings of SWF 5 (and earlier) movies are encoded as Windows-1252, see [[emit_tag_with_legacy_encoding]].
+pub fn emit_tag<W: io::Write + ?Sized>(writer: &mut W, value: &ast::Tag, swf_version: u8) -> io::Result<()> {
+  emit_tag_with_legacy_encoding(writer, value, swf_version, LegacyEncoding::default())
+}
+
+/// Emits a tag, with its header, encoding the strings of SWF 5 (and earlier) movies with `encoding`.
+///
+/// Strings with characters missing from `encoding` are rejected.
+pub fn emit_tag_with_legacy_encoding<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::Tag,
+  swf_version: u8,
+  encoding: LegacyEncoding,
+) -> io::Result<()> {
+  if let ast::Tag::Raw(tag) = value {
+    return writer.write_all(&tag.data);
+  }
+  let mut body: Vec<u8> = Vec::new();
+  let code = emit_tag_body(&mut body, value, swf_version, encoding)?;
+  let length = u32::try_from(body.len()).map_err(|_| invalid_value("tag too large"))?;
+  emit_tag_header(writer, ast::TagHeader { code, length })?;
+  writer.write_all(&body)
+}
+
+/// Emits the body of a tag and returns its code.
+pub(crate) fn emit_tag_body(
+  writer: &mut Vec<u8>,
+  value: &ast::Tag,
+  swf_version: u8,
+  encoding: LegacyEncoding,
+) -> io::Result<u16> {
+  let code: u16 = match value {
+    ast::Tag::CsmTextSettings(tag) => {
+      emit_csm_text_settings(writer, tag)?;
+      74
+    }
+    ast::Tag::DefineBinaryData(tag) => {
+      emit_define_binary_data(writer, tag)?;
+      87
+    }
+    ast::Tag::DefineBitmap(tag) => emit_define_bitmap_any(writer, tag)?,
+    ast::Tag::DefineButton(tag) => match emit_define_button_any(writer, tag)? {
+      ButtonVersion::Button1 => 7,
+      ButtonVersion::Button2 => 34,
+    },
+    ast::Tag::DefineButtonColorTransform(tag) => {
+      emit_define_button_color_transform(writer, tag)?;
+      23
+    }
+    ast::Tag::DefineButtonSound(tag) => {
+      emit_define_button_sound(writer, tag)?;
+      17
+    }
+    ast::Tag::DefineCffFont(tag) => {
+      emit_define_font4(writer, tag)?;
+      91
+    }
+    ast::Tag::DefineDynamicText(tag) => {
+      emit_define_edit_text(writer, tag, swf_version, encoding)?;
+      37
+    }
+    ast::Tag::DefineFont(tag) => match emit_define_font_any(writer, tag, swf_version, encoding)? {
+      FontVersion::Font2 => 48,
+      FontVersion::Font3 => 75,
+    },
+    ast::Tag::DefineFontAlignZones(tag) => {
+      emit_define_font_align_zones(writer, tag)?;
+      73
+    }
+    ast::Tag::DefineFontInfo(tag) => match emit_define_font_info_any(writer, tag, swf_version, encoding)? {
+      FontInfoVersion::FontInfo1 => 13,
+      FontInfoVersion::FontInfo2 => 62,
+    },
+    ast::Tag::DefineFontName(tag) => {
+      emit_define_font_name(writer, tag)?;
+      88
+    }
+    ast::Tag::DefineGlyphFont(tag) => {
+      emit_define_font(writer, tag)?;
+      10
+    }
+    ast::Tag::DefineJpegTables(tag) => {
+      writer.extend_from_slice(&tag.data);
+      8
+    }
+    ast::Tag::DefineMorphShape(tag) => match emit_define_morph_shape_any(writer, tag)? {
+      MorphShapeVersion::MorphShape1 => 46,
+      MorphShapeVersion::MorphShape2 => 84,
+    },
+    ast::Tag::DefineScalingGrid(tag) => {
+      emit_define_scaling_grid(writer, tag)?;
+      78
+    }
+    ast::Tag::DefineSceneAndFrameLabelData(tag) => {
+      emit_define_scene_and_frame_label_data(writer, tag)?;
+      86
+    }
+    ast::Tag::DefineShape(tag) => match emit_define_shape_any(writer, tag)? {
+      ShapeVersion::Shape1 => 2,
+      ShapeVersion::Shape2 => 22,
+      ShapeVersion::Shape3 => 32,
+      ShapeVersion::Shape4 => 83,
+    },
+    ast::Tag::DefineSound(tag) => {
+      emit_define_sound(writer, tag)?;
+      14
+    }
+    ast::Tag::DefineSprite(tag) => {
+      emit_define_sprite(writer, tag, swf_version, encoding)?;
+      39
+    }
+    ast::Tag::DefineText(tag) => match emit_define_text_any(writer, tag)? {
+      TextVersion::Text1 => 11,
+      TextVersion::Text2 => 33,
+    },
+    ast::Tag::DefineVideoStream(tag) => {
+      emit_define_video_stream(writer, tag)?;
+      60
+    }
+    ast::Tag::DoAbc(tag) => {
+      emit_do_abc(writer, tag)?;
+      if tag.header.is_some() {
+        82
+      } else {
+        72
+      }
+    }
+    ast::Tag::DoAction(tag) => {
+      writer.extend_from_slice(&tag.actions);
+      12
+    }
+    ast::Tag::DoInitAction(tag) => {
+      writer.extend_from_slice(&tag.sprite_id.to_le_bytes());
+      writer.extend_from_slice(&tag.actions);
+      59
+    }
+    ast::Tag::EnableDebugger(tag) => {
+      if swf_version >= 6 {
+        writer.extend_from_slice(&[0, 0]); // Reserved
+        emit_c_string(writer, &tag.password)?;
+        64
+      } else {
+        emit_swf_c_string(writer, &tag.password, swf_version, encoding)?;
+        58
+      }
+    }
+    ast::Tag::EnablePostscript => 25,
+    ast::Tag::ExportAssets(tag) => {
+      emit_named_id_list(writer, &tag.assets, |writer, named_id| {
+        emit_swf_named_id(writer, named_id, swf_version, encoding)
+      })?;
+      56
+    }
+    ast::Tag::FileAttributes(tag) => {
+      emit_file_attributes(writer, tag)?;
+      69
+    }
+    ast::Tag::FrameLabel(tag) => {
+      emit_swf_c_string(writer, &tag.name, swf_version, encoding)?;
+      if tag.is_anchor {
+        writer.push(1);
+      }
+      43
+    }
+    ast::Tag::ImportAssets(tag) => {
+      emit_swf_c_string(writer, &tag.url, swf_version, encoding)?;
+      let code = if swf_version >= 8 {
+        writer.extend_from_slice(&[1, 0]); // Reserved, must be `1` then `0`
+        71
+      } else {
+        57
+      };
+      emit_named_id_list(writer, &tag.assets, |writer, named_id| {
+        emit_swf_named_id(writer, named_id, swf_version, encoding)
+      })?;
+      code
+    }
+    ast::Tag::Metadata(tag) => {
+      emit_c_string(writer, &tag.metadata)?;
+      77
+    }
+    ast::Tag::PlaceObject(tag) => emit_place_object_any(writer, tag, swf_version, encoding)?,
+    ast::Tag::Protect(tag) => {
+      // An empty body means that there is no password
+      if !tag.password.is_empty() {
+        emit_swf_c_string(writer, &tag.password, swf_version, encoding)?;
+      }
+      24
+    }
+    // `emit_tag` writes raw tags directly, with their header
+    ast::Tag::Raw(_) => return Err(invalid_value("raw tag has no separate body")),
+    ast::Tag::RawBody(tag) => {
+      writer.extend_from_slice(&tag.data);
+      tag.code
+    }
+    ast::Tag::RemoveObject(tag) => {
+      if let Some(character_id) = tag.character_id {
+        writer.extend_from_slice(&character_id.to_le_bytes());
+        writer.extend_from_slice(&tag.depth.to_le_bytes());
+        5
+      } else {
+        writer.extend_from_slice(&tag.depth.to_le_bytes());
+        28
+      }
+    }
+    ast::Tag::ScriptLimits(tag) => {
+      writer.extend_from_slice(&tag.max_recursion_depth.to_le_bytes());
+      writer.extend_from_slice(&tag.script_timeout.to_le_bytes());
+      65
+    }
+    ast::Tag::SetBackgroundColor(tag) => {
+      emit_s_rgb8(writer, tag.color)?;
+      9
+    }
+    ast::Tag::SetTabIndex(tag) => {
+      writer.extend_from_slice(&tag.depth.to_le_bytes());
+      writer.extend_from_slice(&tag.index.to_le_bytes());
+      66
+    }
+    ast::Tag::ShowFrame => 1,
+    ast::Tag::SoundStreamBlock(tag) => {
+      writer.extend_from_slice(&tag.data);
+      19
+    }
+    ast::Tag::SoundStreamHead(tag) => emit_sound_stream_head_any(writer, tag)?,
+    ast::Tag::StartSound(tag) => {
+      writer.extend_from_slice(&tag.sound_id.to_le_bytes());
+      emit_sound_info(writer, &tag.sound_info)?;
+      15
+    }
+    ast::Tag::StartSound2(tag) => {
+      emit_c_string(writer, &tag.sound_class_name)?;
+      emit_sound_info(writer, &tag.sound_info)?;
+      89
+    }
+    ast::Tag::SymbolClass(tag) => {
+      emit_named_id_list(writer, &tag.symbols, emit_named_id)?;
+      76
+    }
+    ast::Tag::Telemetry(tag) => {
+      emit_telemetry(writer, tag)?;
+      93
+    }
+    ast::Tag::VideoFrame(tag) => {
+      writer.extend_from_slice(&tag.video_id.to_le_bytes());
+      writer.extend_from_slice(&tag.frame.to_le_bytes());
+      writer.extend_from_slice(&tag.packet);
+      61
+    }
+  };
+  Ok(code)
+}
+
+fn emit_named_id_list<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &[ast::NamedId],
+  emit_item: impl Fn(&mut W, &ast::NamedId) -> io::Result<()>,
+) -> io::Result<()> {
+  writer.write_all(&to_u16(value.len(), "too many named ids")?.to_le_bytes())?;
+  for named_id in value {
+    emit_item(writer, named_id)?;
+  }
+  Ok(())
+}
+
+pub fn emit_csm_text_settings<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::CsmTextSettings,
+) -> io::Result<()> {
+  writer.write_all(&value.text_id.to_le_bytes())?;
+  let flags: u8 = (get_grid_fitting_code(value.fitting) << 3) | (get_text_renderer_code(value.renderer) << 6);
+  writer.write_all(&[flags])?;
+  writer.write_all(&value.thickness.to_le_bytes())?;
+  writer.write_all(&value.sharpness.to_le_bytes())?;
+  writer.write_all(&[0]) // Reserved
+}
+
+pub fn emit_define_binary_data<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineBinaryData,
+) -> io::Result<()> {
+  writer.write_all(&value.id.to_le_bytes())?;
+  writer.write_all(&0u32.to_le_bytes())?; // Reserved
+  writer.write_all(&value.data)
+}
+
+/// Emits the body of a bitmap tag and returns its code, selected from the media type.
+pub fn emit_define_bitmap_any<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineBitmap,
+) -> io::Result<u16> {
+  let code: u16 = match value.media_type {
+    ast::ImageType::SwfPartialJpeg => 6,
+    ast::ImageType::Jpeg | ast::ImageType::Png | ast::ImageType::Gif => 21,
+    ast::ImageType::SwfLossless1 => 20,
+    ast::ImageType::SwfJpeg3 => 35,
+    ast::ImageType::SwfLossless2 => 36,
+    ast::ImageType::SwfJpeg4 => 90,
+  };
+  // The data of `SwfJpeg3` and `SwfJpeg4` already includes the image length, and the data of lossless
+  // bitmaps starts with the bitmap format: the body is the id followed by the data in every case.
+  writer.write_all(&value.id.to_le_bytes())?;
+  writer.write_all(&value.data)?;
+  Ok(code)
+}
+
+/// Emits the body of a button tag and returns the version used.
+pub fn emit_define_button_any<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineButton,
+) -> io::Result<ButtonVersion> {
+  writer.write_all(&value.id.to_le_bytes())?;
+  let button1_actions = match value.actions.as_slice() {
+    [cond_action] if cond_action.conditions.is_none() => Some(&cond_action.actions),
+    _ => None,
+  };
+  match button1_actions {
+    Some(actions) if !value.track_as_menu && value.records.iter().all(is_button1_record) => {
+      emit_button_record_string(writer, &value.records, ButtonVersion::Button1)?;
+      writer.write_all(actions)?;
+      Ok(ButtonVersion::Button1)
+    }
+    _ => {
+      #[allow(clippy::identity_op)]
+      let flags: u8 = 0 | (if value.track_as_menu { 1 << 0 } else { 0 });
+      writer.write_all(&[flags])?;
+      let mut records: Vec<u8> = Vec::new();
+      emit_button_record_string(&mut records, &value.records, ButtonVersion::Button2)?;
+      // The offset is relative to the start of the offset field itself
+      let action_offset: u16 = if value.actions.is_empty() {
+        0
+      } else {
+        to_u16(records.len() + 2, "button records too large")?
+      };
+      writer.write_all(&action_offset.to_le_bytes())?;
+      writer.write_all(&records)?;
+      emit_button2_cond_action_string(writer, &value.actions)?;
+      Ok(ButtonVersion::Button2)
+    }
+  }
+}
+
+pub fn emit_define_button_color_transform<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineButtonColorTransform,
+) -> io::Result<()> {
+  writer.write_all(&value.button_id.to_le_bytes())?;
+  emit_color_transform(writer, &value.transform)
+}
+
+pub fn emit_define_button_sound<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineButtonSound,
+) -> io::Result<()> {
+  writer.write_all(&value.button_id.to_le_bytes())?;
+  emit_button_sound(writer, &value.over_up_to_idle)?;
+  emit_button_sound(writer, &value.idle_to_over_up)?;
+  emit_button_sound(writer, &value.over_up_to_over_down)?;
+  emit_button_sound(writer, &value.over_down_to_over_up)
+}
+
+pub fn emit_define_edit_text<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineDynamicText,
+  swf_version: u8,
+  encoding: LegacyEncoding,
+) -> io::Result<()> {
+  let has_font = value.font_id.is_some();
+  if has_font != value.font_size.is_some() {
+    return Err(invalid_value("dynamic text font id and size must be set together"));
+  }
+  let has_layout = value.align != ast::text::TextAlignment::Left
+    || value.margin_left != 0
+    || value.margin_right != 0
+    || value.indent != 0
+    || value.leading != 0;
+
+  writer.write_all(&value.id.to_le_bytes())?;
+  emit_rect(writer, &value.bounds)?;
+  #[allow(clippy::identity_op)]
+  let flags: u16 = 0
+    | (if has_font { 1 << 0 } else { 0 })
+    | (if value.max_length.is_some() { 1 << 1 } else { 0 })
+    | (if value.color.is_some() { 1 << 2 } else { 0 })
+    | (if value.readonly { 1 << 3 } else { 0 })
+    | (if value.password { 1 << 4 } else { 0 })
+    | (if value.multiline { 1 << 5 } else { 0 })
+    | (if value.word_wrap { 1 << 6 } else { 0 })
+    | (if value.text.is_some() { 1 << 7 } else { 0 })
+    | (if value.use_glyph_font { 1 << 8 } else { 0 })
+    | (if value.html { 1 << 9 } else { 0 })
+    | (if value.was_static { 1 << 10 } else { 0 })
+    | (if value.border { 1 << 11 } else { 0 })
+    | (if value.no_select { 1 << 12 } else { 0 })
+    | (if has_layout { 1 << 13 } else { 0 })
+    | (if value.auto_size { 1 << 14 } else { 0 })
+    | (if value.font_class.is_some() { 1 << 15 } else { 0 });
+  writer.write_all(&flags.to_le_bytes())?;
+  if let Some(font_id) = value.font_id {
+    writer.write_all(&font_id.to_le_bytes())?;
+  }
+  if let Some(font_class) = &value.font_class {
+    emit_swf_c_string(writer, font_class, swf_version, encoding)?;
+  }
+  if let Some(font_size) = value.font_size {
+    writer.write_all(&font_size.to_le_bytes())?;
+  }
+  if let Some(color) = value.color {
+    emit_straight_s_rgba8(writer, color)?;
+  }
+  if let Some(max_length) = value.max_length {
+    writer.write_all(&to_u16(max_length, "dynamic text max length too large")?.to_le_bytes())?;
+  }
+  if has_layout {
+    emit_text_alignment(writer, value.align)?;
+    writer.write_all(&value.margin_left.to_le_bytes())?;
+    writer.write_all(&value.margin_right.to_le_bytes())?;
+    writer.write_all(&value.indent.to_le_bytes())?;
+    writer.write_all(&value.leading.to_le_bytes())?;
+  }
+  emit_swf_c_string(writer, value.variable_name.as_deref().unwrap_or(""), swf_version, encoding)?;
+  if let Some(text) = &value.text {
+    emit_swf_c_string(writer, text, swf_version, encoding)?;
+  }
+  Ok(())
+}
+
+pub fn emit_define_font<W: io::Write + ?Sized>(writer: &mut W, value: &ast::tags::DefineGlyphFont) -> io::Result<()> {
+  writer.write_all(&value.id.to_le_bytes())?;
+  // Unlike `DefineFont2`, the offset table has no end offset: its size is given by the first offset
+  let table_size = value.glyphs.len() * 2;
+  let mut glyph_bytes: Vec<u8> = Vec::new();
+  for glyph in &value.glyphs {
+    let offset = to_u16(table_size + glyph_bytes.len(), "glyph font too large")?;
+    writer.write_all(&offset.to_le_bytes())?;
+    emit_glyph(&mut glyph_bytes, glyph)?;
+  }
+  writer.write_all(&glyph_bytes)
+}
+
+/// Emits the body of a `DefineFont2` or `DefineFont3` tag and returns the version used.
+pub fn emit_define_font_any<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineFont,
+  swf_version: u8,
+  encoding: LegacyEncoding,
+) -> io::Result<FontVersion> {
+  let version = match value.em_square_size {
+    ast::text::EmSquareSize::EmSquareSize1024 => FontVersion::Font2,
+    ast::text::EmSquareSize::EmSquareSize20480 => FontVersion::Font3,
+  };
+  let glyphs: &[ast::Glyph] = value.glyphs.as_deref().unwrap_or(&[]);
+  let code_units: &[u16] = value.code_units.as_deref().unwrap_or(&[]);
+  if code_units.len() != glyphs.len() {
+    return Err(invalid_value("font must have one code unit per glyph"));
+  }
+  if glyphs.is_empty() && value.layout.is_some() {
+    return Err(invalid_value("font layout requires glyphs"));
+  }
+  let use_wide_codes =
+    version >= FontVersion::Font3 || swf_version >= 6 || code_units.iter().any(|code_unit| *code_unit > 0xff);
+  let use_wide_offsets = requires_wide_offsets(glyphs)?;
+
+  writer.write_all(&value.id.to_le_bytes())?;
+  #[allow(clippy::identity_op)]
+  let flags: u8 = 0
+    | (if value.is_bold { 1 << 0 } else { 0 })
+    | (if value.is_italic { 1 << 1 } else { 0 })
+    | (if use_wide_codes { 1 << 2 } else { 0 })
+    | (if use_wide_offsets { 1 << 3 } else { 0 })
+    | (if value.is_ansi { 1 << 4 } else { 0 })
+    | (if value.is_small { 1 << 5 } else { 0 })
+    | (if value.is_shift_jis { 1 << 6 } else { 0 })
+    | (if value.layout.is_some() { 1 << 7 } else { 0 });
+  writer.write_all(&[flags])?;
+  emit_language_code(writer, value.language)?;
+  let font_name = encode_swf_string(&value.font_name, swf_version, encoding)?;
+  writer.write_all(&[to_u8(font_name.len(), "font name too long")?])?;
+  writer.write_all(&font_name)?;
+  writer.write_all(&to_u16(glyphs.len(), "too many glyphs")?.to_le_bytes())?;
+  if glyphs.is_empty() {
+    // Device fonts end after the glyph count
+    return Ok(version);
+  }
+  emit_offset_glyphs(writer, glyphs, use_wide_offsets)?;
+  for code_unit in code_units {
+    if use_wide_codes {
+      writer.write_all(&code_unit.to_le_bytes())?;
+    } else {
+      writer.write_all(&[*code_unit as u8])?;
+    }
+  }
+  if let Some(layout) = &value.layout {
+    emit_font_layout(writer, layout, glyphs.len())?;
+  }
+  Ok(version)
+}
+
+pub fn emit_define_font4<W: io::Write + ?Sized>(writer: &mut W, value: &ast::tags::DefineCffFont) -> io::Result<()> {
+  writer.write_all(&value.id.to_le_bytes())?;
+  emit_c_string(writer, &value.font_name)?;
+  #[allow(clippy::identity_op)]
+  let flags: u8 = 0
+    | (if value.is_bold { 1 << 0 } else { 0 })
+    | (if value.is_italic { 1 << 1 } else { 0 })
+    | (if value.data.is_some() { 1 << 2 } else { 0 });
+  writer.write_all(&[flags])?;
+  if let Some(data) = &value.data {
+    writer.write_all(data)?;
+  }
+  Ok(())
+}
+
+pub fn emit_define_font_align_zones<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineFontAlignZones,
+) -> io::Result<()> {
+  writer.write_all(&value.font_id.to_le_bytes())?;
+  writer.write_all(&[get_csm_table_hint_code(value.csm_table_hint) << 6])?;
+  for zone in &value.zones {
+    emit_font_alignment_zone(writer, zone)?;
+  }
+  Ok(())
+}
+
+/// Emits the body of a font info tag and returns the version used.
+pub fn emit_define_font_info_any<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineFontInfo,
+  swf_version: u8,
+  encoding: LegacyEncoding,
+) -> io::Result<FontInfoVersion> {
+  let version = if value.language == ast::LanguageCode::Auto {
+    FontInfoVersion::FontInfo1
+  } else {
+    FontInfoVersion::FontInfo2
+  };
+  let use_wide_codes = version >= FontInfoVersion::FontInfo2 || value.code_units.iter().any(|c| *c > 0xff);
+
+  writer.write_all(&value.font_id.to_le_bytes())?;
+  let font_name = encode_swf_string(&value.font_name, swf_version, encoding)?;
+  writer.write_all(&[to_u8(font_name.len(), "font name too long")?])?;
+  writer.write_all(&font_name)?;
+  #[allow(clippy::identity_op)]
+  let flags: u8 = 0
+    | (if use_wide_codes { 1 << 0 } else { 0 })
+    | (if value.is_bold { 1 << 1 } else { 0 })
+    | (if value.is_italic { 1 << 2 } else { 0 })
+    | (if value.is_ansi { 1 << 3 } else { 0 })
+    | (if value.is_shift_jis { 1 << 4 } else { 0 })
+    | (if value.is_small { 1 << 5 } else { 0 });
+  writer.write_all(&[flags])?;
+  if version >= FontInfoVersion::FontInfo2 {
+    emit_language_code(writer, value.language)?;
+  }
+  for code_unit in &value.code_units {
+    if use_wide_codes {
+      writer.write_all(&code_unit.to_le_bytes())?;
+    } else {
+      writer.write_all(&[*code_unit as u8])?;
+    }
+  }
+  Ok(version)
+}
+
+pub fn emit_define_font_name<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineFontName,
+) -> io::Result<()> {
+  writer.write_all(&value.font_id.to_le_bytes())?;
+  emit_c_string(writer, &value.name)?;
+  emit_c_string(writer, &value.copyright)
+}
+
+/// Emits the body of a morph shape tag and returns the version used.
+pub fn emit_define_morph_shape_any<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineMorphShape,
+) -> io::Result<MorphShapeVersion> {
+  let has_edge_bounds = value.edge_bounds.is_some() || value.morph_edge_bounds.is_some();
+  let version = if has_edge_bounds || value.has_scaling_strokes || value.has_non_scaling_strokes {
+    MorphShapeVersion::MorphShape2
+  } else {
+    get_morph_shape_version(&value.shape)
+  };
+
+  writer.write_all(&value.id.to_le_bytes())?;
+  emit_rect(writer, &value.bounds)?;
+  emit_rect(writer, &value.morph_bounds)?;
+  if version >= MorphShapeVersion::MorphShape2 {
+    match (&value.edge_bounds, &value.morph_edge_bounds) {
+      (Some(edge_bounds), Some(morph_edge_bounds)) => {
+        emit_rect(writer, edge_bounds)?;
+        emit_rect(writer, morph_edge_bounds)?;
+      }
+      _ => return Err(invalid_value("`DefineMorphShape2` requires edge bounds")),
+    }
+    #[allow(clippy::identity_op)]
+    let flags: u8 = 0
+      | (if value.has_scaling_strokes { 1 << 0 } else { 0 })
+      | (if value.has_non_scaling_strokes { 1 << 1 } else { 0 });
+    writer.write_all(&[flags])?;
+  }
+  emit_morph_shape(writer, &value.shape, version)?;
+  Ok(version)
+}
+
+pub fn emit_define_scaling_grid<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineScalingGrid,
+) -> io::Result<()> {
+  writer.write_all(&value.character_id.to_le_bytes())?;
+  emit_rect(writer, &value.splitter)
+}
+
+pub fn emit_define_scene_and_frame_label_data<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineSceneAndFrameLabelData,
+) -> io::Result<()> {
+  let to_leb128_count = |len: usize| u32::try_from(len).map_err(|_| invalid_value("too many scenes or labels"));
+  emit_leb128_u32(writer, to_leb128_count(value.scenes.len())?)?;
+  for scene in &value.scenes {
+    emit_leb128_u32(writer, scene.offset)?;
+    emit_c_string(writer, &scene.name)?;
+  }
+  emit_leb128_u32(writer, to_leb128_count(value.labels.len())?)?;
+  for label in &value.labels {
+    emit_leb128_u32(writer, label.frame)?;
+    emit_c_string(writer, &label.name)?;
+  }
+  Ok(())
+}
+
+/// Emits the body of a shape tag and returns the version used.
+pub fn emit_define_shape_any<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineShape,
+) -> io::Result<ShapeVersion> {
+  let has_shape4_fields = value.edge_bounds.is_some()
+    || value.has_fill_winding
+    || value.has_non_scaling_strokes
+    || value.has_scaling_strokes;
+  let version = if has_shape4_fields {
+    ShapeVersion::Shape4
+  } else {
+    get_shape_version(&value.shape)
+  };
+
+  writer.write_all(&value.id.to_le_bytes())?;
+  emit_rect(writer, &value.bounds)?;
+  if version >= ShapeVersion::Shape4 {
+    match &value.edge_bounds {
+      Some(edge_bounds) => emit_rect(writer, edge_bounds)?,
+      None => return Err(invalid_value("`DefineShape4` requires edge bounds")),
+    }
+    #[allow(clippy::identity_op)]
+    let flags: u8 = 0
+      | (if value.has_scaling_strokes { 1 << 0 } else { 0 })
+      | (if value.has_non_scaling_strokes { 1 << 1 } else { 0 })
+      | (if value.has_fill_winding { 1 << 2 } else { 0 });
+    writer.write_all(&[flags])?;
+  }
+  emit_shape(writer, &value.shape, version)?;
+  Ok(version)
+}
+
+pub fn emit_define_sound<W: io::Write + ?Sized>(writer: &mut W, value: &ast::tags::DefineSound) -> io::Result<()> {
+  writer.write_all(&value.id.to_le_bytes())?;
+  #[allow(clippy::identity_op)]
+  let flags: u8 = 0
+    | (get_sound_type_code(value.sound_type) << 0)
+    | (get_sound_size_code(value.sound_size) << 1)
+    | (get_sound_rate_code(value.sound_rate) << 2)
+    | (get_audio_coding_format_code(value.format) << 4);
+  writer.write_all(&[flags])?;
+  writer.write_all(&value.sample_count.to_le_bytes())?;
+  writer.write_all(&value.data)
+}
+
+pub fn emit_define_sprite<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineSprite,
+  swf_version: u8,
+  encoding: LegacyEncoding,
+) -> io::Result<()> {
+  writer.write_all(&value.id.to_le_bytes())?;
+  writer.write_all(&to_u16(value.frame_count, "sprite frame count too large")?.to_le_bytes())?;
+  for tag in &value.tags {
+    emit_tag_with_legacy_encoding(writer, tag, swf_version, encoding)?;
+  }
+  emit_end_tag(writer)
+}
+
+/// Emits the body of a text tag and returns the version used.
+pub fn emit_define_text_any<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineText,
+) -> io::Result<TextVersion> {
+  let has_alpha = value
+    .records
+    .iter()
+    .any(|record| record.color.map_or(false, |color| color.a != u8::MAX));
+  let version = if has_alpha {
+    TextVersion::Text2
+  } else {
+    TextVersion::Text1
+  };
+  let (index_bits, advance_bits) = get_glyph_entry_bits(&value.records)?;
+
+  writer.write_all(&value.id.to_le_bytes())?;
+  emit_rect(writer, &value.bounds)?;
+  emit_matrix(writer, &value.matrix)?;
+  writer.write_all(&[index_bits as u8, advance_bits as u8])?;
+  emit_text_record_string(writer, &value.records, has_alpha, index_bits, advance_bits)?;
+  Ok(version)
+}
+
+pub fn emit_define_video_stream<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::DefineVideoStream,
+) -> io::Result<()> {
+  writer.write_all(&value.id.to_le_bytes())?;
+  writer.write_all(&to_u16(value.frame_count, "video frame count too large")?.to_le_bytes())?;
+  writer.write_all(&value.width.to_le_bytes())?;
+  writer.write_all(&value.height.to_le_bytes())?;
+  #[allow(clippy::identity_op)]
+  let flags: u8 = 0
+    | (if value.use_smoothing { 1 << 0 } else { 0 })
+    | (get_video_deblocking_code(value.deblocking) << 1);
+  writer.write_all(&[flags, get_video_codec_code(value.codec)])
+}
+
+pub fn emit_do_abc<W: io::Write + ?Sized>(writer: &mut W, value: &ast::tags::DoAbc) -> io::Result<()> {
+  if let Some(header) = &value.header {
+    writer.write_all(&header.flags.to_le_bytes())?;
+    emit_c_string(writer, &header.name)?;
+  }
+  writer.write_all(&value.data)
+}
+
+pub fn emit_file_attributes<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::FileAttributes,
+) -> io::Result<()> {
+  #[allow(clippy::identity_op)]
+  let flags: u32 = 0
+    | (if value.use_network { 1 << 0 } else { 0 })
+    | (if value.use_relative_urls { 1 << 1 } else { 0 })
+    | (if value.no_cross_domain_caching { 1 << 2 } else { 0 })
+    | (if value.use_as3 { 1 << 3 } else { 0 })
+    | (if value.has_metadata { 1 << 4 } else { 0 })
+    | (if value.use_gpu { 1 << 5 } else { 0 })
+    | (if value.use_direct_blit { 1 << 6 } else { 0 });
+  writer.write_all(&flags.to_le_bytes())
+}
+
+/// Checks if the color transform only uses the features of `ColorTransform` (without alpha)
+fn is_opaque_color_transform(value: &ast::ColorTransformWithAlpha) -> bool {
+  value.alpha_mult == ::swf_fixed::Sfixed8P8::ONE && value.alpha_add == 0
+}
+
+/// Emits the body of a place object tag and returns its code.
+///
+/// `PlaceObject` is only used for movies older than SWF 3, `PlaceObject3` only when required by its fields.
+pub fn emit_place_object_any<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::PlaceObject,
+  swf_version: u8,
+  encoding: LegacyEncoding,
+) -> io::Result<u16> {
+  let extended_events = swf_version >= 6;
+  let is_place_object3 = value.class_name.is_some()
+    || value.filters.is_some()
+    || value.blend_mode.is_some()
+    || value.bitmap_cache.is_some()
+    || value.visible.is_some()
+    || value.background_color.is_some();
+
+  if swf_version < 3 && !is_place_object3 {
+    let place_object1 = match (value.character_id, &value.matrix) {
+      (Some(character_id), Some(matrix))
+        if !value.is_update
+          && value.ratio.is_none()
+          && value.name.is_none()
+          && value.clip_depth.is_none()
+          && value.clip_actions.is_none()
+          && value.color_transform.as_ref().map_or(true, is_opaque_color_transform) =>
+      {
+        Some((character_id, matrix))
+      }
+      _ => None,
+    };
+    if let Some((character_id, matrix)) = place_object1 {
+      writer.write_all(&character_id.to_le_bytes())?;
+      writer.write_all(&value.depth.to_le_bytes())?;
+      emit_matrix(writer, matrix)?;
+      if let Some(color_transform) = &value.color_transform {
+        emit_color_transform(
+          writer,
+          &ast::ColorTransform {
+            red_mult: color_transform.red_mult,
+            green_mult: color_transform.green_mult,
+            blue_mult: color_transform.blue_mult,
+            red_add: color_transform.red_add,
+            green_add: color_transform.green_add,
+            blue_add: color_transform.blue_add,
+          },
+        )?;
+      }
+      return Ok(4);
+    }
+  }
+
+  #[allow(clippy::identity_op)]
+  let flags: u16 = 0
+    | (if value.is_update { 1 << 0 } else { 0 })
+    | (if value.character_id.is_some() { 1 << 1 } else { 0 })
+    | (if value.matrix.is_some() { 1 << 2 } else { 0 })
+    | (if value.color_transform.is_some() { 1 << 3 } else { 0 })
+    | (if value.ratio.is_some() { 1 << 4 } else { 0 })
+    | (if value.name.is_some() { 1 << 5 } else { 0 })
+    | (if value.clip_depth.is_some() { 1 << 6 } else { 0 })
+    | (if value.clip_actions.is_some() { 1 << 7 } else { 0 })
+    | (if value.filters.is_some() { 1 << 8 } else { 0 })
+    | (if value.blend_mode.is_some() { 1 << 9 } else { 0 })
+    | (if value.bitmap_cache.is_some() { 1 << 10 } else { 0 })
+    | (if value.class_name.is_some() { 1 << 11 } else { 0 })
+    | (if value.visible.is_some() { 1 << 13 } else { 0 })
+    | (if value.background_color.is_some() { 1 << 14 } else { 0 });
+  if is_place_object3 {
+    writer.write_all(&flags.to_le_bytes())?;
+  } else {
+    writer.write_all(&[flags as u8])?;
+  }
+  writer.write_all(&value.depth.to_le_bytes())?;
+  if let Some(class_name) = &value.class_name {
+    emit_c_string(writer, class_name)?;
+  }
+  if let Some(character_id) = value.character_id {
+    writer.write_all(&character_id.to_le_bytes())?;
+  }
+  if let Some(matrix) = &value.matrix {
+    emit_matrix(writer, matrix)?;
+  }
+  if let Some(color_transform) = &value.color_transform {
+    emit_color_transform_with_alpha(writer, color_transform)?;
+  }
+  if let Some(ratio) = value.ratio {
+    writer.write_all(&ratio.to_le_bytes())?;
+  }
+  if let Some(name) = &value.name {
+    emit_swf_c_string(writer, name, swf_version, encoding)?;
+  }
+  if let Some(clip_depth) = value.clip_depth {
+    writer.write_all(&clip_depth.to_le_bytes())?;
+  }
+  if let Some(filters) = &value.filters {
+    emit_filter_list(writer, filters)?;
+  }
+  if let Some(blend_mode) = value.blend_mode {
+    emit_blend_mode(writer, blend_mode)?;
+  }
+  if let Some(bitmap_cache) = value.bitmap_cache {
+    writer.write_all(&[u8::from(bitmap_cache)])?;
+  }
+  if let Some(visible) = value.visible {
+    writer.write_all(&[u8::from(visible)])?;
+  }
+  if let Some(background_color) = value.background_color {
+    emit_straight_s_rgba8(writer, background_color)?;
+  }
+  if let Some(clip_actions) = &value.clip_actions {
+    emit_clip_actions_string(writer, clip_actions, extended_events)?;
+  }
+  Ok(if is_place_object3 { 70 } else { 26 })
+}
+
+/// Emits the body of a sound stream head tag and returns its code.
+pub fn emit_sound_stream_head_any<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &ast::tags::SoundStreamHead,
+) -> io::Result<u16> {
+  // `SoundStreamHead` only supports compressed 16-bit streams
+  let is_sound_stream_head1 = matches!(
+    value.stream_format,
+    ast::AudioCodingFormat::Adpcm | ast::AudioCodingFormat::Mp3
+  ) && value.playback_sound_size == ast::SoundSize::SoundSize16;
+  let has_latency_seek = value.stream_format == ast::AudioCodingFormat::Mp3;
+  if has_latency_seek != value.latency_seek.is_some() {
+    return Err(invalid_value("latency seek must be set for MP3 streams only"));
+  }
+
+  #[allow(clippy::identity_op)]
+  let flags: u16 = 0
+    | (u16::from(get_sound_type_code(value.playback_sound_type)) << 0)
+    | (u16::from(get_sound_size_code(value.playback_sound_size)) << 1)
+    | (u16::from(get_sound_rate_code(value.playback_sound_rate)) << 2)
+    | (u16::from(get_sound_type_code(value.stream_sound_type)) << 8)
+    | (u16::from(get_sound_size_code(value.stream_sound_size)) << 9)
+    | (u16::from(get_sound_rate_code(value.stream_sound_rate)) << 10)
+    | (u16::from(get_audio_coding_format_code(value.stream_format)) << 12);
+  writer.write_all(&flags.to_le_bytes())?;
+  writer.write_all(&value.stream_sample_count.to_le_bytes())?;
+  if let Some(latency_seek) = value.latency_seek {
+    writer.write_all(&latency_seek.to_le_bytes())?;
+  }
+  Ok(if is_sound_stream_head1 { 18 } else { 45 })
+}
+
+pub fn emit_telemetry<W: io::Write + ?Sized>(writer: &mut W, value: &ast::tags::Telemetry) -> io::Result<()> {
+  writer.write_all(&[0, 0])?; // Reserved
+  if let Some(password) = &value.password {
+    if password.len() != 32 {
+      return Err(invalid_value("telemetry password must be a 32-byte hash"));
+    }
+    writer.write_all(password)?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{emit_tag, emit_tag_header};
+  use crate::complete::parse_tag;
+  use crate::streaming::tag::parse_tag_header;
+  use std::path::Path;
+  use swf_fixed::{Sfixed16P16, Sfixed8P8};
+  use swf_types as ast;
+  use swf_types::{Tag, TagHeader};
+  use test_generator::test_resources;
+
+  /// Emits `tag`, checks that it is parsed back to the same value and returns the emitted tag code
+  fn round_trip(tag: Tag, swf_version: u8) -> u16 {
+    let mut bytes: Vec<u8> = Vec::new();
+    emit_tag(&mut bytes, &tag, swf_version).expect("Failed to emit tag");
+    let (_, header) = parse_tag_header(&bytes).expect("Failed to parse tag header");
+    let (remaining_bytes, actual_value) = parse_tag(&bytes, swf_version);
+    assert_eq!(actual_value, Some(tag));
+    assert_eq!(remaining_bytes, &[] as &[u8]);
+    header.code
+  }
+
+  fn rect(x_max: i32, y_max: i32) -> ast::Rect {
+    ast::Rect {
+      x_min: 0,
+      x_max,
+      y_min: 0,
+      y_max,
+    }
+  }
+
+  fn rgba(r: u8, g: u8, b: u8, a: u8) -> ast::StraightSRgba8 {
+    ast::StraightSRgba8 { r, g, b, a }
+  }
+
+  fn translate(x: i32, y: i32) -> ast::Matrix {
+    ast::Matrix {
+      translate_x: x,
+      translate_y: y,
+      ..ast::Matrix::default()
+    }
+  }
+
+  fn sound_info() -> ast::SoundInfo {
+    ast::SoundInfo {
+      sync_stop: false,
+      sync_no_multiple: true,
+      in_point: Some(10),
+      out_point: None,
+      loop_count: Some(3),
+      envelope_records: Some(vec![ast::SoundEnvelope {
+        pos44: 100,
+        left_level: 32768,
+        right_level: 0,
+      }]),
+    }
+  }
+
+  fn style_change(move_to: Option<(i32, i32)>, fill: Option<usize>, line: Option<usize>) -> ast::ShapeRecord {
+    ast::ShapeRecord::StyleChange(ast::shape_records::StyleChange {
+      move_to: move_to.map(|(x, y)| ast::Vector2D { x, y }),
+      left_fill: None,
+      right_fill: fill,
+      line_style: line,
+      new_styles: None,
+    })
+  }
+
+  fn edge(x: i32, y: i32, control: Option<(i32, i32)>) -> ast::ShapeRecord {
+    ast::ShapeRecord::Edge(ast::shape_records::Edge {
+      delta: ast::Vector2D { x, y },
+      control_delta: control.map(|(x, y)| ast::Vector2D { x, y }),
+    })
+  }
+
+  /// Triangle with a curved side
+  fn triangle_records(fill: Option<usize>, line: Option<usize>) -> Vec<ast::ShapeRecord> {
+    vec![
+      style_change(Some((20, -40)), fill, line),
+      edge(400, 0, None),
+      edge(-200, 300, Some((-100, 150))),
+      edge(-200, -300, None),
+    ]
+  }
+
+  fn line_style(fill: ast::FillStyle) -> ast::LineStyle {
+    ast::LineStyle {
+      width: 20,
+      start_cap: ast::CapStyle::Round,
+      end_cap: ast::CapStyle::Round,
+      join: ast::JoinStyle::Round,
+      no_h_scale: false,
+      no_v_scale: false,
+      no_close: false,
+      pixel_hinting: false,
+      fill,
+    }
+  }
+
+  fn solid(color: ast::StraightSRgba8) -> ast::FillStyle {
+    ast::FillStyle::Solid(ast::fill_styles::Solid { color })
+  }
+
+  fn clip_event_flags() -> ast::ClipEventFlags {
+    ast::ClipEventFlags {
+      key_up: false,
+      key_down: false,
+      mouse_up: false,
+      mouse_down: false,
+      mouse_move: false,
+      unload: false,
+      enter_frame: false,
+      load: false,
+      drag_over: false,
+      roll_out: false,
+      roll_over: false,
+      release_outside: false,
+      release: false,
+      press: false,
+      initialize: false,
+      data: false,
+      construct: false,
+      key_press: false,
+      drag_out: false,
+    }
+  }
+
+  fn place_object(depth: u16) -> ast::tags::PlaceObject {
+    ast::tags::PlaceObject {
+      is_update: false,
+      depth,
+      character_id: Some(1),
+      matrix: Some(translate(100, -100)),
+      color_transform: None,
+      ratio: None,
+      name: None,
+      class_name: None,
+      clip_depth: None,
+      filters: None,
+      blend_mode: None,
+      bitmap_cache: None,
+      visible: None,
+      background_color: None,
+      clip_actions: None,
+    }
+  }
+
+  #[test_resources("../tests/tags/*/*/")]
+  #[test_resources("../tests/local-tags/*/*/")]
+  fn test_emit_tag(path: &str) {
+    let path: &Path = Path::new(path);
+    let name = path
+      .components()
+      .next_back()
+      .unwrap()
+      .as_os_str()
+      .to_str()
+      .expect("Failed to retrieve sample name");
+
+    let swf_version: u8 = match name {
+      "po2-swf5" => 5,
+      _ => 10,
+    };
+
+    let value_path = path.join("value.json");
+    let value_file = ::std::fs::File::open(&value_path).expect("Failed to open value file");
+    let value_reader = ::std::io::BufReader::new(value_file);
+    let value = serde_json_v8::from_reader::<_, Tag>(value_reader).expect("Failed to read AST");
+
+    let mut bytes: Vec<u8> = Vec::new();
+    emit_tag(&mut bytes, &value, swf_version).expect("Failed to emit tag");
+    let (remaining_bytes, actual_value) = parse_tag(&bytes, swf_version);
+
+    assert_eq!(actual_value, Some(value));
+    assert_eq!(remaining_bytes, &[] as &[u8], "Assert all output is consumed");
+  }
+
+  #[test]
+  fn test_emit_tag_header() {
+    let mut bytes: Vec<u8> = Vec::new();
+    emit_tag_header(&mut bytes, TagHeader { code: 9, length: 3 }).unwrap();
+    emit_tag_header(&mut bytes, TagHeader { code: 9, length: 62 }).unwrap();
+    emit_tag_header(&mut bytes, TagHeader { code: 9, length: 63 }).unwrap();
+    assert_eq!(
+      bytes,
+      vec![0x43, 0x02, 0x7e, 0x02, 0x7f, 0x02, 0x3f, 0x00, 0x00, 0x00]
+    );
+    assert!(emit_tag_header(&mut bytes, TagHeader { code: 1024, length: 0 }).is_err());
+  }
+
+  #[test]
+  fn test_emit_control_tags() {
+    assert_eq!(round_trip(Tag::ShowFrame, 10), 1);
+    assert_eq!(round_trip(Tag::EnablePostscript, 10), 25);
+    let tags: Vec<(Tag, u8, u16)> = vec![
+      (
+        Tag::SetBackgroundColor(ast::tags::SetBackgroundColor {
+          color: ast::SRgb8 { r: 1, g: 2, b: 3 },
+        }),
+        10,
+        9,
+      ),
+      (
+        Tag::FileAttributes(ast::tags::FileAttributes {
+          use_network: true,
+          use_relative_urls: false,
+          no_cross_domain_caching: false,
+          use_as3: true,
+          has_metadata: true,
+          use_gpu: false,
+          use_direct_blit: true,
+        }),
+        10,
+        69,
+      ),
+      (
+        Tag::FrameLabel(ast::tags::FrameLabel {
+          name: String::from("start"),
+          is_anchor: true,
+        }),
+        10,
+        43,
+      ),
+      (
+        Tag::Protect(ast::tags::Protect {
+          password: String::new(),
+        }),
+        10,
+        24,
+      ),
+      (
+        Tag::Protect(ast::tags::Protect {
+          password: String::from("$1$hash"),
+        }),
+        10,
+        24,
+      ),
+      (
+        Tag::EnableDebugger(ast::tags::EnableDebugger {
+          password: String::from("$1$hash"),
+        }),
+        5,
+        58,
+      ),
+      (
+        Tag::EnableDebugger(ast::tags::EnableDebugger {
+          password: String::from("$1$hash"),
+        }),
+        10,
+        64,
+      ),
+      (
+        Tag::ExportAssets(ast::tags::ExportAssets {
+          assets: vec![ast::NamedId {
+            id: 1,
+            name: String::from("Foo"),
+          }],
+        }),
+        10,
+        56,
+      ),
+      (
+        Tag::ImportAssets(ast::tags::ImportAssets {
+          url: String::from("lib.swf"),
+          assets: vec![ast::NamedId {
+            id: 2,
+            name: String::from("Bar"),
+          }],
+        }),
+        7,
+        57,
+      ),
+      (
+        Tag::ImportAssets(ast::tags::ImportAssets {
+          url: String::from("lib.swf"),
+          assets: Vec::new(),
+        }),
+        8,
+        71,
+      ),
+      (
+        Tag::SymbolClass(ast::tags::SymbolClass {
+          symbols: vec![ast::NamedId {
+            id: 0,
+            name: String::from("Main"),
+          }],
+        }),
+        10,
+        76,
+      ),
+      (
+        Tag::Metadata(ast::tags::Metadata {
+          metadata: String::from("<rdf:RDF/>"),
+        }),
+        10,
+        77,
+      ),
+      (
+        Tag::ScriptLimits(ast::tags::ScriptLimits {
+          max_recursion_depth: 256,
+          script_timeout: 15,
+        }),
+        10,
+        65,
+      ),
+      (Tag::SetTabIndex(ast::tags::SetTabIndex { depth: 3, index: 4 }), 10, 66),
+      (
+        Tag::RemoveObject(ast::tags::RemoveObject {
+          character_id: Some(1),
+          depth: 2,
+        }),
+        10,
+        5,
+      ),
+      (
+        Tag::RemoveObject(ast::tags::RemoveObject {
+          character_id: None,
+          depth: 2,
+        }),
+        10,
+        28,
+      ),
+      (
+        Tag::DefineSceneAndFrameLabelData(ast::tags::DefineSceneAndFrameLabelData {
+          scenes: vec![ast::control::Scene {
+            offset: 0,
+            name: String::from("Scene 1"),
+          }],
+          labels: vec![ast::control::Label {
+            frame: 200,
+            name: String::from("end"),
+          }],
+        }),
+        10,
+        86,
+      ),
+      (
+        Tag::DefineScalingGrid(ast::tags::DefineScalingGrid {
+          character_id: 1,
+          splitter: rect(100, 200),
+        }),
+        10,
+        78,
+      ),
+      (Tag::Telemetry(ast::tags::Telemetry { password: None }), 10, 93),
+      (
+        Tag::Telemetry(ast::tags::Telemetry {
+          password: Some(vec![7; 32]),
+        }),
+        10,
+        93,
+      ),
+      (
+        Tag::RawBody(ast::tags::RawBody {
+          code: 1000,
+          data: vec![1, 2, 3],
+        }),
+        10,
+        1000,
+      ),
+    ];
+    for (tag, swf_version, code) in tags {
+      assert_eq!(round_trip(tag, swf_version), code);
+    }
+  }
+
+  #[test]
+  fn test_emit_raw_tag() {
+    let data: Vec<u8> = vec![0x43, 0x02, 0xff];
+    let mut bytes: Vec<u8> = Vec::new();
+    emit_tag(&mut bytes, &Tag::Raw(ast::tags::Raw { data: data.clone() }), 10).unwrap();
+    assert_eq!(bytes, data);
+  }
+
+  #[test]
+  fn test_emit_action_and_data_tags() {
+    let tags: Vec<(Tag, u16)> = vec![
+      (Tag::DoAction(ast::tags::DoAction { actions: vec![0x07, 0x00] }), 12),
+      (
+        Tag::DoInitAction(ast::tags::DoInitAction {
+          sprite_id: 3,
+          actions: vec![0x07, 0x00],
+        }),
+        59,
+      ),
+      (
+        Tag::DoAbc(ast::tags::DoAbc {
+          header: None,
+          data: vec![0x10, 0x00, 0x2e, 0x00],
+        }),
+        72,
+      ),
+      (
+        Tag::DoAbc(ast::tags::DoAbc {
+          header: Some(ast::AbcHeader {
+            flags: 1,
+            name: String::from("frame1"),
+          }),
+          data: vec![0x10, 0x00, 0x2e, 0x00],
+        }),
+        82,
+      ),
+      (
+        Tag::DefineBinaryData(ast::tags::DefineBinaryData {
+          id: 1,
+          data: vec![0xff; 100],
+        }),
+        87,
+      ),
+      (Tag::DefineJpegTables(ast::tags::DefineJpegTables { data: vec![0xff, 0xd8, 0xff, 0xd9] }), 8),
+      (
+        Tag::DefineBitmap(ast::tags::DefineBitmap {
+          id: 1,
+          width: 2,
+          height: 3,
+          media_type: ast::ImageType::SwfLossless2,
+          data: vec![5, 2, 0, 3, 0, 0x78, 0x9c],
+        }),
+        36,
+      ),
+      (
+        Tag::DefineBitmap(ast::tags::DefineBitmap {
+          id: 1,
+          width: 2,
+          height: 3,
+          media_type: ast::ImageType::Png,
+          data: vec![
+            0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52, 0x00, 0x00,
+            0x00, 0x02, 0x00, 0x00, 0x00, 0x03,
+          ],
+        }),
+        21,
+      ),
+      (
+        Tag::VideoFrame(ast::tags::VideoFrame {
+          video_id: 1,
+          frame: 2,
+          packet: vec![1, 2, 3],
+        }),
+        61,
+      ),
+      (
+        Tag::DefineVideoStream(ast::tags::DefineVideoStream {
+          id: 1,
+          frame_count: 10,
+          width: 320,
+          height: 240,
+          use_smoothing: true,
+          deblocking: ast::VideoDeblocking::Level2,
+          codec: ast::VideoCodec::Vp6,
+        }),
+        60,
+      ),
+    ];
+    for (tag, code) in tags {
+      assert_eq!(round_trip(tag, 10), code);
+    }
+  }
+
+  #[test]
+  fn test_emit_sound_tags() {
+    let tags: Vec<(Tag, u16)> = vec![
+      (
+        Tag::DefineSound(ast::tags::DefineSound {
+          id: 1,
+          sound_type: ast::SoundType::Stereo,
+          sound_size: ast::SoundSize::SoundSize16,
+          sound_rate: ast::SoundRate::SoundRate44000,
+          format: ast::AudioCodingFormat::Mp3,
+          sample_count: 1152,
+          data: vec![0, 0, 0xff, 0xfb],
+        }),
+        14,
+      ),
+      (
+        Tag::StartSound(ast::tags::StartSound {
+          sound_id: 1,
+          sound_info: sound_info(),
+        }),
+        15,
+      ),
+      (
+        Tag::StartSound2(ast::tags::StartSound2 {
+          sound_class_name: String::from("Beep"),
+          sound_info: sound_info(),
+        }),
+        89,
+      ),
+      (
+        Tag::SoundStreamHead(ast::tags::SoundStreamHead {
+          playback_sound_type: ast::SoundType::Stereo,
+          playback_sound_size: ast::SoundSize::SoundSize16,
+          playback_sound_rate: ast::SoundRate::SoundRate44000,
+          stream_sound_type: ast::SoundType::Stereo,
+          stream_sound_size: ast::SoundSize::SoundSize16,
+          stream_sound_rate: ast::SoundRate::SoundRate44000,
+          stream_format: ast::AudioCodingFormat::Mp3,
+          stream_sample_count: 1470,
+          latency_seek: Some(-10),
+        }),
+        18,
+      ),
+      (
+        Tag::SoundStreamHead(ast::tags::SoundStreamHead {
+          playback_sound_type: ast::SoundType::Mono,
+          playback_sound_size: ast::SoundSize::SoundSize8,
+          playback_sound_rate: ast::SoundRate::SoundRate5500,
+          stream_sound_type: ast::SoundType::Mono,
+          stream_sound_size: ast::SoundSize::SoundSize8,
+          stream_sound_rate: ast::SoundRate::SoundRate5500,
+          stream_format: ast::AudioCodingFormat::UncompressedLittleEndian,
+          stream_sample_count: 100,
+          latency_seek: None,
+        }),
+        45,
+      ),
+      (Tag::SoundStreamBlock(ast::tags::SoundStreamBlock { data: vec![1, 2, 3] }), 19),
+      (
+        Tag::DefineButtonSound(ast::tags::DefineButtonSound {
+          button_id: 2,
+          over_up_to_idle: None,
+          idle_to_over_up: Some(ast::ButtonSound {
+            sound_id: 1,
+            sound_info: sound_info(),
+          }),
+          over_up_to_over_down: None,
+          over_down_to_over_up: None,
+        }),
+        17,
+      ),
+    ];
+    for (tag, code) in tags {
+      assert_eq!(round_trip(tag, 10), code);
+    }
+  }
+
+  #[test]
+  fn test_emit_shape_tags() {
+    let red = solid(rgba(255, 0, 0, 255));
+    let shape1 = ast::tags::DefineShape {
+      id: 1,
+      bounds: rect(400, 300),
+      edge_bounds: None,
+      has_fill_winding: false,
+      has_non_scaling_strokes: false,
+      has_scaling_strokes: false,
+      shape: ast::Shape {
+        initial_styles: ast::ShapeStyles {
+          fill: vec![red.clone()],
+          line: vec![line_style(solid(rgba(0, 0, 255, 255)))],
+        },
+        records: triangle_records(Some(1), Some(1)),
+      },
+    };
+    assert_eq!(round_trip(Tag::DefineShape(shape1.clone()), 10), 2);
+
+    let mut shape2 = shape1.clone();
+    shape2.shape.records.push(ast::ShapeRecord::StyleChange(ast::shape_records::StyleChange {
+      move_to: None,
+      left_fill: None,
+      right_fill: Some(1),
+      line_style: Some(0),
+      new_styles: Some(ast::ShapeStyles {
+        fill: vec![ast::FillStyle::Bitmap(ast::fill_styles::Bitmap {
+          bitmap_id: 2,
+          matrix: translate(10, 10),
+          repeating: false,
+          smoothed: true,
+        })],
+        line: Vec::new(),
+      }),
+    }));
+    shape2.shape.records.extend(triangle_records(None, None).into_iter().skip(1));
+    assert_eq!(round_trip(Tag::DefineShape(shape2), 10), 22);
+
+    let mut shape3 = shape1.clone();
+    shape3.shape.initial_styles.fill = vec![ast::FillStyle::LinearGradient(ast::fill_styles::LinearGradient {
+      matrix: ast::Matrix {
+        scale_x: Sfixed16P16::from_epsilons(0x8000),
+        rotate_skew0: Sfixed16P16::from_epsilons(-0x100),
+        ..translate(-50, 60)
+      },
+      gradient: ast::Gradient {
+        spread: ast::GradientSpread::Reflect,
+        color_space: ast::ColorSpace::LinearRgb,
+        colors: vec![
+          ast::ColorStop {
+            ratio: 0,
+            color: rgba(255, 0, 0, 128),
+          },
+          ast::ColorStop {
+            ratio: 255,
+            color: rgba(0, 255, 0, 255),
+          },
+        ],
+      },
+    })];
+    assert_eq!(round_trip(Tag::DefineShape(shape3), 10), 32);
+
+    let mut shape4 = shape1;
+    shape4.edge_bounds = Some(rect(410, 310));
+    shape4.has_non_scaling_strokes = true;
+    shape4.shape.initial_styles.line = vec![ast::LineStyle {
+      start_cap: ast::CapStyle::None,
+      end_cap: ast::CapStyle::Square,
+      join: ast::JoinStyle::Miter(ast::join_styles::Miter { limit: 3 }),
+      no_h_scale: true,
+      pixel_hinting: true,
+      ..line_style(ast::FillStyle::FocalGradient(ast::fill_styles::FocalGradient {
+        matrix: ast::Matrix::default(),
+        gradient: ast::Gradient {
+          spread: ast::GradientSpread::Pad,
+          color_space: ast::ColorSpace::SRgb,
+          colors: vec![ast::ColorStop {
+            ratio: 10,
+            color: rgba(1, 2, 3, 4),
+          }],
+        },
+        focal_point: Sfixed8P8::from_epsilons(-128),
+      }))
+    }];
+    assert_eq!(round_trip(Tag::DefineShape(shape4.clone()), 10), 83);
+
+    shape4.edge_bounds = None;
+    assert!(emit_tag(&mut Vec::new(), &Tag::DefineShape(shape4), 10).is_err());
+  }
+
+  #[test]
+  fn test_emit_morph_shape_tags() {
+    let morph_shape = ast::tags::DefineMorphShape {
+      id: 1,
+      bounds: rect(400, 300),
+      morph_bounds: rect(800, 600),
+      edge_bounds: None,
+      morph_edge_bounds: None,
+      has_scaling_strokes: false,
+      has_non_scaling_strokes: false,
+      shape: ast::MorphShape {
+        initial_styles: ast::MorphShapeStyles {
+          fill: vec![ast::MorphFillStyle::Solid(ast::fill_styles::MorphSolid {
+            color: rgba(255, 0, 0, 255),
+            morph_color: rgba(0, 0, 255, 128),
+          })],
+          line: vec![ast::MorphLineStyle {
+            width: 20,
+            morph_width: 40,
+            start_cap: ast::CapStyle::Round,
+            end_cap: ast::CapStyle::Round,
+            join: ast::JoinStyle::Round,
+            no_h_scale: false,
+            no_v_scale: false,
+            no_close: false,
+            pixel_hinting: false,
+            fill: ast::MorphFillStyle::Solid(ast::fill_styles::MorphSolid {
+              color: rgba(0, 0, 0, 255),
+              morph_color: rgba(0, 0, 0, 255),
+            }),
+          }],
+        },
+        records: vec![
+          ast::MorphShapeRecord::StyleChange(ast::shape_records::MorphStyleChange {
+            move_to: Some(ast::Vector2D { x: 10, y: 10 }),
+            morph_move_to: Some(ast::Vector2D { x: 20, y: 20 }),
+            left_fill: None,
+            right_fill: Some(1),
+            line_style: Some(1),
+            new_styles: None,
+          }),
+          ast::MorphShapeRecord::Edge(ast::shape_records::MorphEdge {
+            delta: ast::Vector2D { x: 400, y: 0 },
+            morph_delta: ast::Vector2D { x: 800, y: 0 },
+            control_delta: None,
+            morph_control_delta: None,
+          }),
+          ast::MorphShapeRecord::Edge(ast::shape_records::MorphEdge {
+            delta: ast::Vector2D { x: -400, y: 300 },
+            morph_delta: ast::Vector2D { x: -800, y: 600 },
+            control_delta: Some(ast::Vector2D { x: -100, y: 10 }),
+            morph_control_delta: Some(ast::Vector2D { x: -200, y: 20 }),
+          }),
+          ast::MorphShapeRecord::Edge(ast::shape_records::MorphEdge {
+            delta: ast::Vector2D { x: 0, y: -300 },
+            morph_delta: ast::Vector2D { x: 0, y: -600 },
+            control_delta: None,
+            morph_control_delta: None,
+          }),
+        ],
+      },
+    };
+    assert_eq!(round_trip(Tag::DefineMorphShape(morph_shape.clone()), 10), 46);
+
+    let morph_shape2 = ast::tags::DefineMorphShape {
+      edge_bounds: Some(rect(410, 310)),
+      morph_edge_bounds: Some(rect(810, 610)),
+      has_scaling_strokes: true,
+      ..morph_shape
+    };
+    assert_eq!(round_trip(Tag::DefineMorphShape(morph_shape2), 10), 84);
+  }
+
+  #[test]
+  fn test_emit_font_and_text_tags() {
+    let glyphs = vec![
+      ast::Glyph {
+        records: triangle_records(Some(1), None),
+      },
+      ast::Glyph {
+        records: vec![style_change(Some((0, 0)), Some(1), None), edge(100, 100, None), edge(-100, -100, None)],
+      },
+    ];
+    assert_eq!(
+      round_trip(
+        Tag::DefineGlyphFont(ast::tags::DefineGlyphFont {
+          id: 1,
+          glyphs: glyphs.clone()
+        }),
+        10
+      ),
+      10
+    );
+
+    let font = ast::tags::DefineFont {
+      id: 2,
+      font_name: String::from("Arial"),
+      is_bold: true,
+      is_italic: false,
+      is_ansi: false,
+      is_small: true,
+      is_shift_jis: false,
+      em_square_size: ast::text::EmSquareSize::EmSquareSize1024,
+      language: ast::LanguageCode::Latin,
+      glyphs: Some(glyphs),
+      code_units: Some(vec![0x41, 0x3042]),
+      layout: Some(ast::text::FontLayout {
+        ascent: 900,
+        descent: 200,
+        leading: 10,
+        advances: vec![500, 600],
+        bounds: vec![rect(400, 300), rect(100, 100)],
+        kerning: vec![ast::text::KerningRecord {
+          left: 0x41,
+          right: 0x3042,
+          adjustment: -20,
+        }],
+      }),
+    };
+    assert_eq!(round_trip(Tag::DefineFont(font.clone()), 5), 48);
+    let font3 = ast::tags::DefineFont {
+      em_square_size: ast::text::EmSquareSize::EmSquareSize20480,
+      layout: None,
+      ..font.clone()
+    };
+    assert_eq!(round_trip(Tag::DefineFont(font3), 10), 75);
+    let device_font = ast::tags::DefineFont {
+      glyphs: None,
+      code_units: None,
+      layout: None,
+      ..font
+    };
+    assert_eq!(round_trip(Tag::DefineFont(device_font), 10), 48);
+
+    let font_info = ast::tags::DefineFontInfo {
+      font_id: 1,
+      font_name: String::from("Arial"),
+      is_bold: false,
+      is_italic: true,
+      is_ansi: true,
+      is_shift_jis: false,
+      is_small: false,
+      language: ast::LanguageCode::Auto,
+      code_units: vec![0x41, 0x42],
+    };
+    assert_eq!(round_trip(Tag::DefineFontInfo(font_info.clone()), 10), 13);
+    let font_info2 = ast::tags::DefineFontInfo {
+      language: ast::LanguageCode::Japanese,
+      code_units: vec![0x41, 0x3042],
+      ..font_info
+    };
+    assert_eq!(round_trip(Tag::DefineFontInfo(font_info2), 10), 62);
+
+    let tags: Vec<(Tag, u16)> = vec![
+      (
+        Tag::DefineCffFont(ast::tags::DefineCffFont {
+          id: 3,
+          font_name: String::from("Arial"),
+          is_bold: false,
+          is_italic: true,
+          data: Some(vec![0x4f, 0x54, 0x54, 0x4f]),
+        }),
+        91,
+      ),
+      (
+        Tag::DefineFontName(ast::tags::DefineFontName {
+          font_id: 2,
+          name: String::from("Arial"),
+          copyright: String::from("(c)"),
+        }),
+        88,
+      ),
+      (
+        Tag::DefineFontAlignZones(ast::tags::DefineFontAlignZones {
+          font_id: 2,
+          csm_table_hint: ast::text::CsmTableHint::Medium,
+          zones: vec![ast::text::FontAlignmentZone {
+            data: vec![
+              ast::text::FontAlignmentZoneData {
+                origin: 0.5,
+                size: -2.0,
+              },
+              ast::text::FontAlignmentZoneData { origin: 0.0, size: 1.25 },
+            ],
+            has_x: true,
+            has_y: false,
+          }],
+        }),
+        73,
+      ),
+      (
+        Tag::CsmTextSettings(ast::tags::CsmTextSettings {
+          text_id: 4,
+          renderer: ast::text::TextRenderer::Advanced,
+          fitting: ast::text::GridFitting::SubPixel,
+          thickness: 1.5,
+          sharpness: -200.0,
+        }),
+        74,
+      ),
+    ];
+    for (tag, code) in tags {
+      assert_eq!(round_trip(tag, 10), code);
+    }
+
+    let text = ast::tags::DefineText {
+      id: 4,
+      bounds: rect(1000, 200),
+      matrix: translate(5, 5),
+      records: vec![
+        ast::text::TextRecord {
+          font_id: Some(2),
+          color: Some(rgba(0, 0, 0, 255)),
+          offset_x: 0,
+          offset_y: 180,
+          font_size: Some(240),
+          entries: vec![
+            ast::text::GlyphEntry { index: 0, advance: 120 },
+            ast::text::GlyphEntry { index: 1, advance: -3 },
+          ],
+        },
+        ast::text::TextRecord {
+          font_id: None,
+          color: None,
+          offset_x: 10,
+          offset_y: 0,
+          font_size: None,
+          entries: vec![ast::text::GlyphEntry { index: 1, advance: 0 }],
+        },
+      ],
+    };
+    assert_eq!(round_trip(Tag::DefineText(text.clone()), 10), 11);
+    let mut text2 = text;
+    text2.records[0].color = Some(rgba(0, 0, 0, 100));
+    assert_eq!(round_trip(Tag::DefineText(text2), 10), 33);
+
+    let dynamic_text = ast::tags::DefineDynamicText {
+      id: 5,
+      bounds: rect(1000, 200),
+      word_wrap: true,
+      multiline: true,
+      password: false,
+      readonly: true,
+      auto_size: false,
+      no_select: false,
+      border: true,
+      was_static: false,
+      html: true,
+      use_glyph_font: true,
+      font_id: Some(2),
+      font_class: None,
+      font_size: Some(240),
+      color: Some(rgba(1, 2, 3, 4)),
+      max_length: Some(20),
+      align: ast::text::TextAlignment::Center,
+      margin_left: 1,
+      margin_right: 2,
+      indent: 3,
+      leading: -4,
+      variable_name: Some(String::from("_root.name")),
+      text: Some(String::from("<p>Hello</p>")),
+    };
+    assert_eq!(round_trip(Tag::DefineDynamicText(dynamic_text.clone()), 10), 37);
+    let plain_dynamic_text = ast::tags::DefineDynamicText {
+      font_id: None,
+      font_class: Some(String::from("Arial")),
+      font_size: None,
+      color: None,
+      max_length: None,
+      align: ast::text::TextAlignment::Left,
+      margin_left: 0,
+      margin_right: 0,
+      indent: 0,
+      leading: 0,
+      variable_name: None,
+      text: None,
+      ..dynamic_text
+    };
+    assert_eq!(round_trip(Tag::DefineDynamicText(plain_dynamic_text), 10), 37);
+  }
+
+  #[test]
+  fn test_emit_button_tags() {
+    let record = ast::ButtonRecord {
+      state_up: true,
+      state_over: true,
+      state_down: false,
+      state_hit_test: true,
+      character_id: 1,
+      depth: 1,
+      matrix: translate(10, 20),
+      color_transform: ast::ColorTransformWithAlpha::default(),
+      filters: Vec::new(),
+      blend_mode: ast::BlendMode::Normal,
+    };
+    let button1 = ast::tags::DefineButton {
+      id: 2,
+      track_as_menu: false,
+      records: vec![record.clone()],
+      actions: vec![ast::ButtonCondAction {
+        conditions: None,
+        actions: vec![0x07, 0x00],
+      }],
+    };
+    assert_eq!(round_trip(Tag::DefineButton(button1), 10), 7);
+
+    let cond = ast::ButtonCond {
+      idle_to_over_up: false,
+      over_up_to_idle: false,
+      over_up_to_over_down: false,
+      over_down_to_over_up: true,
+      over_down_to_out_down: false,
+      out_down_to_over_down: false,
+      out_down_to_idle: false,
+      idle_to_over_down: false,
+      over_down_to_idle: false,
+      key_press: None,
+    };
+    let button2 = ast::tags::DefineButton {
+      id: 2,
+      track_as_menu: true,
+      records: vec![ast::ButtonRecord {
+        color_transform: ast::ColorTransformWithAlpha {
+          alpha_mult: Sfixed8P8::from_epsilons(128),
+          red_add: -255,
+          ..ast::ColorTransformWithAlpha::default()
+        },
+        filters: vec![ast::Filter::Blur(ast::filters::Blur {
+          blur_x: Sfixed16P16::from_epsilons(0x40000),
+          blur_y: Sfixed16P16::from_epsilons(0x20000),
+          passes: 2,
+        })],
+        blend_mode: ast::BlendMode::Multiply,
+        ..record
+      }],
+      actions: vec![
+        ast::ButtonCondAction {
+          conditions: Some(cond),
+          actions: vec![0x06, 0x00],
+        },
+        ast::ButtonCondAction {
+          conditions: Some(ast::ButtonCond {
+            key_press: Some(13),
+            ..cond
+          }),
+          actions: vec![0x07, 0x00],
+        },
+      ],
+    };
+    assert_eq!(round_trip(Tag::DefineButton(button2.clone()), 10), 34);
+    let button2_without_actions = ast::tags::DefineButton {
+      actions: Vec::new(),
+      ..button2
+    };
+    assert_eq!(round_trip(Tag::DefineButton(button2_without_actions), 10), 34);
+
+    let color_transform = ast::tags::DefineButtonColorTransform {
+      button_id: 2,
+      transform: ast::ColorTransform {
+        red_mult: Sfixed8P8::from_epsilons(-256),
+        red_add: 10,
+        ..ast::ColorTransform::default()
+      },
+    };
+    assert_eq!(round_trip(Tag::DefineButtonColorTransform(color_transform), 10), 23);
+  }
+
+  #[test]
+  fn test_emit_place_object_tags() {
+    let place_object1 = ast::tags::PlaceObject {
+      color_transform: Some(ast::ColorTransformWithAlpha {
+        green_mult: Sfixed8P8::from_epsilons(512),
+        ..ast::ColorTransformWithAlpha::default()
+      }),
+      ..place_object(1)
+    };
+    assert_eq!(round_trip(Tag::PlaceObject(place_object1.clone()), 2), 4);
+    assert_eq!(round_trip(Tag::PlaceObject(place_object1), 10), 26);
+
+    let place_object2 = ast::tags::PlaceObject {
+      is_update: true,
+      character_id: None,
+      ratio: Some(100),
+      name: Some(String::from("clip")),
+      clip_depth: Some(5),
+      clip_actions: Some(vec![
+        ast::ClipAction {
+          events: ast::ClipEventFlags {
+            enter_frame: true,
+            ..clip_event_flags()
+          },
+          key_code: None,
+          actions: vec![0x07, 0x00],
+        },
+        ast::ClipAction {
+          events: ast::ClipEventFlags {
+            key_press: true,
+            construct: true,
+            ..clip_event_flags()
+          },
+          key_code: Some(65),
+          actions: vec![0x06, 0x00],
+        },
+      ]),
+      ..place_object(2)
+    };
+    assert_eq!(round_trip(Tag::PlaceObject(place_object2), 10), 26);
+
+    let place_object3 = ast::tags::PlaceObject {
+      class_name: Some(String::from("Foo")),
+      filters: Some(vec![ast::Filter::DropShadow(ast::filters::DropShadow {
+        color: rgba(0, 0, 0, 128),
+        blur_x: Sfixed16P16::from_epsilons(0x40000),
+        blur_y: Sfixed16P16::from_epsilons(0x40000),
+        angle: Sfixed16P16::from_epsilons(0xc90f),
+        distance: Sfixed16P16::from_epsilons(0x40000),
+        strength: Sfixed8P8::from_epsilons(256),
+        inner: false,
+        knockout: true,
+        composite_source: true,
+        passes: 1,
+      })]),
+      blend_mode: Some(ast::BlendMode::Screen),
+      bitmap_cache: Some(true),
+      visible: Some(false),
+      background_color: Some(rgba(255, 255, 255, 0)),
+      ..place_object(3)
+    };
+    assert_eq!(round_trip(Tag::PlaceObject(place_object3), 10), 70);
+  }
+
+  #[test]
+  fn test_emit_define_sprite() {
+    let sprite = ast::tags::DefineSprite {
+      id: 3,
+      frame_count: 2,
+      tags: vec![
+        Tag::PlaceObject(place_object(1)),
+        Tag::ShowFrame,
+        Tag::DoAction(ast::tags::DoAction { actions: vec![0x07, 0x00] }),
+        Tag::ShowFrame,
+      ],
+    };
+    assert_eq!(round_trip(Tag::DefineSprite(sprite), 10), 39);
+  }
+}
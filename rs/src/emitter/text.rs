@@ -0,0 +1,270 @@
+use crate::emitter::base::{get_i32_max_bit_count, get_u32_bit_count, invalid_value, to_u16, to_u8, BitsWriter};
+use crate::emitter::basic_data_types::{emit_le_f16, emit_opaque_s_rgba8, emit_rect, emit_straight_s_rgba8};
+use crate::emitter::shape::emit_glyph;
+use std::convert::TryFrom;
+use std::io;
+use swf_types as swf;
+
+pub(crate) fn get_grid_fitting_code(value: swf::text::GridFitting) -> u8 {
+  match value {
+    swf::text::GridFitting::None => 0,
+    swf::text::GridFitting::Pixel => 1,
+    swf::text::GridFitting::SubPixel => 2,
+  }
+}
+
+pub(crate) fn get_csm_table_hint_code(value: swf::text::CsmTableHint) -> u8 {
+  match value {
+    swf::text::CsmTableHint::Thin => 0,
+    swf::text::CsmTableHint::Medium => 1,
+    swf::text::CsmTableHint::Thick => 2,
+  }
+}
+
+pub(crate) fn get_text_renderer_code(value: swf::text::TextRenderer) -> u8 {
+  match value {
+    swf::text::TextRenderer::Normal => 0,
+    swf::text::TextRenderer::Advanced => 1,
+  }
+}
+
+pub fn emit_font_alignment_zone<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::text::FontAlignmentZone,
+) -> io::Result<()> {
+  writer.write_all(&[to_u8(value.data.len(), "too many font alignment zone data")?])?;
+  for data in &value.data {
+    emit_font_alignment_zone_data(writer, data)?;
+  }
+  #[allow(clippy::identity_op)]
+  let flags: u8 = 0 | (if value.has_x { 1 << 0 } else { 0 }) | (if value.has_y { 1 << 1 } else { 0 });
+  writer.write_all(&[flags])
+}
+
+pub fn emit_font_alignment_zone_data<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::text::FontAlignmentZoneData,
+) -> io::Result<()> {
+  emit_le_f16(writer, value.origin)?;
+  emit_le_f16(writer, value.size)
+}
+
+/// Returns the number of bits to encode the glyph indices and advances of `records`
+pub(crate) fn get_glyph_entry_bits(records: &[swf::text::TextRecord]) -> io::Result<(u32, u32)> {
+  let entries = records.iter().flat_map(|record| record.entries.iter());
+  let mut index_bits: u32 = 0;
+  let mut advance_bits: u32 = 0;
+  for entry in entries {
+    let index = u32::try_from(entry.index).map_err(|_| invalid_value("glyph index too large"))?;
+    index_bits = index_bits.max(get_u32_bit_count(index));
+    advance_bits = advance_bits.max(get_i32_max_bit_count(&[entry.advance]));
+  }
+  Ok((index_bits, advance_bits))
+}
+
+pub fn emit_text_record_string<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &[swf::text::TextRecord],
+  has_alpha: bool,
+  index_bits: u32,
+  advance_bits: u32,
+) -> io::Result<()> {
+  for record in value {
+    emit_text_record(writer, record, has_alpha, index_bits, advance_bits)?;
+  }
+  writer.write_all(&[0])
+}
+
+pub fn emit_text_record<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::text::TextRecord,
+  has_alpha: bool,
+  index_bits: u32,
+  advance_bits: u32,
+) -> io::Result<()> {
+  let has_font = value.font_id.is_some();
+  if has_font != value.font_size.is_some() {
+    return Err(invalid_value("text record font id and size must be set together"));
+  }
+  let has_offset_x = value.offset_x != 0;
+  let has_offset_y = value.offset_y != 0;
+  let has_color = value.color.is_some();
+  // Bit 7 is the record type, always set for text records
+  #[allow(clippy::identity_op)]
+  let flags: u8 = 0
+    | (if has_offset_x { 1 << 0 } else { 0 })
+    | (if has_offset_y { 1 << 1 } else { 0 })
+    | (if has_color { 1 << 2 } else { 0 })
+    | (if has_font { 1 << 3 } else { 0 })
+    | (1 << 7);
+  writer.write_all(&[flags])?;
+  if let Some(font_id) = value.font_id {
+    writer.write_all(&font_id.to_le_bytes())?;
+  }
+  if let Some(color) = value.color {
+    if has_alpha {
+      emit_straight_s_rgba8(writer, color)?;
+    } else {
+      emit_opaque_s_rgba8(writer, color)?;
+    }
+  }
+  if has_offset_x {
+    writer.write_all(&value.offset_x.to_le_bytes())?;
+  }
+  if has_offset_y {
+    writer.write_all(&value.offset_y.to_le_bytes())?;
+  }
+  if let Some(font_size) = value.font_size {
+    writer.write_all(&font_size.to_le_bytes())?;
+  }
+  writer.write_all(&[to_u8(value.entries.len(), "too many glyph entries in text record")?])?;
+  let mut bits = BitsWriter::new(writer);
+  for entry in &value.entries {
+    bits.write_u32_bits(index_bits, entry.index as u32)?;
+    bits.write_i32_bits(advance_bits, entry.advance)?;
+  }
+  bits.finish()
+}
+
+/// Emits the glyph offset table followed by the glyphs.
+///
+/// The offsets are relative to the start of the table, the end offset is emitted after the glyph
+/// offsets. Returns an error if the offsets require `use_wide_offsets`.
+pub fn emit_offset_glyphs<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &[swf::Glyph],
+  use_wide_offsets: bool,
+) -> io::Result<()> {
+  let (offsets, glyph_bytes) = get_offset_glyphs(value, use_wide_offsets)?;
+  for offset in offsets {
+    if use_wide_offsets {
+      writer.write_all(&offset.to_le_bytes())?;
+    } else {
+      let offset = to_u16(offset as usize, "glyph offset requires wide offsets")?;
+      writer.write_all(&offset.to_le_bytes())?;
+    }
+  }
+  writer.write_all(&glyph_bytes)
+}
+
+/// Returns the glyph offsets (including the end offset) and the emitted glyphs
+fn get_offset_glyphs(value: &[swf::Glyph], use_wide_offsets: bool) -> io::Result<(Vec<u32>, Vec<u8>)> {
+  let offset_size: usize = if use_wide_offsets { 4 } else { 2 };
+  let table_size = (value.len() + 1) * offset_size;
+  let mut glyph_bytes: Vec<u8> = Vec::new();
+  let mut offsets: Vec<u32> = Vec::with_capacity(value.len() + 1);
+  for glyph in value {
+    offsets.push(u32::try_from(table_size + glyph_bytes.len()).map_err(|_| invalid_value("font too large"))?);
+    emit_glyph(&mut glyph_bytes, glyph)?;
+  }
+  offsets.push(u32::try_from(table_size + glyph_bytes.len()).map_err(|_| invalid_value("font too large"))?);
+  Ok((offsets, glyph_bytes))
+}
+
+/// Checks if the glyph offset table of `value` requires 32-bit offsets
+pub(crate) fn requires_wide_offsets(value: &[swf::Glyph]) -> io::Result<bool> {
+  let (offsets, _) = get_offset_glyphs(value, false)?;
+  Ok(offsets.last().map_or(false, |end_offset| *end_offset > u32::from(u16::MAX)))
+}
+
+pub fn emit_kerning_record<W: io::Write + ?Sized>(writer: &mut W, value: &swf::text::KerningRecord) -> io::Result<()> {
+  writer.write_all(&value.left.to_le_bytes())?;
+  writer.write_all(&value.right.to_le_bytes())?;
+  writer.write_all(&value.adjustment.to_le_bytes())
+}
+
+pub fn emit_font_layout<W: io::Write + ?Sized>(
+  writer: &mut W,
+  value: &swf::text::FontLayout,
+  glyph_count: usize,
+) -> io::Result<()> {
+  if value.advances.len() != glyph_count || value.bounds.len() != glyph_count {
+    return Err(invalid_value("font layout must have one advance and bound per glyph"));
+  }
+  writer.write_all(&value.ascent.to_le_bytes())?;
+  writer.write_all(&value.descent.to_le_bytes())?;
+  writer.write_all(&value.leading.to_le_bytes())?;
+  for advance in &value.advances {
+    writer.write_all(&advance.to_le_bytes())?;
+  }
+  for bounds in &value.bounds {
+    emit_rect(writer, bounds)?;
+  }
+  writer.write_all(&to_u16(value.kerning.len(), "too many kerning records")?.to_le_bytes())?;
+  for kerning_record in &value.kerning {
+    emit_kerning_record(writer, kerning_record)?;
+  }
+  Ok(())
+}
+
+pub fn emit_text_alignment<W: io::Write + ?Sized>(writer: &mut W, value: swf::text::TextAlignment) -> io::Result<()> {
+  let code: u8 = match value {
+    swf::text::TextAlignment::Left => 0,
+    swf::text::TextAlignment::Right => 1,
+    swf::text::TextAlignment::Center => 2,
+    swf::text::TextAlignment::Justify => 3,
+  };
+  writer.write_all(&[code])
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::complete::text::parse_text_record_string;
+
+  #[test]
+  fn test_emit_text_record_string() {
+    let records = vec![
+      swf::text::TextRecord {
+        font_id: Some(1),
+        color: Some(swf::StraightSRgba8 { r: 1, g: 2, b: 3, a: 255 }),
+        offset_x: 0,
+        offset_y: 240,
+        font_size: Some(480),
+        entries: vec![
+          swf::text::GlyphEntry { index: 0, advance: 200 },
+          swf::text::GlyphEntry { index: 5, advance: -3 },
+        ],
+      },
+      swf::text::TextRecord {
+        font_id: None,
+        color: None,
+        offset_x: -20,
+        offset_y: 0,
+        font_size: None,
+        entries: vec![swf::text::GlyphEntry { index: 1, advance: 0 }],
+      },
+    ];
+    let (index_bits, advance_bits) = get_glyph_entry_bits(&records).unwrap();
+    assert_eq!((index_bits, advance_bits), (3, 9));
+    for has_alpha in [false, true] {
+      let mut out: Vec<u8> = Vec::new();
+      emit_text_record_string(&mut out, &records, has_alpha, index_bits, advance_bits).unwrap();
+      assert_eq!(
+        parse_text_record_string(&out, has_alpha, index_bits as usize, advance_bits as usize),
+        Ok((&[][..], records.clone()))
+      );
+    }
+
+    // `DefineText` colors are opaque
+    let mut translucent = records;
+    translucent[0].color = Some(swf::StraightSRgba8 { r: 1, g: 2, b: 3, a: 128 });
+    let mut out: Vec<u8> = Vec::new();
+    assert!(emit_text_record_string(&mut out, &translucent, false, index_bits, advance_bits).is_err());
+    out.clear();
+    assert!(emit_text_record_string(&mut out, &translucent, true, index_bits, advance_bits).is_ok());
+  }
+
+  #[test]
+  fn test_emit_text_record_without_font_size() {
+    let record = swf::text::TextRecord {
+      font_id: Some(1),
+      color: None,
+      offset_x: 0,
+      offset_y: 0,
+      font_size: None,
+      entries: Vec::new(),
+    };
+    assert!(emit_text_record(&mut Vec::new(), &record, false, 0, 0).is_err());
+  }
+}
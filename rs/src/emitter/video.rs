@@ -0,0 +1,25 @@
+use swf_types as ast;
+
+pub(crate) fn get_video_deblocking_code(value: ast::VideoDeblocking) -> u8 {
+  match value {
+    ast::VideoDeblocking::PacketValue => 0,
+    ast::VideoDeblocking::Off => 1,
+    ast::VideoDeblocking::Level1 => 2,
+    ast::VideoDeblocking::Level2 => 3,
+    ast::VideoDeblocking::Level3 => 4,
+    ast::VideoDeblocking::Level4 => 5,
+  }
+}
+
+pub(crate) fn get_video_codec_code(value: ast::VideoCodec) -> u8 {
+  match value {
+    ast::VideoCodec::None => 0,
+    ast::VideoCodec::Jpeg => 1,
+    ast::VideoCodec::Sorenson => 2,
+    ast::VideoCodec::Screen => 3,
+    ast::VideoCodec::Vp6 => 4,
+    ast::VideoCodec::Vp6Alpha => 5,
+    ast::VideoCodec::Screen2 => 6,
+    ast::VideoCodec::Avc => 7,
+  }
+}
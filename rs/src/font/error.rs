@@ -0,0 +1,29 @@
+use std::fmt;
+
+/// Error when exporting an embedded font.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FontExportError {
+  /// Indicates that the font has no glyphs: it only references a device font by name.
+  MissingGlyphs(u16),
+
+  /// Indicates that the font has more glyphs than a TrueType font can hold (65535, including `.notdef`).
+  TooManyGlyphs(usize),
+
+  /// Indicates that the embedded font file is corrupted, truncated or not of the expected type.
+  InvalidFontData(String),
+}
+
+impl std::error::Error for FontExportError {}
+
+impl fmt::Display for FontExportError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      FontExportError::MissingGlyphs(id) => write!(f, "font {} has no glyphs (device font)", id),
+      FontExportError::TooManyGlyphs(count) => write!(f, "too many glyphs for a TrueType font: {}", count),
+      FontExportError::InvalidFontData(msg) => {
+        f.write_str("invalid font data: ")?;
+        f.write_str(msg)
+      }
+    }
+  }
+}
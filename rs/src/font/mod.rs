@@ -0,0 +1,8 @@
+pub(crate) mod error;
+pub(crate) mod opentype;
+pub(crate) mod sfnt;
+pub(crate) mod truetype;
+
+pub use error::FontExportError;
+pub use opentype::{cff_font_to_otf, read_opentype_info, OpenTypeInfo};
+pub use truetype::font_to_ttf;
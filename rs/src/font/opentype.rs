@@ -0,0 +1,302 @@
+use crate::font::error::FontExportError;
+use swf_types as ast;
+
+/// Version of fonts with CFF outlines (`OTTO`).
+const SFNT_VERSION_CFF: u32 = 0x4f54_544f;
+
+/// Table directory and main properties of an OpenType font file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpenTypeInfo {
+  /// Tags of the tables, in directory order.
+  pub tables: Vec<String>,
+  /// Full name of the font (or its family name), from the `name` table.
+  pub name: Option<String>,
+  /// Number of glyphs, from the `maxp` table.
+  pub glyph_count: u16,
+  /// Sorted Unicode code points mapped to a glyph by the `cmap` table.
+  pub code_points: Vec<u32>,
+}
+
+fn invalid(msg: &str) -> FontExportError {
+  FontExportError::InvalidFontData(String::from(msg))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, FontExportError> {
+  match data.get(offset..offset + 2) {
+    Some(bytes) => Ok(u16::from_be_bytes([bytes[0], bytes[1]])),
+    None => Err(invalid("unexpected end of table")),
+  }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, FontExportError> {
+  match data.get(offset..offset + 4) {
+    Some(bytes) => Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+    None => Err(invalid("unexpected end of table")),
+  }
+}
+
+/// Reads the table directory of an OpenType font with CFF outlines, such as the font of a `DefineFont4` tag.
+///
+/// The font must contain the `CFF ` (or `CFF2`), `cmap` and `maxp` tables, and all the tables must be inside the
+/// file. The code points are read from the Unicode `cmap` subtables of format 4 or 12.
+pub fn read_opentype_info(data: &[u8]) -> Result<OpenTypeInfo, FontExportError> {
+  if read_u32(data, 0)? != SFNT_VERSION_CFF {
+    return Err(invalid("not an OpenType font with CFF outlines"));
+  }
+  let table_count = usize::from(read_u16(data, 4)?);
+  let mut tables: Vec<(String, &[u8])> = Vec::with_capacity(table_count);
+  for index in 0..table_count {
+    let record = 12 + 16 * index;
+    let tag = data.get(record..record + 4).ok_or_else(|| invalid("incomplete table directory"))?;
+    let offset = read_u32(data, record + 8)? as usize;
+    let length = read_u32(data, record + 12)? as usize;
+    let table = offset
+      .checked_add(length)
+      .and_then(|end| data.get(offset..end))
+      .ok_or_else(|| invalid("table outside of the font file"))?;
+    tables.push((tag.iter().map(|byte| char::from(*byte)).collect(), table));
+  }
+  let find = |tag: &str| tables.iter().find(|table| table.0 == tag).map(|table| table.1);
+  if find("CFF ").is_none() && find("CFF2").is_none() {
+    return Err(invalid("missing `CFF ` table"));
+  }
+  let maxp = find("maxp").ok_or_else(|| invalid("missing `maxp` table"))?;
+  let cmap = find("cmap").ok_or_else(|| invalid("missing `cmap` table"))?;
+  Ok(OpenTypeInfo {
+    name: match find("name") {
+      Some(name) => read_font_name(name)?,
+      None => None,
+    },
+    glyph_count: read_u16(maxp, 4)?,
+    code_points: read_code_points(cmap)?,
+    tables: tables.into_iter().map(|table| table.0).collect(),
+  })
+}
+
+/// Returns the validated OpenType font of a `DefineFont4` tag, ready to be written to an `.otf` file.
+pub fn cff_font_to_otf(font: &ast::tags::DefineCffFont) -> Result<Vec<u8>, FontExportError> {
+  match &font.data {
+    Some(data) => {
+      read_opentype_info(data)?;
+      Ok(data.clone())
+    }
+    None => Err(FontExportError::MissingGlyphs(font.id)),
+  }
+}
+
+/// Reads the full name (ID 4) or else the family name (ID 1) of the font, in English if available.
+fn read_font_name(name: &[u8]) -> Result<Option<String>, FontExportError> {
+  let count = usize::from(read_u16(name, 2)?);
+  let strings = usize::from(read_u16(name, 4)?);
+  let mut best: Option<(u8, String)> = None;
+  for index in 0..count {
+    let record = 6 + 12 * index;
+    let platform = read_u16(name, record)?;
+    let encoding = read_u16(name, record + 2)?;
+    let language = read_u16(name, record + 4)?;
+    let name_id = read_u16(name, record + 6)?;
+    let length = usize::from(read_u16(name, record + 8)?);
+    let offset = strings + usize::from(read_u16(name, record + 10)?);
+    let bytes = name
+      .get(offset..offset + length)
+      .ok_or_else(|| invalid("name outside of the `name` table"))?;
+    let value: String = match (platform, encoding) {
+      (0, _) | (3, 0) | (3, 1) | (3, 10) => {
+        let code_units: Vec<u16> = bytes.chunks_exact(2).map(|unit| u16::from_be_bytes([unit[0], unit[1]])).collect();
+        String::from_utf16_lossy(&code_units)
+      }
+      // Mac Roman, only its ASCII subset is decoded
+      (1, 0) => bytes
+        .iter()
+        .map(|byte| if byte.is_ascii() { char::from(*byte) } else { char::REPLACEMENT_CHARACTER })
+        .collect(),
+      _ => continue,
+    };
+    let english = (platform == 3 && language == 0x0409) || (platform == 1 && language == 0);
+    let rank = match name_id {
+      4 => 2,
+      1 => 0,
+      _ => continue,
+    } + u8::from(english);
+    if best.as_ref().map_or(true, |(best_rank, _)| rank > *best_rank) {
+      best = Some((rank, value));
+    }
+  }
+  Ok(best.map(|(_, value)| value))
+}
+
+/// Reads the code points of the best Unicode subtable: format 12 (full repertoire) over format 4 (BMP).
+fn read_code_points(cmap: &[u8]) -> Result<Vec<u32>, FontExportError> {
+  let count = usize::from(read_u16(cmap, 2)?);
+  let mut best: Option<(u16, usize)> = None;
+  for index in 0..count {
+    let record = 4 + 8 * index;
+    let platform = read_u16(cmap, record)?;
+    let encoding = read_u16(cmap, record + 2)?;
+    let offset = read_u32(cmap, record + 4)? as usize;
+    if !matches!((platform, encoding), (0, _) | (3, 1) | (3, 10)) {
+      continue;
+    }
+    let format = read_u16(cmap, offset)?;
+    if (format == 4 || format == 12) && best.map_or(true, |(best_format, _)| format > best_format) {
+      best = Some((format, offset));
+    }
+  }
+  let mut code_points: Vec<u32> = match best {
+    Some((4, offset)) => read_format4_code_points(cmap, offset)?,
+    Some((_, offset)) => read_format12_code_points(cmap, offset)?,
+    None => return Err(invalid("missing Unicode `cmap` subtable")),
+  };
+  code_points.sort_unstable();
+  code_points.dedup();
+  Ok(code_points)
+}
+
+fn read_format4_code_points(cmap: &[u8], offset: usize) -> Result<Vec<u32>, FontExportError> {
+  let segment_count = usize::from(read_u16(cmap, offset + 6)? / 2);
+  let end_codes = offset + 14;
+  let start_codes = end_codes + 2 * segment_count + 2;
+  let deltas = start_codes + 2 * segment_count;
+  let range_offsets = deltas + 2 * segment_count;
+  let mut code_points: Vec<u32> = Vec::new();
+  for segment in 0..segment_count {
+    let end = read_u16(cmap, end_codes + 2 * segment)?;
+    let start = read_u16(cmap, start_codes + 2 * segment)?;
+    let delta = read_u16(cmap, deltas + 2 * segment)?;
+    let range_offset_position = range_offsets + 2 * segment;
+    let range_offset = usize::from(read_u16(cmap, range_offset_position)?);
+    if start > end {
+      return Err(invalid("invalid `cmap` segment"));
+    }
+    for code in start..=end {
+      let glyph = if range_offset == 0 {
+        code.wrapping_add(delta)
+      } else {
+        let position = range_offset_position + range_offset + 2 * usize::from(code - start);
+        match read_u16(cmap, position)? {
+          0 => 0,
+          glyph => glyph.wrapping_add(delta),
+        }
+      };
+      // The last segment maps `0xffff` to `.notdef`
+      if glyph != 0 && code != 0xffff {
+        code_points.push(u32::from(code));
+      }
+    }
+  }
+  Ok(code_points)
+}
+
+fn read_format12_code_points(cmap: &[u8], offset: usize) -> Result<Vec<u32>, FontExportError> {
+  let group_count = read_u32(cmap, offset + 12)? as usize;
+  let mut code_points: Vec<u32> = Vec::new();
+  for group in 0..group_count {
+    let record = offset + 16 + 12 * group;
+    let start = read_u32(cmap, record)?;
+    let end = read_u32(cmap, record + 4)?.min(0x10ffff);
+    let start_glyph = read_u32(cmap, record + 8)?;
+    if start > end {
+      return Err(invalid("invalid `cmap` group"));
+    }
+    // A group starting at `.notdef` doesn't map its first code point
+    let first = if start_glyph == 0 { start + 1 } else { start };
+    code_points.extend(first..=end);
+  }
+  Ok(code_points)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::font::sfnt::{put_u16, put_u32, write_sfnt};
+  use crate::font::truetype::write_cmap;
+  use std::collections::BTreeMap;
+
+  fn cff_font_data() -> Vec<u8> {
+    let mut maxp: Vec<u8> = Vec::new();
+    put_u32(&mut maxp, 0x0000_5000);
+    put_u16(&mut maxp, 3);
+    let mut name: Vec<u8> = Vec::new();
+    put_u16(&mut name, 0);
+    put_u16(&mut name, 2);
+    put_u16(&mut name, 6 + 2 * 12);
+    for (name_id, length, offset) in [(1, 8, 0), (4, 18, 8)] {
+      for value in [3, 1, 0x0409, name_id, length, offset] {
+        put_u16(&mut name, value);
+      }
+    }
+    for code_unit in "TestTest Bold".encode_utf16() {
+      put_u16(&mut name, code_unit);
+    }
+    let char_map: BTreeMap<u16, u16> = [(0x41, 1), (0x42, 2)].into_iter().collect();
+    write_sfnt(
+      SFNT_VERSION_CFF,
+      vec![
+        (*b"CFF ", vec![1, 0, 4, 1]),
+        (*b"cmap", write_cmap(&char_map)),
+        (*b"maxp", maxp),
+        (*b"name", name),
+      ],
+    )
+  }
+
+  #[test]
+  fn test_read_opentype_info() {
+    assert_eq!(
+      read_opentype_info(&cff_font_data()),
+      Ok(OpenTypeInfo {
+        tables: vec![
+          String::from("CFF "),
+          String::from("cmap"),
+          String::from("maxp"),
+          String::from("name"),
+        ],
+        name: Some(String::from("Test Bold")),
+        glyph_count: 3,
+        code_points: vec![0x41, 0x42],
+      })
+    );
+  }
+
+  #[test]
+  fn test_cff_font_to_otf() {
+    let mut font = ast::tags::DefineCffFont {
+      id: 2,
+      font_name: String::from("Test"),
+      is_bold: true,
+      is_italic: false,
+      data: Some(cff_font_data()),
+    };
+    assert_eq!(cff_font_to_otf(&font), Ok(cff_font_data()));
+    font.data.as_mut().unwrap().truncate(40);
+    assert_eq!(
+      cff_font_to_otf(&font),
+      Err(FontExportError::InvalidFontData(String::from("table outside of the font file")))
+    );
+    font.data = None;
+    assert_eq!(cff_font_to_otf(&font), Err(FontExportError::MissingGlyphs(2)));
+  }
+
+  #[test]
+  fn test_read_truetype_info() {
+    let font = ast::tags::DefineFont {
+      id: 1,
+      font_name: String::from("Test"),
+      is_bold: false,
+      is_italic: false,
+      is_ansi: false,
+      is_small: false,
+      is_shift_jis: false,
+      em_square_size: ast::text::EmSquareSize::EmSquareSize1024,
+      language: ast::LanguageCode::Auto,
+      glyphs: Some(Vec::new()),
+      code_units: Some(Vec::new()),
+      layout: None,
+    };
+    let data = crate::font::font_to_ttf(&font).unwrap();
+    assert_eq!(
+      read_opentype_info(&data),
+      Err(FontExportError::InvalidFontData(String::from("not an OpenType font with CFF outlines")))
+    );
+  }
+}
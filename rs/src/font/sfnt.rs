@@ -0,0 +1,79 @@
+/// Version of fonts with TrueType outlines.
+pub(crate) const SFNT_VERSION_TRUETYPE: u32 = 0x0001_0000;
+
+/// Target of the `checkSumAdjustment` field of the `head` table: the checksum of the whole font file.
+const CHECKSUM_MAGIC: u32 = 0xb1b0_afba;
+
+pub(crate) fn put_u8(out: &mut Vec<u8>, value: u8) {
+  out.push(value);
+}
+
+pub(crate) fn put_u16(out: &mut Vec<u8>, value: u16) {
+  out.extend_from_slice(&value.to_be_bytes());
+}
+
+pub(crate) fn put_i16(out: &mut Vec<u8>, value: i16) {
+  out.extend_from_slice(&value.to_be_bytes());
+}
+
+pub(crate) fn put_u32(out: &mut Vec<u8>, value: u32) {
+  out.extend_from_slice(&value.to_be_bytes());
+}
+
+/// Returns the `searchRange`, `entrySelector` and `rangeShift` fields of a binary search header.
+pub(crate) fn search_params(count: u16, item_size: u16) -> (u16, u16, u16) {
+  let mut power: u16 = 1;
+  let mut selector: u16 = 0;
+  while power.saturating_mul(2) <= count {
+    power *= 2;
+    selector += 1;
+  }
+  let range = power.wrapping_mul(item_size);
+  (range, selector, count.wrapping_mul(item_size).wrapping_sub(range))
+}
+
+/// Sum of the big-endian 32-bit words of the data, padded with zeros.
+pub(crate) fn checksum(data: &[u8]) -> u32 {
+  data.chunks(4).fold(0u32, |sum, chunk| {
+    let mut word = [0u8; 4];
+    word[..chunk.len()].copy_from_slice(chunk);
+    sum.wrapping_add(u32::from_be_bytes(word))
+  })
+}
+
+/// Writes a font file from its tables.
+///
+/// The tables are sorted by tag and aligned on 4 bytes. The `checkSumAdjustment` field of the `head` table is
+/// computed once the file is complete.
+pub(crate) fn write_sfnt(sfnt_version: u32, mut tables: Vec<([u8; 4], Vec<u8>)>) -> Vec<u8> {
+  tables.sort_by_key(|table| table.0);
+  let table_count = tables.len() as u16;
+  let (search_range, entry_selector, range_shift) = search_params(table_count, 16);
+  let mut out: Vec<u8> = Vec::new();
+  put_u32(&mut out, sfnt_version);
+  put_u16(&mut out, table_count);
+  put_u16(&mut out, search_range);
+  put_u16(&mut out, entry_selector);
+  put_u16(&mut out, range_shift);
+  let mut offset = 12 + 16 * tables.len();
+  let mut head_offset: Option<usize> = None;
+  for (tag, data) in tables.iter() {
+    if tag == b"head" {
+      head_offset = Some(offset);
+    }
+    out.extend_from_slice(tag);
+    put_u32(&mut out, checksum(data));
+    put_u32(&mut out, offset as u32);
+    put_u32(&mut out, data.len() as u32);
+    offset += (data.len() + 3) & !3;
+  }
+  for (_, data) in tables.iter() {
+    out.extend_from_slice(data);
+    out.resize((out.len() + 3) & !3, 0);
+  }
+  if let Some(head_offset) = head_offset {
+    let adjustment = CHECKSUM_MAGIC.wrapping_sub(checksum(&out));
+    out[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+  }
+  out
+}
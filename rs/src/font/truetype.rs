@@ -0,0 +1,677 @@
+use crate::font::error::FontExportError;
+use crate::font::sfnt::{put_i16, put_u16, put_u32, put_u8, search_params, write_sfnt, SFNT_VERSION_TRUETYPE};
+use crate::svg::path::Segment;
+use crate::svg::shape::glyph_contours;
+use std::collections::BTreeMap;
+use swf_types as ast;
+
+/// Size of the EM square of the exported fonts, the EM square of `DefineFont2` fonts.
+const UNITS_PER_EM: i32 = 1024;
+
+const FLAG_ON_CURVE: u8 = 1 << 0;
+const FLAG_X_SHORT: u8 = 1 << 1;
+const FLAG_Y_SHORT: u8 = 1 << 2;
+const FLAG_X_SAME_OR_POSITIVE: u8 = 1 << 4;
+const FLAG_Y_SAME_OR_POSITIVE: u8 = 1 << 5;
+
+/// Point of a TrueType contour, in font units with the y-axis pointing up.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct OutlinePoint {
+  x: i16,
+  y: i16,
+  on_curve: bool,
+}
+
+/// Glyph outline converted to TrueType contours.
+struct Outline {
+  contours: Vec<Vec<OutlinePoint>>,
+}
+
+impl Outline {
+  fn points(&self) -> impl Iterator<Item = &OutlinePoint> {
+    self.contours.iter().flatten()
+  }
+
+  /// Returns `(x_min, y_min, x_max, y_max)`, or `None` for empty glyphs.
+  fn bounds(&self) -> Option<(i16, i16, i16, i16)> {
+    self.points().fold(None, |bounds, p| match bounds {
+      None => Some((p.x, p.y, p.x, p.y)),
+      Some((x_min, y_min, x_max, y_max)) => Some((x_min.min(p.x), y_min.min(p.y), x_max.max(p.x), y_max.max(p.y))),
+    })
+  }
+}
+
+/// Converts the glyphs of a `DefineFont2` or `DefineFont3` font into a TrueType font file (`.ttf`).
+///
+/// The glyph edges become quadratic TrueType contours and the glyph with the index `i` becomes the glyph `i + 1`,
+/// after `.notdef`. The code units are mapped to the glyphs with a `cmap` table, assuming they are Unicode code
+/// units. Coordinates use a 1024 units EM square: `DefineFont3` coordinates (20480 units EM square) are divided by 20.
+/// The advances, ascent, descent, leading and kerning come from the font layout when available.
+pub fn font_to_ttf(font: &ast::tags::DefineFont) -> Result<Vec<u8>, FontExportError> {
+  let glyphs = match &font.glyphs {
+    Some(glyphs) => glyphs,
+    None => return Err(FontExportError::MissingGlyphs(font.id)),
+  };
+  if glyphs.len() >= usize::from(u16::MAX) {
+    return Err(FontExportError::TooManyGlyphs(glyphs.len() + 1));
+  }
+  let scale = match font.em_square_size {
+    ast::text::EmSquareSize::EmSquareSize1024 => 1.0,
+    ast::text::EmSquareSize::EmSquareSize20480 => 20.0,
+  };
+  let to_units = |value: i32| -> i16 { (f64::from(value) / scale).round().clamp(-32768.0, 32767.0) as i16 };
+
+  // Glyph 0 is `.notdef`, left empty
+  let mut outlines: Vec<Outline> = vec![Outline { contours: Vec::new() }];
+  outlines.extend(glyphs.iter().map(|glyph| Outline {
+    contours: glyph_contours(glyph)
+      .iter()
+      .map(|contour| contour_points(contour, &to_units))
+      .filter(|points| points.len() >= 2)
+      .collect(),
+  }));
+  let advances: Vec<u16> = std::iter::once(0)
+    .chain((0..glyphs.len()).map(|index| match &font.layout {
+      Some(layout) => layout.advances.get(index).map_or(0, |advance| to_units(i32::from(*advance)).max(0) as u16),
+      None => outlines[index + 1].bounds().map_or(0, |bounds| bounds.2.max(0) as u16),
+    }))
+    .collect();
+
+  // Code units mapped to glyph indices, the first glyph wins if a code unit is used twice
+  let mut char_map: BTreeMap<u16, u16> = BTreeMap::new();
+  if let Some(code_units) = &font.code_units {
+    for (index, code_unit) in code_units.iter().enumerate().take(glyphs.len()) {
+      char_map.entry(*code_unit).or_insert(index as u16 + 1);
+    }
+  }
+
+  let font_bounds = outlines
+    .iter()
+    .filter_map(Outline::bounds)
+    .fold(None, |bounds, (x0, y0, x1, y1)| match bounds {
+      None => Some((x0, y0, x1, y1)),
+      Some((x_min, y_min, x_max, y_max)) => Some((x_min.min(x0), y_min.min(y0), x_max.max(x1), y_max.max(y1))),
+    })
+    .unwrap_or((0, 0, 0, 0));
+  let (ascent, descent, leading) = match &font.layout {
+    Some(layout) => (
+      to_units(i32::from(layout.ascent)),
+      to_units(i32::from(layout.descent)),
+      to_units(i32::from(layout.leading)),
+    ),
+    None => (font_bounds.3.max(0), font_bounds.1.min(0).saturating_neg(), 0),
+  };
+  let metrics = FontMetrics {
+    bounds: font_bounds,
+    ascent,
+    descent,
+    leading,
+  };
+
+  let (glyf, loca) = write_glyf_loca(&outlines);
+  let mut tables: Vec<([u8; 4], Vec<u8>)> = vec![
+    (*b"OS/2", write_os2(font, &advances, &char_map, &metrics)),
+    (*b"cmap", write_cmap(&char_map)),
+    (*b"glyf", glyf),
+    (*b"head", write_head(font, &metrics)),
+    (*b"hhea", write_hhea(&outlines, &advances, &metrics)),
+    (*b"hmtx", write_hmtx(&outlines, &advances)),
+    (*b"loca", loca),
+    (*b"maxp", write_maxp(&outlines)),
+    (*b"name", write_name(font)),
+    (*b"post", write_post()),
+  ];
+  if let Some(layout) = &font.layout {
+    if let Some(kern) = write_kern(&layout.kerning, &char_map, &to_units) {
+      tables.push((*b"kern", kern));
+    }
+  }
+  Ok(write_sfnt(SFNT_VERSION_TRUETYPE, tables))
+}
+
+/// Vertical metrics of the font, in font units.
+struct FontMetrics {
+  bounds: (i16, i16, i16, i16),
+  ascent: i16,
+  /// Distance below the baseline (positive).
+  descent: i16,
+  leading: i16,
+}
+
+/// Converts a contour to TrueType points: SWF glyphs have the y-axis pointing down, TrueType glyphs up.
+///
+/// The contour is implicitly closed: its last point is dropped when it matches the first one. The fill of SWF glyph
+/// edges is on their right side, as the clockwise outer contours of TrueType glyphs.
+fn contour_points(contour: &[Segment], to_units: &impl Fn(i32) -> i16) -> Vec<OutlinePoint> {
+  let mut points: Vec<OutlinePoint> = Vec::new();
+  let mut push = |x: i32, y: i32, on_curve: bool| {
+    let point = OutlinePoint {
+      x: to_units(x),
+      y: to_units(y).saturating_neg(),
+      on_curve,
+    };
+    // Rounding may produce duplicate points
+    if on_curve && points.last() == Some(&point) {
+      return;
+    }
+    points.push(point);
+  };
+  for segment in contour {
+    push(segment.from.x, segment.from.y, true);
+    if let Some(control) = segment.control {
+      push(control.x, control.y, false);
+    }
+  }
+  if let Some(last) = contour.last() {
+    push(last.to.x, last.to.y, true);
+  }
+  if points.len() > 1 && points.first() == points.last() {
+    points.pop();
+  }
+  points
+}
+
+/// Encodes the coordinate delta of a point, returns its flags.
+fn encode_delta(delta: i32, short_flag: u8, same_or_positive_flag: u8, out: &mut Vec<u8>) -> u8 {
+  if delta == 0 {
+    same_or_positive_flag
+  } else if delta.abs() < 256 {
+    put_u8(out, delta.unsigned_abs() as u8);
+    if delta > 0 {
+      short_flag | same_or_positive_flag
+    } else {
+      short_flag
+    }
+  } else {
+    put_i16(out, delta as i16);
+    0
+  }
+}
+
+fn write_glyph(outline: &Outline) -> Vec<u8> {
+  let mut out: Vec<u8> = Vec::new();
+  let (x_min, y_min, x_max, y_max) = match outline.bounds() {
+    Some(bounds) => bounds,
+    None => return out,
+  };
+  put_i16(&mut out, outline.contours.len() as i16);
+  put_i16(&mut out, x_min);
+  put_i16(&mut out, y_min);
+  put_i16(&mut out, x_max);
+  put_i16(&mut out, y_max);
+  let mut end: usize = 0;
+  for contour in outline.contours.iter() {
+    end += contour.len();
+    put_u16(&mut out, (end - 1) as u16);
+  }
+  // No instructions
+  put_u16(&mut out, 0);
+  let mut flags: Vec<u8> = Vec::new();
+  let mut xs: Vec<u8> = Vec::new();
+  let mut ys: Vec<u8> = Vec::new();
+  let mut previous = (0i32, 0i32);
+  for point in outline.points() {
+    let (x, y) = (i32::from(point.x), i32::from(point.y));
+    let mut flag = if point.on_curve { FLAG_ON_CURVE } else { 0 };
+    flag |= encode_delta(x - previous.0, FLAG_X_SHORT, FLAG_X_SAME_OR_POSITIVE, &mut xs);
+    flag |= encode_delta(y - previous.1, FLAG_Y_SHORT, FLAG_Y_SAME_OR_POSITIVE, &mut ys);
+    flags.push(flag);
+    previous = (x, y);
+  }
+  out.extend_from_slice(&flags);
+  out.extend_from_slice(&xs);
+  out.extend_from_slice(&ys);
+  out.resize((out.len() + 3) & !3, 0);
+  out
+}
+
+/// Returns the `glyf` table and the matching long `loca` table.
+fn write_glyf_loca(outlines: &[Outline]) -> (Vec<u8>, Vec<u8>) {
+  let mut glyf: Vec<u8> = Vec::new();
+  let mut loca: Vec<u8> = Vec::new();
+  for outline in outlines {
+    put_u32(&mut loca, glyf.len() as u32);
+    glyf.extend_from_slice(&write_glyph(outline));
+  }
+  put_u32(&mut loca, glyf.len() as u32);
+  (glyf, loca)
+}
+
+fn write_head(font: &ast::tags::DefineFont, metrics: &FontMetrics) -> Vec<u8> {
+  let mut out: Vec<u8> = Vec::new();
+  put_u32(&mut out, 0x0001_0000);
+  // Font revision 1.0
+  put_u32(&mut out, 0x0001_0000);
+  // `checkSumAdjustment`, set when writing the font file
+  put_u32(&mut out, 0);
+  put_u32(&mut out, 0x5f0f_3cf5);
+  // Baseline at `y = 0`, left side bearing at `x = 0`, integer scaling
+  put_u16(&mut out, 0b1011);
+  put_u16(&mut out, UNITS_PER_EM as u16);
+  // Creation and modification dates
+  out.extend_from_slice(&[0; 16]);
+  put_i16(&mut out, metrics.bounds.0);
+  put_i16(&mut out, metrics.bounds.1);
+  put_i16(&mut out, metrics.bounds.2);
+  put_i16(&mut out, metrics.bounds.3);
+  put_u16(&mut out, u16::from(font.is_bold) | (u16::from(font.is_italic) << 1));
+  // Smallest readable size in pixels
+  put_u16(&mut out, 8);
+  // Font direction hint (deprecated)
+  put_i16(&mut out, 2);
+  // Long `loca` offsets
+  put_i16(&mut out, 1);
+  put_i16(&mut out, 0);
+  out
+}
+
+fn write_hhea(outlines: &[Outline], advances: &[u16], metrics: &FontMetrics) -> Vec<u8> {
+  let mut min_left_side_bearing = i16::MAX;
+  let mut min_right_side_bearing = i16::MAX;
+  let mut max_extent = i16::MIN;
+  for (outline, advance) in outlines.iter().zip(advances) {
+    if let Some((x_min, _, x_max, _)) = outline.bounds() {
+      min_left_side_bearing = min_left_side_bearing.min(x_min);
+      min_right_side_bearing = min_right_side_bearing.min((i32::from(*advance) - i32::from(x_max)) as i16);
+      max_extent = max_extent.max(x_max);
+    }
+  }
+  if max_extent == i16::MIN {
+    min_left_side_bearing = 0;
+    min_right_side_bearing = 0;
+    max_extent = 0;
+  }
+  let mut out: Vec<u8> = Vec::new();
+  put_u32(&mut out, 0x0001_0000);
+  put_i16(&mut out, metrics.ascent);
+  put_i16(&mut out, metrics.descent.saturating_neg());
+  put_i16(&mut out, metrics.leading);
+  put_u16(&mut out, advances.iter().copied().max().unwrap_or(0));
+  put_i16(&mut out, min_left_side_bearing);
+  put_i16(&mut out, min_right_side_bearing);
+  put_i16(&mut out, max_extent);
+  // Vertical caret
+  put_i16(&mut out, 1);
+  put_i16(&mut out, 0);
+  put_i16(&mut out, 0);
+  // Reserved
+  out.extend_from_slice(&[0; 8]);
+  // Metric data format
+  put_i16(&mut out, 0);
+  put_u16(&mut out, advances.len() as u16);
+  out
+}
+
+fn write_hmtx(outlines: &[Outline], advances: &[u16]) -> Vec<u8> {
+  let mut out: Vec<u8> = Vec::new();
+  for (outline, advance) in outlines.iter().zip(advances) {
+    put_u16(&mut out, *advance);
+    put_i16(&mut out, outline.bounds().map_or(0, |bounds| bounds.0));
+  }
+  out
+}
+
+fn write_maxp(outlines: &[Outline]) -> Vec<u8> {
+  let max_points = outlines.iter().map(|outline| outline.points().count()).max().unwrap_or(0);
+  let max_contours = outlines.iter().map(|outline| outline.contours.len()).max().unwrap_or(0);
+  let mut out: Vec<u8> = Vec::new();
+  put_u32(&mut out, 0x0001_0000);
+  put_u16(&mut out, outlines.len() as u16);
+  put_u16(&mut out, max_points.min(usize::from(u16::MAX)) as u16);
+  put_u16(&mut out, max_contours.min(usize::from(u16::MAX)) as u16);
+  // No composite glyphs
+  put_u16(&mut out, 0);
+  put_u16(&mut out, 0);
+  // Zones: the glyph zone and the twilight zone, unused without instructions
+  put_u16(&mut out, 2);
+  // Twilight points, storage, function and instruction definitions, stack, instructions and components
+  out.extend_from_slice(&[0; 16]);
+  out
+}
+
+/// Writes a `cmap` table with a format 4 subtable, shared by the Unicode and Windows Unicode BMP encodings.
+pub(crate) fn write_cmap(char_map: &BTreeMap<u16, u16>) -> Vec<u8> {
+  // Segments of consecutive code units mapped to consecutive glyphs: `(start, end, glyph - start)`
+  let mut segments: Vec<(u16, u16, u16)> = Vec::new();
+  for (code_unit, glyph) in char_map.iter().filter(|(code_unit, _)| **code_unit != 0xffff) {
+    let delta = glyph.wrapping_sub(*code_unit);
+    match segments.last_mut() {
+      Some(last) if last.1 + 1 == *code_unit && last.2 == delta => last.1 = *code_unit,
+      _ => segments.push((*code_unit, *code_unit, delta)),
+    }
+  }
+  // The last segment must map `0xffff` to `.notdef`
+  segments.push((0xffff, 0xffff, 1));
+  let segment_count = segments.len() as u16;
+  let (search_range, entry_selector, range_shift) = search_params(segment_count, 2);
+
+  let mut out: Vec<u8> = Vec::new();
+  put_u16(&mut out, 0);
+  put_u16(&mut out, 2);
+  // Unicode BMP, then Windows Unicode BMP
+  for (platform, encoding) in [(0, 3), (3, 1)] {
+    put_u16(&mut out, platform);
+    put_u16(&mut out, encoding);
+    put_u32(&mut out, 4 + 2 * 8);
+  }
+  put_u16(&mut out, 4);
+  put_u16(&mut out, 16 + 8 * segment_count);
+  // Language
+  put_u16(&mut out, 0);
+  put_u16(&mut out, segment_count * 2);
+  put_u16(&mut out, search_range);
+  put_u16(&mut out, entry_selector);
+  put_u16(&mut out, range_shift);
+  for segment in segments.iter() {
+    put_u16(&mut out, segment.1);
+  }
+  // Reserved pad
+  put_u16(&mut out, 0);
+  for segment in segments.iter() {
+    put_u16(&mut out, segment.0);
+  }
+  for segment in segments.iter() {
+    put_u16(&mut out, segment.2);
+  }
+  // No range offsets: glyphs are found with the deltas
+  for _ in segments.iter() {
+    put_u16(&mut out, 0);
+  }
+  out
+}
+
+/// Writes a `kern` table with the kerning pairs of the code units mapped to glyphs, `None` if there are none.
+fn write_kern(
+  kerning: &[ast::text::KerningRecord],
+  char_map: &BTreeMap<u16, u16>,
+  to_units: &impl Fn(i32) -> i16,
+) -> Option<Vec<u8>> {
+  let mut pairs: BTreeMap<(u16, u16), i16> = BTreeMap::new();
+  for record in kerning {
+    if let (Some(left), Some(right)) = (char_map.get(&record.left), char_map.get(&record.right)) {
+      pairs.entry((*left, *right)).or_insert_with(|| to_units(i32::from(record.adjustment)));
+    }
+  }
+  if pairs.is_empty() || pairs.len() > usize::from(u16::MAX / 6) {
+    return None;
+  }
+  let pair_count = pairs.len() as u16;
+  let (search_range, entry_selector, range_shift) = search_params(pair_count, 6);
+  let mut out: Vec<u8> = Vec::new();
+  put_u16(&mut out, 0);
+  put_u16(&mut out, 1);
+  // Format 0 subtable
+  put_u16(&mut out, 0);
+  put_u16(&mut out, 14 + 6 * pair_count);
+  // Horizontal kerning values
+  put_u16(&mut out, 0x0001);
+  put_u16(&mut out, pair_count);
+  put_u16(&mut out, search_range);
+  put_u16(&mut out, entry_selector);
+  put_u16(&mut out, range_shift);
+  for ((left, right), value) in pairs {
+    put_u16(&mut out, left);
+    put_u16(&mut out, right);
+    put_i16(&mut out, value);
+  }
+  Some(out)
+}
+
+fn write_os2(
+  font: &ast::tags::DefineFont,
+  advances: &[u16],
+  char_map: &BTreeMap<u16, u16>,
+  metrics: &FontMetrics,
+) -> Vec<u8> {
+  let non_zero: Vec<u32> = advances
+    .iter()
+    .filter(|advance| **advance != 0)
+    .map(|advance| u32::from(*advance))
+    .collect();
+  let average_advance = if non_zero.is_empty() {
+    0
+  } else {
+    non_zero.iter().sum::<u32>() / non_zero.len() as u32
+  };
+  let em = UNITS_PER_EM;
+  let mut out: Vec<u8> = Vec::new();
+  put_u16(&mut out, 1);
+  put_i16(&mut out, average_advance as i16);
+  put_u16(&mut out, if font.is_bold { 700 } else { 400 });
+  // Medium width
+  put_u16(&mut out, 5);
+  // Installable embedding
+  put_u16(&mut out, 0);
+  // Subscript, superscript and strikeout metrics
+  for value in [
+    em * 65 / 100,
+    em * 60 / 100,
+    0,
+    em * 7 / 100,
+    em * 65 / 100,
+    em * 60 / 100,
+    0,
+    em * 35 / 100,
+    em / 20,
+    em / 4,
+  ] {
+    put_i16(&mut out, value as i16);
+  }
+  // Family class and PANOSE classification
+  put_i16(&mut out, 0);
+  out.extend_from_slice(&[0; 10]);
+  // Unicode ranges
+  out.extend_from_slice(&[0; 16]);
+  out.extend_from_slice(b"    ");
+  let selection = if font.is_bold || font.is_italic {
+    u16::from(font.is_italic) | (u16::from(font.is_bold) << 5)
+  } else {
+    1 << 6
+  };
+  put_u16(&mut out, selection);
+  put_u16(&mut out, char_map.keys().next().copied().unwrap_or(0));
+  put_u16(&mut out, char_map.keys().next_back().copied().unwrap_or(0));
+  put_i16(&mut out, metrics.ascent);
+  put_i16(&mut out, metrics.descent.saturating_neg());
+  put_i16(&mut out, metrics.leading);
+  put_u16(&mut out, metrics.ascent.max(metrics.bounds.3).max(0) as u16);
+  put_u16(&mut out, metrics.descent.max(metrics.bounds.1.saturating_neg()).max(0) as u16);
+  // Code page ranges: Latin 1
+  put_u32(&mut out, 1);
+  put_u32(&mut out, 0);
+  out
+}
+
+/// Writes a `name` table with the family, subfamily, unique, full and PostScript names of the font.
+fn write_name(font: &ast::tags::DefineFont) -> Vec<u8> {
+  let postscript_name: String = {
+    let name: String = font
+      .font_name
+      .chars()
+      .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+      .take(63)
+      .collect();
+    if name.is_empty() {
+      format!("SwfFont{}", font.id)
+    } else {
+      name
+    }
+  };
+  let family = match font.font_name.trim_end_matches('\0') {
+    "" => postscript_name.clone(),
+    name => String::from(name),
+  };
+  let subfamily = match (font.is_bold, font.is_italic) {
+    (false, false) => "Regular",
+    (true, false) => "Bold",
+    (false, true) => "Italic",
+    (true, true) => "Bold Italic",
+  };
+  let full_name = if subfamily == "Regular" {
+    family.clone()
+  } else {
+    format!("{} {}", family, subfamily)
+  };
+  let names: [(u16, &str); 5] = [
+    (1, &family),
+    (2, subfamily),
+    (3, &full_name),
+    (4, &full_name),
+    (6, &postscript_name),
+  ];
+  let mut strings: Vec<u8> = Vec::new();
+  let mut records: Vec<u8> = Vec::new();
+  for (name_id, value) in names {
+    let offset = strings.len();
+    for code_unit in value.encode_utf16() {
+      put_u16(&mut strings, code_unit);
+    }
+    // Windows, Unicode BMP, English (United States)
+    put_u16(&mut records, 3);
+    put_u16(&mut records, 1);
+    put_u16(&mut records, 0x0409);
+    put_u16(&mut records, name_id);
+    put_u16(&mut records, (strings.len() - offset) as u16);
+    put_u16(&mut records, offset as u16);
+  }
+  let mut out: Vec<u8> = Vec::new();
+  put_u16(&mut out, 0);
+  put_u16(&mut out, names.len() as u16);
+  put_u16(&mut out, (6 + records.len()) as u16);
+  out.extend_from_slice(&records);
+  out.extend_from_slice(&strings);
+  out
+}
+
+/// Writes a version 3 `post` table, without glyph names.
+fn write_post() -> Vec<u8> {
+  let mut out: Vec<u8> = Vec::new();
+  put_u32(&mut out, 0x0003_0000);
+  // Italic angle
+  put_u32(&mut out, 0);
+  // Underline position and thickness
+  put_i16(&mut out, -(UNITS_PER_EM / 10) as i16);
+  put_i16(&mut out, (UNITS_PER_EM / 20) as i16);
+  // Not monospaced, no memory usage hints
+  out.extend_from_slice(&[0; 20]);
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::font::sfnt::checksum;
+
+  fn edge(x: i32, y: i32) -> ast::ShapeRecord {
+    ast::ShapeRecord::Edge(ast::shape_records::Edge {
+      delta: ast::Vector2D { x, y },
+      control_delta: None,
+    })
+  }
+
+  /// Square glyph above the baseline, drawn counterclockwise with the fill on the left.
+  fn square_glyph(size: i32) -> ast::Glyph {
+    ast::Glyph {
+      records: vec![
+        ast::ShapeRecord::StyleChange(ast::shape_records::StyleChange {
+          move_to: Some(ast::Vector2D { x: 0, y: -size }),
+          left_fill: Some(1),
+          right_fill: None,
+          line_style: None,
+          new_styles: None,
+        }),
+        edge(0, size),
+        edge(size, 0),
+        edge(0, -size),
+        edge(-size, 0),
+      ],
+    }
+  }
+
+  fn font(em_square_size: ast::text::EmSquareSize, unit: i32) -> ast::tags::DefineFont {
+    ast::tags::DefineFont {
+      id: 1,
+      font_name: String::from("Test Sans"),
+      is_bold: false,
+      is_italic: false,
+      is_ansi: false,
+      is_small: false,
+      is_shift_jis: false,
+      em_square_size,
+      language: ast::LanguageCode::Auto,
+      glyphs: Some(vec![square_glyph(500 * unit), ast::Glyph { records: Vec::new() }]),
+      code_units: Some(vec![0x41, 0x20]),
+      layout: Some(ast::text::FontLayout {
+        ascent: (800 * unit) as u16,
+        descent: (200 * unit) as u16,
+        leading: 0,
+        advances: vec![(600 * unit) as u16, (250 * unit) as u16],
+        bounds: Vec::new(),
+        kerning: vec![ast::text::KerningRecord {
+          left: 0x41,
+          right: 0x20,
+          adjustment: (-50 * unit) as i16,
+        }],
+      }),
+    }
+  }
+
+  /// Returns the tables of a font file.
+  fn read_tables(data: &[u8]) -> BTreeMap<String, &[u8]> {
+    let count = usize::from(u16::from_be_bytes([data[4], data[5]]));
+    (0..count)
+      .map(|index| {
+        let record = &data[12 + 16 * index..28 + 16 * index];
+        let offset = u32::from_be_bytes([record[8], record[9], record[10], record[11]]) as usize;
+        let length = u32::from_be_bytes([record[12], record[13], record[14], record[15]]) as usize;
+        (
+          String::from_utf8(record[0..4].to_vec()).unwrap(),
+          &data[offset..offset + length],
+        )
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_font_to_ttf() {
+    let data = font_to_ttf(&font(ast::text::EmSquareSize::EmSquareSize1024, 1)).unwrap();
+    assert_eq!(checksum(&data), 0xb1b0_afba);
+    let tables = read_tables(&data);
+    assert_eq!(
+      tables.keys().map(String::as_str).collect::<Vec<_>>(),
+      vec!["OS/2", "cmap", "glyf", "head", "hhea", "hmtx", "kern", "loca", "maxp", "name", "post"]
+    );
+    assert_eq!(tables["loca"], &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 24, 0, 0, 0, 24][..]);
+    assert_eq!(
+      tables["glyf"],
+      &[
+        0, 1, 0, 0, 0, 0, 1, 244, 1, 244, // 1 contour, bounds (0, 0, 500, 500)
+        0, 3, 0, 0, // 4 points, no instructions
+        0x31, 0x11, 0x21, 0x11, // flags
+        1, 244, // x: 0, 0, +500, 0
+        1, 244, 254, 12, // y: 0, +500, 0, -500
+      ][..]
+    );
+    assert_eq!(tables["hmtx"], &[0, 0, 0, 0, 2, 88, 0, 0, 0, 250, 0, 0][..]);
+    assert_eq!(&tables["kern"][4..], &[0, 0, 0, 20, 0, 1, 0, 1, 0, 6, 0, 0, 0, 0, 0, 1, 0, 2, 255, 206][..]);
+    let cmap = &tables["cmap"][20..];
+    // Segments: 0x20 -> 2, 0x41 -> 1 and 0xffff
+    assert_eq!(&cmap[14..20], &[0x00, 0x20, 0x00, 0x41, 0xff, 0xff][..]);
+    assert_eq!(&cmap[22..28], &[0x00, 0x20, 0x00, 0x41, 0xff, 0xff][..]);
+    assert_eq!(&cmap[28..34], &[0xff, 0xe2, 0xff, 0xc0, 0x00, 0x01][..]);
+  }
+
+  #[test]
+  fn test_font3_to_ttf_scaling() {
+    let font2 = font_to_ttf(&font(ast::text::EmSquareSize::EmSquareSize1024, 1)).unwrap();
+    let font3 = font_to_ttf(&font(ast::text::EmSquareSize::EmSquareSize20480, 20)).unwrap();
+    assert_eq!(font3, font2);
+  }
+
+  #[test]
+  fn test_device_font_to_ttf() {
+    let mut device_font = font(ast::text::EmSquareSize::EmSquareSize1024, 1);
+    device_font.glyphs = None;
+    assert_eq!(font_to_ttf(&device_font), Err(FontExportError::MissingGlyphs(1)));
+  }
+}
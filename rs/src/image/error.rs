@@ -0,0 +1,43 @@
+use std::fmt;
+use swf_types as ast;
+
+/// Error when decoding the image of a bitmap tag.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImageDecodeError {
+  /// Indicates that the decoder doesn't handle the media type of the bitmap.
+  UnsupportedImageType(ast::ImageType),
+
+  /// Indicates that the bitmap uses an unknown `BitmapFormat` code.
+  UnsupportedBitmapFormat(u8),
+
+  /// Indicates that the image data is compressed but the `deflate` feature is disabled.
+  UnsupportedCompression,
+
+  /// Indicates that the image data is corrupted or truncated.
+  InvalidData(String),
+
+  /// Indicates that the movie doesn't define a bitmap with the requested character id.
+  MissingBitmap(u16),
+}
+
+impl std::error::Error for ImageDecodeError {}
+
+impl fmt::Display for ImageDecodeError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ImageDecodeError::UnsupportedImageType(media_type) => {
+        f.write_str("unsupported image type: ")?;
+        fmt::Debug::fmt(media_type, f)
+      }
+      ImageDecodeError::UnsupportedBitmapFormat(code) => write!(f, "unsupported bitmap format: {}", code),
+      ImageDecodeError::UnsupportedCompression => {
+        f.write_str("unsupported compressed image: compile `swf-parser` with the `deflate` feature")
+      }
+      ImageDecodeError::InvalidData(msg) => {
+        f.write_str("invalid image data: ")?;
+        f.write_str(msg)
+      }
+      ImageDecodeError::MissingBitmap(id) => write!(f, "missing bitmap with character id {}", id),
+    }
+  }
+}
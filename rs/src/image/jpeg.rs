@@ -0,0 +1,309 @@
+use crate::complete::image::{ERRONEOUS_JPEG_START, JPEG_START};
+use crate::image::error::ImageDecodeError;
+use crate::image::lossless::inflate_zlib;
+#[cfg(feature = "jpeg")]
+use crate::image::lossless::{unpremultiply, RgbaImage};
+use swf_fixed::Ufixed8P8;
+use swf_types as ast;
+
+/// EOI (End of image) marker.
+const JPEG_END: [u8; 2] = [0xff, 0xd9];
+
+/// JPEG image with a separate alpha channel, from a `DefineBitsJpeg3` or `DefineBitsJpeg4` tag.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JpegWithAlpha {
+  pub width: usize,
+  pub height: usize,
+  /// Standalone JPEG file.
+  pub jpeg: Vec<u8>,
+  /// Alpha channel, one byte per pixel in row-major order (`width * height` bytes), `None` for opaque images.
+  ///
+  /// The colors of the JPEG image are premultiplied by this alpha channel.
+  pub alpha: Option<Vec<u8>>,
+  /// Strength of the deblocking filter of `DefineBitsJpeg4`, from `0` (disabled) to `1` (100%).
+  ///
+  /// `None` for the other tags. The filter itself is not applied to the image.
+  pub deblock: Option<Ufixed8P8>,
+}
+
+/// Splits the image of a `DefineBitsJpeg3` (`SwfJpeg3`) or `DefineBitsJpeg4` (`SwfJpeg4`) bitmap into the JPEG
+/// image and its inflated alpha channel.
+///
+/// Complete JPEG images (`Jpeg`), including `DefineBitsJpeg3` tags without alpha data, are opaque: they have no alpha
+/// channel. Returns an error if the alpha channel doesn't have one byte per pixel.
+pub fn decode_jpeg_with_alpha(bitmap: &ast::tags::DefineBitmap) -> Result<JpegWithAlpha, ImageDecodeError> {
+  let width = usize::from(bitmap.width);
+  let height = usize::from(bitmap.height);
+  let data = &bitmap.data[..];
+  let (jpeg, alpha_data, deblock) = match bitmap.media_type {
+    ast::ImageType::Jpeg => (data, &[][..], None),
+    ast::ImageType::SwfJpeg3 | ast::ImageType::SwfJpeg4 => {
+      let has_deblock = bitmap.media_type == ast::ImageType::SwfJpeg4;
+      let header_len = if has_deblock { 6 } else { 4 };
+      if data.len() < header_len {
+        return Err(ImageDecodeError::InvalidData(String::from("incomplete bitmap header")));
+      }
+      let jpeg_len = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+      let deblock = if has_deblock {
+        Some(Ufixed8P8::from_epsilons(u16::from_le_bytes([data[4], data[5]])))
+      } else {
+        None
+      };
+      let data = &data[header_len..];
+      match usize::try_from(jpeg_len) {
+        Ok(jpeg_len) if jpeg_len <= data.len() => {
+          let (jpeg, alpha_data) = data.split_at(jpeg_len);
+          (jpeg, alpha_data, deblock)
+        }
+        _ => return Err(ImageDecodeError::InvalidData(String::from("incomplete JPEG data"))),
+      }
+    }
+    media_type => return Err(ImageDecodeError::UnsupportedImageType(media_type)),
+  };
+  let alpha = if alpha_data.is_empty() {
+    None
+  } else {
+    let alpha = inflate_zlib(alpha_data)?;
+    if alpha.len() != width * height {
+      return Err(ImageDecodeError::InvalidData(format!(
+        "expected {} bytes of alpha data for a {}x{} image, got {}",
+        width * height,
+        width,
+        height,
+        alpha.len()
+      )));
+    }
+    Some(alpha)
+  };
+  Ok(JpegWithAlpha {
+    width,
+    height,
+    jpeg: strip_erroneous_jpeg_start(jpeg).to_vec(),
+    alpha,
+    deblock,
+  })
+}
+
+/// Decodes a JPEG bitmap (`Jpeg`, `SwfJpeg3` or `SwfJpeg4`) into straight RGBA pixels, applying its alpha channel.
+///
+/// See [[decode_jpeg_with_alpha]]. Partial JPEG images must first be merged with their tables, see
+/// [[get_standalone_jpeg]].
+#[cfg(feature = "jpeg")]
+pub fn decode_jpeg_rgba(bitmap: &ast::tags::DefineBitmap) -> Result<RgbaImage, ImageDecodeError> {
+  use jpeg_decoder::PixelFormat;
+
+  let image = decode_jpeg_with_alpha(bitmap)?;
+  let mut decoder = jpeg_decoder::Decoder::new(&image.jpeg[..]);
+  let pixels = decoder
+    .decode()
+    .map_err(|e| ImageDecodeError::InvalidData(e.to_string()))?;
+  let info = match decoder.info() {
+    Some(info) => info,
+    None => return Err(ImageDecodeError::InvalidData(String::from("missing JPEG frame"))),
+  };
+  if (usize::from(info.width), usize::from(info.height)) != (image.width, image.height) {
+    return Err(ImageDecodeError::InvalidData(String::from(
+      "the JPEG dimensions don't match the bitmap dimensions",
+    )));
+  }
+  let rgb: Vec<[u8; 3]> = match info.pixel_format {
+    PixelFormat::L8 => pixels.iter().map(|l| [*l, *l, *l]).collect(),
+    PixelFormat::L16 => pixels.chunks(2).map(|l| [l[0], l[0], l[0]]).collect(),
+    PixelFormat::RGB24 => pixels.chunks(3).map(|c| [c[0], c[1], c[2]]).collect(),
+    PixelFormat::CMYK32 => {
+      return Err(ImageDecodeError::InvalidData(String::from("unsupported CMYK JPEG image")));
+    }
+  };
+  let mut data: Vec<u8> = Vec::with_capacity(image.width * image.height * 4);
+  match image.alpha {
+    Some(alpha) => {
+      for ([r, g, b], a) in rgb.into_iter().zip(alpha) {
+        data.extend_from_slice(&unpremultiply([r.min(a), g.min(a), b.min(a), a]));
+      }
+    }
+    None => {
+      for [r, g, b] in rgb {
+        data.extend_from_slice(&[r, g, b, 0xff]);
+      }
+    }
+  }
+  Ok(RgbaImage {
+    width: image.width,
+    height: image.height,
+    data,
+  })
+}
+
+/// Returns the standalone JPEG file of the bitmap `id` of a movie.
+///
+/// The partial JPEG of a `DefineBits` tag (`SwfPartialJpeg`) is merged with the tables of the `DefineJpegTables`
+/// tag of the movie, see [[merge_jpeg_tables]]. Complete JPEG images (`DefineBitsJpeg2`) are returned without their
+/// erroneous header.
+pub fn get_standalone_jpeg(movie: &ast::Movie, id: u16) -> Result<Vec<u8>, ImageDecodeError> {
+  let bitmap = movie
+    .tags
+    .iter()
+    .find_map(|tag| match tag {
+      ast::Tag::DefineBitmap(bitmap) if bitmap.id == id => Some(bitmap),
+      _ => None,
+    })
+    .ok_or(ImageDecodeError::MissingBitmap(id))?;
+  match bitmap.media_type {
+    ast::ImageType::SwfPartialJpeg => {
+      let tables = movie
+        .tags
+        .iter()
+        .find_map(|tag| match tag {
+          ast::Tag::DefineJpegTables(tables) => Some(&tables.data[..]),
+          _ => None,
+        })
+        .unwrap_or(&[]);
+      Ok(merge_jpeg_tables(tables, &bitmap.data))
+    }
+    ast::ImageType::Jpeg => Ok(strip_erroneous_jpeg_start(&bitmap.data).to_vec()),
+    media_type => Err(ImageDecodeError::UnsupportedImageType(media_type)),
+  }
+}
+
+/// Merges the encoding tables of a `DefineJpegTables` tag into the partial JPEG image of a `DefineBits` tag.
+///
+/// The erroneous header written before the start of the JPEG data by old SWF encoders (see
+/// `ERRONEOUS_JPEG_START`) is removed from both inputs. The tables are inserted after the start of the image. If
+/// the tables are empty (no data or only the start and end markers), the image is returned unchanged: it is
+/// expected to contain its own tables.
+pub fn merge_jpeg_tables(tables: &[u8], image: &[u8]) -> Vec<u8> {
+  let tables = strip_erroneous_jpeg_start(tables);
+  let tables = tables.strip_prefix(&JPEG_START[..]).unwrap_or(tables);
+  let tables = tables.strip_suffix(&JPEG_END[..]).unwrap_or(tables);
+  let image = strip_erroneous_jpeg_start(image);
+  if tables.is_empty() {
+    return image.to_vec();
+  }
+  let image = image.strip_prefix(&JPEG_START[..]).unwrap_or(image);
+  let mut jpeg: Vec<u8> = Vec::with_capacity(JPEG_START.len() + tables.len() + image.len());
+  jpeg.extend_from_slice(&JPEG_START);
+  jpeg.extend_from_slice(tables);
+  jpeg.extend_from_slice(image);
+  jpeg
+}
+
+/// Removes the `[0xff, 0xd9, 0xff, 0xd8]` prefix of erroneous JPEG data.
+pub(crate) fn strip_erroneous_jpeg_start(data: &[u8]) -> &[u8] {
+  if data.starts_with(&ERRONEOUS_JPEG_START) {
+    &data[JPEG_END.len() + JPEG_START.len()..]
+  } else {
+    data
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const TABLES: &[u8] = &[0xff, 0xd8, 0xff, 0xdb, 0x00, 0x03, 0x01, 0xff, 0xd9];
+  const IMAGE: &[u8] = &[0xff, 0xd8, 0xff, 0xc0, 0x00, 0x03, 0x02, 0xff, 0xd9];
+
+  #[test]
+  fn test_merge_jpeg_tables() {
+    assert_eq!(
+      merge_jpeg_tables(TABLES, IMAGE),
+      vec![0xff, 0xd8, 0xff, 0xdb, 0x00, 0x03, 0x01, 0xff, 0xc0, 0x00, 0x03, 0x02, 0xff, 0xd9]
+    );
+  }
+
+  #[test]
+  fn test_merge_jpeg_tables_erroneous_start() {
+    let tables: Vec<u8> = [&[0xff, 0xd9, 0xff, 0xd8], TABLES].concat();
+    let image: Vec<u8> = [&[0xff, 0xd9, 0xff, 0xd8], IMAGE].concat();
+    assert_eq!(merge_jpeg_tables(&tables, &image), merge_jpeg_tables(TABLES, IMAGE));
+  }
+
+  #[test]
+  fn test_merge_empty_jpeg_tables() {
+    assert_eq!(merge_jpeg_tables(&[], IMAGE), IMAGE.to_vec());
+    assert_eq!(merge_jpeg_tables(&[0xff, 0xd8, 0xff, 0xd9], IMAGE), IMAGE.to_vec());
+  }
+
+  fn jpeg3(jpeg: &[u8], alpha: &[u8]) -> ast::tags::DefineBitmap {
+    let mut data = (jpeg.len() as u32).to_le_bytes().to_vec();
+    data.extend_from_slice(jpeg);
+    data.extend(deflate::deflate_bytes_zlib(alpha));
+    ast::tags::DefineBitmap {
+      id: 1,
+      width: 2,
+      height: 1,
+      media_type: ast::ImageType::SwfJpeg3,
+      data,
+    }
+  }
+
+  #[test]
+  fn test_decode_jpeg3_with_alpha() {
+    let bitmap = jpeg3(IMAGE, &[0x00, 0x80]);
+    assert_eq!(
+      decode_jpeg_with_alpha(&bitmap),
+      Ok(JpegWithAlpha {
+        width: 2,
+        height: 1,
+        jpeg: IMAGE.to_vec(),
+        alpha: Some(vec![0x00, 0x80]),
+        deblock: None,
+      })
+    );
+  }
+
+  #[test]
+  fn test_decode_jpeg3_with_invalid_alpha() {
+    let bitmap = jpeg3(IMAGE, &[0x00, 0x80, 0xff]);
+    assert!(matches!(
+      decode_jpeg_with_alpha(&bitmap),
+      Err(ImageDecodeError::InvalidData(_))
+    ));
+  }
+
+  #[test]
+  fn test_decode_jpeg4_with_deblock() {
+    let mut data: Vec<u8> = vec![0x09, 0x00, 0x00, 0x00, 0x80, 0x00];
+    data.extend_from_slice(IMAGE);
+    let bitmap = ast::tags::DefineBitmap {
+      id: 1,
+      width: 2,
+      height: 1,
+      media_type: ast::ImageType::SwfJpeg4,
+      data,
+    };
+    let image = decode_jpeg_with_alpha(&bitmap).unwrap();
+    assert_eq!(image.deblock, Some(Ufixed8P8::from_epsilons(0x80)));
+    assert_eq!(image.jpeg, IMAGE.to_vec());
+    assert_eq!(image.alpha, None);
+  }
+
+  #[test]
+  fn test_get_standalone_jpeg() {
+    let movie = ast::Movie {
+      header: ast::Header {
+        swf_version: 6,
+        frame_size: ast::Rect {
+          x_min: 0,
+          x_max: 0,
+          y_min: 0,
+          y_max: 0,
+        },
+        frame_rate: ast::fixed::Ufixed8P8::from_epsilons(0),
+        frame_count: 1,
+      },
+      tags: vec![
+        ast::Tag::DefineJpegTables(ast::tags::DefineJpegTables { data: TABLES.to_vec() }),
+        ast::Tag::DefineBitmap(ast::tags::DefineBitmap {
+          id: 1,
+          width: 0,
+          height: 0,
+          media_type: ast::ImageType::SwfPartialJpeg,
+          data: IMAGE.to_vec(),
+        }),
+      ],
+    };
+    assert_eq!(get_standalone_jpeg(&movie, 1), Ok(merge_jpeg_tables(TABLES, IMAGE)));
+    assert_eq!(get_standalone_jpeg(&movie, 2), Err(ImageDecodeError::MissingBitmap(2)));
+  }
+}
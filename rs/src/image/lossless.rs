@@ -0,0 +1,261 @@
+use crate::image::error::ImageDecodeError;
+use swf_types as ast;
+
+/// Decoded image, with straight (non-premultiplied) RGBA pixels.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RgbaImage {
+  pub width: usize,
+  pub height: usize,
+  /// Pixels in row-major order, 4 bytes per pixel (red, green, blue, alpha) without padding.
+  pub data: Vec<u8>,
+}
+
+/// Colormapped pixels: 8-bit indices in a palette of RGB (or RGBA) colors.
+const BITMAP_FORMAT_COLORMAPPED: u8 = 3;
+/// 15-bit RGB pixels, only for `DefineBitsLossless`.
+const BITMAP_FORMAT_RGB15: u8 = 4;
+/// 32-bit XRGB (or premultiplied ARGB) pixels.
+const BITMAP_FORMAT_ARGB32: u8 = 5;
+
+/// Decodes the image of a `DefineBitsLossless` (`SwfLossless1`) or `DefineBitsLossless2` (`SwfLossless2`) bitmap.
+///
+/// Rows padded to 32 bits are unpadded and the premultiplied colors of `DefineBitsLossless2` are converted to
+/// straight colors. Palette indices outside of the color table produce transparent pixels.
+pub fn decode_lossless(bitmap: &ast::tags::DefineBitmap) -> Result<RgbaImage, ImageDecodeError> {
+  let has_alpha = match bitmap.media_type {
+    ast::ImageType::SwfLossless1 => false,
+    ast::ImageType::SwfLossless2 => true,
+    media_type => return Err(ImageDecodeError::UnsupportedImageType(media_type)),
+  };
+  let data = &bitmap.data[..];
+  if data.len() < 5 {
+    return Err(ImageDecodeError::InvalidData(String::from("incomplete bitmap header")));
+  }
+  let format = data[0];
+  let width = usize::from(u16::from_le_bytes([data[1], data[2]]));
+  let height = usize::from(u16::from_le_bytes([data[3], data[4]]));
+  match format {
+    BITMAP_FORMAT_COLORMAPPED => {
+      let color_count = match data.get(5) {
+        Some(max_index) => usize::from(*max_index) + 1,
+        None => return Err(ImageDecodeError::InvalidData(String::from("incomplete bitmap header"))),
+      };
+      let pixels = inflate_zlib(&data[6..])?;
+      decode_colormapped(&pixels, width, height, color_count, has_alpha)
+    }
+    BITMAP_FORMAT_RGB15 => {
+      let pixels = inflate_zlib(&data[5..])?;
+      decode_rgb15(&pixels, width, height)
+    }
+    BITMAP_FORMAT_ARGB32 => {
+      let pixels = inflate_zlib(&data[5..])?;
+      decode_argb32(&pixels, width, height, has_alpha)
+    }
+    code => Err(ImageDecodeError::UnsupportedBitmapFormat(code)),
+  }
+}
+
+#[cfg(feature = "deflate")]
+pub(crate) fn inflate_zlib(bytes: &[u8]) -> Result<Vec<u8>, ImageDecodeError> {
+  inflate::inflate_bytes_zlib(bytes).map_err(ImageDecodeError::InvalidData)
+}
+
+#[cfg(not(feature = "deflate"))]
+pub(crate) fn inflate_zlib(_bytes: &[u8]) -> Result<Vec<u8>, ImageDecodeError> {
+  Err(ImageDecodeError::UnsupportedCompression)
+}
+
+/// Returns the rows of the pixel data, each row is padded to a multiple of 32 bits.
+fn get_rows(pixels: &[u8], row_len: usize, height: usize) -> Result<std::slice::Chunks<'_, u8>, ImageDecodeError> {
+  let stride = (row_len + 3) & !3;
+  let pixels_len = stride * height;
+  if pixels.len() < pixels_len {
+    return Err(ImageDecodeError::InvalidData(format!(
+      "expected {} bytes of pixel data, got {}",
+      pixels_len,
+      pixels.len()
+    )));
+  }
+  // `max(1)` avoids a panic for empty images: there are no rows in this case
+  Ok(pixels[..pixels_len].chunks(stride.max(1)))
+}
+
+fn decode_colormapped(
+  pixels: &[u8],
+  width: usize,
+  height: usize,
+  color_count: usize,
+  has_alpha: bool,
+) -> Result<RgbaImage, ImageDecodeError> {
+  let color_len = if has_alpha { 4 } else { 3 };
+  let palette_len = color_count * color_len;
+  if pixels.len() < palette_len {
+    return Err(ImageDecodeError::InvalidData(String::from("incomplete color table")));
+  }
+  let (palette, pixels) = pixels.split_at(palette_len);
+  let palette: Vec<[u8; 4]> = palette
+    .chunks(color_len)
+    .map(|color| {
+      if has_alpha {
+        unpremultiply([color[0], color[1], color[2], color[3]])
+      } else {
+        [color[0], color[1], color[2], 0xff]
+      }
+    })
+    .collect();
+
+  // The rows are checked before allocating the image: the dimensions are not bounded by the pixel data
+  let rows = get_rows(pixels, width, height)?;
+  let mut data: Vec<u8> = Vec::with_capacity(width * height * 4);
+  for row in rows {
+    for index in &row[..width] {
+      data.extend_from_slice(palette.get(usize::from(*index)).unwrap_or(&[0, 0, 0, 0]));
+    }
+  }
+  Ok(RgbaImage { width, height, data })
+}
+
+fn decode_rgb15(pixels: &[u8], width: usize, height: usize) -> Result<RgbaImage, ImageDecodeError> {
+  /// Expands a 5-bit channel to 8 bits.
+  fn expand(channel: u16) -> u8 {
+    let channel = (channel & 0x1f) as u8;
+    (channel << 3) | (channel >> 2)
+  }
+
+  let rows = get_rows(pixels, width * 2, height)?;
+  let mut data: Vec<u8> = Vec::with_capacity(width * height * 4);
+  for row in rows {
+    for pixel in row[..width * 2].chunks(2) {
+      let pixel = u16::from_be_bytes([pixel[0], pixel[1]]);
+      data.extend_from_slice(&[expand(pixel >> 10), expand(pixel >> 5), expand(pixel), 0xff]);
+    }
+  }
+  Ok(RgbaImage { width, height, data })
+}
+
+fn decode_argb32(pixels: &[u8], width: usize, height: usize, has_alpha: bool) -> Result<RgbaImage, ImageDecodeError> {
+  let rows = get_rows(pixels, width * 4, height)?;
+  let mut data: Vec<u8> = Vec::with_capacity(width * height * 4);
+  for row in rows {
+    for pixel in row.chunks(4) {
+      let color = if has_alpha {
+        unpremultiply([pixel[1], pixel[2], pixel[3], pixel[0]])
+      } else {
+        [pixel[1], pixel[2], pixel[3], 0xff]
+      };
+      data.extend_from_slice(&color);
+    }
+  }
+  Ok(RgbaImage { width, height, data })
+}
+
+/// Converts a premultiplied RGBA color to a straight color.
+pub(crate) fn unpremultiply([r, g, b, a]: [u8; 4]) -> [u8; 4] {
+  if a == 0 {
+    return [0, 0, 0, 0];
+  }
+  let channel = |c: u8| -> u8 {
+    let c = (u16::from(c) * 255 + u16::from(a) / 2) / u16::from(a);
+    c.min(255) as u8
+  };
+  [channel(r), channel(g), channel(b), a]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lossless(media_type: ast::ImageType, header: &[u8], pixels: &[u8]) -> ast::tags::DefineBitmap {
+    let mut data = header.to_vec();
+    data.extend(deflate::deflate_bytes_zlib(pixels));
+    ast::tags::DefineBitmap {
+      id: 1,
+      width: u16::from_le_bytes([header[1], header[2]]),
+      height: u16::from_le_bytes([header[3], header[4]]),
+      media_type,
+      data,
+    }
+  }
+
+  #[test]
+  fn test_decode_colormapped_with_padding() {
+    let bitmap = lossless(
+      ast::ImageType::SwfLossless1,
+      &[0x03, 0x03, 0x00, 0x02, 0x00, 0x01],
+      &[
+        0xff, 0x00, 0x00, 0x00, 0x00, 0xff, // palette: [red, blue]
+        0x00, 0x01, 0x05, 0xaa, // row 1, out of range index and padding
+        0x01, 0x01, 0x00, 0xaa, // row 2 and padding
+      ],
+    );
+    assert_eq!(
+      decode_lossless(&bitmap),
+      Ok(RgbaImage {
+        width: 3,
+        height: 2,
+        data: vec![
+          0xff, 0x00, 0x00, 0xff, 0x00, 0x00, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, //
+          0x00, 0x00, 0xff, 0xff, 0x00, 0x00, 0xff, 0xff, 0xff, 0x00, 0x00, 0xff,
+        ],
+      })
+    );
+  }
+
+  #[test]
+  fn test_decode_colormapped_with_alpha() {
+    let bitmap = lossless(
+      ast::ImageType::SwfLossless2,
+      &[0x03, 0x01, 0x00, 0x01, 0x00, 0x00],
+      &[0x40, 0x20, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00],
+    );
+    assert_eq!(decode_lossless(&bitmap).unwrap().data, vec![0x80, 0x40, 0x00, 0x80]);
+  }
+
+  #[test]
+  fn test_decode_rgb15() {
+    let bitmap = lossless(
+      ast::ImageType::SwfLossless1,
+      &[0x04, 0x01, 0x00, 0x02, 0x00],
+      &[0x7c, 0x00, 0x00, 0x00, 0x03, 0xe1, 0x00, 0x00],
+    );
+    assert_eq!(
+      decode_lossless(&bitmap).unwrap().data,
+      vec![0xff, 0x00, 0x00, 0xff, 0x00, 0xff, 0x08, 0xff]
+    );
+  }
+
+  #[test]
+  fn test_decode_argb32() {
+    let pixels: &[u8] = &[0x00, 0x11, 0x22, 0x33, 0x80, 0x40, 0x20, 0x80, 0x00, 0x12, 0x34, 0x56];
+    let bitmap = lossless(ast::ImageType::SwfLossless2, &[0x05, 0x03, 0x00, 0x01, 0x00], pixels);
+    assert_eq!(
+      decode_lossless(&bitmap).unwrap().data,
+      vec![0x00, 0x00, 0x00, 0x00, 0x80, 0x40, 0xff, 0x80, 0x00, 0x00, 0x00, 0x00]
+    );
+    let bitmap = lossless(ast::ImageType::SwfLossless1, &[0x05, 0x03, 0x00, 0x01, 0x00], pixels);
+    assert_eq!(
+      decode_lossless(&bitmap).unwrap().data,
+      vec![0x11, 0x22, 0x33, 0xff, 0x40, 0x20, 0x80, 0xff, 0x12, 0x34, 0x56, 0xff]
+    );
+  }
+
+  #[test]
+  fn test_decode_truncated() {
+    let bitmap = lossless(ast::ImageType::SwfLossless1, &[0x05, 0x02, 0x00, 0x01, 0x00], &[0x00; 4]);
+    assert!(matches!(decode_lossless(&bitmap), Err(ImageDecodeError::InvalidData(_))));
+  }
+
+  #[test]
+  fn test_decode_huge_dimensions() {
+    // 65535x65535 pixels with a single byte of pixel data fail before allocating the image
+    let headers: [&[u8]; 3] = [
+      &[0x03, 0xff, 0xff, 0xff, 0xff, 0x00],
+      &[0x04, 0xff, 0xff, 0xff, 0xff],
+      &[0x05, 0xff, 0xff, 0xff, 0xff],
+    ];
+    for header in headers {
+      let bitmap = lossless(ast::ImageType::SwfLossless1, header, &[0x00; 4]);
+      assert!(matches!(decode_lossless(&bitmap), Err(ImageDecodeError::InvalidData(_))));
+    }
+  }
+}
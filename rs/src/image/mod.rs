@@ -0,0 +1,9 @@
+pub(crate) mod error;
+pub(crate) mod jpeg;
+pub(crate) mod lossless;
+
+pub use error::ImageDecodeError;
+#[cfg(feature = "jpeg")]
+pub use jpeg::decode_jpeg_rgba;
+pub use jpeg::{decode_jpeg_with_alpha, get_standalone_jpeg, merge_jpeg_tables, JpegWithAlpha};
+pub use lossless::{decode_lossless, RgbaImage};
@@ -0,0 +1,325 @@
+use crate::complete::skim_tags;
+use crate::streaming::basic_data_types::LAST_LEGACY_STRING_VERSION;
+use encoding_rs::Encoding;
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use swf_types as ast;
+
+/// Encoding of the strings of SWF 5 (and earlier) movies, which depends on the system of the author.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LegacyEncoding {
+  /// Keep the raw bytes: each byte is mapped to the character with the same code point (Latin-1).
+  Raw,
+  /// Western ANSI codepage (Windows-1252, superset of Latin-1).
+  Windows1252,
+  /// Japanese codepage (Shift-JIS).
+  ShiftJis,
+  /// Korean codepage (EUC-KR).
+  EucKr,
+  /// Simplified Chinese codepage (GBK).
+  Gbk,
+  /// Traditional Chinese codepage (Big5).
+  Big5,
+}
+
+/// Windows-1252, used when nothing hints at the system of the author.
+impl Default for LegacyEncoding {
+  fn default() -> Self {
+    LegacyEncoding::Windows1252
+  }
+}
+
+impl LegacyEncoding {
+  /// Returns the codepage matching a font language, `None` for `Auto`.
+  pub fn from_language_code(language: ast::LanguageCode) -> Option<Self> {
+    match language {
+      ast::LanguageCode::Auto => None,
+      ast::LanguageCode::Latin => Some(LegacyEncoding::Windows1252),
+      ast::LanguageCode::Japanese => Some(LegacyEncoding::ShiftJis),
+      ast::LanguageCode::Korean => Some(LegacyEncoding::EucKr),
+      ast::LanguageCode::SimplifiedChinese => Some(LegacyEncoding::Gbk),
+      ast::LanguageCode::TraditionalChinese => Some(LegacyEncoding::Big5),
+    }
+  }
+
+  /// Decodes a string, invalid sequences are replaced by `U+FFFD`.
+  pub fn decode(self, bytes: &[u8]) -> String {
+    match self.encoding() {
+      None => bytes.iter().map(|byte| char::from(*byte)).collect(),
+      Some(encoding) => encoding.decode_without_bom_handling(bytes).0.into_owned(),
+    }
+  }
+
+  /// Encodes a string, returns `None` if some characters are missing from the codepage.
+  pub fn encode(self, value: &str) -> Option<Cow<'_, [u8]>> {
+    match self.encoding() {
+      None => value.chars().map(|c| u8::try_from(u32::from(c)).ok()).collect::<Option<Vec<u8>>>().map(Cow::Owned),
+      Some(encoding) => match encoding.encode(value) {
+        (_, _, true) => None,
+        (bytes, _, false) => Some(bytes),
+      },
+    }
+  }
+
+  fn encoding(self) -> Option<&'static Encoding> {
+    match self {
+      LegacyEncoding::Raw => None,
+      LegacyEncoding::Windows1252 => Some(encoding_rs::WINDOWS_1252),
+      LegacyEncoding::ShiftJis => Some(encoding_rs::SHIFT_JIS),
+      LegacyEncoding::EucKr => Some(encoding_rs::EUC_KR),
+      LegacyEncoding::Gbk => Some(encoding_rs::GBK),
+      LegacyEncoding::Big5 => Some(encoding_rs::BIG5),
+    }
+  }
+}
+
+/// Guesses the legacy encoding of a movie from the hints of its fonts.
+///
+/// The first font with a language code (`DefineFont2`, `DefineFontInfo2`) or a Shift-JIS flag decides the encoding.
+/// Movies without hints use Windows-1252.
+pub fn default_legacy_encoding(tags: &[ast::Tag]) -> LegacyEncoding {
+  fn find_language_hint(tags: &[ast::Tag]) -> Option<LegacyEncoding> {
+    tags.iter().find_map(|tag| match tag {
+      ast::Tag::DefineSprite(sprite) => find_language_hint(&sprite.tags),
+      tag => language_hint(tag),
+    })
+  }
+
+  find_language_hint(tags).unwrap_or_default()
+}
+
+/// Guesses the legacy encoding of a tag string from the hints of its fonts, see [[default_legacy_encoding]].
+///
+/// Only the font tags are parsed. Returns the default encoding for SWF 6 and later, which use UTF-8.
+pub(crate) fn sniff_legacy_encoding(input: &[u8], swf_version: u8) -> LegacyEncoding {
+  /// Codes of the `DefineFontInfo`, `DefineFont2` and `DefineFontInfo2` tags
+  const FONT_CODES: [u16; 3] = [13, 48, 62];
+
+  if swf_version > LAST_LEGACY_STRING_VERSION {
+    return LegacyEncoding::default();
+  }
+  skim_tags(input, swf_version)
+    .filter(|handle| handle.code().map_or(false, |code| FONT_CODES.contains(&code)))
+    .find_map(|handle| language_hint(&handle.decode()))
+    .unwrap_or_default()
+}
+
+fn language_hint(tag: &ast::Tag) -> Option<LegacyEncoding> {
+  match tag {
+    ast::Tag::DefineFont(font) if font.is_shift_jis => Some(LegacyEncoding::ShiftJis),
+    ast::Tag::DefineFont(font) => LegacyEncoding::from_language_code(font.language),
+    ast::Tag::DefineFontInfo(info) if info.is_shift_jis => Some(LegacyEncoding::ShiftJis),
+    ast::Tag::DefineFontInfo(info) => LegacyEncoding::from_language_code(info.language),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse_swf_with_legacy_encoding;
+
+  /// Builds a tag with a short header.
+  fn short_tag(code: u16, body: &[u8]) -> Vec<u8> {
+    let mut tag: Vec<u8> = ((code << 6) | (body.len() as u16)).to_le_bytes().to_vec();
+    tag.extend_from_slice(body);
+    tag
+  }
+
+  /// Builds a `FrameLabel` tag.
+  fn frame_label_tag(label: &[u8]) -> Vec<u8> {
+    short_tag(43, &[label, b"\x00"].concat())
+  }
+
+  /// Builds an uncompressed movie with the provided tags, followed by `End`.
+  fn movie(swf_version: u8, tags: &[u8]) -> Vec<u8> {
+    // Header: empty frame size (single `0` byte), frame rate and frame count
+    let mut payload: Vec<u8> = vec![0x00, 0x00, 0x0c, 0x01, 0x00];
+    payload.extend_from_slice(tags);
+    payload.extend_from_slice(&[0x00, 0x00]);
+    let mut swf: Vec<u8> = vec![b'F', b'W', b'S', swf_version];
+    swf.extend_from_slice(&(8 + payload.len() as u32).to_le_bytes());
+    swf.extend_from_slice(&payload);
+    swf
+  }
+
+  fn frame_label_movie(swf_version: u8, label: &[u8]) -> Vec<u8> {
+    movie(swf_version, &frame_label_tag(label))
+  }
+
+  fn frame_label(tags: &[ast::Tag]) -> &str {
+    match tags.last() {
+      Some(ast::Tag::FrameLabel(label)) => &label.name,
+      tags => panic!("Expected a frame label, got: {:?}", tags),
+    }
+  }
+
+  #[test]
+  fn test_legacy_frame_label() {
+    let swf = frame_label_movie(5, b"caf\xe9 \x80");
+    let movie = crate::parse_swf(&swf).unwrap();
+    assert_eq!(frame_label(&movie.tags), "café €");
+    let movie = parse_swf_with_legacy_encoding(&swf, Some(LegacyEncoding::Raw)).unwrap();
+    assert_eq!(frame_label(&movie.tags), "caf\u{e9} \u{80}");
+  }
+
+  #[test]
+  fn test_legacy_frame_label_round_trip() {
+    let swf = frame_label_movie(5, b"caf\xe9");
+    let (movie, encoding) = crate::parse_swf_with_encoding(&swf).unwrap();
+    assert_eq!(frame_label(&movie.tags), "café");
+    assert_eq!(crate::emit_swf_with_encoding(&movie, &encoding).unwrap(), swf);
+  }
+
+  #[test]
+  fn test_legacy_strings_emit_round_trip() {
+    let tags: Vec<u8> = [
+      frame_label_tag(b"caf\xe9"),
+      // `Protect`
+      short_tag(24, b"caf\xe9\x00"),
+      // `EnableDebugger`
+      short_tag(58, b"caf\xe9\x00"),
+      // `DefineEditText`: id, empty bounds, `has_text` flag, variable name and text
+      short_tag(37, b"\x01\x00\x00\x80\x00caf\xe9\x00\xe9t\xe9\x00"),
+    ]
+    .concat();
+    let swf = movie(5, &tags);
+    let movie = crate::parse_swf(&swf).unwrap();
+    match &movie.tags[..] {
+      [
+        ast::Tag::FrameLabel(label),
+        ast::Tag::Protect(protect),
+        ast::Tag::EnableDebugger(debugger),
+        ast::Tag::DefineDynamicText(text),
+      ] => {
+        assert_eq!(label.name, "café");
+        assert_eq!(protect.password, "café");
+        assert_eq!(debugger.password, "café");
+        assert_eq!(text.variable_name.as_deref(), Some("café"));
+        assert_eq!(text.text.as_deref(), Some("été"));
+      }
+      tags => panic!("Unexpected tags: {:?}", tags),
+    }
+    let emitted = crate::emit_swf(&movie, ast::CompressionMethod::None).unwrap();
+    assert_eq!(emitted, swf);
+    assert_eq!(crate::parse_swf(&emitted).unwrap(), movie);
+  }
+
+  #[test]
+  fn test_legacy_strings_emit_unmappable() {
+    let movie = ast::Movie {
+      header: crate::parse_swf(&movie(5, &[])).unwrap().header,
+      tags: vec![ast::Tag::FrameLabel(ast::tags::FrameLabel {
+        name: String::from("テスト"),
+        is_anchor: false,
+      })],
+    };
+    assert!(crate::emit_swf(&movie, ast::CompressionMethod::None).is_err());
+    let encoding = Some(LegacyEncoding::ShiftJis);
+    let emitted = crate::emit_swf_with_legacy_encoding(&movie, ast::CompressionMethod::None, encoding).unwrap();
+    assert_eq!(emitted, frame_label_movie(5, b"\x83\x65\x83\x58\x83\x67"));
+  }
+
+  #[test]
+  fn test_legacy_shift_jis_frame_label() {
+    let swf = frame_label_movie(4, b"\x83\x65\x83\x58\x83\x67");
+    let movie = parse_swf_with_legacy_encoding(&swf, Some(LegacyEncoding::ShiftJis)).unwrap();
+    assert_eq!(frame_label(&movie.tags), "テスト");
+  }
+
+  #[test]
+  fn test_sniffed_shift_jis_frame_label() {
+    // `DefineFontInfo` with the Shift-JIS flag
+    let mut tags: Vec<u8> = vec![0x44, 0x03, 0x01, 0x00, 0x00, 0x10];
+    tags.extend_from_slice(&frame_label_tag(b"\x83\x65\x83\x58\x83\x67"));
+    let swf = movie(5, &tags);
+    let movie = crate::parse_swf(&swf).unwrap();
+    assert_eq!(frame_label(&movie.tags), "テスト");
+    let (movie, _) = crate::parse_swf_with_spans(&swf).unwrap();
+    assert_eq!(frame_label(&movie.tags), "テスト");
+    let (movie, _) = crate::parse_swf_with_diagnostics(&swf).unwrap();
+    assert_eq!(frame_label(&movie.tags), "テスト");
+  }
+
+  #[test]
+  fn test_legacy_tag_parsers() {
+    let tag = frame_label_tag(b"\x83\x65\x83\x58\x83\x67");
+    let (_, parsed) = crate::parse_tag(&tag, 5);
+    assert_eq!(frame_label(&parsed.into_iter().collect::<Vec<_>>()), "\u{192}e\u{192}X\u{192}g");
+    let (_, parsed) = crate::parse_tag_with_legacy_encoding(&tag, 5, LegacyEncoding::ShiftJis);
+    assert_eq!(frame_label(&parsed.into_iter().collect::<Vec<_>>()), "テスト");
+
+    let tags: Vec<ast::Tag> = crate::parse_swf_tags(&tag, 5)
+      .with_legacy_encoding(LegacyEncoding::ShiftJis)
+      .map(|(_, _, tag)| tag)
+      .collect();
+    assert_eq!(frame_label(&tags), "テスト");
+    let tags: Vec<ast::Tag> = crate::skim_tags(&tag, 5)
+      .with_legacy_encoding(LegacyEncoding::ShiftJis)
+      .map(|handle| handle.decode())
+      .collect();
+    assert_eq!(frame_label(&tags), "テスト");
+    let tags: Vec<ast::Tag> = crate::parse_borrowed_swf_tags(&tag, 5)
+      .with_legacy_encoding(LegacyEncoding::ShiftJis)
+      .map(|(_, _, tag)| tag.into_owned())
+      .collect();
+    assert_eq!(frame_label(&tags), "テスト");
+
+    let swf = frame_label_movie(5, b"\x83\x65\x83\x58\x83\x67");
+    let (_, mut parser) = match crate::streaming::parser::HeaderParser::new().header(&swf) {
+      Ok(ok) => ok,
+      Err((_, e)) => panic!("{:?}", e),
+    };
+    parser.set_legacy_encoding(LegacyEncoding::ShiftJis);
+    let tags: Vec<ast::Tag> = parser.tags(&[]).unwrap().unwrap();
+    assert_eq!(frame_label(&tags), "テスト");
+  }
+
+  #[test]
+  fn test_utf8_frame_label() {
+    let swf = frame_label_movie(6, "café".as_bytes());
+    let movie = parse_swf_with_legacy_encoding(&swf, Some(LegacyEncoding::ShiftJis)).unwrap();
+    assert_eq!(frame_label(&movie.tags), "café");
+    let swf = frame_label_movie(6, b"caf\xe9");
+    let movie = crate::parse_swf(&swf).unwrap();
+    assert!(matches!(&movie.tags[..], [ast::Tag::RawBody(_)]));
+  }
+
+  #[test]
+  fn test_legacy_encode() {
+    assert_eq!(LegacyEncoding::Windows1252.encode("café €").as_deref(), Some(&b"caf\xe9 \x80"[..]));
+    assert_eq!(LegacyEncoding::ShiftJis.encode("テスト").as_deref(), Some(&b"\x83\x65\x83\x58\x83\x67"[..]));
+    assert_eq!(LegacyEncoding::Raw.encode("caf\u{e9}").as_deref(), Some(&b"caf\xe9"[..]));
+    assert_eq!(LegacyEncoding::Raw.encode("€"), None);
+    assert_eq!(LegacyEncoding::Windows1252.encode("テスト"), None);
+  }
+
+  #[test]
+  fn test_default_legacy_encoding() {
+    let font_info = |language: ast::LanguageCode, is_shift_jis: bool| {
+      ast::Tag::DefineFontInfo(ast::tags::DefineFontInfo {
+        font_id: 1,
+        font_name: String::from("Test"),
+        is_bold: false,
+        is_italic: false,
+        is_ansi: false,
+        is_shift_jis,
+        is_small: false,
+        language,
+        code_units: Vec::new(),
+      })
+    };
+    assert_eq!(default_legacy_encoding(&[]), LegacyEncoding::Windows1252);
+    assert_eq!(
+      default_legacy_encoding(&[font_info(ast::LanguageCode::Auto, true)]),
+      LegacyEncoding::ShiftJis
+    );
+    assert_eq!(
+      default_legacy_encoding(&[
+        font_info(ast::LanguageCode::Auto, false),
+        font_info(ast::LanguageCode::Korean, false),
+      ]),
+      LegacyEncoding::EucKr
+    );
+  }
+}
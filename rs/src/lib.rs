@@ -1,18 +1,37 @@
+pub mod abc;
+pub mod avm1;
 pub mod complete;
+pub mod emitter;
+pub mod font;
+pub mod image;
+pub mod legacy;
+pub mod morph;
 mod stream_buffer;
 pub mod streaming;
+pub mod svg;
+pub mod text;
 
 pub use swf_types;
 
-pub use complete::tag::parse_tag;
-pub use complete::{parse_swf, SwfParseError};
+pub use complete::skim::{skim_tags, SkimIter, TagHandle};
+pub use complete::tag::{parse_swf_tags, parse_tag, parse_tag_with_legacy_encoding, TagIter};
+pub use complete::{parse_borrowed_movie, parse_borrowed_swf_tags, BorrowedTag, BorrowedTagIter};
+pub use complete::{
+  decompress_swf, parse_movie, parse_swf, parse_swf_with_diagnostics, parse_swf_with_encoding,
+  parse_swf_with_legacy_encoding, parse_swf_with_spans, MovieEncoding, SwfParseError, TagDiagnostic, TagDiagnosticKind,
+  TagSpan,
+};
+pub use emitter::{
+  emit_swf, emit_swf_with_encoding, emit_swf_with_legacy_encoding, emit_tag, emit_tag_with_legacy_encoding,
+  SwfEmitError,
+};
 
 #[cfg(test)]
 mod tests {
   use crate::parse_swf;
   use ::swf_types::Movie;
   use ::test_generator::test_resources;
-  use nom::IResult as NomResult;
+  use crate::streaming::error::NomResult;
   use std::io::Write;
   use std::path::Path;
 
@@ -23,7 +42,7 @@ mod tests {
     let path: &Path = Path::new(path);
     let _name = path
       .components()
-      .last()
+      .next_back()
       .unwrap()
       .as_os_str()
       .to_str()
@@ -58,7 +77,7 @@ mod tests {
         let path: &Path = Path::new(path);
         let _name = path
           .components()
-          .last()
+          .next_back()
           .unwrap()
           .as_os_str()
           .to_str()
@@ -0,0 +1,416 @@
+use swf_fixed::{Sfixed16P16, Sfixed8P8};
+use swf_types as ast;
+
+/// Returns the shape of a morph shape at `ratio`, from `0` (start shape) to `65535` (end shape).
+///
+/// The ratio is the one set by `PlaceObject2` and `PlaceObject3`. Edges, fill styles (colors, gradient stops and
+/// matrices) and line widths are interpolated linearly. When a straight edge is paired with a curved edge, the
+/// control point of the straight edge is its middle.
+///
+/// The points of the edges are interpolated between their absolute positions in both shapes, so the rounding
+/// errors don't accumulate along the path: closed contours stay closed.
+pub fn interpolate_morph_shape(shape: &ast::MorphShape, ratio: u16) -> ast::Shape {
+  let mut pens = Pens {
+    start: ORIGIN,
+    end: ORIGIN,
+    current: ORIGIN,
+  };
+  let records = shape
+    .records
+    .iter()
+    .map(|record| match record {
+      ast::MorphShapeRecord::Edge(edge) => ast::ShapeRecord::Edge(pens.interpolate_edge(edge, ratio)),
+      ast::MorphShapeRecord::StyleChange(change) => {
+        ast::ShapeRecord::StyleChange(ast::shape_records::StyleChange {
+          move_to: change.move_to.map(|start| {
+            // Style changes without `morph_move_to` move to the same point in both shapes
+            pens.move_to(start, change.morph_move_to.unwrap_or(start), ratio)
+          }),
+          left_fill: change.left_fill,
+          right_fill: change.right_fill,
+          line_style: change.line_style,
+          new_styles: change.new_styles.as_ref().map(|styles| interpolate_styles(styles, ratio)),
+        })
+      }
+    })
+    .collect();
+  ast::Shape {
+    initial_styles: interpolate_styles(&shape.initial_styles, ratio),
+    records,
+  }
+}
+
+fn lerp(start: i64, end: i64, ratio: u16) -> i64 {
+  start + ((end - start) as f64 * f64::from(ratio) / f64::from(u16::MAX)).round() as i64
+}
+
+fn lerp_i32(start: i32, end: i32, ratio: u16) -> i32 {
+  lerp(i64::from(start), i64::from(end), ratio) as i32
+}
+
+fn lerp_u16(start: u16, end: u16, ratio: u16) -> u16 {
+  lerp(i64::from(start), i64::from(end), ratio) as u16
+}
+
+fn lerp_u8(start: u8, end: u8, ratio: u16) -> u8 {
+  lerp(i64::from(start), i64::from(end), ratio) as u8
+}
+
+fn interpolate_vector(start: &ast::Vector2D, end: &ast::Vector2D, ratio: u16) -> ast::Vector2D {
+  ast::Vector2D {
+    x: lerp_i32(start.x, end.x, ratio),
+    y: lerp_i32(start.y, end.y, ratio),
+  }
+}
+
+fn add(point: ast::Vector2D, delta: ast::Vector2D) -> ast::Vector2D {
+  ast::Vector2D {
+    x: point.x.wrapping_add(delta.x),
+    y: point.y.wrapping_add(delta.y),
+  }
+}
+
+fn sub(point: ast::Vector2D, origin: ast::Vector2D) -> ast::Vector2D {
+  ast::Vector2D {
+    x: point.x.wrapping_sub(origin.x),
+    y: point.y.wrapping_sub(origin.y),
+  }
+}
+
+const ORIGIN: ast::Vector2D = ast::Vector2D { x: 0, y: 0 };
+
+/// Pen positions in the start shape, in the end shape and in the interpolated shape.
+struct Pens {
+  start: ast::Vector2D,
+  end: ast::Vector2D,
+  /// Rounded interpolated position, the deltas of the interpolated shape are relative to it.
+  current: ast::Vector2D,
+}
+
+impl Pens {
+  /// Moves the pens and returns the interpolated position.
+  fn move_to(&mut self, start: ast::Vector2D, end: ast::Vector2D, ratio: u16) -> ast::Vector2D {
+    self.start = start;
+    self.end = end;
+    self.current = interpolate_vector(&start, &end, ratio);
+    self.current
+  }
+
+  /// Interpolates the absolute points of the edge in both shapes and returns the deltas between the rounded points.
+  fn interpolate_edge(&mut self, edge: &ast::shape_records::MorphEdge, ratio: u16) -> ast::shape_records::Edge {
+    let middle = |delta: ast::Vector2D| ast::Vector2D {
+      x: delta.x / 2,
+      y: delta.y / 2,
+    };
+    let control_delta = match (edge.control_delta, edge.morph_control_delta) {
+      (None, None) => None,
+      (start, end) => {
+        let start = add(self.start, start.unwrap_or_else(|| middle(edge.delta)));
+        let end = add(self.end, end.unwrap_or_else(|| middle(edge.morph_delta)));
+        Some(sub(interpolate_vector(&start, &end, ratio), self.current))
+      }
+    };
+    let start = add(self.start, edge.delta);
+    let end = add(self.end, edge.morph_delta);
+    let anchor = interpolate_vector(&start, &end, ratio);
+    let delta = sub(anchor, self.current);
+    self.start = start;
+    self.end = end;
+    self.current = anchor;
+    ast::shape_records::Edge { delta, control_delta }
+  }
+}
+
+fn interpolate_styles(styles: &ast::MorphShapeStyles, ratio: u16) -> ast::ShapeStyles {
+  ast::ShapeStyles {
+    fill: styles.fill.iter().map(|style| interpolate_fill_style(style, ratio)).collect(),
+    line: styles.line.iter().map(|style| interpolate_line_style(style, ratio)).collect(),
+  }
+}
+
+fn interpolate_line_style(style: &ast::MorphLineStyle, ratio: u16) -> ast::LineStyle {
+  ast::LineStyle {
+    width: lerp_u16(style.width, style.morph_width, ratio),
+    start_cap: style.start_cap,
+    end_cap: style.end_cap,
+    join: style.join,
+    no_h_scale: style.no_h_scale,
+    no_v_scale: style.no_v_scale,
+    no_close: style.no_close,
+    pixel_hinting: style.pixel_hinting,
+    fill: interpolate_fill_style(&style.fill, ratio),
+  }
+}
+
+fn interpolate_fill_style(style: &ast::MorphFillStyle, ratio: u16) -> ast::FillStyle {
+  match style {
+    ast::MorphFillStyle::Bitmap(fill) => ast::FillStyle::Bitmap(ast::fill_styles::Bitmap {
+      bitmap_id: fill.bitmap_id,
+      matrix: interpolate_matrix(&fill.matrix, &fill.morph_matrix, ratio),
+      repeating: fill.repeating,
+      smoothed: fill.smoothed,
+    }),
+    ast::MorphFillStyle::FocalGradient(fill) => ast::FillStyle::FocalGradient(ast::fill_styles::FocalGradient {
+      matrix: interpolate_matrix(&fill.matrix, &fill.morph_matrix, ratio),
+      gradient: interpolate_gradient(&fill.gradient, ratio),
+      focal_point: Sfixed8P8::from_epsilons(lerp(
+        i64::from(fill.focal_point.epsilons),
+        i64::from(fill.morph_focal_point.epsilons),
+        ratio,
+      ) as i16),
+    }),
+    ast::MorphFillStyle::LinearGradient(fill) => ast::FillStyle::LinearGradient(ast::fill_styles::LinearGradient {
+      matrix: interpolate_matrix(&fill.matrix, &fill.morph_matrix, ratio),
+      gradient: interpolate_gradient(&fill.gradient, ratio),
+    }),
+    ast::MorphFillStyle::RadialGradient(fill) => ast::FillStyle::RadialGradient(ast::fill_styles::RadialGradient {
+      matrix: interpolate_matrix(&fill.matrix, &fill.morph_matrix, ratio),
+      gradient: interpolate_gradient(&fill.gradient, ratio),
+    }),
+    ast::MorphFillStyle::Solid(fill) => ast::FillStyle::Solid(ast::fill_styles::Solid {
+      color: interpolate_color(&fill.color, &fill.morph_color, ratio),
+    }),
+  }
+}
+
+fn interpolate_color(start: &ast::StraightSRgba8, end: &ast::StraightSRgba8, ratio: u16) -> ast::StraightSRgba8 {
+  ast::StraightSRgba8 {
+    r: lerp_u8(start.r, end.r, ratio),
+    g: lerp_u8(start.g, end.g, ratio),
+    b: lerp_u8(start.b, end.b, ratio),
+    a: lerp_u8(start.a, end.a, ratio),
+  }
+}
+
+fn interpolate_gradient(gradient: &ast::MorphGradient, ratio: u16) -> ast::Gradient {
+  ast::Gradient {
+    spread: gradient.spread,
+    color_space: gradient.color_space,
+    colors: gradient
+      .colors
+      .iter()
+      .map(|stop| ast::ColorStop {
+        ratio: lerp_u8(stop.ratio, stop.morph_ratio, ratio),
+        color: interpolate_color(&stop.color, &stop.morph_color, ratio),
+      })
+      .collect(),
+  }
+}
+
+fn interpolate_matrix(start: &ast::Matrix, end: &ast::Matrix, ratio: u16) -> ast::Matrix {
+  let fixed =
+    |start: Sfixed16P16, end: Sfixed16P16| Sfixed16P16::from_epsilons(lerp_i32(start.epsilons, end.epsilons, ratio));
+  ast::Matrix {
+    scale_x: fixed(start.scale_x, end.scale_x),
+    scale_y: fixed(start.scale_y, end.scale_y),
+    rotate_skew0: fixed(start.rotate_skew0, end.rotate_skew0),
+    rotate_skew1: fixed(start.rotate_skew1, end.rotate_skew1),
+    translate_x: lerp_i32(start.translate_x, end.translate_x, ratio),
+    translate_y: lerp_i32(start.translate_y, end.translate_y, ratio),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn color(r: u8, a: u8) -> ast::StraightSRgba8 {
+    ast::StraightSRgba8 { r, g: 0, b: 0, a }
+  }
+
+  fn morph_shape() -> ast::MorphShape {
+    ast::MorphShape {
+      initial_styles: ast::MorphShapeStyles {
+        fill: vec![ast::MorphFillStyle::Solid(ast::fill_styles::MorphSolid {
+          color: color(0, 255),
+          morph_color: color(200, 55),
+        })],
+        line: vec![ast::MorphLineStyle {
+          width: 20,
+          morph_width: 60,
+          start_cap: ast::CapStyle::Round,
+          end_cap: ast::CapStyle::Round,
+          join: ast::JoinStyle::Round,
+          no_h_scale: false,
+          no_v_scale: false,
+          no_close: false,
+          pixel_hinting: false,
+          fill: ast::MorphFillStyle::LinearGradient(ast::fill_styles::MorphLinearGradient {
+            matrix: ast::Matrix::default(),
+            morph_matrix: ast::Matrix {
+              translate_x: 100,
+              rotate_skew0: Sfixed16P16::from_epsilons(100),
+              ..ast::Matrix::default()
+            },
+            gradient: ast::MorphGradient {
+              spread: ast::GradientSpread::Pad,
+              color_space: ast::ColorSpace::SRgb,
+              colors: vec![ast::MorphColorStop {
+                ratio: 0,
+                morph_ratio: 100,
+                color: color(0, 0),
+                morph_color: color(100, 100),
+              }],
+            },
+          }),
+        }],
+      },
+      records: vec![
+        ast::MorphShapeRecord::StyleChange(ast::shape_records::MorphStyleChange {
+          move_to: Some(ast::Vector2D { x: 0, y: 0 }),
+          morph_move_to: Some(ast::Vector2D { x: 100, y: -100 }),
+          left_fill: Some(1),
+          right_fill: None,
+          line_style: Some(1),
+          new_styles: None,
+        }),
+        ast::MorphShapeRecord::Edge(ast::shape_records::MorphEdge {
+          delta: ast::Vector2D { x: 200, y: 0 },
+          morph_delta: ast::Vector2D { x: 0, y: 400 },
+          control_delta: None,
+          morph_control_delta: Some(ast::Vector2D { x: 100, y: 100 }),
+        }),
+        ast::MorphShapeRecord::Edge(ast::shape_records::MorphEdge {
+          delta: ast::Vector2D { x: -200, y: 0 },
+          morph_delta: ast::Vector2D { x: 0, y: -400 },
+          control_delta: None,
+          morph_control_delta: None,
+        }),
+      ],
+    }
+  }
+
+  #[test]
+  fn test_interpolate_morph_shape_ends() {
+    let shape = interpolate_morph_shape(&morph_shape(), 0);
+    assert_eq!(
+      shape.initial_styles.fill,
+      vec![ast::FillStyle::Solid(ast::fill_styles::Solid { color: color(0, 255) })]
+    );
+    assert_eq!(
+      shape.records[1],
+      ast::ShapeRecord::Edge(ast::shape_records::Edge {
+        delta: ast::Vector2D { x: 200, y: 0 },
+        control_delta: Some(ast::Vector2D { x: 100, y: 0 }),
+      })
+    );
+    let shape = interpolate_morph_shape(&morph_shape(), u16::MAX);
+    assert_eq!(shape.initial_styles.line[0].width, 60);
+    assert_eq!(
+      shape.records[0],
+      ast::ShapeRecord::StyleChange(ast::shape_records::StyleChange {
+        move_to: Some(ast::Vector2D { x: 100, y: -100 }),
+        left_fill: Some(1),
+        right_fill: None,
+        line_style: Some(1),
+        new_styles: None,
+      })
+    );
+  }
+
+  #[test]
+  fn test_interpolate_morph_shape_closed_contour() {
+    let edge = |delta: (i32, i32), morph_delta: (i32, i32), control: bool| {
+      ast::MorphShapeRecord::Edge(ast::shape_records::MorphEdge {
+        delta: ast::Vector2D { x: delta.0, y: delta.1 },
+        morph_delta: ast::Vector2D {
+          x: morph_delta.0,
+          y: morph_delta.1,
+        },
+        control_delta: if control { Some(ast::Vector2D { x: 1, y: 0 }) } else { None },
+        morph_control_delta: None,
+      })
+    };
+    let mut records = vec![ast::MorphShapeRecord::StyleChange(ast::shape_records::MorphStyleChange {
+      move_to: Some(ast::Vector2D { x: 5, y: 5 }),
+      morph_move_to: Some(ast::Vector2D { x: -5, y: 7 }),
+      left_fill: Some(1),
+      right_fill: None,
+      line_style: None,
+      new_styles: None,
+    })];
+    // 101 edges, alternately straight and curved, closed by a single edge
+    records.extend((0..101).map(|i| edge((1, 1), (0, -1), i % 2 == 1)));
+    records.push(edge((-101, -101), (0, 101), false));
+    let shape = ast::MorphShape {
+      records,
+      ..morph_shape()
+    };
+
+    for ratio in [1, 3, 12345, 32768, 65533] {
+      let interpolated = interpolate_morph_shape(&shape, ratio);
+      let mut position = ORIGIN;
+      let mut start = None;
+      for record in &interpolated.records {
+        match record {
+          ast::ShapeRecord::StyleChange(change) => {
+            position = change.move_to.unwrap();
+            start = Some(position);
+          }
+          ast::ShapeRecord::Edge(edge) => {
+            position = add(position, edge.delta);
+          }
+        }
+      }
+      assert_eq!(Some(position), start, "contour not closed at ratio {}", ratio);
+    }
+
+    // Each point is the rounded interpolation of the points of both shapes
+    let interpolated = interpolate_morph_shape(&shape, 32768);
+    assert_eq!(
+      &interpolated.records[1..3],
+      &[
+        ast::ShapeRecord::Edge(ast::shape_records::Edge {
+          delta: ast::Vector2D { x: 0, y: 0 },
+          control_delta: None,
+        }),
+        ast::ShapeRecord::Edge(ast::shape_records::Edge {
+          delta: ast::Vector2D { x: 1, y: 0 },
+          control_delta: Some(ast::Vector2D { x: 1, y: 0 }),
+        }),
+      ][..]
+    );
+  }
+
+  #[test]
+  fn test_interpolate_morph_shape_middle() {
+    let shape = interpolate_morph_shape(&morph_shape(), 32768);
+    assert_eq!(
+      shape.initial_styles.fill,
+      vec![ast::FillStyle::Solid(ast::fill_styles::Solid { color: color(100, 155) })]
+    );
+    let line = &shape.initial_styles.line[0];
+    assert_eq!(line.width, 40);
+    assert_eq!(
+      line.fill,
+      ast::FillStyle::LinearGradient(ast::fill_styles::LinearGradient {
+        matrix: ast::Matrix {
+          translate_x: 50,
+          rotate_skew0: Sfixed16P16::from_epsilons(50),
+          ..ast::Matrix::default()
+        },
+        gradient: ast::Gradient {
+          spread: ast::GradientSpread::Pad,
+          color_space: ast::ColorSpace::SRgb,
+          colors: vec![ast::ColorStop {
+            ratio: 50,
+            color: color(50, 50),
+          }],
+        },
+      })
+    );
+    assert_eq!(
+      &shape.records[1..],
+      &[
+        ast::ShapeRecord::Edge(ast::shape_records::Edge {
+          delta: ast::Vector2D { x: 100, y: 200 },
+          control_delta: Some(ast::Vector2D { x: 100, y: 50 }),
+        }),
+        ast::ShapeRecord::Edge(ast::shape_records::Edge {
+          delta: ast::Vector2D { x: -100, y: -200 },
+          control_delta: None,
+        }),
+      ][..]
+    );
+  }
+}
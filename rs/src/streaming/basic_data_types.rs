@@ -1,8 +1,11 @@
+use crate::legacy::LegacyEncoding;
+use crate::streaming::error::{LocatedError, NomResult};
 use half::f16;
+use nom::error::context;
 use nom::number::streaming::{
   be_u16 as parse_be_u16, le_i16 as parse_le_i16, le_i32 as parse_le_i32, le_u16 as parse_le_u16, le_u8 as parse_u8,
 };
-use nom::{IResult as NomResult, Needed};
+use nom::Needed;
 use swf_fixed::{Sfixed16P16, Sfixed8P8, Ufixed8P8};
 use swf_types as swf;
 use swf_types::LanguageCode;
@@ -34,7 +37,7 @@ pub fn parse_block_c_string(input: &[u8]) -> NomResult<&[u8], String> {
 
   match std::str::from_utf8(raw) {
     Ok(checked) => Ok((&[], checked.to_string())),
-    Err(_) => Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify))),
+    Err(_) => Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Verify))),
   }
 }
 
@@ -48,7 +51,48 @@ pub fn parse_c_string(input: &[u8]) -> NomResult<&[u8], String> {
 
   match std::str::from_utf8(raw) {
     Ok(checked) => Ok((input, checked.to_string())),
-    Err(_) => Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify))),
+    Err(_) => Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Verify))),
+  }
+}
+
+/// Last SWF version where strings use the legacy encoding of the system (ANSI, Shift-JIS, ...) instead of UTF-8.
+pub const LAST_LEGACY_STRING_VERSION: u8 = 5;
+
+/// Converts the bytes of a string to a Rust string according to the SWF version.
+///
+/// Strings of SWF 6 and later must be valid UTF-8. Older movies use the legacy encoding of the system of their
+/// author, `encoding`.
+fn decode_swf_string(raw: &[u8], swf_version: u8, encoding: LegacyEncoding) -> Option<String> {
+  if swf_version <= LAST_LEGACY_STRING_VERSION {
+    Some(encoding.decode(raw))
+  } else {
+    std::str::from_utf8(raw).ok().map(String::from)
+  }
+}
+
+/// Version-aware [[parse_block_c_string]]: decodes legacy (SWF 5 and earlier) strings with `encoding`.
+pub fn parse_swf_block_c_string(input: &[u8], swf_version: u8, encoding: LegacyEncoding) -> NomResult<&[u8], String> {
+  let raw = match memchr::memchr(0, input) {
+    Some(idx) => &input[0..idx],
+    None => input,
+  };
+
+  match decode_swf_string(raw, swf_version, encoding) {
+    Some(value) => Ok((&[], value)),
+    None => Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Verify))),
+  }
+}
+
+/// Version-aware [[parse_c_string]]: decodes legacy (SWF 5 and earlier) strings with `encoding`.
+pub fn parse_swf_c_string(input: &[u8], swf_version: u8, encoding: LegacyEncoding) -> NomResult<&[u8], String> {
+  const NUL_BYTE: &[u8] = b"\x00";
+
+  let (input, raw) = nom::bytes::streaming::take_until(NUL_BYTE)(input)?;
+  let (input, _) = nom::bytes::streaming::take(NUL_BYTE.len())(input)?;
+
+  match decode_swf_string(raw, swf_version, encoding) {
+    Some(value) => Ok((input, value)),
+    None => Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Verify))),
   }
 }
 
@@ -85,6 +129,7 @@ pub fn parse_fixed8_p8_bits(input: (&[u8], usize), n: usize) -> NomResult<(&[u8]
 }
 
 /// Generates a bits parser reading a `i16` over `n` bits.
+#[allow(clippy::type_complexity)]
 pub fn do_parse_i16_bits(n: usize) -> impl Fn((&[u8], usize)) -> NomResult<(&[u8], usize), i16> {
   debug_assert!(n <= 16);
   move |input: (&[u8], usize)| {
@@ -110,6 +155,7 @@ pub fn parse_i16_bits(input: (&[u8], usize), n: usize) -> NomResult<(&[u8], usiz
 }
 
 /// Generates a bits parser reading a `i32` over `n` bits.
+#[allow(clippy::type_complexity)]
 pub fn do_parse_i32_bits(n: usize) -> impl Fn((&[u8], usize)) -> NomResult<(&[u8], usize), i32> {
   debug_assert!(n <= 32);
   move |input: (&[u8], usize)| {
@@ -134,6 +180,7 @@ pub fn parse_i32_bits(input: (&[u8], usize), n: usize) -> NomResult<(&[u8], usiz
 }
 
 /// Generates a bits parser reading a `u32` over `n` bits.
+#[allow(clippy::type_complexity)]
 pub fn do_parse_u32_bits(n: usize) -> impl Fn((&[u8], usize)) -> NomResult<(&[u8], usize), u32> {
   debug_assert!(n <= 32);
   move |input: (&[u8], usize)| nom::bits::streaming::take::<_, u32, _, _>(n)(input)
@@ -177,7 +224,7 @@ pub fn parse_le_fixed16_p16(input: &[u8]) -> NomResult<&[u8], Sfixed16P16> {
 
 pub fn parse_rect(input: &[u8]) -> NomResult<&[u8], swf::Rect> {
   use nom::bits::bits;
-  bits(parse_rect_bits)(input)
+  context("parse_rect", bits(parse_rect_bits))(input)
 }
 
 pub fn parse_rect_bits(input: (&[u8], usize)) -> NomResult<(&[u8], usize), swf::Rect> {
@@ -229,6 +276,7 @@ pub fn skip_bits((input_slice, bit_pos): (&[u8], usize), n: usize) -> NomResult<
 }
 
 /// Generates a bits parser reading a `u16` over `n` bits.
+#[allow(clippy::type_complexity)]
 pub fn do_parse_u16_bits(n: usize) -> impl Fn((&[u8], usize)) -> NomResult<(&[u8], usize), u16> {
   move |input: (&[u8], usize)| nom::bits::streaming::take::<_, u16, _, _>(n)(input)
 }
@@ -248,14 +296,14 @@ pub fn parse_language_code(input: &[u8]) -> NomResult<&[u8], swf::LanguageCode>
     3 => swf::LanguageCode::Korean,
     4 => swf::LanguageCode::SimplifiedChinese,
     5 => swf::LanguageCode::TraditionalChinese,
-    _ => return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch))),
+    _ => return Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch))),
   };
   Ok((input, lang))
 }
 
 pub fn parse_matrix(input: &[u8]) -> NomResult<&[u8], swf::Matrix> {
   use nom::bits::bits;
-  bits(parse_matrix_bits)(input)
+  context("parse_matrix", bits(parse_matrix_bits))(input)
 }
 
 pub fn parse_matrix_bits(input: (&[u8], usize)) -> NomResult<(&[u8], usize), swf::Matrix> {
@@ -299,9 +347,16 @@ pub fn parse_named_id(input: &[u8]) -> NomResult<&[u8], swf::NamedId> {
   Ok((input, swf::NamedId { id, name }))
 }
 
+/// Version-aware [[parse_named_id]]: decodes legacy (SWF 5 and earlier) names with `encoding`.
+pub fn parse_swf_named_id(input: &[u8], swf_version: u8, encoding: LegacyEncoding) -> NomResult<&[u8], swf::NamedId> {
+  let (input, id) = parse_le_u16(input)?;
+  let (input, name) = parse_swf_c_string(input, swf_version, encoding)?;
+  Ok((input, swf::NamedId { id, name }))
+}
+
 pub fn parse_color_transform(input: &[u8]) -> NomResult<&[u8], swf::ColorTransform> {
   use nom::bits::bits;
-  bits(parse_color_transform_bits)(input)
+  context("parse_color_transform", bits(parse_color_transform_bits))(input)
 }
 
 #[allow(unused_variables)]
@@ -340,7 +395,10 @@ pub fn parse_color_transform_bits(input: (&[u8], usize)) -> NomResult<(&[u8], us
 
 pub fn parse_color_transform_with_alpha(input: &[u8]) -> NomResult<&[u8], swf::ColorTransformWithAlpha> {
   use nom::bits::bits;
-  bits(parse_color_transform_with_alpha_bits)(input)
+  context(
+    "parse_color_transform_with_alpha",
+    bits(parse_color_transform_with_alpha_bits),
+  )(input)
 }
 
 #[allow(unused_variables)]
@@ -384,6 +442,7 @@ pub fn parse_color_transform_with_alpha_bits(
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod tests {
   use nom::Needed;
 
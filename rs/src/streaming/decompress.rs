@@ -22,14 +22,61 @@ pub(crate) fn decompress_zlib(_bytes: &[u8]) -> Result<Output<'_>, Box<dyn Error
   Err(Box::<dyn Error>::from("unsupported SWF compression method `Deflate`: compile `swf-parser` with the `deflate` feature"))
 }
 
+/// Decompresses an SWF LZMA payload, `payload_len` is the length of the uncompressed payload.
+///
+/// Unlike `.lzma` files, the SWF payload starts with the compressed length (`u32`) followed by the
+/// LZMA properties (5 bytes): the uncompressed length is only available in the SWF signature.
 #[cfg(feature = "lzma")]
-pub(crate) fn decompress_lzma(mut bytes: &[u8]) -> Result<Output<'_>, Box<dyn Error>> {
+pub(crate) fn decompress_lzma(bytes: &[u8], payload_len: usize) -> Result<Output<'_>, Box<dyn Error>> {
+  use lzma_rs::decompress::{Options, UnpackedSize};
+
+  if bytes.len() < 4 {
+    return Err(Box::<dyn Error>::from("incomplete LZMA payload"));
+  }
+  let mut bytes = &bytes[4..];
   let mut out = Vec::new();
-  lzma_rs::lzma_decompress(&mut bytes, &mut out).map_err(Box::new)?;
+  let options = Options {
+    unpacked_size: UnpackedSize::UseProvided(Some(payload_len as u64)),
+    ..Default::default()
+  };
+  lzma_rs::lzma_decompress_with_options(&mut bytes, &mut out, &options).map_err(Box::new)?;
   Ok((bytes, out.into()))
 }
 
 #[cfg(not(feature = "lzma"))]
-pub(crate) fn decompress_lzma(_bytes: &[u8]) -> Result<Output<'_>, Box<dyn Error>> {
+pub(crate) fn decompress_lzma(_bytes: &[u8], _payload_len: usize) -> Result<Output<'_>, Box<dyn Error>> {
   Err(Box::<dyn Error>::from("unsupported SWF compression method `Lzma`: compile `swf-parser` with the `lzma` feature"))
 }
+
+#[cfg(all(test, feature = "lzma"))]
+mod tests {
+  use super::*;
+
+  /// Converts a `.lzma` file to the layout used by SWF files: the compressed length replaces the uncompressed
+  /// length and is moved before the LZMA properties.
+  fn to_swf_lzma(lzma: &[u8]) -> Vec<u8> {
+    let data = &lzma[13..];
+    let mut swf_lzma: Vec<u8> = Vec::new();
+    swf_lzma.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    swf_lzma.extend_from_slice(&lzma[..5]);
+    swf_lzma.extend_from_slice(data);
+    swf_lzma
+  }
+
+  #[test]
+  fn test_decompress_lzma() {
+    let payload: &[u8] = &include_bytes!("../../../tests/movies/blank/main.swf")[8..];
+    let mut lzma: Vec<u8> = Vec::new();
+    lzma_rs::lzma_compress(&mut &payload[..], &mut lzma).unwrap();
+    let swf_lzma = to_swf_lzma(&lzma);
+    let (_, actual) = decompress_lzma(&swf_lzma, payload.len()).unwrap();
+    assert_eq!(actual, payload);
+    // The uncompressed length is only known from the signature
+    assert!(decompress_lzma(&swf_lzma, payload.len() + 1).is_err());
+  }
+
+  #[test]
+  fn test_decompress_lzma_incomplete() {
+    assert!(decompress_lzma(&[0x10, 0x00, 0x00], 0).is_err());
+  }
+}
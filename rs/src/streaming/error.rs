@@ -0,0 +1,85 @@
+use nom::error::{ContextError, ErrorKind, ParseError};
+use nom::ErrorConvert;
+
+/// Result type of the `nom` parsers of this crate.
+pub type NomResult<I, O, E = LocatedError<I>> = nom::IResult<I, O, E>;
+
+/// Error of the `nom` parsers of this crate.
+///
+/// In addition to the failing input, it records the bit position for bit-level parsers and
+/// the names of the parsers wrapped with `nom::error::context`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocatedError<I> {
+  /// Remaining input when the error occurred.
+  pub input: I,
+  /// Position of the failing bit in the first byte of `input`, `0` is the most significant bit.
+  ///
+  /// It is always `0` for errors created by byte-level parsers.
+  pub bit: usize,
+  /// Kind of the `nom` parser that failed.
+  pub code: ErrorKind,
+  /// Name of the innermost named parser containing the failure.
+  pub parser: Option<&'static str>,
+  /// Name of the outermost named parser containing the failure.
+  pub root_parser: Option<&'static str>,
+}
+
+impl<I> LocatedError<I> {
+  /// Creates an error at the start of `input`, outside of any named parser.
+  pub fn new(input: I, code: ErrorKind) -> Self {
+    Self {
+      input,
+      bit: 0,
+      code,
+      parser: None,
+      root_parser: None,
+    }
+  }
+}
+
+impl<I> ParseError<I> for LocatedError<I> {
+  fn from_error_kind(input: I, code: ErrorKind) -> Self {
+    Self::new(input, code)
+  }
+
+  fn append(_input: I, _kind: ErrorKind, other: Self) -> Self {
+    other
+  }
+}
+
+impl<I> ContextError<I> for LocatedError<I> {
+  fn add_context(_input: I, ctx: &'static str, mut other: Self) -> Self {
+    if other.parser.is_none() {
+      other.parser = Some(ctx);
+    }
+    other.root_parser = Some(ctx);
+    other
+  }
+}
+
+/// Conversion used when leaving a bit-level parser (see `nom::bits::bits`)
+impl<I> ErrorConvert<LocatedError<I>> for LocatedError<(I, usize)> {
+  fn convert(self) -> LocatedError<I> {
+    let (input, bit) = self.input;
+    LocatedError {
+      input,
+      bit,
+      code: self.code,
+      parser: self.parser,
+      root_parser: self.root_parser,
+    }
+  }
+}
+
+/// Conversion used when entering a bit-level parser
+impl<I> ErrorConvert<LocatedError<(I, usize)>> for LocatedError<I> {
+  fn convert(self) -> LocatedError<(I, usize)> {
+    LocatedError {
+      input: (self.input, self.bit),
+      bit: 0,
+      code: self.code,
+      parser: self.parser,
+      root_parser: self.root_parser,
+    }
+  }
+}
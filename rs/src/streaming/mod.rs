@@ -1,5 +1,6 @@
 pub mod basic_data_types;
 pub(crate) mod decompress;
+pub mod error;
 pub mod movie;
 pub mod parser;
 pub mod tag;
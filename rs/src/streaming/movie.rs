@@ -1,8 +1,10 @@
+use crate::legacy::LegacyEncoding;
 use crate::streaming::basic_data_types::{parse_le_ufixed8_p8, parse_rect};
 use crate::streaming::tag::parse_tag;
 use crate::streaming::decompress;
+use crate::streaming::error::{LocatedError, NomResult};
 use nom::number::streaming::{le_u16 as parse_le_u16, le_u32 as parse_le_u32, le_u8 as parse_u8};
-use nom::{IResult as NomResult, Needed};
+use nom::Needed;
 use std::convert::TryFrom;
 use swf_types as ast;
 
@@ -12,7 +14,7 @@ pub fn parse_swf(input: &[u8]) -> NomResult<&[u8], ast::Movie> {
   let result = match signature.compression_method {
     ast::CompressionMethod::None => decompress::decompress_none(input),
     ast::CompressionMethod::Deflate => decompress::decompress_zlib(input),
-    ast::CompressionMethod::Lzma => decompress::decompress_lzma(input),
+    ast::CompressionMethod::Lzma => decompress::decompress_lzma(input, signature.uncompressed_file_length.saturating_sub(8)),
   };
   let (input, payload) = result.unwrap();
 
@@ -21,8 +23,8 @@ pub fn parse_swf(input: &[u8]) -> NomResult<&[u8], ast::Movie> {
   match parse_movie(&payload, signature.swf_version) {
     // TODO: should we assert that the payload is fully consumed?
     Ok((_payload, movie)) => Ok((input, movie)),
-    Err(nom::Err::Error(e)) => Err(nom::Err::Error(nom::error::Error::new(&[], e.code))),
-    Err(nom::Err::Failure(e)) => Err(nom::Err::Failure(nom::error::Error::new(&[], e.code))),
+    Err(nom::Err::Error(e)) => Err(nom::Err::Error(LocatedError::new(&[][..], e.code))),
+    Err(nom::Err::Failure(e)) => Err(nom::Err::Failure(LocatedError::new(&[][..], e.code))),
     Err(nom::Err::Incomplete(n)) => Err(nom::Err::Incomplete(n)),
   }
 }
@@ -51,7 +53,7 @@ pub fn parse_compression_method(input: &[u8]) -> NomResult<&[u8], ast::Compressi
     b"FWS" => Ok((input, ast::CompressionMethod::None)),
     b"CWS" => Ok((input, ast::CompressionMethod::Deflate)),
     b"ZWS" => Ok((input, ast::CompressionMethod::Lzma)),
-    _ => Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Switch))),
+    _ => Err(nom::Err::Error(LocatedError::new(input, nom::error::ErrorKind::Switch))),
   }
 }
 
@@ -81,7 +83,7 @@ pub(crate) fn parse_movie(input: &[u8], swf_version: u8) -> NomResult<&[u8], ast
 pub(crate) fn parse_tag_block_string(mut input: &[u8], swf_version: u8) -> NomResult<&[u8], Vec<ast::Tag>> {
   let mut result: Vec<ast::Tag> = Vec::new();
   loop {
-    input = match parse_tag(input, swf_version) {
+    input = match parse_tag(input, swf_version, LegacyEncoding::default()) {
       Ok((input, Some(tag))) => {
         result.push(tag);
         input
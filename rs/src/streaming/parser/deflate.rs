@@ -1,59 +1,140 @@
+use crate::complete::tag::NestedTag;
 use crate::stream_buffer::StreamBuffer;
-use inflate::InflateStream;
-use swf_types::{Header as SwfHeader, SwfSignature, Tag};
-use super::{ParseTagsError, SimpleStream};
+use miniz_oxide::inflate::stream::{inflate, InflateState};
+use miniz_oxide::{DataFormat, MZError, MZFlush, MZStatus};
+use swf_types::{CompressionMethod, Header as SwfHeader, SwfSignature};
+use super::{HeaderParserError, ParseTagsError, SimpleStream};
+
+/// Size of the buffer receiving the decompressed bytes
+const OUTPUT_CHUNK_LEN: usize = 1 << 15;
 
 /// State of the `Deflate` payload parser
 pub(crate) struct DeflateStream<B: StreamBuffer> {
-  inflater: InflateStream,
+  inflater: Box<InflateState>,
+  /// Buffer receiving the decompressed bytes, reused across writes
+  output: Vec<u8>,
   simple: SimpleStream<B>,
+  /// The compressed payload is corrupted, no more data is decompressed
+  is_invalid: bool,
+  /// The zlib stream is complete: the final block and the Adler-32 trailer were consumed
+  is_end: bool,
+  /// There is compressed data after the end of the zlib stream
+  has_trailing_data: bool,
 }
 
 impl<B: StreamBuffer> DeflateStream<B> {
   pub(crate) fn new(buffer: B, signature: SwfSignature) -> Self {
-    let inflater = inflate::InflateStream::from_zlib();
+    let inflater = InflateState::new_boxed(DataFormat::Zlib);
     let simple = SimpleStream::new(B::new(), signature);
-    let mut deflate_stream = Self { inflater, simple };
-    deflate_stream.write(buffer.get());
-    deflate_stream
+    let mut stream = Self {
+      inflater,
+      output: vec![0; OUTPUT_CHUNK_LEN],
+      simple,
+      is_invalid: false,
+      is_end: false,
+      has_trailing_data: false,
+    };
+    stream.write(buffer.get());
+    stream
   }
 
   /// Appends data to the internal buffer.
+  ///
+  /// If the payload is corrupted, the data decompressed so far is kept and the error is reported
+  /// once it is consumed.
   pub(crate) fn write(&mut self, mut bytes: &[u8]) {
-    while !bytes.is_empty() {
-      match self.inflater.update(bytes) {
-        Ok((read_count, chunk)) => {
-          bytes = &bytes[read_count..];
-          self.simple.write(chunk);
+    if self.is_invalid {
+      return;
+    }
+    if self.is_end {
+      self.has_trailing_data |= !bytes.is_empty();
+      return;
+    }
+    loop {
+      let result = inflate(&mut self.inflater, bytes, &mut self.output, MZFlush::None);
+      self.simple.write(&self.output[..result.bytes_written]);
+      bytes = &bytes[result.bytes_consumed..];
+      match result.status {
+        Ok(MZStatus::StreamEnd) => {
+          // The inflater consumes the input up to the end of the trailer, the rest is trailing data
+          self.is_end = true;
+          self.has_trailing_data = !bytes.is_empty();
+          return;
+        }
+        Ok(_) => {}
+        // No progress is possible without more input
+        Err(MZError::Buf) => return,
+        Err(_) => {
+          self.is_invalid = true;
+          return;
         }
-        Err(e) => panic!("Failed to write Deflate payload {}", e),
+      }
+      // Keep going while there is input left or the output chunk was filled
+      if (bytes.is_empty() && result.bytes_written < self.output.len())
+        || (result.bytes_consumed == 0 && result.bytes_written == 0)
+      {
+        return;
       }
     }
   }
 
   /// Finishes parsing the SWF header from the internal buffer.
-  pub(crate) fn header(self) -> Result<(SwfHeader, Self), Self> {
+  #[allow(clippy::result_large_err)]
+  pub(crate) fn header(self) -> Result<(SwfHeader, Self), (Self, HeaderParserError)> {
     match self.simple.header() {
       Ok((header, simple)) => Ok((
         header,
         Self {
           inflater: self.inflater,
+          output: self.output,
           simple,
+          is_invalid: self.is_invalid,
+          is_end: self.is_end,
+          has_trailing_data: self.has_trailing_data,
         },
       )),
-      Err(simple) => Err(Self {
-        inflater: self.inflater,
-        simple,
-      }),
+      Err((simple, e)) => {
+        let e = match e {
+          HeaderParserError::Incomplete if self.is_invalid => HeaderParserError::InvalidPayload(CompressionMethod::Deflate),
+          HeaderParserError::Incomplete if self.is_end => HeaderParserError::InvalidHeader,
+          e => e,
+        };
+        Err((
+          Self {
+            inflater: self.inflater,
+            output: self.output,
+            simple,
+            is_invalid: self.is_invalid,
+            is_end: self.is_end,
+            has_trailing_data: self.has_trailing_data,
+          },
+          e,
+        ))
+      }
     }
   }
 
-  /// Parses the available tags from the internal buffer.
+  /// Parses the next tag from the internal buffer with `parse`, see `SimpleStream::next_tag_with`.
   ///
   /// Returns `Ok(None)` if parsing is complete (there are no more tags).
-  /// Returns `Ok(Some(Vec<Tag>))` when some tags are available. `Vec` is non-empty.
-  /// Returns `Err(())` when there's not enough data or an error occurs.
-  pub(crate) fn tags(&mut self) -> Result<Option<Vec<Tag>>, ParseTagsError> {
-    self.simple.tags()
+  /// Returns `Ok(Some(T))` when a tag is available.
+  /// Returns `Err(ParseTagsError::InvalidPayload(_))` when there's not enough data and the payload is corrupted.
+  /// Returns `Err(ParseTagsError::UnexpectedEndOfStream)` when there's not enough data and the compressed
+  /// stream is complete.
+  /// Returns `Err(ParseTagsError::TrailingData)` once all the tags were returned, if there is data
+  /// after the end of the tags or of the compressed stream.
+  pub(crate) fn next_tag_with<T>(
+    &mut self,
+    parse: impl FnOnce(NestedTag, u8) -> T,
+  ) -> Result<Option<T>, ParseTagsError> {
+    match self.simple.next_tag_with(parse) {
+      Err(ParseTagsError::Incomplete { .. } | ParseTagsError::InvalidTagHeader | ParseTagsError::UnexpectedEndOfStream)
+        if self.is_invalid => Err(ParseTagsError::InvalidPayload(CompressionMethod::Deflate)),
+      Err(ParseTagsError::Incomplete { .. } | ParseTagsError::InvalidTagHeader) if self.is_end => {
+        Err(ParseTagsError::UnexpectedEndOfStream)
+      }
+      Ok(None) if self.has_trailing_data => Err(ParseTagsError::TrailingData),
+      result => result,
+    }
   }
 }
@@ -1,42 +1,777 @@
+use crate::complete::tag::NestedTag;
 use crate::stream_buffer::StreamBuffer;
-use swf_types::{Header as SwfHeader, SwfSignature, Tag};
+use swf_types::{CompressionMethod, Header as SwfHeader, SwfSignature};
 
-use super::{ParseTagsError, SimpleStream};
+use super::{HeaderParserError, ParseTagsError, SimpleStream};
 
-// TODO: Send PR to lzma-rs to support LZMA stream parsing
-struct LzmaParser {}
+/// Length of the SWF signature, it is not part of the compressed payload
+const SWF_SIGNATURE_LEN: usize = 8;
+/// Length of the LZMA header in SWF files: compressed length (4 bytes), then
+/// the LZMA properties (1 byte) and dictionary size (4 bytes)
+const SWF_LZMA_HEADER_LEN: usize = 9;
+/// Length of the data used to initialize the range decoder
+const RANGE_DECODER_INIT_LEN: usize = 5;
+/// Maximum count of input bytes consumed when decoding a single LZMA symbol
+/// (`LZMA_REQUIRED_INPUT_MAX` in the LZMA SDK)
+const MAX_SYMBOL_INPUT_LEN: usize = 20;
+
+const STATE_COUNT: usize = 12;
+const POS_STATE_COUNT: usize = 1 << 4;
+const MATCH_MIN_LEN: usize = 2;
+const END_POS_MODEL_INDEX: u32 = 14;
+const TOP_VALUE: u32 = 1 << 24;
+const BIT_MODEL_TOTAL_BITS: u32 = 11;
+const BIT_MODEL_TOTAL: u16 = 1 << BIT_MODEL_TOTAL_BITS;
+const MOVE_BITS: u32 = 5;
+const PROB_INIT: u16 = BIT_MODEL_TOTAL / 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LzmaError {
+  /// The LZMA properties byte is out of range
+  InvalidProperties,
+  /// The compressed data is corrupted
+  InvalidData,
+}
+
+/// Error while decoding a single symbol
+enum SymbolError {
+  /// More input is required to decode the symbol
+  Incomplete,
+  Invalid,
+}
+
+type SymbolResult<T> = Result<T, SymbolError>;
+
+/// Incremental decoder for the LZMA payload of SWF files.
+///
+/// `lzma-rs` only flushes its output once its dictionary is full so it can't
+/// be used to parse tags as bytes arrive: this decoder emits the decompressed
+/// bytes as soon as the corresponding symbols are decoded.
+struct LzmaParser {
+  /// Compressed bytes received but not decoded yet
+  input: Vec<u8>,
+  /// Expected length of the decompressed payload
+  unpacked_len: u64,
+  state: LzmaState,
+}
+
+enum LzmaState {
+  /// Waiting for the SWF LZMA header and range decoder initialization data
+  Header,
+  /// Decoding LZMA symbols
+  Data(Box<LzmaDecoder>),
+  /// Decoded the whole payload, the data may still contain an end of payload marker
+  Marker(Box<LzmaDecoder>),
+  /// Finished decoding the payload
+  End {
+    /// There is compressed data after the end of the payload
+    has_trailing_data: bool,
+  },
+}
 
 impl LzmaParser {
-  pub fn new() -> Self {
-    unimplemented!("LZMA decompression is unsupported in streaming mode");
+  pub fn new(unpacked_len: u64) -> Self {
+    Self {
+      input: Vec::new(),
+      unpacked_len,
+      state: LzmaState::Header,
+    }
+  }
+
+  /// Appends compressed bytes and returns the newly decompressed bytes.
+  ///
+  /// If the data is corrupted, the bytes decompressed before the corrupted symbol are returned
+  /// with the error.
+  pub fn update(&mut self, bytes: &[u8]) -> (Vec<u8>, Option<LzmaError>) {
+    let mut output: Vec<u8> = Vec::new();
+    let error = self.decode(bytes, &mut output).err();
+    (output, error)
+  }
+
+  /// The compressed stream is complete: it reached the end of payload marker or the expected length.
+  pub fn is_end(&self) -> bool {
+    matches!(self.state, LzmaState::Marker(_) | LzmaState::End { .. })
+  }
+
+  /// There is compressed data after the end of the payload.
+  pub fn has_trailing_data(&self) -> bool {
+    matches!(self.state, LzmaState::End { has_trailing_data: true })
+  }
+
+  fn decode(&mut self, bytes: &[u8], output: &mut Vec<u8>) -> Result<(), LzmaError> {
+    if let LzmaState::End { has_trailing_data } = &mut self.state {
+      *has_trailing_data |= !bytes.is_empty();
+      return Ok(());
+    }
+    self.input.extend_from_slice(bytes);
+
+    if let LzmaState::Header = self.state {
+      const LEN: usize = SWF_LZMA_HEADER_LEN + RANGE_DECODER_INIT_LEN;
+      if self.input.len() < LEN {
+        return Ok(());
+      }
+      // The compressed length (first 4 bytes) is not needed to decode the payload.
+      let props = self.input[4];
+      let dict_size = u32::from_le_bytes([self.input[5], self.input[6], self.input[7], self.input[8]]);
+      let code = u32::from_be_bytes([self.input[10], self.input[11], self.input[12], self.input[13]]);
+      let decoder = LzmaDecoder::new(props, dict_size, code, self.unpacked_len)?;
+      self.input.drain(..LEN);
+      self.state = if self.unpacked_len == 0 {
+        LzmaState::Marker(Box::new(decoder))
+      } else {
+        LzmaState::Data(Box::new(decoder))
+      };
+    }
+
+    if let LzmaState::Data(decoder) = &mut self.state {
+      let mut input: &[u8] = &self.input;
+      let mut is_end = false;
+      let mut result: Result<(), LzmaError> = Ok(());
+      while decoder.window.total < self.unpacked_len {
+        if input.len() < MAX_SYMBOL_INPUT_LEN {
+          // Not enough input to be sure that the symbol can be decoded: check it without updating the
+          // model before decoding it.
+          match decoder.model.peek(&mut &input[..], &decoder.window) {
+            Ok(_) => {}
+            Err(SymbolError::Incomplete) => break,
+            Err(SymbolError::Invalid) => {
+              result = Err(LzmaError::InvalidData);
+              break;
+            }
+          }
+        }
+        let symbol = match decoder.model.decode(&mut input, &decoder.window) {
+          Ok(symbol) => symbol,
+          Err(_) => {
+            result = Err(LzmaError::InvalidData);
+            break;
+          }
+        };
+        match symbol {
+          Symbol::Literal(byte) => decoder.window.push(byte, output),
+          Symbol::Match { len, dist } => {
+            if !decoder.window.copy(dist, len, self.unpacked_len, output) {
+              result = Err(LzmaError::InvalidData);
+              break;
+            }
+          }
+          Symbol::End => {
+            is_end = true;
+            break;
+          }
+        }
+      }
+      let consumed = self.input.len() - input.len();
+      self.input.drain(..consumed);
+      result?;
+      if is_end {
+        self.state = LzmaState::End {
+          has_trailing_data: !self.input.is_empty(),
+        };
+        self.input.clear();
+      } else if decoder.window.total >= self.unpacked_len {
+        match std::mem::replace(&mut self.state, LzmaState::Header) {
+          LzmaState::Data(decoder) => self.state = LzmaState::Marker(decoder),
+          _ => unreachable!(),
+        }
+      }
+    }
+
+    if let LzmaState::Marker(decoder) = &mut self.state {
+      if self.input.is_empty() {
+        return Ok(());
+      }
+      // The payload length is known so the end of payload marker is optional: anything else is
+      // trailing data.
+      let mut input: &[u8] = &self.input;
+      let has_trailing_data = match decoder.model.peek(&mut input, &decoder.window) {
+        Ok(Symbol::End) => !input.is_empty(),
+        Ok(_) | Err(SymbolError::Invalid) => true,
+        Err(SymbolError::Incomplete) => return Ok(()),
+      };
+      self.input.clear();
+      self.state = LzmaState::End { has_trailing_data };
+    }
+    Ok(())
+  }
+}
+
+/// Decoded LZMA symbol
+enum Symbol {
+  Literal(u8),
+  /// Copy `len` bytes starting `dist` bytes before the current position
+  Match { len: usize, dist: usize },
+  /// End of payload marker
+  End,
+}
+
+struct LzmaDecoder {
+  model: Model,
+  window: Window,
+}
+
+impl LzmaDecoder {
+  fn new(props: u8, dict_size: u32, code: u32, unpacked_len: u64) -> Result<Self, LzmaError> {
+    if props >= 9 * 5 * 5 {
+      return Err(LzmaError::InvalidProperties);
+    }
+    let props = u32::from(props);
+    let (lc, lp, pb) = (props % 9, (props / 9) % 5, props / 45);
+    let dict_size = usize::try_from(dict_size.max(1 << 12)).unwrap_or(usize::MAX);
+    let window_len = usize::try_from(unpacked_len).map_or(dict_size, |len| dict_size.min(len));
+    Ok(Self {
+      model: Model::new(lc, lp, pb, code),
+      window: Window::new(window_len),
+    })
+  }
+}
+
+/// Sliding window over the decompressed bytes, used to resolve matches
+struct Window {
+  buffer: Vec<u8>,
+  /// Maximum length of `buffer`
+  len: usize,
+  /// Index of the oldest byte once `buffer` is full
+  pos: usize,
+  /// Total count of decompressed bytes
+  total: u64,
+}
+
+impl Window {
+  fn new(len: usize) -> Self {
+    Self {
+      buffer: Vec::new(),
+      len,
+      pos: 0,
+      total: 0,
+    }
+  }
+
+  /// Returns the byte `dist` bytes before the current position (`dist >= 1`).
+  fn get(&self, dist: usize) -> Option<u8> {
+    if dist == 0 || dist > self.buffer.len() {
+      None
+    } else if self.buffer.len() < self.len {
+      Some(self.buffer[self.buffer.len() - dist])
+    } else {
+      Some(self.buffer[(self.pos + self.len - dist) % self.len])
+    }
+  }
+
+  fn push(&mut self, byte: u8, output: &mut Vec<u8>) {
+    if self.buffer.len() < self.len {
+      self.buffer.push(byte);
+    } else {
+      self.buffer[self.pos] = byte;
+      self.pos = (self.pos + 1) % self.len;
+    }
+    self.total += 1;
+    output.push(byte);
+  }
+
+  /// Copies a match, truncated to `max_total` bytes. Returns `false` if the distance is invalid.
+  fn copy(&mut self, dist: usize, len: usize, max_total: u64, output: &mut Vec<u8>) -> bool {
+    for _ in 0..len {
+      if self.total >= max_total {
+        break;
+      }
+      match self.get(dist) {
+        Some(byte) => self.push(byte, output),
+        None => return false,
+      }
+    }
+    true
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct RangeDecoder {
+  range: u32,
+  code: u32,
+  /// Decode the bits without updating their probabilities
+  dry_run: bool,
+}
+
+impl RangeDecoder {
+  fn normalize(&mut self, input: &mut &[u8]) -> SymbolResult<()> {
+    if self.range < TOP_VALUE {
+      let (&byte, rest) = input.split_first().ok_or(SymbolError::Incomplete)?;
+      *input = rest;
+      self.range <<= 8;
+      self.code = (self.code << 8) | u32::from(byte);
+    }
+    Ok(())
+  }
+
+  fn bit(&mut self, prob: &mut u16, input: &mut &[u8]) -> SymbolResult<usize> {
+    let bound = (self.range >> BIT_MODEL_TOTAL_BITS) * u32::from(*prob);
+    let bit = if self.code < bound {
+      self.range = bound;
+      if !self.dry_run {
+        *prob += (BIT_MODEL_TOTAL - *prob) >> MOVE_BITS;
+      }
+      0
+    } else {
+      self.range -= bound;
+      self.code -= bound;
+      if !self.dry_run {
+        *prob -= *prob >> MOVE_BITS;
+      }
+      1
+    };
+    self.normalize(input)?;
+    Ok(bit)
+  }
+
+  fn direct_bits(&mut self, count: u32, input: &mut &[u8]) -> SymbolResult<u32> {
+    let mut result: u32 = 0;
+    for _ in 0..count {
+      self.range >>= 1;
+      let bit = if self.code >= self.range {
+        self.code -= self.range;
+        1
+      } else {
+        0
+      };
+      result = (result << 1) | bit;
+      self.normalize(input)?;
+    }
+    Ok(result)
+  }
+
+  fn bit_tree(&mut self, probs: &mut [u16], bits: u32, input: &mut &[u8]) -> SymbolResult<u32> {
+    let mut m: usize = 1;
+    for _ in 0..bits {
+      m = (m << 1) | self.bit(&mut probs[m], input)?;
+    }
+    Ok((m - (1 << bits)) as u32)
+  }
+
+  fn reverse_bit_tree(&mut self, probs: &mut [u16], bits: u32, input: &mut &[u8]) -> SymbolResult<u32> {
+    let mut m: usize = 1;
+    let mut result: u32 = 0;
+    for i in 0..bits {
+      let bit = self.bit(&mut probs[m], input)?;
+      m = (m << 1) | bit;
+      result |= (bit as u32) << i;
+    }
+    Ok(result)
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct LenDecoder {
+  choice: u16,
+  choice2: u16,
+  low: [[u16; 1 << 3]; POS_STATE_COUNT],
+  mid: [[u16; 1 << 3]; POS_STATE_COUNT],
+  high: [u16; 1 << 8],
+}
+
+impl LenDecoder {
+  fn new() -> Self {
+    Self {
+      choice: PROB_INIT,
+      choice2: PROB_INIT,
+      low: [[PROB_INIT; 1 << 3]; POS_STATE_COUNT],
+      mid: [[PROB_INIT; 1 << 3]; POS_STATE_COUNT],
+      high: [PROB_INIT; 1 << 8],
+    }
+  }
+
+  fn decode(&mut self, rc: &mut RangeDecoder, pos_state: usize, input: &mut &[u8]) -> SymbolResult<usize> {
+    let len = if rc.bit(&mut self.choice, input)? == 0 {
+      rc.bit_tree(&mut self.low[pos_state], 3, input)?
+    } else if rc.bit(&mut self.choice2, input)? == 0 {
+      8 + rc.bit_tree(&mut self.mid[pos_state], 3, input)?
+    } else {
+      16 + rc.bit_tree(&mut self.high, 8, input)?
+    };
+    Ok(len as usize)
+  }
+}
+
+/// Probabilities and state of the LZMA decoder, excluding the window
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Model {
+  rc: RangeDecoder,
+  lc: u32,
+  lp: u32,
+  pb: u32,
+  literal: Vec<u16>,
+  is_match: [u16; STATE_COUNT * POS_STATE_COUNT],
+  is_rep: [u16; STATE_COUNT],
+  is_rep_g0: [u16; STATE_COUNT],
+  is_rep_g1: [u16; STATE_COUNT],
+  is_rep_g2: [u16; STATE_COUNT],
+  is_rep0_long: [u16; STATE_COUNT * POS_STATE_COUNT],
+  pos_slot: [[u16; 1 << 6]; 4],
+  pos_special: [u16; 115],
+  align: [u16; 1 << 4],
+  len: LenDecoder,
+  rep_len: LenDecoder,
+  state: usize,
+  reps: [usize; 4],
+}
+
+impl Model {
+  fn new(lc: u32, lp: u32, pb: u32, code: u32) -> Self {
+    Self {
+      rc: RangeDecoder {
+        range: u32::MAX,
+        code,
+        dry_run: false,
+      },
+      lc,
+      lp,
+      pb,
+      literal: vec![PROB_INIT; 0x300 << (lc + lp)],
+      is_match: [PROB_INIT; STATE_COUNT * POS_STATE_COUNT],
+      is_rep: [PROB_INIT; STATE_COUNT],
+      is_rep_g0: [PROB_INIT; STATE_COUNT],
+      is_rep_g1: [PROB_INIT; STATE_COUNT],
+      is_rep_g2: [PROB_INIT; STATE_COUNT],
+      is_rep0_long: [PROB_INIT; STATE_COUNT * POS_STATE_COUNT],
+      pos_slot: [[PROB_INIT; 1 << 6]; 4],
+      pos_special: [PROB_INIT; 115],
+      align: [PROB_INIT; 1 << 4],
+      len: LenDecoder::new(),
+      rep_len: LenDecoder::new(),
+      state: 0,
+      reps: [0; 4],
+    }
+  }
+
+  /// Decodes the next symbol without updating the model.
+  ///
+  /// A symbol reads each of its probabilities at most once, so decoding it again with the same input gives the same
+  /// symbol. This avoids copying the whole model (up to several megabytes) when the input may be incomplete.
+  fn peek(&mut self, input: &mut &[u8], window: &Window) -> SymbolResult<Symbol> {
+    let (rc, state, reps) = (self.rc.clone(), self.state, self.reps);
+    self.rc.dry_run = true;
+    let result = self.decode(input, window);
+    self.rc = rc;
+    self.state = state;
+    self.reps = reps;
+    result
+  }
+
+  fn decode(&mut self, input: &mut &[u8], window: &Window) -> SymbolResult<Symbol> {
+    let state = self.state;
+    let pos_state = (window.total as usize) & ((1 << self.pb) - 1);
+
+    if self.rc.bit(&mut self.is_match[(state << 4) + pos_state], input)? == 0 {
+      let prev_byte = usize::from(window.get(1).unwrap_or(0));
+      let lit_state = (((window.total as usize) & ((1 << self.lp) - 1)) << self.lc) + (prev_byte >> (8 - self.lc));
+      let probs = &mut self.literal[0x300 * lit_state..][..0x300];
+      let mut symbol: usize = 1;
+      if state >= 7 {
+        let mut match_byte = usize::from(window.get(self.reps[0] + 1).ok_or(SymbolError::Invalid)?);
+        while symbol < 0x100 {
+          let match_bit = (match_byte >> 7) & 1;
+          match_byte <<= 1;
+          let bit = self.rc.bit(&mut probs[((1 + match_bit) << 8) + symbol], input)?;
+          symbol = (symbol << 1) | bit;
+          if match_bit != bit {
+            break;
+          }
+        }
+      }
+      while symbol < 0x100 {
+        symbol = (symbol << 1) | self.rc.bit(&mut probs[symbol], input)?;
+      }
+      self.state = match state {
+        0..=3 => 0,
+        4..=9 => state - 3,
+        _ => state - 6,
+      };
+      return Ok(Symbol::Literal((symbol - 0x100) as u8));
+    }
+
+    if self.rc.bit(&mut self.is_rep[state], input)? == 1 {
+      if window.total == 0 {
+        return Err(SymbolError::Invalid);
+      }
+      if self.rc.bit(&mut self.is_rep_g0[state], input)? == 0 {
+        if self.rc.bit(&mut self.is_rep0_long[(state << 4) + pos_state], input)? == 0 {
+          self.state = if state < 7 { 9 } else { 11 };
+          return Ok(Symbol::Match { len: 1, dist: self.reps[0] + 1 });
+        }
+      } else {
+        let dist = if self.rc.bit(&mut self.is_rep_g1[state], input)? == 0 {
+          self.reps[1]
+        } else {
+          let dist = if self.rc.bit(&mut self.is_rep_g2[state], input)? == 0 {
+            self.reps[2]
+          } else {
+            let dist = self.reps[3];
+            self.reps[3] = self.reps[2];
+            dist
+          };
+          self.reps[2] = self.reps[1];
+          dist
+        };
+        self.reps[1] = self.reps[0];
+        self.reps[0] = dist;
+      }
+      let len = self.rep_len.decode(&mut self.rc, pos_state, input)?;
+      self.state = if state < 7 { 8 } else { 11 };
+      return Ok(Symbol::Match {
+        len: len + MATCH_MIN_LEN,
+        dist: self.reps[0] + 1,
+      });
+    }
+
+    self.reps[3] = self.reps[2];
+    self.reps[2] = self.reps[1];
+    self.reps[1] = self.reps[0];
+    let len = self.len.decode(&mut self.rc, pos_state, input)?;
+    self.state = if state < 7 { 7 } else { 10 };
+    let dist = self.decode_distance(len, input)?;
+    if dist == u32::MAX {
+      return Ok(Symbol::End);
+    }
+    self.reps[0] = dist as usize;
+    Ok(Symbol::Match {
+      len: len + MATCH_MIN_LEN,
+      dist: self.reps[0] + 1,
+    })
+  }
+
+  fn decode_distance(&mut self, len: usize, input: &mut &[u8]) -> SymbolResult<u32> {
+    let pos_slot = self.rc.bit_tree(&mut self.pos_slot[len.min(3)], 6, input)?;
+    if pos_slot < 4 {
+      return Ok(pos_slot);
+    }
+    let direct_bits = (pos_slot >> 1) - 1;
+    let dist = (2 | (pos_slot & 1)) << direct_bits;
+    if pos_slot < END_POS_MODEL_INDEX {
+      let probs = &mut self.pos_special[(dist - pos_slot) as usize..];
+      Ok(dist + self.rc.reverse_bit_tree(probs, direct_bits, input)?)
+    } else {
+      let high = self.rc.direct_bits(direct_bits - 4, input)? << 4;
+      Ok(dist + high + self.rc.reverse_bit_tree(&mut self.align, 4, input)?)
+    }
   }
 }
 
 /// State of the `Lzma` payload parser
 pub(crate) struct LzmaStream<B: StreamBuffer> {
-  #[allow(dead_code)]
   lzma_parser: LzmaParser,
   simple: SimpleStream<B>,
+  /// The compressed payload is corrupted, no more data is decompressed
+  is_invalid: bool,
 }
 
 impl<B: StreamBuffer> LzmaStream<B> {
   pub(crate) fn new(buffer: B, signature: SwfSignature) -> Self {
-    let lzma_parser = LzmaParser::new();
+    let unpacked_len = signature.uncompressed_file_length.saturating_sub(SWF_SIGNATURE_LEN) as u64;
+    let lzma_parser = LzmaParser::new(unpacked_len);
     let simple = SimpleStream::new(B::new(), signature);
-    let mut stream = Self { lzma_parser, simple };
+    let mut stream = Self {
+      lzma_parser,
+      simple,
+      is_invalid: false,
+    };
     stream.write(buffer.get());
     stream
   }
 
-  pub(crate) fn write(&mut self, mut _bytes: &[u8]) {
-    unreachable!()
+  /// Appends data to the internal buffer.
+  ///
+  /// If the payload is corrupted, the data decompressed so far is kept and the error is reported
+  /// once it is consumed.
+  pub(crate) fn write(&mut self, bytes: &[u8]) {
+    if self.is_invalid {
+      return;
+    }
+    let (chunk, error) = self.lzma_parser.update(bytes);
+    self.simple.write(&chunk);
+    self.is_invalid = error.is_some();
   }
 
-  pub(crate) fn header(self) -> Result<(SwfHeader, Self), Self> {
-    unreachable!()
+  /// Finishes parsing the SWF header from the internal buffer.
+  pub(crate) fn header(self) -> Result<(SwfHeader, Self), (Self, HeaderParserError)> {
+    match self.simple.header() {
+      Ok((header, simple)) => Ok((
+        header,
+        Self {
+          lzma_parser: self.lzma_parser,
+          simple,
+          is_invalid: self.is_invalid,
+        },
+      )),
+      Err((simple, e)) => {
+        let e = match e {
+          HeaderParserError::Incomplete if self.is_invalid => HeaderParserError::InvalidPayload(CompressionMethod::Lzma),
+          HeaderParserError::Incomplete if self.lzma_parser.is_end() => HeaderParserError::InvalidHeader,
+          e => e,
+        };
+        Err((
+          Self {
+            lzma_parser: self.lzma_parser,
+            simple,
+            is_invalid: self.is_invalid,
+          },
+          e,
+        ))
+      }
+    }
   }
 
-  pub(crate) fn tags(&mut self) -> Result<Option<Vec<Tag>>, ParseTagsError> {
-    self.simple.tags()
+  /// Parses the next tag from the internal buffer with `parse`, see `SimpleStream::next_tag_with`.
+  ///
+  /// Returns `Ok(None)` if parsing is complete (there are no more tags).
+  /// Returns `Ok(Some(T))` when a tag is available.
+  /// Returns `Err(ParseTagsError::InvalidPayload(_))` when there's not enough data and the payload is corrupted.
+  /// Returns `Err(ParseTagsError::UnexpectedEndOfStream)` when there's not enough data and the compressed
+  /// stream is complete.
+  /// Returns `Err(ParseTagsError::TrailingData)` once all the tags were returned, if there is data
+  /// after the end of the tags or of the compressed stream.
+  pub(crate) fn next_tag_with<T>(
+    &mut self,
+    parse: impl FnOnce(NestedTag, u8) -> T,
+  ) -> Result<Option<T>, ParseTagsError> {
+    match self.simple.next_tag_with(parse) {
+      Err(ParseTagsError::Incomplete { .. } | ParseTagsError::InvalidTagHeader | ParseTagsError::UnexpectedEndOfStream)
+        if self.is_invalid => Err(ParseTagsError::InvalidPayload(CompressionMethod::Lzma)),
+      Err(ParseTagsError::Incomplete { .. } | ParseTagsError::InvalidTagHeader) if self.lzma_parser.is_end() => {
+        Err(ParseTagsError::UnexpectedEndOfStream)
+      }
+      Ok(None) if self.lzma_parser.has_trailing_data() => Err(ParseTagsError::TrailingData),
+      result => result,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// `xz --format=lzma -9` output for `sample_text()`, it uses matches, repeated
+  /// matches and ends with an end of payload marker.
+  const SAMPLE_LZMA: &str = "5d00000004ffffffffffffffff003a184925a5d2e2c27a18b4fe867b057fb917fa1531176b3562168d8946f067fdd7bd8ac803ba3f375f5d6e240af407414e2f1b120d25dce9e5c5e2de686cf9867a7620f568ad780d1cf77ffd4dd680";
+
+  fn sample_text() -> Vec<u8> {
+    (0..24)
+      .flat_map(|i| format!("tag {}: DefineShape, PlaceObject2, ShowFrame; ", i % 7).into_bytes())
+      .collect()
+  }
+
+  /// Converts a `.lzma` file to the layout used by SWF files.
+  fn to_swf_lzma(lzma: &[u8]) -> Vec<u8> {
+    let data = &lzma[13..];
+    let mut swf_lzma: Vec<u8> = Vec::new();
+    swf_lzma.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    swf_lzma.extend_from_slice(&lzma[..5]);
+    swf_lzma.extend_from_slice(data);
+    swf_lzma
+  }
+
+  fn from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+      .step_by(2)
+      .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+      .collect()
+  }
+
+  #[test]
+  fn test_lzma_parser() {
+    let expected = sample_text();
+    let input = to_swf_lzma(&from_hex(SAMPLE_LZMA));
+    let mut parser = LzmaParser::new(expected.len() as u64);
+    let mut actual: Vec<u8> = Vec::new();
+    for byte in input.iter() {
+      let (chunk, error) = parser.update(&[*byte]);
+      assert_eq!(error, None);
+      actual.extend(chunk);
+    }
+    assert_eq!(actual, expected);
+    assert!(parser.is_end());
+    assert!(!parser.has_trailing_data());
+
+    let mut parser = LzmaParser::new(expected.len() as u64);
+    assert_eq!(parser.update(&input), (expected, None));
+    assert!(parser.is_end());
+    assert!(!parser.has_trailing_data());
+  }
+
+  #[test]
+  fn test_lzma_model_peek() {
+    let input = to_swf_lzma(&from_hex(SAMPLE_LZMA));
+    let code = u32::from_be_bytes([input[10], input[11], input[12], input[13]]);
+    let mut model = Model::new(3, 0, 2, code);
+    let window = Window::new(1 << 16);
+    let data = &input[SWF_LZMA_HEADER_LEN + RANGE_DECODER_INIT_LEN..];
+    let initial = model.clone();
+    assert!(matches!(model.peek(&mut &data[..0], &window), Err(SymbolError::Incomplete)));
+    assert_eq!(model, initial);
+    let mut peeked: &[u8] = data;
+    assert!(matches!(model.peek(&mut peeked, &window), Ok(Symbol::Literal(b't'))));
+    assert_eq!(model, initial);
+    let mut decoded: &[u8] = data;
+    assert!(matches!(model.decode(&mut decoded, &window), Ok(Symbol::Literal(b't'))));
+    assert_eq!(decoded.len(), peeked.len());
+    assert_ne!(model, initial);
+  }
+
+  #[test]
+  fn test_lzma_parser_corrupted() {
+    let mut input = to_swf_lzma(&from_hex(SAMPLE_LZMA));
+    input[4] = 0xff;
+    let mut parser = LzmaParser::new(sample_text().len() as u64);
+    assert_eq!(parser.update(&input), (Vec::new(), Some(LzmaError::InvalidProperties)));
+  }
+
+  #[test]
+  fn test_lzma_parser_corrupted_data() {
+    let expected = sample_text();
+    let mut input = to_swf_lzma(&from_hex(SAMPLE_LZMA));
+    let index = 70;
+    input[index] ^= 0xff;
+    let mut parser = LzmaParser::new(expected.len() as u64);
+    let (actual, error) = parser.update(&input);
+    assert_eq!(error, Some(LzmaError::InvalidData));
+    // The symbols preceding the corrupted byte are decoded
+    assert_eq!(actual[..200], expected[..200]);
+
+    // Same output as when the bytes are received one at a time
+    let mut parser = LzmaParser::new(expected.len() as u64);
+    let mut byte_by_byte: Vec<u8> = Vec::new();
+    for byte in input.iter() {
+      let (chunk, error) = parser.update(&[*byte]);
+      byte_by_byte.extend(chunk);
+      if error.is_some() {
+        break;
+      }
+    }
+    assert_eq!(byte_by_byte, actual);
+  }
+
+  #[test]
+  fn test_lzma_parser_early_end() {
+    let expected = sample_text();
+    let input = to_swf_lzma(&from_hex(SAMPLE_LZMA));
+    let mut parser = LzmaParser::new(expected.len() as u64 + 10);
+    assert_eq!(parser.update(&input), (expected, None));
+    assert!(parser.is_end());
+    assert!(!parser.has_trailing_data());
+  }
+
+  #[test]
+  fn test_lzma_parser_trailing_data() {
+    let expected = sample_text();
+    let mut input = to_swf_lzma(&from_hex(SAMPLE_LZMA));
+    input.push(0);
+    let mut parser = LzmaParser::new(expected.len() as u64);
+    assert_eq!(parser.update(&input), (expected.clone(), None));
+    assert!(parser.has_trailing_data());
+
+    let input = to_swf_lzma(&from_hex(SAMPLE_LZMA));
+    let mut parser = LzmaParser::new(expected.len() as u64);
+    assert_eq!(parser.update(&input), (expected, None));
+    assert!(!parser.has_trailing_data());
+    assert_eq!(parser.update(&[0]), (Vec::new(), None));
+    assert!(parser.has_trailing_data());
   }
 }
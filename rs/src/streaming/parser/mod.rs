@@ -1,5 +1,8 @@
 use std::fmt;
 
+use crate::complete::span::{parse_tag_with_spans, TagSpan};
+use crate::complete::tag::{parse_tag_body, NestedTag};
+use crate::legacy::LegacyEncoding;
 use crate::stream_buffer::{FlatBuffer, StreamBuffer};
 use crate::streaming::movie::parse_swf_signature;
 use swf_types::CompressionMethod;
@@ -46,8 +49,14 @@ enum InnerHeaderParser {
 pub enum HeaderParserError {
   /// Failed to parse the header due to a disabled feature in `swf-parser`
   MissingFeature(&'static str),
-  /// Other error (todo: replace this variant to provide more details)
-  Other,
+  /// Not enough data to parse the header, more bytes are needed
+  Incomplete,
+  /// The SWF signature is invalid (unknown compression method)
+  InvalidSignature,
+  /// The SWF header is invalid
+  InvalidHeader,
+  /// The compressed payload is corrupted
+  InvalidPayload(CompressionMethod),
 }
 
 impl std::error::Error for HeaderParserError {}
@@ -60,11 +69,16 @@ impl fmt::Display for HeaderParserError {
           "unsupported compression type in SWF header: compile `swf-parser` with the `{}` feature",
           feat,
         ),
-        HeaderParserError::Other => f.write_str("couldn't parse SWF header"),
+        HeaderParserError::Incomplete => f.write_str("incomplete SWF header"),
+        HeaderParserError::InvalidSignature => f.write_str("invalid SWF signature"),
+        HeaderParserError::InvalidHeader => f.write_str("invalid SWF header"),
+        HeaderParserError::InvalidPayload(method) => write!(f, "invalid SWF payload for compression method {:?}", method),
     }
   }
 }
 
+// The parser is handed back through the `Err` variant so parsing can resume with more bytes
+#[allow(clippy::result_large_err)]
 impl HeaderParser {
   /// Creates a new empty streaming parser.
   pub fn new() -> Self {
@@ -73,9 +87,10 @@ impl HeaderParser {
 
   /// Appends `bytes` to the internal buffer and tries to parse the SWF header.
   ///
-  /// If there is not enough data to parse the SWF header, it returns an error containing a
-  /// `HeaderParser` to continue parsing when more data is available.
-  /// If the data is unreadable (e.g. due to an invalid compression) it returns a failure (TODO).
+  /// If there is not enough data to parse the SWF header, it returns a `HeaderParserError::Incomplete`
+  /// error containing a `HeaderParser` to continue parsing when more data is available.
+  /// If the data is unreadable (e.g. due to an invalid compression) it returns the corresponding
+  /// error: parsing can't be resumed.
   /// If there is enough data to parse the header, it returns an `Ok` result with the parsed header
   /// and a `TagParser` to start parsing the SWF tags.
   ///
@@ -87,11 +102,11 @@ impl HeaderParser {
         let (input, parser) = Self::parser_from_signature(&mut buffer, bytes)?;
         parser.header(input)
       }
-      InnerHeaderParser::Simple(stream) => HeaderParser::simple_header(stream, bytes).map_err(|this| (this, HeaderParserError::Other)),
+      InnerHeaderParser::Simple(stream) => HeaderParser::simple_header(stream, bytes),
       #[cfg(feature="lzma")]
-      InnerHeaderParser::Lzma(stream) => HeaderParser::lzma_header(stream, bytes).map_err(|this| (this, HeaderParserError::Other)),
+      InnerHeaderParser::Lzma(stream) => HeaderParser::lzma_header(stream, bytes),
       #[cfg(feature="deflate")]
-      InnerHeaderParser::Deflate(stream) => HeaderParser::deflate_header(stream, bytes).map_err(|this| (this, HeaderParserError::Other)),
+      InnerHeaderParser::Deflate(stream) => HeaderParser::deflate_header(stream, bytes),
     }
   }
 
@@ -104,7 +119,13 @@ impl HeaderParser {
     });
     let (input, signature) = match consumed_and_sig {
       Ok((off, signature)) => (&buffer[off..], signature),
-      Err(_) => return Err((Self(InnerHeaderParser::Signature(std::mem::take(buffer))), HeaderParserError::Other)),
+      Err(e) => {
+        let err = match e {
+          nom::Err::Incomplete(_) => HeaderParserError::Incomplete,
+          nom::Err::Error(_) | nom::Err::Failure(_) => HeaderParserError::InvalidSignature,
+        };
+        return Err((Self(InnerHeaderParser::Signature(std::mem::take(buffer))), err));
+      }
     };
 
     let buffer: FlatBuffer = FlatBuffer::new();
@@ -123,31 +144,31 @@ impl HeaderParser {
   }
 
   /// Finish parsing the header from an uncompressed payload.
-  fn simple_header(mut stream: SimpleStream<FlatBuffer>, bytes: &[u8]) -> Result<(SwfHeader, TagParser), Self> {
+  fn simple_header(mut stream: SimpleStream<FlatBuffer>, bytes: &[u8]) -> Result<(SwfHeader, TagParser), (Self, HeaderParserError)> {
     stream.write(bytes);
     match stream.header() {
-      Ok((header, stream)) => Ok((header, TagParser(InnerTagParser::Simple(stream)))),
-      Err(stream) => Err(Self(InnerHeaderParser::Simple(stream))),
+      Ok((header, stream)) => Ok((header, TagParser::from_inner(InnerTagParser::Simple(stream)))),
+      Err((stream, e)) => Err((Self(InnerHeaderParser::Simple(stream)), e)),
     }
   }
 
   /// Finish parsing the header from a LZMA-compressed payload.
   #[cfg(feature="lzma")]
-  fn lzma_header(mut stream: LzmaStream<FlatBuffer>, bytes: &[u8]) -> Result<(SwfHeader, TagParser), Self> {
+  fn lzma_header(mut stream: LzmaStream<FlatBuffer>, bytes: &[u8]) -> Result<(SwfHeader, TagParser), (Self, HeaderParserError)> {
     stream.write(bytes);
     match stream.header() {
-      Ok((header, stream)) => Ok((header, TagParser(InnerTagParser::Lzma(stream)))),
-      Err(stream) => Err(Self(InnerHeaderParser::Lzma(stream))),
+      Ok((header, stream)) => Ok((header, TagParser::from_inner(InnerTagParser::Lzma(stream)))),
+      Err((stream, e)) => Err((Self(InnerHeaderParser::Lzma(stream)), e)),
     }
   }
 
   /// Finish parsing the header from a deflate-compressed payload.
   #[cfg(feature="deflate")]
-  fn deflate_header(mut stream: DeflateStream<FlatBuffer>, bytes: &[u8]) -> Result<(SwfHeader, TagParser), Self> {
+  fn deflate_header(mut stream: DeflateStream<FlatBuffer>, bytes: &[u8]) -> Result<(SwfHeader, TagParser), (Self, HeaderParserError)> {
     stream.write(bytes);
     match stream.header() {
-      Ok((header, stream)) => Ok((header, TagParser(InnerTagParser::Deflate(stream)))),
-      Err(stream) => Err(Self(InnerHeaderParser::Deflate(stream))),
+      Ok((header, stream)) => Ok((header, TagParser::from_inner(InnerTagParser::Deflate(stream)))),
+      Err((stream, e)) => Err((Self(InnerHeaderParser::Deflate(stream)), e)),
     }
   }
 }
@@ -168,7 +189,15 @@ impl Default for HeaderParser {
 ///
 /// This struct is logically an enum where each variant represents the state
 /// of the parser. See `InnerTagParser` for details on these states.
-pub struct TagParser(InnerTagParser);
+pub struct TagParser {
+  inner: InnerTagParser,
+  /// Count of top-level tags parsed so far
+  tag_count: usize,
+  /// Error that stopped the last call to `tags` after some tags were returned, reported by the next call
+  pending_error: Option<ParseTagsError>,
+  /// Encoding of the strings of SWF 5 (and earlier) movies
+  legacy_encoding: LegacyEncoding,
+}
 
 enum InnerTagParser {
   /// Parse tags from an uncompressed stream
@@ -181,46 +210,194 @@ enum InnerTagParser {
   Lzma(LzmaStream<FlatBuffer>),
 }
 
-// TODO: Implement proper error type
-#[derive(Debug)]
-pub struct ParseTagsError;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ParseTagsError {
+  /// Not enough data to parse the next tag, more bytes are needed
+  ///
+  /// `needed` is the count of missing bytes of the (decompressed) payload to complete the tag.
+  Incomplete { needed: usize },
+  /// The length of the next tag exceeds the payload length declared in the SWF signature
+  InvalidTagHeader,
+  /// The payload length declared in the SWF signature was reached before the end of the tags
+  UnexpectedEndOfStream,
+  /// The compressed payload is corrupted
+  InvalidPayload(CompressionMethod),
+  /// The payload contains data after the end of the tags
+  ///
+  /// All the tags were already returned: the movie is complete.
+  TrailingData,
+}
 
 impl std::error::Error for ParseTagsError {}
 
 impl fmt::Display for ParseTagsError {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    f.write_str("failed to parse SWF tag")
+    match self {
+      ParseTagsError::Incomplete { needed } => write!(f, "incomplete SWF tag: {} more bytes are needed", needed),
+      ParseTagsError::InvalidTagHeader => f.write_str("SWF tag length exceeds the payload length"),
+      ParseTagsError::UnexpectedEndOfStream => f.write_str("unexpected end of SWF payload"),
+      ParseTagsError::InvalidPayload(method) => write!(f, "invalid SWF payload for compression method {:?}", method),
+      ParseTagsError::TrailingData => f.write_str("trailing data after the end of the SWF tags"),
+    }
   }
 }
 
 impl TagParser {
+  fn from_inner(inner: InnerTagParser) -> Self {
+    Self {
+      inner,
+      tag_count: 0,
+      pending_error: None,
+      legacy_encoding: LegacyEncoding::default(),
+    }
+  }
+
+  /// Sets the encoding of the strings of SWF 5 (and earlier) movies, Windows-1252 by default.
+  ///
+  /// The encoding applies to the tags parsed after this call.
+  pub fn set_legacy_encoding(&mut self, encoding: LegacyEncoding) {
+    self.legacy_encoding = encoding;
+  }
+
   /// Appends the provided bytes to the internal buffer and tries to parse most of the tags.
   /// Return `None` if it has finished parsing the movie.
   ///
-  /// TODO: `impl Iterator<Item=Tag>` instead of `Vec<Tag>`
+  /// `ParseTagsError::Incomplete` means that more bytes are needed. `ParseTagsError::InvalidTagHeader` and
+  /// `ParseTagsError::UnexpectedEndOfStream` are reported when the payload length declared in the signature
+  /// is exceeded: parsing still resumes if more bytes are provided. `ParseTagsError::UnexpectedEndOfStream`
+  /// is also reported when the compressed stream ends before the tags. Other errors are final.
+  ///
+  /// If an error other than `ParseTagsError::Incomplete` stops parsing after some tags, the tags are returned
+  /// and the error is returned by the next call.
+  ///
+  /// See `write` and `ready_tags` to process the tags without collecting them.
   pub fn tags(&mut self, bytes: &[u8]) -> Result<Option<Vec<Tag>>, ParseTagsError> {
-    match &mut self.0 {
-      InnerTagParser::Simple(ref mut stream) => {
-        stream.write(bytes);
-        stream.tags()
+    self.write(bytes);
+    if let Some(e) = self.pending_error.take() {
+      return Err(e);
+    }
+    let mut tags: Vec<Tag> = Vec::new();
+    loop {
+      match self.next_tag() {
+        Ok(Some(tag)) => tags.push(tag),
+        Ok(None) if tags.is_empty() => return Ok(None),
+        Err(e) if tags.is_empty() => return Err(e),
+        Ok(None) | Err(ParseTagsError::Incomplete { .. }) => return Ok(Some(tags)),
+        Err(e) => {
+          self.pending_error = Some(e);
+          return Ok(Some(tags));
+        }
       }
+    }
+  }
+
+  /// Appends the provided bytes to the internal buffer, without parsing tags.
+  pub fn write(&mut self, bytes: &[u8]) {
+    match &mut self.inner {
+      InnerTagParser::Simple(ref mut stream) => stream.write(bytes),
       #[cfg(feature="deflate")]
-      InnerTagParser::Deflate(ref mut stream) => {
-        stream.write(bytes);
-        stream.tags()
-      }
+      InnerTagParser::Deflate(ref mut stream) => stream.write(bytes),
+      #[cfg(feature="lzma")]
+      InnerTagParser::Lzma(ref mut stream) => stream.write(bytes),
+    }
+  }
+
+  /// Parses the next tag from the internal buffer.
+  ///
+  /// Returns `Ok(None)` if it has finished parsing the movie. Errors are the same as for `tags`.
+  pub fn next_tag(&mut self) -> Result<Option<Tag>, ParseTagsError> {
+    let legacy_encoding = self.legacy_encoding;
+    self.next_tag_with(|tag, _, swf_version| parse_tag_body(tag.body, tag.header.code, swf_version, legacy_encoding))
+  }
+
+  /// Parses the next tag from the internal buffer and reports its location.
+  ///
+  /// The first span locates the tag in the decompressed payload, it is followed by the spans of
+  /// the nested tags if the tag is a `DefineSprite`. See `crate::complete::parse_swf_with_spans`.
+  ///
+  /// Returns `Ok(None)` if it has finished parsing the movie. Errors are the same as for `tags`.
+  pub fn next_tag_with_spans(&mut self) -> Result<Option<(Tag, Vec<TagSpan>)>, ParseTagsError> {
+    let legacy_encoding = self.legacy_encoding;
+    self.next_tag_with(|tag, index, swf_version| {
+      let mut spans: Vec<TagSpan> = Vec::new();
+      let tag = parse_tag_with_spans(tag, swf_version, legacy_encoding, vec![index], &mut spans);
+      (tag, spans)
+    })
+  }
+
+  /// Parses the next tag from the internal buffer with `parse`.
+  ///
+  /// `parse` receives the complete tag, its index in the top-level tag list and the SWF version.
+  fn next_tag_with<T>(&mut self, parse: impl FnOnce(NestedTag, usize, u8) -> T) -> Result<Option<T>, ParseTagsError> {
+    let index = self.tag_count;
+    let parse = |tag: NestedTag, swf_version: u8| parse(tag, index, swf_version);
+    let result = match &mut self.inner {
+      InnerTagParser::Simple(ref mut stream) => stream.next_tag_with(parse),
+      #[cfg(feature="deflate")]
+      InnerTagParser::Deflate(ref mut stream) => stream.next_tag_with(parse),
       #[cfg(feature="lzma")]
-      InnerTagParser::Lzma(ref mut stream) => {
-        stream.write(bytes);
-        stream.tags()
+      InnerTagParser::Lzma(ref mut stream) => stream.next_tag_with(parse),
+    };
+    if let Ok(Some(_)) = result {
+      self.tag_count += 1;
+    }
+    result
+  }
+
+  /// Returns an iterator over the tags available in the internal buffer.
+  ///
+  /// The iterator stops when more bytes are needed, at the end of the movie or on error: see
+  /// `ReadyTags::end` to get the reason.
+  pub fn ready_tags(&mut self) -> ReadyTags<'_> {
+    ReadyTags { parser: self, end: None }
+  }
+}
+
+/// Iterator over the tags available in the internal buffer of a `TagParser`
+///
+/// See `TagParser::ready_tags`.
+pub struct ReadyTags<'a> {
+  parser: &'a mut TagParser,
+  end: Option<Result<(), ParseTagsError>>,
+}
+
+impl ReadyTags<'_> {
+  /// Returns why the iterator stopped, or `None` if it was not exhausted yet.
+  ///
+  /// `Ok(())` means that the movie is complete. Otherwise, the error is the one returned by
+  /// `TagParser::next_tag`, such as `ParseTagsError::Incomplete` when more bytes are needed.
+  pub fn end(&self) -> Option<Result<(), ParseTagsError>> {
+    self.end
+  }
+}
+
+impl Iterator for ReadyTags<'_> {
+  type Item = Tag;
+
+  fn next(&mut self) -> Option<Tag> {
+    if self.end.is_some() {
+      return None;
+    }
+    match self.parser.next_tag() {
+      Ok(Some(tag)) => Some(tag),
+      Ok(None) => {
+        self.end = Some(Ok(()));
+        None
+      }
+      Err(e) => {
+        self.end = Some(Err(e));
+        None
       }
     }
   }
 }
 
+impl std::iter::FusedIterator for ReadyTags<'_> {}
+
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::complete::parse_swf_tags;
   use swf_types::Movie;
 
   #[test]
@@ -240,7 +417,7 @@ mod tests {
           header_output = Some((header, tag_parser));
           break;
         }
-        Err((next_parser, HeaderParserError::Other)) => parser = next_parser,
+        Err((next_parser, HeaderParserError::Incomplete)) => parser = next_parser,
         Err((_, e)) => panic!("{e:?}"),
       }
     }
@@ -260,4 +437,318 @@ mod tests {
     let actual: Movie = Movie { header, tags };
     assert_eq!(actual, expected);
   }
+
+  /// Builds a `ZWS` movie from a `FWS` movie and its payload compressed in the `.lzma` format
+  #[cfg(feature="lzma")]
+  fn to_zws(fws: &[u8], lzma: &[u8]) -> Vec<u8> {
+    // `.lzma` header: properties (5 bytes), unpacked size (8 bytes)
+    let data = &lzma[13..];
+    let mut zws: Vec<u8> = vec![b'Z', b'W', b'S'];
+    zws.extend_from_slice(&fws[3..8]);
+    zws.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    zws.extend_from_slice(&lzma[..5]);
+    zws.extend_from_slice(data);
+    zws
+  }
+
+  /// Parses a movie with the streaming parsers, feeding them one byte at a time
+  #[cfg(any(feature="deflate", feature="lzma"))]
+  fn parse_byte_by_byte(movie_bytes: &[u8]) -> Movie {
+    let mut parser = HeaderParser::new();
+    let mut bytes = movie_bytes.iter().copied();
+    let (header, mut parser) = loop {
+      let byte = bytes.next().expect("Failed to parse header");
+      match parser.header(&[byte]) {
+        Ok(header_output) => break header_output,
+        Err((next_parser, HeaderParserError::Incomplete)) => parser = next_parser,
+        Err((_, e)) => panic!("{e:?}"),
+      }
+    };
+    let mut tags: Vec<Tag> = Vec::new();
+    for byte in bytes {
+      match parser.tags(&[byte]) {
+        Ok(Some(new_tags)) => tags.extend_from_slice(&new_tags),
+        Ok(None) => break,
+        Err(_) => {}
+      }
+    }
+    Movie { header, tags }
+  }
+
+  #[cfg(feature="lzma")]
+  #[test]
+  fn test_stream_parse_blank_lzma() {
+    let movie_ast_bytes: &[u8] = include_bytes!("../../../../tests/movies/blank/ast.json");
+    let expected: Movie = serde_json_v8::from_slice::<Movie>(movie_ast_bytes).expect("Failed to read AST");
+
+    let movie_bytes: &[u8] = include_bytes!("../../../../tests/movies/blank/main.swf");
+    let mut lzma: Vec<u8> = Vec::new();
+    lzma_rs::lzma_compress(&mut &movie_bytes[8..], &mut lzma).expect("Failed to compress payload");
+
+    assert_eq!(parse_byte_by_byte(&to_zws(movie_bytes, &lzma)), expected);
+  }
+
+  /// `FWS` movie with 64 frames labeled `frame_0` to `frame_63`, using the header of the blank movie
+  #[cfg(feature="lzma")]
+  fn frame_labels_movie() -> Vec<u8> {
+    let blank: &[u8] = include_bytes!("../../../../tests/movies/blank/main.swf");
+    let mut payload: Vec<u8> = blank[8..21].to_vec();
+    for i in 0..64 {
+      let name = format!("frame_{}\0", i).into_bytes();
+      payload.extend_from_slice(&((43 << 6) | name.len() as u16).to_le_bytes());
+      payload.extend_from_slice(&name);
+      payload.extend_from_slice(&(1u16 << 6).to_le_bytes());
+    }
+    payload.extend_from_slice(&[0, 0]);
+    let mut movie: Vec<u8> = vec![b'F', b'W', b'S', blank[3]];
+    movie.extend_from_slice(&(payload.len() as u32 + 8).to_le_bytes());
+    movie.extend_from_slice(&payload);
+    movie
+  }
+
+  /// `frame_labels_movie` payload compressed by `xz --format=lzma`, with various settings.
+  ///
+  /// Unlike `lzma_rs::lzma_compress`, `xz` emits matches and repeated matches.
+  #[cfg(feature="lzma")]
+  const FRAME_LABELS_XZ_SAMPLES: [&[&str]; 2] = [
+    // --lzma1=preset=9e
+    &[
+    "5d00000004ffffffffffffffff003ffffc245f81ff258f7c478dca8b89b6e62dfa2898c4c89cebb836c3b2552e54017d",
+    "fe00b50c0fe72b4aa662983c4b7e75b6b4cd91e1673044274f2618f49853c9b73c7e01360d5f286026fbab3fcd90f6fc",
+    "d23620693490266a5f1e921d4ee280d9dd33ae4a864fb19a3d2ef6d36fa7a332043ac5b898ed60134af65636a6521f6a",
+    "290fffff51390000",
+    ],
+    // --lzma1=preset=6,lc=0,lp=2,pb=0
+    &[
+    "1200008000ffffffffffffffff003ffffc298d129c489d9123a2561fe92cca83a5e5375ccd3d6c89e8cc3561922416d5",
+    "dda8a6cc43b5cc1cc270b87b935834b4c6050aadbb4eee6b7475479260d6e8941c2fb4a382b596b12b0780805982d70e",
+    "23c429f00404ddcd4cdcecbe02e6e0e95b4a8166afa5f0b8f33a30994f2748ca006ae22e321ffed24706a5ea8677c508",
+    "c6baff08e71c253ffff709dcc0",
+    ],
+  ];
+
+  #[cfg(feature="lzma")]
+  #[test]
+  fn test_stream_parse_xz_lzma() {
+    let fws = frame_labels_movie();
+    let expected = crate::parse_swf(&fws).expect("Failed to parse movie");
+    assert_eq!(expected.tags.len(), 128);
+
+    for sample in FRAME_LABELS_XZ_SAMPLES {
+      let hex = sample.concat();
+      let lzma: Vec<u8> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect();
+      let zws = to_zws(&fws, &lzma);
+      assert_eq!(parse_byte_by_byte(&zws), expected);
+
+      let (header, mut parser) = HeaderParser::new().header(&zws).map_err(|(_, e)| e).unwrap();
+      assert_eq!(header, expected.header);
+      assert_eq!(parser.tags(&[]), Ok(Some(expected.tags.clone())));
+    }
+  }
+
+  /// Builds a `CWS` movie from a `FWS` movie, storing `payload` in uncompressed deflate blocks
+  #[cfg(feature="deflate")]
+  fn to_cws(fws: &[u8], payload: &[u8]) -> Vec<u8> {
+    let mut cws: Vec<u8> = vec![b'C', b'W', b'S'];
+    cws.extend_from_slice(&fws[3..8]);
+    // zlib header: deflate method, 32K window, no preset dictionary
+    cws.extend_from_slice(&[0x78, 0x01]);
+    let mut chunks = payload.chunks(16).peekable();
+    while let Some(chunk) = chunks.next() {
+      let is_final = chunks.peek().is_none();
+      cws.push(u8::from(is_final));
+      cws.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+      cws.extend_from_slice(&(!(chunk.len() as u16)).to_le_bytes());
+      cws.extend_from_slice(chunk);
+    }
+    let (a, b) = payload.iter().fold((1u32, 0u32), |(a, b), byte| {
+      let a = (a + u32::from(*byte)) % 65521;
+      (a, (b + a) % 65521)
+    });
+    cws.extend_from_slice(&((b << 16) | a).to_be_bytes());
+    cws
+  }
+
+  /// Consumes all the tags of a movie and returns the final result of `next_tag`
+  #[cfg(any(feature="deflate", feature="lzma"))]
+  fn final_next_tag(movie_bytes: &[u8], chunk_len: usize) -> Result<Option<Tag>, ParseTagsError> {
+    let mut chunks = movie_bytes.chunks(chunk_len);
+    let mut parser = HeaderParser::new();
+    let mut parser = loop {
+      match parser.header(chunks.next().unwrap_or(&[])) {
+        Ok((_, parser)) => break parser,
+        Err((next_parser, HeaderParserError::Incomplete)) => parser = next_parser,
+        Err((_, e)) => panic!("{e:?}"),
+      }
+    };
+    for chunk in chunks {
+      parser.write(chunk);
+      while let Ok(Some(_)) = parser.next_tag() {}
+    }
+    while let Ok(Some(_)) = parser.next_tag() {}
+    parser.next_tag()
+  }
+
+  /// Checks the errors reported at the end of a compressed stream
+  #[cfg(any(feature="deflate", feature="lzma"))]
+  fn check_compressed_stream_end(compress: impl Fn(&[u8], &[u8]) -> Vec<u8>) {
+    let fws: &[u8] = include_bytes!("../../../../tests/movies/blank/main.swf");
+    let complete = compress(fws, &fws[8..]);
+    // Missing `End` tag
+    let truncated = compress(fws, &fws[8..fws.len() - 2]);
+    let mut trailing = complete.clone();
+    trailing.push(0);
+
+    for chunk_len in [1, complete.len()] {
+      assert_eq!(final_next_tag(&complete, chunk_len), Ok(None));
+      assert_eq!(final_next_tag(&truncated, chunk_len), Err(ParseTagsError::UnexpectedEndOfStream));
+      assert_eq!(final_next_tag(&trailing, chunk_len), Err(ParseTagsError::TrailingData));
+    }
+  }
+
+  #[cfg(feature="deflate")]
+  #[test]
+  fn test_stream_parse_deflate_end() {
+    check_compressed_stream_end(to_cws);
+  }
+
+  #[cfg(feature="deflate")]
+  #[test]
+  fn test_stream_parse_deflate_trailer_copy() {
+    // Trailing data repeating the Adler-32 trailer is still reported
+    let fws: &[u8] = include_bytes!("../../../../tests/movies/blank/main.swf");
+    let mut cws = to_cws(fws, &fws[8..]);
+    let trailer: Vec<u8> = cws[cws.len() - 4..].to_vec();
+    cws.extend_from_slice(&trailer);
+    for chunk_len in [1, 3, cws.len()] {
+      assert_eq!(final_next_tag(&cws, chunk_len), Err(ParseTagsError::TrailingData));
+    }
+  }
+
+  #[cfg(feature="lzma")]
+  #[test]
+  fn test_stream_parse_lzma_end() {
+    check_compressed_stream_end(|fws, payload| {
+      let mut lzma: Vec<u8> = Vec::new();
+      lzma_rs::lzma_compress(&mut &payload[..], &mut lzma).expect("Failed to compress payload");
+      to_zws(fws, &lzma)
+    });
+  }
+
+  #[cfg(feature="deflate")]
+  #[test]
+  fn test_stream_parse_invalid_deflate() {
+    // Zlib header followed by a block with the reserved block type
+    let movie_bytes: &[u8] = b"CWS\x0a\x20\x00\x00\x00\x78\x9c\xff\xff";
+    match HeaderParser::new().header(movie_bytes) {
+      Err((_, e)) => assert_eq!(e, HeaderParserError::InvalidPayload(CompressionMethod::Deflate)),
+      Ok(_) => panic!("Expected header parsing to fail"),
+    }
+  }
+
+  #[test]
+  fn test_stream_parse_trailing_data() {
+    let movie_bytes: &[u8] = include_bytes!("../../../../tests/movies/blank/main.swf");
+    let (header, mut parser) = match HeaderParser::new().header(movie_bytes) {
+      Ok(ok) => ok,
+      Err((_, e)) => panic!("{e:?}"),
+    };
+    assert_eq!(header.swf_version, movie_bytes[3]);
+    assert!(matches!(parser.tags(&[]), Ok(Some(_))));
+    assert_eq!(parser.tags(&[]), Ok(None));
+    assert_eq!(parser.tags(&[0]), Err(ParseTagsError::TrailingData));
+
+    // The trailing data is reported after the tags preceding it
+    let mut trailing: Vec<u8> = movie_bytes.to_vec();
+    trailing.push(0);
+    let (_, mut parser) = match HeaderParser::new().header(&trailing) {
+      Ok(ok) => ok,
+      Err((_, e)) => panic!("{e:?}"),
+    };
+    assert!(matches!(parser.tags(&[]), Ok(Some(_))));
+    assert_eq!(parser.tags(&[]), Err(ParseTagsError::TrailingData));
+  }
+
+  #[test]
+  fn test_stream_parse_incomplete() {
+    let movie_bytes: &[u8] = include_bytes!("../../../../tests/movies/blank/main.swf");
+    // Signature (8 bytes) and header (13 bytes), followed by a `FileAttributes` tag (6 bytes)
+    let parse_tags = |uncompressed_file_length: u8, input: &[&[u8]]| {
+      let mut movie_bytes = movie_bytes.to_vec();
+      movie_bytes[4] = uncompressed_file_length;
+      let (_, mut parser) = match HeaderParser::new().header(&movie_bytes[..21]) {
+        Ok(ok) => ok,
+        Err((_, e)) => panic!("{e:?}"),
+      };
+      let mut result = parser.tags(&[]);
+      for range in input {
+        result = parser.tags(range);
+      }
+      result
+    };
+    assert_eq!(parse_tags(53, &[&movie_bytes[21..22]]), Err(ParseTagsError::Incomplete { needed: 1 }));
+    assert_eq!(parse_tags(53, &[&movie_bytes[21..22], &movie_bytes[22..23]]), Err(ParseTagsError::Incomplete { needed: 4 }));
+    assert_eq!(parse_tags(26, &[&movie_bytes[21..23]]), Err(ParseTagsError::InvalidTagHeader));
+    assert_eq!(parse_tags(25, &[&movie_bytes[21..25]]), Err(ParseTagsError::UnexpectedEndOfStream));
+    assert!(matches!(parse_tags(25, &[&movie_bytes[21..25], &movie_bytes[25..27]]), Ok(Some(_))));
+  }
+
+  #[test]
+  fn test_stream_ready_tags() {
+    let movie_bytes: &[u8] = include_bytes!("../../../../tests/movies/blank/main.swf");
+    let (_, mut parser) = match HeaderParser::new().header(&movie_bytes[..21]) {
+      Ok(ok) => ok,
+      Err((_, e)) => panic!("{e:?}"),
+    };
+    // `FileAttributes` (6 bytes), then `SetBackgroundColor` (5 bytes)
+    parser.write(&movie_bytes[21..30]);
+    let mut ready_tags = parser.ready_tags();
+    assert_eq!(ready_tags.end(), None);
+    assert_eq!(ready_tags.by_ref().count(), 1);
+    assert_eq!(ready_tags.end(), Some(Err(ParseTagsError::Incomplete { needed: 2 })));
+    assert_eq!(ready_tags.next(), None);
+    parser.write(&movie_bytes[30..]);
+    let mut ready_tags = parser.ready_tags();
+    let tags: Vec<Tag> = ready_tags.by_ref().collect();
+    let expected: Vec<Tag> = parse_swf_tags(&movie_bytes[27..], 10).map(|(_, _, tag)| tag).collect();
+    assert_eq!(tags, expected);
+    assert_eq!(ready_tags.end(), Some(Ok(())));
+    assert_eq!(parser.next_tag(), Ok(None));
+
+    parser.write(&[0]);
+    let mut ready_tags = parser.ready_tags();
+    assert_eq!(ready_tags.next(), None);
+    assert_eq!(ready_tags.end(), Some(Err(ParseTagsError::TrailingData)));
+  }
+
+  /// Parses a movie with `TagParser::next_tag_with_spans`, providing the whole movie at once
+  fn parse_spans(movie_bytes: &[u8]) -> (Vec<Tag>, Vec<TagSpan>) {
+    let (_, mut parser) = match HeaderParser::new().header(movie_bytes) {
+      Ok(ok) => ok,
+      Err((_, e)) => panic!("{e:?}"),
+    };
+    let mut tags: Vec<Tag> = Vec::new();
+    let mut spans: Vec<TagSpan> = Vec::new();
+    while let Some((tag, tag_spans)) = parser.next_tag_with_spans().expect("Failed to parse tag") {
+      tags.push(tag);
+      spans.extend(tag_spans);
+    }
+    (tags, spans)
+  }
+
+  #[test]
+  fn test_stream_tag_spans() {
+    let movie_bytes: &[u8] = include_bytes!("../../../../tests/movies/blank/main.swf");
+    let (movie, expected_spans) = crate::complete::parse_swf_with_spans(movie_bytes).unwrap();
+    assert_eq!(parse_spans(movie_bytes), (movie.tags.clone(), expected_spans.clone()));
+    // `Metadata` uses the long header form
+    assert!(expected_spans[2].is_long_header());
+    assert_eq!(expected_spans[2].range(), 24..41);
+
+    #[cfg(feature="deflate")]
+    assert_eq!(parse_spans(&to_cws(movie_bytes, &movie_bytes[8..])), (movie.tags, expected_spans));
+  }
 }
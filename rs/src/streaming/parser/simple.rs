@@ -1,39 +1,57 @@
+use crate::complete::tag::NestedTag;
 use crate::stream_buffer::StreamBuffer;
 use crate::streaming::movie::parse_header;
-use crate::streaming::tag::parse_tag;
-use swf_types::{Header as SwfHeader, Tag, SwfSignature};
+use crate::streaming::tag::{parse_tag_header, StreamingTagError};
+use std::convert::TryFrom;
+use swf_types::{Header as SwfHeader, SwfSignature};
 
-use super::ParseTagsError;
+use super::{HeaderParserError, ParseTagsError};
+
+/// Length of the SWF signature, it is not part of the payload
+const SWF_SIGNATURE_LEN: usize = 8;
 
 /// State of the uncompressed payload parser
 pub(crate) struct SimpleStream<B: StreamBuffer> {
   buffer: B,
   swf_version: u8,
+  /// Length of the payload, as declared by the signature
+  payload_len: usize,
+  /// Count of payload bytes received so far
+  received_len: usize,
   is_end: bool,
+  /// Data was received after the end of the tags
+  has_trailing_data: bool,
 }
 
 impl<B: StreamBuffer> SimpleStream<B> {
   pub(crate) fn new(buffer: B, signature: SwfSignature) -> Self {
-    // TODO: track uncompressed length of signature?
     Self {
       buffer,
       swf_version: signature.swf_version,
+      payload_len: signature.uncompressed_file_length.saturating_sub(SWF_SIGNATURE_LEN),
+      received_len: 0,
       is_end: false,
+      has_trailing_data: false,
     }
   }
 
   /// Appends data to the internal buffer.
   pub(crate) fn write(&mut self, bytes: &[u8]) {
-    self.buffer.write(bytes);
+    if self.is_end {
+      self.has_trailing_data |= !bytes.is_empty();
+    } else {
+      self.received_len += bytes.len();
+      self.buffer.write(bytes);
+    }
   }
 
   /// Finishes parsing the SWF header from the internal buffer.
-  pub(crate) fn header(mut self) -> Result<(SwfHeader, Self), Self> {
+  pub(crate) fn header(mut self) -> Result<(SwfHeader, Self), (Self, HeaderParserError)> {
     let buffer: &[u8] = self.buffer.get();
     let (remaining, header) = match parse_header(buffer, self.swf_version) {
       Ok(ok) => ok,
-      Err(nom::Err::Incomplete(_)) => return Err(self),
-      Err(nom::Err::Error(_)) | Err(nom::Err::Failure(_)) => return Err(self),
+      Err(nom::Err::Incomplete(_)) => return Err((self, HeaderParserError::Incomplete)),
+      Err(nom::Err::Error(_)) | Err(nom::Err::Failure(_)) => return Err((self, HeaderParserError::InvalidHeader)),
     };
     let parsed_len: usize = buffer.len() - remaining.len();
 
@@ -42,51 +60,89 @@ impl<B: StreamBuffer> SimpleStream<B> {
     Ok((header, self))
   }
 
-  /// Parses the available tags from the internal buffer.
+  /// Parses the next tag from the internal buffer with `parse`.
+  ///
+  /// `parse` receives the complete tag, located in the payload, and the SWF version.
   ///
   /// Returns `Ok(None)` if parsing is complete (there are no more tags).
-  /// Returns `Ok(Some(Vec<Tag>))` when some tags are available. `Vec` is non-empty.
-  /// Returns `Err(())` when there's not enough data or an error occurs.
-  pub(crate) fn tags(&mut self) -> Result<Option<Vec<Tag>>, ParseTagsError> {
+  /// Returns `Ok(Some(T))` when a tag is available.
+  /// Returns `Err(ParseTagsError::Incomplete { .. })` when there's not enough data.
+  /// Returns `Err(ParseTagsError::InvalidTagHeader)` or `Err(ParseTagsError::UnexpectedEndOfStream)` when
+  /// there's not enough data and the payload length declared by the signature is (or would be) exceeded.
+  /// Returns `Err(ParseTagsError::TrailingData)` once all the tags were returned, if there is data
+  /// after the end of the tags.
+  pub(crate) fn next_tag_with<T>(
+    &mut self,
+    parse: impl FnOnce(NestedTag, u8) -> T,
+  ) -> Result<Option<T>, ParseTagsError> {
     if self.is_end {
-      return Ok(None);
+      return self.end();
     }
 
     let buffer: &[u8] = self.buffer.get();
-
-    let mut input: &[u8] = buffer;
-    let mut tags: Vec<Tag> = Vec::new();
-    let is_end: bool = loop {
-      match parse_tag(input, self.swf_version) {
-        Ok((_, None)) => {
-          input = &[][..];
-          break true;
-        }
-        Ok((next_input, Some(tag))) => {
-          tags.push(tag);
-          input = next_input;
-        }
-        Err(_) => {
-          break false;
-        }
-      };
+    let (input, header) = match parse_tag_header(buffer) {
+      Ok(ok) => ok,
+      Err(_) => return Err(self.incomplete_error(buffer, StreamingTagError::IncompleteHeader)),
     };
+    let header_len = buffer.len() - input.len();
 
-    if is_end {
+    // `EndOfTags`
+    if header.code == 0 {
+      self.has_trailing_data |= !input.is_empty();
+      let parsed_len: usize = buffer.len();
+      self.buffer.clear(parsed_len);
       self.is_end = true;
+      return self.end();
     }
 
-    let parsed_len: usize = buffer.len() - input.len();
+    let body_len = usize::try_from(header.length).unwrap();
+    if input.len() < body_len {
+      return Err(self.incomplete_error(buffer, StreamingTagError::IncompleteTag(header_len + body_len)));
+    }
+    let tag = NestedTag {
+      // The buffer holds the received bytes that were not parsed yet
+      offset: self.received_len - buffer.len(),
+      header_len,
+      header,
+      body: &input[..body_len],
+    };
+    let tag = parse(tag, self.swf_version);
+    let parsed_len: usize = header_len + body_len;
     self.buffer.clear(parsed_len);
+    Ok(Some(tag))
+  }
 
-    if tags.is_empty() {
-      if is_end {
-        Ok(None)
-      } else {
-        Err(ParseTagsError)
+  /// Builds the error for an incomplete tag at the start of `input`.
+  ///
+  /// The declared payload length is only used to report errors: parsing resumes if more data
+  /// is received anyway.
+  fn incomplete_error(&self, input: &[u8], error: StreamingTagError) -> ParseTagsError {
+    let tag_len = match error {
+      StreamingTagError::IncompleteHeader => {
+        if input.len() < 2 {
+          2
+        } else {
+          6
+        }
       }
+      StreamingTagError::IncompleteTag(tag_len) => tag_len,
+    };
+    let needed = tag_len - input.len();
+    let payload_remaining = self.payload_len.saturating_sub(self.received_len);
+    if payload_remaining == 0 {
+      ParseTagsError::UnexpectedEndOfStream
+    } else if needed > payload_remaining {
+      ParseTagsError::InvalidTagHeader
+    } else {
+      ParseTagsError::Incomplete { needed }
+    }
+  }
+
+  fn end<T>(&self) -> Result<Option<T>, ParseTagsError> {
+    if self.has_trailing_data {
+      Err(ParseTagsError::TrailingData)
     } else {
-      Ok(Some(tags))
+      Ok(None)
     }
   }
 }
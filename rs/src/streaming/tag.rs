@@ -1,6 +1,7 @@
 use crate::complete::tag::parse_tag_body;
+use crate::legacy::LegacyEncoding;
+use crate::streaming::error::NomResult;
 use nom::number::streaming::{le_u16 as parse_le_u16, le_u32 as parse_le_u32};
-use nom::IResult as NomResult;
 use std::convert::TryFrom;
 use swf_types as ast;
 
@@ -26,7 +27,11 @@ pub(crate) enum StreamingTagError {
 ///
 /// In case of success, returns the remaining input and `Tag`.
 /// In case of error, returns the original input and error description.
-pub(crate) fn parse_tag(input: &[u8], swf_version: u8) -> Result<(&[u8], Option<ast::Tag>), StreamingTagError> {
+pub(crate) fn parse_tag(
+  input: &[u8],
+  swf_version: u8,
+  encoding: LegacyEncoding,
+) -> Result<(&[u8], Option<ast::Tag>), StreamingTagError> {
   let base_input = input; // Keep original input to compute lengths.
   let (input, header) = match parse_tag_header(input) {
     Ok(ok) => ok,
@@ -45,7 +50,7 @@ pub(crate) fn parse_tag(input: &[u8], swf_version: u8) -> Result<(&[u8], Option<
     return Err(StreamingTagError::IncompleteTag(tag_len));
   }
   let (tag_body, input) = input.split_at(body_len);
-  let tag = parse_tag_body(tag_body, header.code, swf_version);
+  let tag = parse_tag_body(tag_body, header.code, swf_version, encoding);
   Ok((input, Some(tag)))
 }
 
@@ -0,0 +1,4 @@
+pub(crate) mod path;
+pub(crate) mod shape;
+
+pub use shape::{glyph_to_svg, glyph_to_svg_path, shape_to_svg};
@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use swf_types as ast;
+
+/// Point of a shape, in twips (or glyph units).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Point {
+  pub x: i32,
+  pub y: i32,
+}
+
+/// Straight or quadratic Bézier edge.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Segment {
+  pub from: Point,
+  pub control: Option<Point>,
+  pub to: Point,
+}
+
+impl Segment {
+  fn reversed(self) -> Self {
+    Segment {
+      from: self.to,
+      control: self.control,
+      to: self.from,
+    }
+  }
+}
+
+/// Closed (or open, for malformed shapes) sequence of connected segments.
+pub(crate) type Contour = Vec<Segment>;
+
+/// Edges of a group of shape records sharing the same style lists.
+///
+/// A new layer starts at each style change record with new styles: its style indices refer to the new lists, and
+/// it is drawn above the previous layers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ShapeLayer<'a> {
+  /// Style lists of the layer, `None` for glyphs.
+  pub styles: Option<&'a ast::ShapeStyles>,
+  /// Edges of each fill style (the item `i - 1` for the style index `i`), oriented with the fill on their right side.
+  pub fills: Vec<Vec<Segment>>,
+  /// Edges of each line style (the item `i - 1` for the style index `i`), in drawing order.
+  pub lines: Vec<Vec<Segment>>,
+}
+
+impl<'a> ShapeLayer<'a> {
+  fn new(styles: Option<&'a ast::ShapeStyles>) -> Self {
+    ShapeLayer {
+      styles,
+      fills: Vec::new(),
+      lines: Vec::new(),
+    }
+  }
+
+  fn is_empty(&self) -> bool {
+    self.fills.iter().all(Vec::is_empty) && self.lines.iter().all(Vec::is_empty)
+  }
+}
+
+/// Pushes an edge to the list of the style `index` (`0` is no style).
+fn push_edge(lists: &mut Vec<Vec<Segment>>, index: usize, segment: Segment) {
+  if index == 0 {
+    return;
+  }
+  if lists.len() < index {
+    lists.resize_with(index, Vec::new);
+  }
+  lists[index - 1].push(segment);
+}
+
+/// Splits the records of a shape into layers and sorts their edges by style.
+///
+/// SWF edges have a fill style on each side (`left_fill`, also known as "fill style 0", and `right_fill`, "fill
+/// style 1"): each edge is added to both fills, reversed for the left fill, so the edges of a fill can be connected
+/// into contours with a consistent orientation.
+pub(crate) fn shape_layers<'a>(
+  initial_styles: Option<&'a ast::ShapeStyles>,
+  records: &'a [ast::ShapeRecord],
+) -> Vec<ShapeLayer<'a>> {
+  let mut layers: Vec<ShapeLayer<'a>> = Vec::new();
+  let mut layer = ShapeLayer::new(initial_styles);
+  let mut position = Point { x: 0, y: 0 };
+  let mut left_fill: usize = 0;
+  let mut right_fill: usize = 0;
+  let mut line_style: usize = 0;
+  for record in records {
+    match record {
+      ast::ShapeRecord::StyleChange(change) => {
+        if let Some(styles) = &change.new_styles {
+          let previous = std::mem::replace(&mut layer, ShapeLayer::new(Some(styles)));
+          if !previous.is_empty() {
+            layers.push(previous);
+          }
+          left_fill = 0;
+          right_fill = 0;
+          line_style = 0;
+        }
+        if let Some(move_to) = change.move_to {
+          position = Point {
+            x: move_to.x,
+            y: move_to.y,
+          };
+        }
+        left_fill = change.left_fill.unwrap_or(left_fill);
+        right_fill = change.right_fill.unwrap_or(right_fill);
+        line_style = change.line_style.unwrap_or(line_style);
+      }
+      ast::ShapeRecord::Edge(edge) => {
+        let control = edge.control_delta.map(|delta| Point {
+          x: position.x.wrapping_add(delta.x),
+          y: position.y.wrapping_add(delta.y),
+        });
+        let to = Point {
+          x: position.x.wrapping_add(edge.delta.x),
+          y: position.y.wrapping_add(edge.delta.y),
+        };
+        let segment = Segment {
+          from: position,
+          control,
+          to,
+        };
+        push_edge(&mut layer.fills, right_fill, segment);
+        push_edge(&mut layer.fills, left_fill, segment.reversed());
+        push_edge(&mut layer.lines, line_style, segment);
+        position = to;
+      }
+    }
+  }
+  if !layer.is_empty() {
+    layers.push(layer);
+  }
+  layers
+}
+
+/// Connects the edges of a fill into contours, following the edges starting where the previous one ends.
+pub(crate) fn connect_contours(edges: &[Segment]) -> Vec<Contour> {
+  let mut starts: HashMap<Point, Vec<usize>> = HashMap::new();
+  for (index, edge) in edges.iter().enumerate().rev() {
+    starts.entry(edge.from).or_default().push(index);
+  }
+  let mut used: Vec<bool> = vec![false; edges.len()];
+  let mut contours: Vec<Contour> = Vec::new();
+  for first in 0..edges.len() {
+    if used[first] {
+      continue;
+    }
+    used[first] = true;
+    let mut contour: Contour = vec![edges[first]];
+    let start = edges[first].from;
+    let mut end = edges[first].to;
+    while end != start {
+      let next = starts
+        .get_mut(&end)
+        .and_then(|candidates| {
+          while let Some(candidate) = candidates.pop() {
+            if !used[candidate] {
+              return Some(candidate);
+            }
+          }
+          None
+        });
+      match next {
+        Some(next) => {
+          used[next] = true;
+          contour.push(edges[next]);
+          end = edges[next].to;
+        }
+        None => break,
+      }
+    }
+    contours.push(contour);
+  }
+  contours
+}
+
+/// Writes SVG path data for the segments, coordinates are divided by `unit`.
+///
+/// Closed contours end with `Z` if `close` is set. A new subpath is started when a segment doesn't start at the end
+/// of the previous one.
+pub(crate) fn write_path_data(out: &mut String, segments: &[Segment], unit: f64, close: bool) {
+  let coord = |value: i32| f64::from(value) / unit;
+  let mut start: Option<Point> = None;
+  let mut pen: Option<Point> = None;
+  for segment in segments {
+    if pen != Some(segment.from) {
+      if close && pen.is_some() && pen == start {
+        out.push('Z');
+      }
+      if !out.is_empty() {
+        out.push(' ');
+      }
+      write!(out, "M{} {}", coord(segment.from.x), coord(segment.from.y)).unwrap();
+      start = Some(segment.from);
+    }
+    match segment.control {
+      Some(control) => write!(
+        out,
+        " Q{} {} {} {}",
+        coord(control.x),
+        coord(control.y),
+        coord(segment.to.x),
+        coord(segment.to.y)
+      )
+      .unwrap(),
+      None => write!(out, " L{} {}", coord(segment.to.x), coord(segment.to.y)).unwrap(),
+    }
+    pen = Some(segment.to);
+  }
+  if close && pen.is_some() && pen == start {
+    out.push('Z');
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn edge(x: i32, y: i32) -> ast::ShapeRecord {
+    ast::ShapeRecord::Edge(ast::shape_records::Edge {
+      delta: ast::Vector2D { x, y },
+      control_delta: None,
+    })
+  }
+
+  fn style_change(
+    move_to: Option<(i32, i32)>,
+    left_fill: Option<usize>,
+    right_fill: Option<usize>,
+  ) -> ast::ShapeRecord {
+    ast::ShapeRecord::StyleChange(ast::shape_records::StyleChange {
+      move_to: move_to.map(|(x, y)| ast::Vector2D { x, y }),
+      left_fill,
+      right_fill,
+      line_style: None,
+      new_styles: None,
+    })
+  }
+
+  #[test]
+  fn test_connect_shared_edge() {
+    // Two adjacent squares: the middle edge has fill 2 on its left and fill 1 on its right
+    let records = vec![
+      style_change(Some((0, 0)), None, Some(1)),
+      edge(20, 0),
+      style_change(None, Some(2), Some(1)),
+      edge(0, 20),
+      style_change(None, Some(0), Some(1)),
+      edge(-20, 0),
+      edge(0, -20),
+      style_change(Some((20, 0)), None, Some(2)),
+      edge(20, 0),
+      edge(0, 20),
+      edge(-20, 0),
+    ];
+    let layers = shape_layers(None, &records);
+    assert_eq!(layers.len(), 1);
+    let mut first = String::new();
+    for contour in connect_contours(&layers[0].fills[0]) {
+      write_path_data(&mut first, &contour, 1.0, true);
+    }
+    assert_eq!(first, "M0 0 L20 0 L20 20 L0 20 L0 0Z");
+    let mut second = String::new();
+    for contour in connect_contours(&layers[0].fills[1]) {
+      write_path_data(&mut second, &contour, 1.0, true);
+    }
+    assert_eq!(second, "M20 20 L20 0 L40 0 L40 20 L20 20Z");
+  }
+}
@@ -0,0 +1,383 @@
+use crate::svg::path::{connect_contours, shape_layers, write_path_data, Segment};
+use std::fmt::Write;
+use swf_types as ast;
+
+/// Number of twips per pixel (SVG user unit).
+const TWIPS_PER_PIXEL: f64 = 20.0;
+
+/// Half the size of the gradient square: gradients are defined from `-16384` to `16384` twips in gradient space.
+const GRADIENT_SQUARE_HALF_SIZE: f64 = 16384.0 / TWIPS_PER_PIXEL;
+
+/// Converts a shape into a standalone SVG document, `bounds` is the bounding box of the shape (from its
+/// `DefineShape` tag).
+///
+/// Coordinates are converted from twips to pixels. The edges of each fill style are connected into closed paths and
+/// drawn with the `evenodd` fill rule, below the line styles of the same style group. Bitmap fills are not drawn.
+pub fn shape_to_svg(shape: &ast::Shape, bounds: &ast::Rect) -> String {
+  let mut defs = String::new();
+  let mut body = String::new();
+  let mut gradient_count: usize = 0;
+  for layer in shape_layers(Some(&shape.initial_styles), &shape.records) {
+    let styles = layer.styles.unwrap_or(&shape.initial_styles);
+    for (index, edges) in layer.fills.iter().enumerate() {
+      let style = match styles.fill.get(index) {
+        Some(style) if !edges.is_empty() => style,
+        _ => continue,
+      };
+      let fill = match write_paint(&mut defs, &mut gradient_count, style) {
+        Some(fill) => fill,
+        None => continue,
+      };
+      let mut data = String::new();
+      for contour in connect_contours(edges) {
+        write_path_data(&mut data, &contour, TWIPS_PER_PIXEL, true);
+      }
+      writeln!(body, "  <path {} fill-rule=\"evenodd\" d=\"{}\"/>", fill_attributes("fill", &fill), data).unwrap();
+    }
+    for (index, edges) in layer.lines.iter().enumerate() {
+      let style = match styles.line.get(index) {
+        Some(style) if !edges.is_empty() => style,
+        _ => continue,
+      };
+      let stroke = match write_paint(&mut defs, &mut gradient_count, &style.fill) {
+        Some(stroke) => stroke,
+        None => continue,
+      };
+      let mut data = String::new();
+      write_path_data(&mut data, edges, TWIPS_PER_PIXEL, false);
+      writeln!(
+        body,
+        "  <path fill=\"none\" {} {} d=\"{}\"/>",
+        fill_attributes("stroke", &stroke),
+        stroke_attributes(style),
+        data
+      )
+      .unwrap();
+    }
+  }
+
+  let x = f64::from(bounds.x_min) / TWIPS_PER_PIXEL;
+  let y = f64::from(bounds.y_min) / TWIPS_PER_PIXEL;
+  let width = f64::from(bounds.x_max.saturating_sub(bounds.x_min)) / TWIPS_PER_PIXEL;
+  let height = f64::from(bounds.y_max.saturating_sub(bounds.y_min)) / TWIPS_PER_PIXEL;
+  let mut svg = format!(
+    "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\">\n",
+    width, height, x, y, width, height
+  );
+  if !defs.is_empty() {
+    svg.push_str("  <defs>\n");
+    svg.push_str(&defs);
+    svg.push_str("  </defs>\n");
+  }
+  svg.push_str(&body);
+  svg.push_str("</svg>\n");
+  svg
+}
+
+/// Converts a glyph into SVG path data, in glyph units.
+///
+/// The EM square of a glyph is 1024 units (20480 for `DefineFont3`), with the baseline at `y = 0` and the y-axis
+/// pointing down. All the filled areas of the glyph are merged, use the `evenodd` fill rule.
+pub fn glyph_to_svg_path(glyph: &ast::Glyph) -> String {
+  let mut data = String::new();
+  for contour in glyph_contours(glyph) {
+    write_path_data(&mut data, &contour, 1.0, true);
+  }
+  data
+}
+
+/// Converts a glyph into a standalone SVG document showing its EM square, see [[glyph_to_svg_path]].
+pub fn glyph_to_svg(glyph: &ast::Glyph, em_size: u32) -> String {
+  format!(
+    concat!(
+      "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 -{} {} {}\">\n",
+      "  <path fill-rule=\"evenodd\" d=\"{}\"/>\n",
+      "</svg>\n"
+    ),
+    em_size,
+    em_size,
+    em_size,
+    glyph_to_svg_path(glyph)
+  )
+}
+
+/// Returns the closed contours of a glyph, merging all its fills.
+pub(crate) fn glyph_contours(glyph: &ast::Glyph) -> Vec<Vec<Segment>> {
+  shape_layers(None, &glyph.records)
+    .iter()
+    .flat_map(|layer| layer.fills.iter())
+    .flat_map(|edges| connect_contours(edges))
+    .collect()
+}
+
+/// SVG paint of a fill style: a color or a reference to a gradient.
+struct Paint {
+  value: String,
+  opacity: Option<f64>,
+}
+
+fn fill_attributes(name: &str, paint: &Paint) -> String {
+  match paint.opacity {
+    Some(opacity) => format!("{}=\"{}\" {}-opacity=\"{}\"", name, paint.value, name, opacity),
+    None => format!("{}=\"{}\"", name, paint.value),
+  }
+}
+
+fn stroke_attributes(style: &ast::LineStyle) -> String {
+  // Zero-width lines are drawn as hairlines
+  let width = f64::from(style.width.max(1)) / TWIPS_PER_PIXEL;
+  let cap = match style.start_cap {
+    ast::CapStyle::None => "butt",
+    ast::CapStyle::Round => "round",
+    ast::CapStyle::Square => "square",
+  };
+  let join = match style.join {
+    ast::JoinStyle::Bevel => String::from("stroke-linejoin=\"bevel\""),
+    ast::JoinStyle::Round => String::from("stroke-linejoin=\"round\""),
+    ast::JoinStyle::Miter(miter) => format!(
+      "stroke-linejoin=\"miter\" stroke-miterlimit=\"{}\"",
+      (f64::from(miter.limit) / 256.0).max(1.0)
+    ),
+  };
+  format!("stroke-width=\"{}\" stroke-linecap=\"{}\" {}", width, cap, join)
+}
+
+fn color_hex(color: &ast::StraightSRgba8) -> String {
+  format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+fn opacity(alpha: u8) -> Option<f64> {
+  if alpha == u8::MAX {
+    None
+  } else {
+    Some(f64::from(alpha) / 255.0)
+  }
+}
+
+/// Returns the paint of a fill style, writing its gradient definition to `defs`.
+///
+/// Returns `None` for bitmap fills.
+fn write_paint(defs: &mut String, gradient_count: &mut usize, style: &ast::FillStyle) -> Option<Paint> {
+  let (element, matrix, gradient, attributes) = match style {
+    ast::FillStyle::Solid(solid) => {
+      return Some(Paint {
+        value: color_hex(&solid.color),
+        opacity: opacity(solid.color.a),
+      })
+    }
+    ast::FillStyle::Bitmap(_) => return None,
+    ast::FillStyle::LinearGradient(fill) => (
+      "linearGradient",
+      &fill.matrix,
+      &fill.gradient,
+      format!(
+        "x1=\"-{}\" y1=\"0\" x2=\"{}\" y2=\"0\"",
+        GRADIENT_SQUARE_HALF_SIZE, GRADIENT_SQUARE_HALF_SIZE
+      ),
+    ),
+    ast::FillStyle::RadialGradient(fill) => (
+      "radialGradient",
+      &fill.matrix,
+      &fill.gradient,
+      format!("cx=\"0\" cy=\"0\" r=\"{}\"", GRADIENT_SQUARE_HALF_SIZE),
+    ),
+    ast::FillStyle::FocalGradient(fill) => (
+      "radialGradient",
+      &fill.matrix,
+      &fill.gradient,
+      format!(
+        "cx=\"0\" cy=\"0\" r=\"{}\" fx=\"{}\" fy=\"0\"",
+        GRADIENT_SQUARE_HALF_SIZE,
+        f64::from(f32::from(fill.focal_point)) * GRADIENT_SQUARE_HALF_SIZE
+      ),
+    ),
+  };
+  let id = format!("gradient{}", *gradient_count);
+  *gradient_count += 1;
+  let spread = match gradient.spread {
+    ast::GradientSpread::Pad => "pad",
+    ast::GradientSpread::Reflect => "reflect",
+    ast::GradientSpread::Repeat => "repeat",
+  };
+  let color_interpolation = match gradient.color_space {
+    ast::ColorSpace::SRgb => "sRGB",
+    ast::ColorSpace::LinearRgb => "linearRGB",
+  };
+  writeln!(
+    defs,
+    concat!(
+      "    <{} id=\"{}\" gradientUnits=\"userSpaceOnUse\" {} spreadMethod=\"{}\" color-interpolation=\"{}\"",
+      " gradientTransform=\"matrix({} {} {} {} {} {})\">"
+    ),
+    element,
+    id,
+    attributes,
+    spread,
+    color_interpolation,
+    f64::from(matrix.scale_x),
+    f64::from(matrix.rotate_skew0),
+    f64::from(matrix.rotate_skew1),
+    f64::from(matrix.scale_y),
+    f64::from(matrix.translate_x) / TWIPS_PER_PIXEL,
+    f64::from(matrix.translate_y) / TWIPS_PER_PIXEL
+  )
+  .unwrap();
+  for stop in &gradient.colors {
+    write!(
+      defs,
+      "      <stop offset=\"{}\" stop-color=\"{}\"",
+      f64::from(stop.ratio) / 255.0,
+      color_hex(&stop.color)
+    )
+    .unwrap();
+    if let Some(opacity) = opacity(stop.color.a) {
+      write!(defs, " stop-opacity=\"{}\"", opacity).unwrap();
+    }
+    defs.push_str("/>\n");
+  }
+  writeln!(defs, "    </{}>", element).unwrap();
+  Some(Paint {
+    value: format!("url(#{})", id),
+    opacity: None,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn square_shape(fill: ast::FillStyle) -> ast::Shape {
+    let edge = |x: i32, y: i32| {
+      ast::ShapeRecord::Edge(ast::shape_records::Edge {
+        delta: ast::Vector2D { x, y },
+        control_delta: None,
+      })
+    };
+    ast::Shape {
+      initial_styles: ast::ShapeStyles {
+        fill: vec![fill],
+        line: vec![ast::LineStyle {
+          width: 40,
+          start_cap: ast::CapStyle::Round,
+          end_cap: ast::CapStyle::Round,
+          join: ast::JoinStyle::Round,
+          no_h_scale: false,
+          no_v_scale: false,
+          no_close: false,
+          pixel_hinting: false,
+          fill: ast::FillStyle::Solid(ast::fill_styles::Solid {
+            color: ast::StraightSRgba8 {
+              r: 0,
+              g: 0,
+              b: 0,
+              a: 255,
+            },
+          }),
+        }],
+      },
+      records: vec![
+        ast::ShapeRecord::StyleChange(ast::shape_records::StyleChange {
+          move_to: Some(ast::Vector2D { x: 0, y: 0 }),
+          left_fill: Some(1),
+          right_fill: None,
+          line_style: Some(1),
+          new_styles: None,
+        }),
+        edge(0, 200),
+        edge(200, 0),
+        edge(0, -200),
+        edge(-200, 0),
+      ],
+    }
+  }
+
+  const BOUNDS: ast::Rect = ast::Rect {
+    x_min: 0,
+    x_max: 200,
+    y_min: 0,
+    y_max: 200,
+  };
+
+  #[test]
+  fn test_shape_to_svg_solid() {
+    let shape = square_shape(ast::FillStyle::Solid(ast::fill_styles::Solid {
+      color: ast::StraightSRgba8 {
+        r: 255,
+        g: 0,
+        b: 0,
+        a: 128,
+      },
+    }));
+    assert_eq!(
+      shape_to_svg(&shape, &BOUNDS),
+      "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"10\" height=\"10\" viewBox=\"0 0 10 10\">
+  <path fill=\"#ff0000\" fill-opacity=\"0.5019607843137255\" fill-rule=\"evenodd\" d=\"M0 10 L0 0 L10 0 L10 10 L0 10Z\"/>
+  <path fill=\"none\" stroke=\"#000000\" stroke-width=\"2\" stroke-linecap=\"round\" stroke-linejoin=\"round\" d=\"M0 0 L0 10 L10 10 L10 0 L0 0\"/>
+</svg>
+"
+    );
+  }
+
+  #[test]
+  fn test_shape_to_svg_gradient() {
+    let color = |r: u8, a: u8| ast::StraightSRgba8 { r, g: 0, b: 0, a };
+    let shape = square_shape(ast::FillStyle::LinearGradient(ast::fill_styles::LinearGradient {
+      matrix: ast::Matrix {
+        scale_x: ast::fixed::Sfixed16P16::from_value(0.5),
+        scale_y: ast::fixed::Sfixed16P16::from_value(0.5),
+        rotate_skew0: ast::fixed::Sfixed16P16::from_value(0.0),
+        rotate_skew1: ast::fixed::Sfixed16P16::from_value(0.0),
+        translate_x: 100,
+        translate_y: 100,
+      },
+      gradient: ast::Gradient {
+        spread: ast::GradientSpread::Pad,
+        color_space: ast::ColorSpace::SRgb,
+        colors: vec![
+          ast::ColorStop {
+            ratio: 0,
+            color: color(0, 255),
+          },
+          ast::ColorStop {
+            ratio: 255,
+            color: color(255, 0),
+          },
+        ],
+      },
+    }));
+    let svg = shape_to_svg(&shape, &BOUNDS);
+    assert!(svg.contains(
+      "    <linearGradient id=\"gradient0\" gradientUnits=\"userSpaceOnUse\" x1=\"-819.2\" y1=\"0\" x2=\"819.2\" y2=\"0\" spreadMethod=\"pad\" color-interpolation=\"sRGB\" gradientTransform=\"matrix(0.5 0 0 0.5 5 5)\">
+      <stop offset=\"0\" stop-color=\"#000000\"/>
+      <stop offset=\"1\" stop-color=\"#ff0000\" stop-opacity=\"0\"/>
+    </linearGradient>
+"
+    ));
+    assert!(svg.contains("<path fill=\"url(#gradient0)\" fill-rule=\"evenodd\""));
+  }
+
+  #[test]
+  fn test_glyph_to_svg_path() {
+    let edge = |x: i32, y: i32| {
+      ast::ShapeRecord::Edge(ast::shape_records::Edge {
+        delta: ast::Vector2D { x, y },
+        control_delta: None,
+      })
+    };
+    let glyph = ast::Glyph {
+      records: vec![
+        ast::ShapeRecord::StyleChange(ast::shape_records::StyleChange {
+          move_to: Some(ast::Vector2D { x: 0, y: 0 }),
+          left_fill: Some(1),
+          right_fill: None,
+          line_style: None,
+          new_styles: None,
+        }),
+        edge(200, 0),
+        edge(-100, -200),
+        edge(-100, 200),
+      ],
+    };
+    assert_eq!(glyph_to_svg_path(&glyph), "M200 0 L0 0 L100 -200 L200 0Z");
+  }
+}
@@ -0,0 +1,483 @@
+use crate::legacy::LegacyEncoding;
+use crate::streaming::basic_data_types::LAST_LEGACY_STRING_VERSION;
+use std::collections::HashMap;
+use swf_types as ast;
+
+/// Text of a `DefineText` or `DefineDynamicText` character.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CharacterText {
+  Static(StaticText),
+  Dynamic(DynamicText),
+}
+
+/// Text of a `DefineText` tag, with its glyphs converted back to characters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StaticText {
+  pub id: u16,
+  pub bounds: ast::Rect,
+  /// Transform from the text coordinates (used by the runs) to the character coordinates.
+  pub matrix: ast::Matrix,
+  pub runs: Vec<TextRun>,
+}
+
+/// Text record of a `DefineText` tag: characters sharing the same font, size, color and line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextRun {
+  pub font_id: Option<u16>,
+  /// Font size in twips.
+  pub font_size: Option<u16>,
+  pub color: Option<ast::StraightSRgba8>,
+  /// Position of the first glyph (left of its baseline), in twips.
+  pub x: i32,
+  pub y: i32,
+  /// Characters of the glyphs, `U+FFFD` if the font doesn't have a code unit for the glyph.
+  pub text: String,
+}
+
+/// Initial text of a `DefineDynamicText` (`DefineEditText`) tag.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DynamicText {
+  pub id: u16,
+  pub font_id: Option<u16>,
+  pub font_class: Option<String>,
+  /// Font size in twips.
+  pub font_size: Option<u16>,
+  pub color: Option<ast::StraightSRgba8>,
+  pub variable_name: Option<String>,
+  /// Initial text, with the markup removed for HTML texts.
+  pub text: Option<String>,
+  /// Initial HTML source, for HTML texts.
+  pub html: Option<String>,
+}
+
+/// Returns the texts defined by a movie, in definition order.
+///
+/// The glyph indices of `DefineText` tags are joined to the code units of their font: the code table of the
+/// `DefineFont2`/`DefineFont3` tag or of the `DefineFontInfo` tag for `DefineFont` fonts. Code units are decoded as
+/// UTF-16, except for the ANSI, Shift-JIS or localized fonts of SWF 5 (and earlier) movies which use the matching
+/// [[LegacyEncoding]].
+pub fn extract_texts(tags: &[ast::Tag], swf_version: u8) -> Vec<CharacterText> {
+  let mut code_tables: HashMap<u16, CodeTable> = HashMap::new();
+  for tag in tags {
+    let (font_id, code_table) = match tag {
+      ast::Tag::DefineFont(font) => match &font.code_units {
+        Some(code_units) => (
+          font.id,
+          CodeTable {
+            code_units,
+            encoding: font_legacy_encoding(swf_version, font.is_ansi, font.is_shift_jis, font.language),
+          },
+        ),
+        None => continue,
+      },
+      ast::Tag::DefineFontInfo(info) => (
+        info.font_id,
+        CodeTable {
+          code_units: &info.code_units,
+          encoding: font_legacy_encoding(swf_version, info.is_ansi, info.is_shift_jis, info.language),
+        },
+      ),
+      _ => continue,
+    };
+    code_tables.entry(font_id).or_insert(code_table);
+  }
+
+  let mut texts: Vec<CharacterText> = Vec::new();
+  for tag in tags {
+    match tag {
+      ast::Tag::DefineText(text) => texts.push(CharacterText::Static(read_static_text(text, &code_tables))),
+      ast::Tag::DefineDynamicText(text) => texts.push(CharacterText::Dynamic(DynamicText {
+        id: text.id,
+        font_id: text.font_id,
+        font_class: text.font_class.clone(),
+        font_size: text.font_size,
+        color: text.color,
+        variable_name: text.variable_name.clone(),
+        text: match &text.text {
+          Some(html) if text.html => Some(html_to_text(html)),
+          initial => initial.clone(),
+        },
+        html: if text.html { text.text.clone() } else { None },
+      })),
+      _ => {}
+    }
+  }
+  texts
+}
+
+/// Code units of a font, with the legacy encoding of its code units (`None` for UTF-16).
+struct CodeTable<'a> {
+  code_units: &'a [u16],
+  encoding: Option<LegacyEncoding>,
+}
+
+impl CodeTable<'_> {
+  fn decode(&self, indices: impl Iterator<Item = usize>) -> String {
+    let units = indices.map(|index| self.code_units.get(index).copied());
+    match self.encoding {
+      None => char::decode_utf16(units.map(|unit| unit.unwrap_or(0xfffd)))
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect(),
+      // Double-byte characters are stored as a single code unit, lead byte first
+      Some(encoding) => units
+        .map(|unit| match unit {
+          Some(unit @ 0..=0xff) => encoding.decode(&[unit as u8]),
+          Some(unit) => encoding.decode(&unit.to_be_bytes()),
+          None => String::from(char::REPLACEMENT_CHARACTER),
+        })
+        .collect(),
+    }
+  }
+}
+
+/// Returns the legacy encoding of the code units of a font, `None` if they are UTF-16.
+fn font_legacy_encoding(
+  swf_version: u8,
+  is_ansi: bool,
+  is_shift_jis: bool,
+  language: ast::LanguageCode,
+) -> Option<LegacyEncoding> {
+  if swf_version > LAST_LEGACY_STRING_VERSION {
+    None
+  } else if is_shift_jis {
+    Some(LegacyEncoding::ShiftJis)
+  } else if let Some(encoding) = LegacyEncoding::from_language_code(language) {
+    Some(encoding)
+  } else if is_ansi {
+    Some(LegacyEncoding::Windows1252)
+  } else {
+    None
+  }
+}
+
+fn read_static_text(text: &ast::tags::DefineText, code_tables: &HashMap<u16, CodeTable>) -> StaticText {
+  let mut runs: Vec<TextRun> = Vec::with_capacity(text.records.len());
+  let mut font_id: Option<u16> = None;
+  let mut font_size: Option<u16> = None;
+  let mut color: Option<ast::StraightSRgba8> = None;
+  let mut x: i32 = 0;
+  let mut y: i32 = 0;
+  for record in text.records.iter() {
+    // The style of a record applies to the next records, until it is changed
+    if record.font_id.is_some() {
+      font_id = record.font_id;
+      font_size = record.font_size;
+    }
+    if record.color.is_some() {
+      color = record.color;
+    }
+    // Offsets are absolute, a zero offset (absent) continues after the previous record
+    if record.offset_x != 0 {
+      x = i32::from(record.offset_x);
+    }
+    if record.offset_y != 0 {
+      y = i32::from(record.offset_y);
+    }
+    let indices = record.entries.iter().map(|entry| entry.index);
+    let text: String = match font_id.and_then(|id| code_tables.get(&id)) {
+      Some(code_table) => code_table.decode(indices),
+      None => indices.map(|_| char::REPLACEMENT_CHARACTER).collect(),
+    };
+    runs.push(TextRun {
+      font_id,
+      font_size,
+      color,
+      x,
+      y,
+      text,
+    });
+    x += record.entries.iter().map(|entry| entry.advance).sum::<i32>();
+  }
+  StaticText {
+    id: text.id,
+    bounds: text.bounds,
+    matrix: text.matrix,
+    runs,
+  }
+}
+
+/// Converts the HTML subset of dynamic texts to plain text.
+///
+/// Tags are removed, paragraphs and line breaks become new lines and the entities of XML, `&nbsp;` and character
+/// references are decoded.
+pub fn html_to_text(html: &str) -> String {
+  let mut text = String::with_capacity(html.len());
+  let mut rest = html;
+  while let Some(c) = rest.chars().next() {
+    match c {
+      '<' => {
+        let end = rest.find('>').map_or(rest.len(), |end| end + 1);
+        let name: String = rest[1..end]
+          .trim_start_matches('/')
+          .chars()
+          .take_while(|c| c.is_ascii_alphanumeric())
+          .collect::<String>()
+          .to_ascii_lowercase();
+        let is_closing = rest[1..].starts_with('/');
+        match name.as_str() {
+          "br" => text.push('\n'),
+          // Each paragraph ends with a new line, except the last one
+          "p" | "li" if is_closing => text.push('\n'),
+          _ => {}
+        }
+        rest = &rest[end..];
+      }
+      '&' => {
+        let decoded = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+          let c = decode_entity(&rest[1..end])?;
+          Some((c, end + 1))
+        });
+        match decoded {
+          Some((c, len)) => {
+            text.push(c);
+            rest = &rest[len..];
+          }
+          None => {
+            text.push('&');
+            rest = &rest[1..];
+          }
+        }
+      }
+      c => {
+        text.push(c);
+        rest = &rest[c.len_utf8()..];
+      }
+    }
+  }
+  if text.ends_with('\n') {
+    text.pop();
+  }
+  text
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+  match name {
+    "lt" => Some('<'),
+    "gt" => Some('>'),
+    "amp" => Some('&'),
+    "quot" => Some('"'),
+    "apos" => Some('\''),
+    "nbsp" => Some('\u{a0}'),
+    _ => {
+      let code = if let Some(hex) = name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+        u32::from_str_radix(hex, 16).ok()?
+      } else {
+        name.strip_prefix('#')?.parse::<u32>().ok()?
+      };
+      char::from_u32(code)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn bounds() -> ast::Rect {
+    ast::Rect {
+      x_min: 0,
+      x_max: 2000,
+      y_min: 0,
+      y_max: 500,
+    }
+  }
+
+  fn entry(index: usize, advance: i32) -> ast::text::GlyphEntry {
+    ast::text::GlyphEntry { index, advance }
+  }
+
+  fn define_font(id: u16, code_units: Vec<u16>) -> ast::Tag {
+    ast::Tag::DefineFont(ast::tags::DefineFont {
+      id,
+      font_name: String::from("Test"),
+      is_bold: false,
+      is_italic: false,
+      is_ansi: false,
+      is_small: false,
+      is_shift_jis: false,
+      em_square_size: ast::text::EmSquareSize::EmSquareSize1024,
+      language: ast::LanguageCode::Auto,
+      glyphs: Some(vec![ast::Glyph { records: Vec::new() }; code_units.len()]),
+      code_units: Some(code_units),
+      layout: None,
+    })
+  }
+
+  fn dynamic_text(id: u16, html: bool, text: &str) -> ast::Tag {
+    ast::Tag::DefineDynamicText(ast::tags::DefineDynamicText {
+      id,
+      bounds: bounds(),
+      word_wrap: false,
+      multiline: true,
+      password: false,
+      readonly: true,
+      auto_size: false,
+      no_select: false,
+      border: false,
+      was_static: false,
+      html,
+      use_glyph_font: false,
+      font_id: Some(1),
+      font_class: None,
+      font_size: Some(240),
+      color: None,
+      max_length: None,
+      align: ast::text::TextAlignment::Left,
+      margin_left: 0,
+      margin_right: 0,
+      indent: 0,
+      leading: 0,
+      variable_name: Some(String::from("label")),
+      text: Some(String::from(text)),
+    })
+  }
+
+  #[test]
+  fn test_extract_static_text() {
+    let red = ast::StraightSRgba8 { r: 255, g: 0, b: 0, a: 255 };
+    let tags = vec![
+      define_font(1, vec![0x48, 0x69, 0x21]),
+      ast::Tag::DefineFontInfo(ast::tags::DefineFontInfo {
+        font_id: 2,
+        font_name: String::from("Old"),
+        is_bold: false,
+        is_italic: false,
+        is_ansi: false,
+        is_shift_jis: false,
+        is_small: false,
+        language: ast::LanguageCode::Auto,
+        code_units: vec![0xe9, 0xd83d, 0xde00],
+      }),
+      ast::Tag::DefineText(ast::tags::DefineText {
+        id: 3,
+        bounds: bounds(),
+        matrix: ast::Matrix::default(),
+        records: vec![
+          ast::text::TextRecord {
+            font_id: Some(1),
+            color: Some(red),
+            offset_x: 20,
+            offset_y: 200,
+            font_size: Some(240),
+            entries: vec![entry(0, 100), entry(1, 50)],
+          },
+          ast::text::TextRecord {
+            font_id: Some(2),
+            color: None,
+            offset_x: 0,
+            offset_y: 0,
+            font_size: Some(120),
+            entries: vec![entry(0, 60), entry(1, 0), entry(2, 60), entry(5, 10)],
+          },
+          ast::text::TextRecord {
+            font_id: None,
+            color: None,
+            offset_x: 20,
+            offset_y: 400,
+            font_size: None,
+            entries: vec![entry(2, 60)],
+          },
+        ],
+      }),
+    ];
+    let run = |font_id: u16, font_size: u16, x: i32, y: i32, text: &str| TextRun {
+      font_id: Some(font_id),
+      font_size: Some(font_size),
+      color: Some(red),
+      x,
+      y,
+      text: String::from(text),
+    };
+    assert_eq!(
+      extract_texts(&tags, 10),
+      vec![CharacterText::Static(StaticText {
+        id: 3,
+        bounds: bounds(),
+        matrix: ast::Matrix::default(),
+        runs: vec![
+          run(1, 240, 20, 200, "Hi"),
+          run(2, 120, 170, 200, "é😀\u{fffd}"),
+          run(2, 120, 20, 400, "\u{fffd}"),
+        ],
+      })]
+    );
+  }
+
+  #[test]
+  fn test_extract_legacy_static_text() {
+    let mut ansi_font = define_font(1, vec![0x41, 0x80]);
+    if let ast::Tag::DefineFont(font) = &mut ansi_font {
+      font.is_ansi = true;
+    }
+    let tags = vec![
+      ansi_font,
+      ast::Tag::DefineFontInfo(ast::tags::DefineFontInfo {
+        font_id: 2,
+        font_name: String::from("Osaka"),
+        is_bold: false,
+        is_italic: false,
+        is_ansi: false,
+        is_shift_jis: true,
+        is_small: false,
+        language: ast::LanguageCode::Auto,
+        code_units: vec![0x82a0, 0x41, 0xb1],
+      }),
+      ast::Tag::DefineText(ast::tags::DefineText {
+        id: 3,
+        bounds: bounds(),
+        matrix: ast::Matrix::default(),
+        records: vec![
+          ast::text::TextRecord {
+            font_id: Some(1),
+            color: None,
+            offset_x: 0,
+            offset_y: 200,
+            font_size: Some(240),
+            entries: vec![entry(0, 100), entry(1, 100)],
+          },
+          ast::text::TextRecord {
+            font_id: Some(2),
+            color: None,
+            offset_x: 0,
+            offset_y: 400,
+            font_size: Some(240),
+            entries: vec![entry(0, 100), entry(1, 100), entry(2, 100), entry(3, 100)],
+          },
+        ],
+      }),
+    ];
+    let run_texts = |swf_version: u8| -> Vec<String> {
+      match &extract_texts(&tags, swf_version)[..] {
+        [CharacterText::Static(text)] => text.runs.iter().map(|run| run.text.clone()).collect(),
+        texts => panic!("Expected a single static text, got: {:?}", texts),
+      }
+    };
+    assert_eq!(run_texts(5), vec!["A€", "あAｱ\u{fffd}"]);
+    assert_eq!(run_texts(6), vec!["A\u{80}", "\u{82a0}A\u{b1}\u{fffd}"]);
+  }
+
+  #[test]
+  fn test_extract_dynamic_text() {
+    let tags = vec![
+      dynamic_text(1, false, "Score: 0"),
+      dynamic_text(2, true, "<p align=\"left\"><font face=\"Arial\">A &amp; B<br/>C&#233;</font></p><p>&lt;D&gt;</p>"),
+    ];
+    let texts = extract_texts(&tags, 10);
+    match &texts[..] {
+      [CharacterText::Dynamic(plain), CharacterText::Dynamic(html)] => {
+        assert_eq!(plain.text.as_deref(), Some("Score: 0"));
+        assert_eq!(plain.html, None);
+        assert_eq!(plain.variable_name.as_deref(), Some("label"));
+        assert_eq!(html.text.as_deref(), Some("A & B\nCé\n<D>"));
+        assert!(html.html.as_deref().unwrap().starts_with("<p align="));
+      }
+      _ => panic!("Expected two dynamic texts, got: {:?}", texts),
+    }
+  }
+
+  #[test]
+  fn test_html_to_text_malformed() {
+    assert_eq!(html_to_text("a & b <b>c"), "a & b c");
+    assert_eq!(html_to_text("&unknown; &#xZZ; <unclosed"), "&unknown; &#xZZ; ");
+  }
+}